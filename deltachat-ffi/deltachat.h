@@ -19,6 +19,10 @@ typedef struct _dc_msg      dc_msg_t;
 typedef struct _dc_contact  dc_contact_t;
 typedef struct _dc_lot      dc_lot_t;
 typedef struct _dc_provider dc_provider_t;
+typedef struct _dc_accounts dc_accounts_t;
+typedef struct _dc_event_emitter dc_event_emitter_t;
+typedef struct _dc_event    dc_event_t;
+typedef struct _dc_backup_provider dc_backup_provider_t;
 
 
 /**
@@ -49,35 +53,14 @@ typedef struct _dc_provider dc_provider_t;
  *
  * After that, you should make sure,
  * sending and receiving jobs are processed as needed.
- * For this purpose, you have to **create two threads:**
+ * For this purpose, you have to **start the IO:**
  *
  * ~~~
- * #include <pthread.h>
- *
- * void* imap_thread_func(void* context)
- * {
- *     while (true) {
- *         dc_perform_imap_jobs(context);
- *         dc_perform_imap_fetch(context);
- *         dc_perform_imap_idle(context);
- *     }
- * }
- *
- * void* smtp_thread_func(void* context)
- * {
- *     while (true) {
- *         dc_perform_smtp_jobs(context);
- *         dc_perform_smtp_idle(context);
- *     }
- * }
- *
- * static pthread_t imap_thread, smtp_thread;
- * pthread_create(&imap_thread, NULL, imap_thread_func, context);
- * pthread_create(&smtp_thread, NULL, smtp_thread_func, context);
+ * dc_start_io(context);
  * ~~~
  *
- * The example above uses "pthreads",
- * however, you can also use anything else for thread handling.
+ * The IO runs in the background until dc_stop_io() is called,
+ * there is no need to create threads for sending and receiving.
  * All deltachat-core-functions, unless stated otherwise, are thread-safe.
  *
  * After that you can  **define and open a database.**
@@ -97,7 +80,7 @@ typedef struct _dc_provider dc_provider_t;
  * ~~~
  *
  * dc_configure() returns immediately, the configuration itself may take a while
- * and is done by a job in the imap-thread you've defined above.
+ * and is done by a job in the IO started above.
  * Once done, the #DC_EVENT_CONFIGURE_PROGRESS reports success
  * to the event_handler_func() that is also defined above.
  *
@@ -116,11 +99,11 @@ typedef struct _dc_provider dc_provider_t;
  * ~~~
  *
  * dc_send_text_msg() returns immediately;
- * the sending itself is done by a job in the smtp-thread you've defined above.
+ * the sending itself is done by a job in the IO started by dc_start_io().
  * If you check the testing address (bob)
  * and you should have received a normal email.
  * Answer this email in any email program with "Got it!"
- * and the imap-thread you've create above will **receive the message**.
+ * and the IO started above will **receive the message**.
  *
  * You can then **list all messages** of a chat as follow:
  *
@@ -230,6 +213,7 @@ typedef uintptr_t (*dc_callback_t) (dc_context_t* context, int event, uintptr_t
  *     - The callback SHOULD return _fast_, for GUI updates etc. you should
  *       post yourself an asynchronous message to your GUI thread, if needed.
  *     - If not mentioned otherweise, the callback should return 0.
+ *     - NULL may be passed if the events are received using dc_get_event_emitter().
  * @param userdata can be used by the client for any purpuse.  He finds it
  *     later in dc_get_userdata().
  * @param os_name is only for decorative use
@@ -253,6 +237,11 @@ dc_context_t*   dc_context_new               (dc_callback_t cb, void* userdata,
  * are closed. You can also do this explicitly by calling dc_close() on your own
  * before calling dc_context_unref().
  *
+ * Context objects returned by dc_accounts_get_account()
+ * or dc_accounts_get_selected_account() are only handles to accounts
+ * owned by the dc_accounts_t object;
+ * freeing them does not stop IO nor close the account.
+ *
  * @memberof dc_context_t
  * @param context The context object as created by dc_context_new().
  *     If NULL is given, nothing is done.
@@ -289,6 +278,60 @@ void*           dc_get_userdata              (dc_context_t* context);
 int             dc_open                      (dc_context_t* context, const char* dbfile, const char* blobdir);
 
 
+/**
+ * Open context database encrypted with a passphrase.
+ * This works as dc_open() but the database is encrypted using SQLCipher;
+ * an existing unencrypted database is encrypted when it is opened the first time.
+ *
+ * Encrypted databases need deltachat-core built with the `sqlcipher` feature
+ * and linked against a SQLCipher supporting FTS5, otherwise the function fails.
+ * Backups exported by dc_imex() contain the unencrypted database,
+ * so exporting a backup of an encrypted database needs a backup passphrase.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as created by dc_context_new().
+ * @param dbfile The file to use to store the database, see dc_open().
+ * @param blobdir A directory to store the blobs in, see dc_open().
+ * @param passphrase The passphrase used to encrypt the database, must not be empty.
+ * @return 1 on success, 0 on failure
+ *     eg. if the passphrase is wrong
+ *     or if there is already a database opened for the context.
+ */
+int             dc_open_encrypted            (dc_context_t* context, const char* dbfile, const char* blobdir, const char* passphrase);
+
+
+/**
+ * Change the passphrase of the database opened by dc_open_encrypted()
+ * or encrypt a database opened by dc_open().
+ * The database is rewritten, IO must be stopped using dc_stop_io() before.
+ *
+ * @memberof dc_context_t
+ * @param context The context object, must be opened.
+ * @param passphrase The new passphrase, must not be empty.
+ *     NULL removes the encryption.
+ * @return 1 on success, 0 on failure.
+ */
+int             dc_change_passphrase         (dc_context_t* context, const char* passphrase);
+
+
+/**
+ * Get an event emitter to receive the events of a context.
+ *
+ * This is an alternative to the callback given to dc_context_new():
+ * Events emitted after the emitter is created are queued
+ * and can be fetched using dc_get_next_event(), typically from a dedicated thread.
+ * You may pass NULL as callback to dc_context_new() then.
+ * Every emitter has its own queue and receives all events,
+ * if the queue is full, the oldest events are dropped.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as created by dc_context_new(), must be opened.
+ * @return The event emitter, must be freed using dc_event_emitter_unref() after usage.
+ *     NULL if the context is not opened.
+ */
+dc_event_emitter_t* dc_get_event_emitter     (dc_context_t* context);
+
+
 /**
  * Close context database opened by dc_open().
  * Before this, connections to SMTP and IMAP are closed; these connections
@@ -349,6 +392,9 @@ char*           dc_get_blobdir               (const dc_context_t* context);
  * - `e2ee_enabled` = 0=no end-to-end-encryption, 1=prefer end-to-end-encryption (default)
  * - `mdns_enabled` = 0=do not send or request read receipts,
  *                    1=send and request read receipts (default)
+ * - `typing_notifications` = 1=send typing notifications, see dc_send_typing(),
+ *                    0=do not send typing notifications (default);
+ *                    typing notifications of others are always received
  * - `inbox_watch`  = 1=watch `INBOX`-folder for changes (default),
  *                    0=do not watch the `INBOX`-folder
  * - `sentbox_watch`= 1=watch `Sent`-folder for changes (default),
@@ -358,6 +404,18 @@ char*           dc_get_blobdir               (const dc_context_t* context);
  * - `mvbox_move`   = 1=heuristically detect chat-messages
  *                    and move them to the `DeltaChat`-folder,
  *                    0=do not move chat-messages
+ * - `watch_folders`= additional folders to fetch messages from, one folder per line,
+ *                    eg. folders mail is sorted into by server-side filters.
+ *                    If more than one folder is set, the folders are polled for changes.
+ *                    Folders already watched by the options above are ignored.
+ * - `delete_server_after` = 0=do not delete messages from the server automatically (default),
+ *                    >=1=seconds after which downloaded messages are deleted from the server,
+ *                    the messages are kept on the device.
+ *                    Starred messages are not deleted.
+ * - `delete_device_after` = 0=do not delete messages from the device automatically (default),
+ *                    >=1=seconds after which messages are deleted from the device,
+ *                    including their attachments.
+ *                    Starred messages and messages in the device chat are not deleted.
  * - `show_emails`  = DC_SHOW_EMAILS_OFF (0)=
  *                    show direct replies to chats only (default),
  *                    DC_SHOW_EMAILS_ACCEPTED_CONTACTS (1)=
@@ -457,6 +515,43 @@ int             dc_set_config_from_qr   (dc_context_t* context, const char* qr);
 char*           dc_get_info                  (dc_context_t* context);
 
 
+/**
+ * Get the storage quota of the account, in json format.
+ *
+ * The quota is requested from the IMAP server after fetching messages,
+ * at most every 10 minutes.
+ * The returned json object contains the time of the request
+ * and the resources with their usage and limit, eg.
+ * `{"resources":[{"root":"","name":"STORAGE","usage":8192,"limit":10240}],"timestamp":1585000000}`;
+ * `STORAGE` is given in units of 1024 octets.
+ * If the mailbox has several quota roots, the resources of all roots are listed.
+ * If the server does not support quotas, the list of resources is empty,
+ * if the quota was not requested yet, `null` is returned.
+ *
+ * If a resource is almost exhausted, a device message warns the user.
+ *
+ * @memberof dc_context_t
+ * @param context The context as created by dc_context_new().
+ * @return a utf8-encoded json string, must be freed using dc_str_unref(). NULL is never returned.
+ */
+char*           dc_get_quota_json            (dc_context_t* context);
+
+
+/**
+ * Get a human readable summary of the connectivity, ie.
+ * whether the account is connected to the IMAP and SMTP servers
+ * and how much of the storage quota is used, see dc_get_quota_json().
+ *
+ * The summary is meant to be shown to the user as is,
+ * it contains one item per line.
+ *
+ * @memberof dc_context_t
+ * @param context The context as created by dc_context_new().
+ * @return a utf8-encoded string, must be freed using dc_str_unref(). NULL is never returned.
+ */
+char*           dc_get_connectivity_info     (dc_context_t* context);
+
+
 /**
  * Get url that can be used to initiate an OAuth2 authorisation.
  *
@@ -495,7 +590,7 @@ char*           dc_get_oauth2_url            (dc_context_t* context, const char*
  * Configure a context.
  * For this purpose, the function creates a job
  * that is executed in the IMAP-thread then;
- * this requires to call dc_perform_imap_jobs() regularly.
+ * this requires the IO to be started by dc_start_io().
  * If the context is already configured,
  * this function will try to change the configuration.
  *
@@ -561,312 +656,50 @@ int             dc_is_configured             (const dc_context_t* context);
 
 
 /**
- * Execute pending imap-jobs.
- * This function and dc_perform_imap_fetch() and dc_perform_imap_idle()
- * must be called from the same thread, typically in a loop.
- *
- * Example:
- *
- *     void* imap_thread_func(void* context)
- *     {
- *         while (true) {
- *             dc_perform_imap_jobs(context);
- *             dc_perform_imap_fetch(context);
- *             dc_perform_imap_idle(context);
- *         }
- *     }
- *
- *     // start imap-thread that runs forever
- *     pthread_t imap_thread;
- *     pthread_create(&imap_thread, NULL, imap_thread_func, context);
- *
- *     ... program runs ...
- *
- *     // network becomes available again -
- *     // the interrupt causes dc_perform_imap_idle() in the thread above
- *     // to return so that jobs are executed and messages are fetched.
- *     dc_maybe_network(context);
- *
- * @memberof dc_context_t
- * @param context The context as created by dc_context_new().
- * @return None.
- */
-void            dc_perform_imap_jobs         (dc_context_t* context);
-
-
-/**
- * Fetch new messages, if any.
- * This function and dc_perform_imap_jobs() and dc_perform_imap_idle() must be called from the same thread,
- * typically in a loop.
- *
- * See dc_perform_imap_jobs() for an example.
- *
- * @memberof dc_context_t
- * @param context The context as created by dc_context_new().
- * @return None.
- */
-void            dc_perform_imap_fetch        (dc_context_t* context);
-
-
-/**
- * Wait for messages or jobs.
- * This function and dc_perform_imap_jobs() and dc_perform_imap_fetch() must be called from the same thread,
- * typically in a loop.
- *
- * You should call this function directly after calling dc_perform_imap_fetch().
- *
- * See dc_perform_imap_jobs() for an example.
- *
- * @memberof dc_context_t
- * @param context The context as created by dc_context_new().
- * @return None.
- */
-void            dc_perform_imap_idle         (dc_context_t* context);
-
-
-/**
- * Interrupt waiting for imap-jobs.
- * If dc_perform_imap_jobs(), dc_perform_imap_fetch() and dc_perform_imap_idle() are called in a loop,
- * calling this function causes imap-jobs to be executed and messages to be fetched.
- *
- * dc_interrupt_imap_idle() does _not_ interrupt dc_perform_imap_jobs() or dc_perform_imap_fetch().
- * If the imap-thread is inside one of these functions when dc_interrupt_imap_idle() is called, however,
- * the next call of the imap-thread to dc_perform_imap_idle() is interrupted immediately.
- *
- * Internally, this function is called whenever a imap-jobs should be processed
- * (delete message, markseen etc.).
- *
- * When you need to call this function just because to get jobs done after network changes,
- * use dc_maybe_network() instead.
- *
- * @memberof dc_context_t
- * @param context The context as created by dc_context_new().
- * @return None.
- */
-void            dc_interrupt_imap_idle       (dc_context_t* context);
-
-
-/**
- * Execute pending mvbox-jobs.
- * This function and dc_perform_mvbox_fetch() and dc_perform_mvbox_idle()
- * must be called from the same thread, typically in a loop.
- *
- * Example:
- *
- *     void* mvbox_thread_func(void* context)
- *     {
- *         while (true) {
- *             dc_perform_mvbox_jobs(context);
- *             dc_perform_mvbox_fetch(context);
- *             dc_perform_mvbox_idle(context);
- *         }
- *     }
- *
- *     // start mvbox-thread that runs forever
- *     pthread_t mvbox_thread;
- *     pthread_create(&mvbox_thread, NULL, mvbox_thread_func, context);
- *
- *     ... program runs ...
- *
- *     // network becomes available again -
- *     // the interrupt causes dc_perform_mvbox_idle() in the thread above
- *     // to return so that jobs are executed and messages are fetched.
- *     dc_maybe_network(context);
- *
- * @memberof dc_context_t
- * @param context The context as created by dc_context_new().
- * @return None.
- */
-void            dc_perform_mvbox_jobs         (dc_context_t* context);
-
-
-/**
- * Fetch new messages from the MVBOX, if any.
- * The MVBOX is a folder on the account where chat messages are moved to.
- * The moving is done to not disturb shared accounts that are used by both,
- * Delta Chat and a classical MUA.
- *
- * @memberof dc_context_t
- * @param context The context as created by dc_context_new().
- * @return None.
- */
-void            dc_perform_mvbox_fetch       (dc_context_t* context);
-
-
-/**
- * Wait for messages or jobs in the MVBOX-thread.
- * This function and dc_perform_mvbox_fetch().
- * must be called from the same thread, typically in a loop.
- *
- * You should call this function directly after calling dc_perform_mvbox_fetch().
- *
- * See dc_perform_mvbox_fetch() for an example.
- *
- * @memberof dc_context_t
- * @param context The context as created by dc_context_new().
- * @return None.
- */
-void            dc_perform_mvbox_idle        (dc_context_t* context);
-
-
-/**
- * Interrupt waiting for MVBOX-fetch.
- * dc_interrupt_mvbox_idle() does _not_ interrupt dc_perform_mvbox_fetch().
- * If the MVBOX-thread is inside this function when dc_interrupt_mvbox_idle() is called, however,
- * the next call of the MVBOX-thread to dc_perform_mvbox_idle() is interrupted immediately.
- *
- * Internally, this function is called whenever a imap-jobs should be processed.
- *
- * When you need to call this function just because to get jobs done after network changes,
- * use dc_maybe_network() instead.
- *
- * @memberof dc_context_t
- * @param context The context as created by dc_context_new().
- * @return None.
- */
-void            dc_interrupt_mvbox_idle      (dc_context_t* context);
-
-
-/**
- * Execute pending sentbox-jobs.
- * This function and dc_perform_sentbox_fetch() and dc_perform_sentbox_idle()
- * must be called from the same thread, typically in a loop.
- *
- * Example:
- *
- *     void* sentbox_thread_func(void* context)
- *     {
- *         while (true) {
- *             dc_perform_sentbox_jobs(context);
- *             dc_perform_sentbox_fetch(context);
- *             dc_perform_sentbox_idle(context);
- *         }
- *     }
- *
- *     // start sentbox-thread that runs forever
- *     pthread_t sentbox_thread;
- *     pthread_create(&sentbox_thread, NULL, sentbox_thread_func, context);
- *
- *     ... program runs ...
- *
- *     // network becomes available again -
- *     // the interrupt causes dc_perform_sentbox_idle() in the thread above
- *     // to return so that jobs are executed and messages are fetched.
- *     dc_maybe_network(context);
- *
- * @memberof dc_context_t
- * @param context The context as created by dc_context_new().
- * @return None.
- */
-void            dc_perform_sentbox_jobs         (dc_context_t* context);
-
-
-/**
- * Fetch new messages from the Sent folder, if any.
- * This function and dc_perform_sentbox_idle()
- * must be called from the same thread, typically in a loop.
+ * Start the IO of a context.
  *
- * @memberof dc_context_t
- * @param context The context as created by dc_context_new().
- * @return None.
- */
-void            dc_perform_sentbox_fetch     (dc_context_t* context);
-
-
-/**
- * Wait for messages or jobs in the SENTBOX-thread.
- * This function and dc_perform_sentbox_fetch()
- * must be called from the same thread, typically in a loop.
- *
- * @memberof dc_context_t
- * @param context The context as created by dc_context_new().
- * @return None.
- */
-void            dc_perform_sentbox_idle      (dc_context_t* context);
-
-
-/**
- * Interrupt waiting for messages or jobs in the SENTBOX-thread.
- *
- * @memberof dc_context_t
- * @param context The context as created by dc_context_new().
- * @return None.
- */
-void            dc_interrupt_sentbox_idle    (dc_context_t* context);
-
-
-/**
- * Execute pending smtp-jobs.
- * This function and dc_perform_smtp_idle() must be called from the same thread,
- * typically in a loop.
- *
- * Example:
- *
- *     void* smtp_thread_func(void* context)
- *     {
- *         while (true) {
- *             dc_perform_smtp_jobs(context);
- *             dc_perform_smtp_idle(context);
- *         }
- *     }
- *
- *     // start smtp-thread that runs forever
- *     pthread_t smtp_thread;
- *     pthread_create(&smtp_thread, NULL, smtp_thread_func, context);
+ * The IO runs in the background:
+ * jobs are executed, messages are sent and fetched
+ * and the IMAP folders are watched for new messages
+ * until dc_stop_io() is called.
  *
- *     ... program runs ...
- *
- *     // network becomes available again -
- *     // the interrupt causes dc_perform_smtp_idle() in the thread above
- *     // to return so that jobs are executed
- *     dc_maybe_network(context);
+ * Calling this function while the IO is already running does nothing.
  *
  * @memberof dc_context_t
  * @param context The context as created by dc_context_new().
  * @return None.
  */
-void            dc_perform_smtp_jobs         (dc_context_t* context);
+void            dc_start_io                  (dc_context_t* context);
 
 
 /**
- * Wait for smtp-jobs.
- * This function and dc_perform_smtp_jobs() must be called from the same thread,
- * typically in a loop.
+ * Stop the IO of a context.
  *
- * See dc_interrupt_smtp_idle() for an example.
+ * The function waits until the IO is stopped,
+ * jobs that were not done yet are executed after the next dc_start_io().
  *
  * @memberof dc_context_t
  * @param context The context as created by dc_context_new().
  * @return None.
  */
-void            dc_perform_smtp_idle         (dc_context_t* context);
+void            dc_stop_io                   (dc_context_t* context);
 
 
 /**
- * Interrupt waiting for smtp-jobs.
- * If dc_perform_smtp_jobs() and dc_perform_smtp_idle() are called in a loop,
- * calling this function causes jobs to be executed.
- *
- * dc_interrupt_smtp_idle() does _not_ interrupt dc_perform_smtp_jobs().
- * If the smtp-thread is inside this function when dc_interrupt_smtp_idle() is called, however,
- * the next call of the smtp-thread to dc_perform_smtp_idle() is interrupted immediately.
- *
- * Internally, this function is called whenever a message is to be sent.
- *
- * When you need to call this function just because to get jobs done after network changes,
- * use dc_maybe_network() instead.
+ * Check if the IO of a context is running.
  *
  * @memberof dc_context_t
  * @param context The context as created by dc_context_new().
- * @return None.
+ * @return 1=IO is running, started by dc_start_io(); 0=IO is not running.
  */
-void            dc_interrupt_smtp_idle       (dc_context_t* context);
+int             dc_is_io_running             (dc_context_t* context);
 
 
 /**
  * This function can be called whenever there is a hint
  * that the network is available again.
- * The library will try to send pending messages out.
+ * The library will try to send pending messages out
+ * and to fetch new messages, if the IO is started by dc_start_io().
  *
  * @memberof dc_context_t
  * @param context The context as created by dc_context_new().
@@ -1119,6 +952,97 @@ uint32_t        dc_send_msg                  (dc_context_t* context, uint32_t ch
 uint32_t        dc_send_text_msg             (dc_context_t* context, uint32_t chat_id, const char* text_to_send);
 
 
+/**
+ * Schedule a message to be sent later.
+ *
+ * Until the given time, the message is shown in the chat
+ * in the state DC_STATE_OUT_SCHEDULED.
+ * Until then, the text can be changed using dc_edit_msg(),
+ * the time can be changed using dc_reschedule_msg()
+ * and the message can be cancelled using dc_cancel_scheduled_msgs().
+ * The message is encrypted and sent to the chat members
+ * of the time the message is due.
+ *
+ * Sends the event #DC_EVENT_MSGS_CHANGED on succcess.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as returned from dc_context_new().
+ * @param chat_id Chat ID to send the message to.
+ * @param msg Message object to send to the chat defined by the chat ID.
+ *     On succcess, msg_id of the object is set up,
+ *     The function does not take ownership of the object,
+ *     so you have to free it using dc_msg_unref() as usual.
+ * @param timestamp Time to send the message at as a unix timestamp in seconds,
+ *     must be in the future.
+ * @return The ID of the scheduled message. 0 on errors.
+ */
+uint32_t        dc_send_msg_at               (dc_context_t* context, uint32_t chat_id, dc_msg_t* msg, int64_t timestamp);
+
+
+/**
+ * Get the messages scheduled by dc_send_msg_at() that are not yet sent.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as returned from dc_context_new().
+ * @param chat_id The chat to get the scheduled messages for,
+ *     0 to get the scheduled messages of all chats.
+ * @return Array of message IDs, ordered by the time the messages are sent at.
+ *     Must be dc_array_unref()'d when no longer used.
+ *     On errors, the list is empty. NULL is never returned.
+ */
+dc_array_t*     dc_get_scheduled_msgs        (dc_context_t* context, uint32_t chat_id);
+
+
+/**
+ * Change the time a message scheduled by dc_send_msg_at() is sent at.
+ *
+ * Sends the event #DC_EVENT_MSGS_CHANGED on succcess.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as returned from dc_context_new().
+ * @param msg_id The ID of the scheduled message.
+ * @param timestamp New time to send the message at as a unix timestamp in seconds,
+ *     must be in the future.
+ * @return 1=success, 0=error
+ */
+int             dc_reschedule_msg            (dc_context_t* context, uint32_t msg_id, int64_t timestamp);
+
+
+/**
+ * Cancel messages scheduled by dc_send_msg_at().
+ * The messages are not sent and are deleted.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as returned from dc_context_new().
+ * @param msg_ids An array of uint32_t containing the IDs of the scheduled messages
+ * @param msg_cnt The number of messages IDs in the msg_ids array
+ * @return 1=success, 0=error
+ */
+int             dc_cancel_scheduled_msgs     (dc_context_t* context, const uint32_t* msg_ids, int msg_cnt);
+
+
+/**
+ * Tell the other members of a chat that the user started or stopped typing.
+ *
+ * The UI should call this function with typing=1 when the user types into the text field
+ * of the chat, it is fine to do so on every keystroke.
+ * When the text field is cleared or left, the function should be called with typing=0.
+ *
+ * Typing notifications are only sent if the config option `typing_notifications` is enabled,
+ * see dc_set_config().
+ * The core sends them end-to-end-encrypted only, at most every few seconds
+ * and never before waiting messages.
+ * The other members get #DC_EVENT_CONTACT_TYPING and #DC_EVENT_CONTACT_STOPPED_TYPING.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as returned from dc_context_new().
+ * @param chat_id The chat the user is typing in.
+ * @param typing 1=the user started typing, 0=the user stopped typing
+ * @return 1=success, 0=error
+ */
+int             dc_send_typing               (dc_context_t* context, uint32_t chat_id, int typing);
+
+
 /**
  * Save a draft for a chat in the database.
  *
@@ -1305,6 +1229,21 @@ int             dc_get_fresh_msg_cnt         (dc_context_t* context, uint32_t ch
 dc_array_t*     dc_get_fresh_msgs            (dc_context_t* context);
 
 
+/**
+ * Returns the message IDs of all own messages of any chat that are not sent yet,
+ * these are messages in the states DC_STATE_OUT_PENDING and DC_STATE_OUT_FAILED.
+ * Typically used for implementing an outbox where the user can resend messages
+ * using dc_resend_msgs().
+ * The list is already sorted and starts with the most recent message.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as returned from dc_context_new().
+ * @return Array of message IDs, must be dc_array_unref()'d when no longer used.
+ *     On errors, the list is empty. NULL is never returned.
+ */
+dc_array_t*     dc_get_outbox_msgs           (dc_context_t* context);
+
+
 /**
  * Mark all messages in a chat as _noticed_.
  * _Noticed_ messages are no longer _fresh_ and do not count as being unseen
@@ -1446,6 +1385,13 @@ dc_array_t*     dc_get_chat_contacts         (dc_context_t* context, uint32_t ch
  * Searching can be done globally (chat_id=0) or in a specified chat only (chat_id
  * set).
  *
+ * All words of the query have to be found in the message text
+ * or in the filename of an attachment, the words may be incomplete at the end.
+ * Messages from senders whose name starts with the query are found as well.
+ * Chat search results are returned in chat order,
+ * global results are returned with the best matches first.
+ * Use dc_get_search_snippet() to get the matching part of a message.
+ *
  * Global chat results are typically displayed using dc_msg_get_summary(), chat
  * search results may just hilite the corresponding messages and present a
  * prev/next button.
@@ -1461,6 +1407,26 @@ dc_array_t*     dc_get_chat_contacts         (dc_context_t* context, uint32_t ch
 dc_array_t*     dc_search_msgs               (dc_context_t* context, uint32_t chat_id, const char* query);
 
 
+/**
+ * Get the part of a message matching a search query.
+ * The query is typically the one passed to dc_search_msgs() before,
+ * the message text and the filename of an attachment are searched.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as returned from dc_context_new().
+ * @param msg_id ID of the message, typically returned by dc_search_msgs().
+ * @param query The query to search for.
+ * @param start_mark String inserted before each matching word, eg. `<b>`.
+ *     NULL is the same as an empty string.
+ * @param end_mark String inserted after each matching word, eg. `</b>`.
+ *     NULL is the same as an empty string.
+ * @return The matching part of the text, omitted text is replaced by `...`.
+ *     NULL if the message does not match the query.
+ *     Must be released using dc_str_unref() after usage.
+ */
+char*           dc_get_search_snippet        (dc_context_t* context, uint32_t msg_id, const char* query, const char* start_mark, const char* end_mark);
+
+
 /**
  * Get chat object by a chat ID.
  *
@@ -1609,6 +1575,54 @@ int             dc_set_chat_profile_image    (dc_context_t* context, uint32_t ch
  */
 int             dc_set_chat_mute_duration             (dc_context_t* context, uint32_t chat_id, int64_t duration);
 
+
+/**
+ * Set the ephemeral timer of a chat.
+ *
+ * If the timer is enabled, messages in the chat are deleted locally and from the server
+ * the given number of seconds after they were seen (incoming messages) or sent (outgoing messages).
+ * The other chat members are informed about the change by a system message
+ * and apply the timer to their chat, too.
+ *
+ * Sends out #DC_EVENT_CHAT_MODIFIED.
+ *
+ * @memberof dc_context_t
+ * @param context The context as created by dc_context_new().
+ * @param chat_id The chat ID to set the ephemeral timer for.
+ * @param timer The timer in seconds, 0 disables the timer.
+ * @return 1=success, 0=error
+ */
+int             dc_set_chat_ephemeral_timer           (dc_context_t* context, uint32_t chat_id, uint32_t timer);
+
+
+/**
+ * Allow or forbid sending messages to a mailing list.
+ *
+ * Mailing lists are read-only by default.
+ * Sending can only be enabled for mailing lists
+ * that have an address to post to, see dc_chat_get_mailinglist_addr().
+ *
+ * Sends out #DC_EVENT_CHAT_MODIFIED.
+ *
+ * @memberof dc_context_t
+ * @param context The context as created by dc_context_new().
+ * @param chat_id The chat ID of the mailing list.
+ * @param enabled 1=allow sending, 0=make the mailing list read-only.
+ * @return 1=success, 0=error
+ */
+int             dc_set_mailinglist_sending            (dc_context_t* context, uint32_t chat_id, int enabled);
+
+
+/**
+ * Get the ephemeral timer of a chat.
+ *
+ * @memberof dc_context_t
+ * @param context The context as created by dc_context_new().
+ * @param chat_id The chat ID to get the ephemeral timer for.
+ * @return The timer in seconds, 0 if the timer is disabled.
+ */
+uint32_t        dc_get_chat_ephemeral_timer           (dc_context_t* context, uint32_t chat_id);
+
 // handle messages
 
 /**
@@ -1626,6 +1640,59 @@ int             dc_set_chat_mute_duration             (dc_context_t* context, ui
 char*           dc_get_msg_info              (dc_context_t* context, uint32_t msg_id);
 
 
+/**
+ * Get the read receipts of an own message.
+ *
+ * In groups, typically used to show which members have read the message and when,
+ * eg. "read by 3 of 5".
+ * For every receipt, use dc_array_get_id() to get the ID of the contact
+ * and dc_array_get_timestamp() to get the time the contact read the message.
+ *
+ * #DC_EVENT_MSG_READ is emitted for every new receipt.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as created by dc_context_new().
+ * @param msg_id The ID of the message.
+ * @return Array of read receipts, the earliest first,
+ *     must be dc_array_unref()'d when no longer used.
+ *     On errors, the list is empty. NULL is never returned.
+ */
+dc_array_t*     dc_get_msg_read_receipts     (dc_context_t* context, uint32_t msg_id);
+
+
+/**
+ * Send a reaction to a message.
+ *
+ * Reactions are typically a single emoji.
+ * They are not shown as messages, instead, they are collected for the message they refer to
+ * and can be read by dc_get_reactions_json().
+ * A new reaction replaces the previous reaction of the same contact.
+ *
+ * Sends out #DC_EVENT_REACTIONS_CHANGED.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as created by dc_context_new().
+ * @param msg_id The ID of the message to react to.
+ * @param reaction The reaction, eg. an emoji; pass an empty string or NULL to remove the reaction.
+ * @return The ID of the message that transports the reaction, 0 on errors.
+ */
+uint32_t        dc_send_reaction             (dc_context_t* context, uint32_t msg_id, const char* reaction);
+
+
+/**
+ * Get the reactions to a message, in json format.
+ *
+ * The returned json object maps contact IDs to the reaction of the contact,
+ * eg. `{"1":"👍","10":"😂"}`.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as created by dc_context_new().
+ * @param msg_id The ID of the message to get the reactions for.
+ * @return a utf8-encoded json string, must be freed using dc_str_unref(). NULL is never returned.
+ */
+char*           dc_get_reactions_json        (dc_context_t* context, uint32_t msg_id);
+
+
 /**
  * Get the raw mime-headers of the given message.
  * Raw headers are saved for incoming messages
@@ -1655,6 +1722,64 @@ char*           dc_get_mime_headers          (dc_context_t* context, uint32_t ms
  */
 void            dc_delete_msgs               (dc_context_t* context, const uint32_t* msg_ids, int msg_cnt);
 
+
+/**
+ * Delete own messages for everyone.
+ * The messages are deleted on the current device, on the IMAP server
+ * and the other members of the chats are asked to delete the messages as well.
+ *
+ * Other members may not use Delta Chat or may have copied the messages already,
+ * so there is no guarantee that the messages are gone everywhere.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as created by dc_context_new()
+ * @param msg_ids an array of uint32_t containing all message IDs that should be deleted,
+ *     all messages must be sent by ourself
+ * @param msg_cnt The number of messages IDs in the msg_ids array
+ * @return 1=success, 0=error
+ */
+int             dc_delete_msgs_for_everyone  (dc_context_t* context, const uint32_t* msg_ids, int msg_cnt);
+
+
+/**
+ * Send own messages again that could not be sent yet.
+ *
+ * Failed messages (DC_STATE_OUT_FAILED) are queued for sending again,
+ * pending messages (DC_STATE_OUT_PENDING) waiting for a retry are tried at once.
+ * The error returned by dc_msg_get_error() is removed from the messages.
+ *
+ * Sends out #DC_EVENT_MSGS_CHANGED for every message.
+ * To get all messages that could not be sent yet, use dc_get_outbox_msgs().
+ *
+ * @memberof dc_context_t
+ * @param context The context object as created by dc_context_new()
+ * @param msg_ids an array of uint32_t containing all message IDs that should be sent again,
+ *     all messages must be sent by ourself and must be pending or failed
+ * @param msg_cnt The number of messages IDs in the msg_ids array
+ * @return 1=success, 0=error
+ */
+int             dc_resend_msgs               (dc_context_t* context, const uint32_t* msg_ids, int msg_cnt);
+
+
+/**
+ * Edit the text of an own message.
+ * The text is replaced on the current device
+ * and the new text is sent to the other members of the chat.
+ *
+ * Sends out #DC_EVENT_MSGS_CHANGED for the edited message,
+ * dc_msg_is_edited() returns true for it from now on.
+ *
+ * Messages scheduled by dc_send_msg_at() are not sent yet,
+ * for them, only the text is replaced and dc_msg_is_edited() stays false.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as created by dc_context_new()
+ * @param msg_id The ID of the message to edit, must be sent by ourself
+ * @param text The new text of the message
+ * @return 1=success, 0=error
+ */
+int             dc_edit_msg                  (dc_context_t* context, uint32_t msg_id, const char* text);
+
 /**
  * Empty IMAP server folder: delete all messages.
  *
@@ -1825,6 +1950,42 @@ uint32_t        dc_create_contact            (dc_context_t* context, const char*
 int             dc_add_address_book          (dc_context_t* context, const char* addr_book);
 
 
+/**
+ * Export contacts as a vCard.
+ * The vCard contains the name, the email address,
+ * the profile image and the public key of each contact, if known.
+ * The vCard can be shared with other users, e.g. as a file attachment,
+ * and imported there using dc_import_vcard().
+ *
+ * @memberof dc_context_t
+ * @param context The context object as created by dc_context_new().
+ * @param contact_ids Array of contact IDs to export.
+ *     DC_CONTACT_ID_SELF exports the own name, profile image and key.
+ * @param contact_cnt Number of contact IDs in the array.
+ * @return The vCard as a string, NULL on errors.
+ *     Must be released using dc_str_unref() after usage.
+ */
+char*           dc_make_vcard                (dc_context_t* context, const uint32_t* contact_ids, int contact_cnt);
+
+
+/**
+ * Import contacts from a vCard.
+ * Contacts are added as with dc_add_address_book(),
+ * vCards without a valid email address are ignored.
+ * Profile images and keys contained in the vCard
+ * are used only if there is none known for the contact yet.
+ * If any contact is added or updated,
+ * the event #DC_EVENT_CONTACTS_CHANGED is sent.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as created by dc_context_new().
+ * @param vcard The vCard, may contain several contacts.
+ * @return Array of the IDs of the imported contacts.
+ *     Must be freed using dc_array_unref() when no longer used.
+ */
+dc_array_t*     dc_import_vcard              (dc_context_t* context, const char* vcard);
+
+
 /**
  * Returns known and unblocked contacts.
  *
@@ -1925,26 +2086,34 @@ dc_contact_t*   dc_get_contact               (dc_context_t* context, uint32_t co
 
 #define         DC_IMEX_EXPORT_SELF_KEYS      1 // param1 is a directory where the keys are written to
 #define         DC_IMEX_IMPORT_SELF_KEYS      2 // param1 is a directory where the keys are searched in and read from
-#define         DC_IMEX_EXPORT_BACKUP        11 // param1 is a directory where the backup is written to
-#define         DC_IMEX_IMPORT_BACKUP        12 // param1 is the file with the backup to import
+#define         DC_IMEX_EXPORT_BACKUP        11 // param1 is a directory where the backup is written to, param2 is an optional passphrase
+#define         DC_IMEX_IMPORT_BACKUP        12 // param1 is the file with the backup to import, param2 is the passphrase of encrypted backups
 
 
 /**
  * Import/export things.
  * For this purpose, the function creates a job that is executed in the IMAP-thread then;
- * this requires to call dc_perform_imap_jobs() regularly.
+ * this requires the IO to be started by dc_start_io().
  *
  * What to do is defined by the _what_ parameter which may be one of the following:
  *
  * - **DC_IMEX_EXPORT_BACKUP** (11) - Export a backup to the directory given as `param1`.
+ *   The backup is encrypted with the passphrase given as `param2`;
+ *   if it is NULL or empty, the backup is not encrypted.
+ *   Backups of databases opened by dc_open_encrypted() need a passphrase,
+ *   without one the export fails with DC_EVENT_IMEX_PROGRESS(0).
+ *   The passphrase is only kept in memory, it is not stored in the database.
  *   The backup contains all contacts, chats, images and other data and device independent settings.
  *   The backup does not contain device dependent settings as ringtones or LED notification settings.
  *   The name of the backup is typically `delta-chat.<day>.bak`, if more than one backup is create on a day,
  *   the format is `delta-chat.<day>-<number>.bak`
  *
- * - **DC_IMEX_IMPORT_BACKUP** (12) - `param1` is the file (not: directory) to import. The file is normally
+ * - **DC_IMEX_IMPORT_BACKUP** (12) - `param1` is the file (not: directory) to import,
+ *   `param2` is the passphrase the backup was exported with, NULL for unencrypted backups. The file is normally
  *   created by DC_IMEX_EXPORT_BACKUP and detected by dc_imex_has_backup(). Importing a backup
  *   is only possible as long as the context is not configured or used in another way.
+ *   The backup is verified before the database is replaced,
+ *   if it is damaged or the passphrase is wrong, the database is not changed.
  *
  * - **DC_IMEX_EXPORT_SELF_KEYS** (1) - Export all private keys and all public keys of the user to the
  *   directory given as `param1`.  The default key is written to the files `public-key-default.asc`
@@ -1980,6 +2149,7 @@ void            dc_imex                      (dc_context_t* context, int what, c
 /**
  * Check if there is a backup file.
  * May only be used on fresh installations (eg. dc_is_configured() returns 0).
+ * Damaged or incomplete backups are ignored, the passphrase is not needed for this check.
  *
  * Example:
  *
@@ -2100,6 +2270,27 @@ char*           dc_initiate_key_transfer     (dc_context_t* context);
 int             dc_continue_key_transfer     (dc_context_t* context, uint32_t msg_id, const char* setup_code);
 
 
+/**
+ * Receive a backup from another device in the local network and import it.
+ *
+ * The other device has to create a dc_backup_provider_t using dc_backup_provider_new()
+ * and show the QR code returned by dc_backup_provider_get_qr().
+ * This QR code is scanned on this device and dc_check_qr() returns DC_QR_BACKUP for it,
+ * the QR code is passed to this function then.
+ *
+ * Same as for importing a backup with dc_imex(), the context must not be configured.
+ * The function blocks until the backup is transferred and imported,
+ * DC_EVENT_IMEX_PROGRESS events are emitted meanwhile.
+ * To interrupt the transfer, you can use dc_stop_ongoing_process().
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param qr The QR code of the providing device.
+ * @return 1=backup received and imported, 0=error.
+ */
+int             dc_receive_backup            (dc_context_t* context, const char* qr);
+
+
 /**
  * Signal an ongoing process to stop.
  *
@@ -2128,6 +2319,7 @@ void            dc_stop_ongoing_process      (dc_context_t* context);
 #define         DC_QR_FPR_MISMATCH           220 // id=contact
 #define         DC_QR_FPR_WITHOUT_ADDR       230 // test1=formatted fingerprint
 #define         DC_QR_ACCOUNT                250 // text1=domain
+#define         DC_QR_BACKUP                 251 // text1=address
 #define         DC_QR_ADDR                   320 // id=contact
 #define         DC_QR_TEXT                   330 // text1=text
 #define         DC_QR_URL                    332 // text1=URL
@@ -2146,6 +2338,7 @@ void            dc_stop_ongoing_process      (dc_context_t* context);
  * - DC_QR_FPR_MISMATCH with dc_lot_t::id=Contact ID
  * - DC_QR_FPR_WITHOUT_ADDR with dc_lot_t::test1=Formatted fingerprint
  * - DC_QR_ACCOUNT allows creation of an account, dc_lot_t::text1=domain
+ * - DC_QR_BACKUP allows receiving a backup with dc_receive_backup(), dc_lot_t::text1=address of the other device
  * - DC_QR_ADDR with dc_lot_t::id=Contact ID
  * - DC_QR_TEXT with dc_lot_t::text1=Text
  * - DC_QR_URL with dc_lot_t::text1=URL
@@ -2190,21 +2383,20 @@ char*           dc_get_securejoin_qr         (dc_context_t* context, uint32_t ch
  * This function is typically called when dc_check_qr() returns
  * lot.state=DC_QR_ASK_VERIFYCONTACT or lot.state=DC_QR_ASK_VERIFYGROUP.
  *
- * Depending on the given QR code,
- * this function may takes some time and sends and receives several messages.
- * Therefore, you should call it always in a separate thread;
- * if you want to abort it, you should call dc_stop_ongoing_process().
+ * The function returns immediately and the handshake runs in background,
+ * the progress is reported by #DC_EVENT_SECUREJOIN_JOINER_PROGRESS.
+ * Several handshakes, also with different contacts, may run at the same time.
  *
  * - If the given QR code starts the Setup-Contact protocol,
- *   the function typically returns immediately
- *   and the handshake runs in background.
- *   Subsequent calls of dc_join_securejoin() will abort unfinished tasks.
- *   The returned chat is the one-to-one opportunistic chat.
+ *   the returned chat is the one-to-one opportunistic chat.
  *   When the protocol has finished, an info-message is added to that chat.
  * - If the given QR code starts the Verified-Group-Invite protocol,
- *   the function waits until the protocol has finished.
- *   This is because the verified group is not opportunistic
- *   and can be created only when the contacts have verified each other.
+ *   the returned chat is the group to join.
+ *   If the group is not yet known, it is created with the inviter as the only other member;
+ *   the remaining members are added when the inviter adds us to the group.
+ *   If the group is verified, it becomes a verified group when the protocol has finished.
+ *   A group created this way is deleted again if the protocol fails
+ *   or does not finish within a week.
  *
  * See https://countermitm.readthedocs.io/en/latest/new.html
  * for details about both protocols.
@@ -2214,7 +2406,7 @@ char*           dc_get_securejoin_qr         (dc_context_t* context, uint32_t ch
  * @param qr The text of the scanned QR code. Typically, the same string as given
  *     to dc_check_qr().
  * @return Chat-id of the joined chat, the UI may redirect to the this chat.
- *     If the out-of-band verification failed or was aborted, 0 is returned.
+ *     If the QR code cannot be used, 0 is returned.
  *     A returned chat-id does not guarantee that the chat or the belonging contact is verified.
  *     If needed, this be checked with dc_chat_is_verified() and dc_contact_is_verified(),
  *     however, in practise, the UI will just listen to #DC_EVENT_CONTACTS_CHANGED unconditionally.
@@ -2288,99 +2480,383 @@ int         dc_is_sending_locations_to_chat (dc_context_t* context, uint32_t cha
  *     0: location streaming is no longer needed,
  *     dc_is_sending_locations_to_chat() is false for all chats.
  */
-int         dc_set_location                 (dc_context_t* context, double latitude, double longitude, double accuracy);
+int         dc_set_location                 (dc_context_t* context, double latitude, double longitude, double accuracy);
+
+
+/**
+ * Get shared locations from the database.
+ * The locations can be filtered by the chat-id, the contact-id
+ * and by a timespan.
+ *
+ * The number of returned locations can be retrieved using dc_array_get_cnt().
+ * To get information for each location,
+ * use dc_array_get_latitude(), dc_array_get_longitude(),
+ * dc_array_get_accuracy(), dc_array_get_timestamp(), dc_array_get_contact_id()
+ * and dc_array_get_msg_id().
+ * The latter returns 0 if there is no message bound to the location.
+ *
+ * Note that only if dc_array_is_independent() returns 0,
+ * the location is the current or a past position of the user.
+ * If dc_array_is_independent() returns 1,
+ * the location is any location on earth that is marked by the user.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param chat_id Chat-id to get location information for.
+ *     0 to get locations independently of the chat.
+ * @param contact_id Contact-id to get location information for.
+ *     If also a chat-id is given, this should be a member of the given chat.
+ *     0 to get locations independently of the contact.
+ * @param timestamp_begin Start of timespan to return.
+ *     Must be given in number of seconds since 00:00 hours, Jan 1, 1970 UTC.
+ *     0 for "start from the beginning".
+ * @param timestamp_end End of timespan to return.
+ *     Must be given in number of seconds since 00:00 hours, Jan 1, 1970 UTC.
+ *     0 for "all up to now".
+ * @return Array of locations, NULL is never returned.
+ *     The array is sorted decending;
+ *     the first entry in the array is the location with the newest timestamp.
+ *     Note that this is only realated to the recent postion of the user
+ *     if dc_array_is_independent() returns 0.
+ *     The returned array must be freed using dc_array_unref().
+ *
+ * Examples:
+ * ~~~
+ * // get locations from the last hour for a global map
+ * dc_array_t* loc = dc_get_locations(context, 0, 0, time(NULL)-60*60, 0);
+ * for (int i=0; i<dc_array_get_cnt(); i++) {
+ *     double lat = dc_array_get_latitude(loc, i);
+ *     ...
+ * }
+ * dc_array_unref(loc);
+ *
+ * // get locations from a contact for a global map
+ * dc_array_t* loc = dc_get_locations(context, 0, contact_id, 0, 0);
+ * ...
+ *
+ * // get all locations known for a given chat
+ * dc_array_t* loc = dc_get_locations(context, chat_id, 0, 0, 0);
+ * ...
+ *
+ * // get locations from a single contact for a given chat
+ * dc_array_t* loc = dc_get_locations(context, chat_id, contact_id, 0, 0);
+ * ...
+ * ~~~
+ */
+dc_array_t* dc_get_locations                (dc_context_t* context, uint32_t chat_id, uint32_t contact_id, int64_t timestamp_begin, int64_t timestamp_end);
+
+
+/**
+ * Delete all locations on the current device.
+ * Locations already sent cannot be deleted.
+ *
+ * Typically results in the event #DC_EVENT_LOCATION_CHANGED
+ * with contact_id set to 0.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @return None.
+ */
+void        dc_delete_all_locations         (dc_context_t* context);
+
+
+/**
+ * Release a string returned by another deltachat-core function.
+ * - Strings returned by any deltachat-core-function
+ *   MUST NOT be released by the standard free() function;
+ *   always use dc_str_unref() for this purpose.
+ * - dc_str_unref() MUST NOT be called for strings not returned by deltachat-core.
+ * - dc_str_unref() MUST NOT be called for other objectes returned by deltachat-core.
+ *
+ * @memberof dc_context_t
+ * @param str The string to release.
+ * @return None.
+ */
+void dc_str_unref (char* str);
+
+
+/**
+ * @class dc_accounts_t
+ *
+ * An object containing a set of accounts, each represented by a dc_context_t.
+ * The accounts are stored in one directory together with an index
+ * of all accounts and the selected account.
+ * Events of all accounts are passed to a single callback
+ * together with the ID of the account the event belongs to.
+ */
+
+
+/**
+ * Callback function that should be given to dc_accounts_new().
+ *
+ * @memberof dc_accounts_t
+ * @param accounts The accounts object as returned by dc_accounts_new().
+ * @param account_id The ID of the account the event belongs to.
+ * @param event one of the @ref DC_EVENT constants
+ * @param data1 depends on the event parameter
+ * @param data2 depends on the event parameter
+ * @return return 0 unless stated otherwise in the event parameter documentation
+ */
+typedef uintptr_t (*dc_accounts_callback_t) (dc_accounts_t* accounts, uint32_t account_id, int event, uintptr_t data1, uintptr_t data2);
+
+
+/**
+ * Open or create the accounts in a directory.
+ * All existing accounts are opened.
+ *
+ * @memberof dc_accounts_t
+ * @param cb a callback function that is called for the events of all accounts,
+ *     the same rules as for the callback given to dc_context_new() apply.
+ * @param userdata can be used by the client for any purpuse.  He finds it
+ *     later in dc_accounts_get_userdata().
+ * @param os_name is only for decorative use, see dc_context_new().
+ * @param dir The directory to store the accounts in, created if it does not exist.
+ * @return An accounts object, must be freed using dc_accounts_unref().
+ *     NULL on errors, eg. if the directory cannot be created.
+ */
+dc_accounts_t*  dc_accounts_new              (dc_accounts_callback_t cb, void* userdata, const char* os_name, const char* dir);
+
+
+/**
+ * Free an accounts object.
+ * The IO of all accounts is stopped and all accounts are closed.
+ *
+ * @memberof dc_accounts_t
+ * @param accounts The accounts object as created by dc_accounts_new().
+ * @return None.
+ */
+void            dc_accounts_unref            (dc_accounts_t* accounts);
+
+
+/**
+ * Get user data associated with an accounts object.
+ *
+ * @memberof dc_accounts_t
+ * @param accounts The accounts object as created by dc_accounts_new().
+ * @return User data, this is the second parameter given to dc_accounts_new().
+ */
+void*           dc_accounts_get_userdata     (dc_accounts_t* accounts);
+
+
+/**
+ * Add a new, unconfigured account and select it.
+ * Use dc_accounts_get_account() to configure the new account.
+ *
+ * @memberof dc_accounts_t
+ * @param accounts The accounts object as created by dc_accounts_new().
+ * @return The ID of the new account, 0 on errors.
+ */
+uint32_t        dc_accounts_add_account      (dc_accounts_t* accounts);
+
+
+/**
+ * Move an existing database into the accounts directory and add it as a new account.
+ * The blobdir is expected beside the database as created by dc_open()
+ * and is moved as well.
+ * The new account is selected.
+ *
+ * @memberof dc_accounts_t
+ * @param accounts The accounts object as created by dc_accounts_new().
+ * @param dbfile The database file of the account to migrate, must not be opened by any dc_context_t.
+ * @return The ID of the new account, 0 on errors.
+ */
+uint32_t        dc_accounts_migrate_account  (dc_accounts_t* accounts, const char* dbfile);
+
+
+/**
+ * Remove an account and delete all its data.
+ * If the account was selected, another account is selected.
+ *
+ * @memberof dc_accounts_t
+ * @param accounts The accounts object as created by dc_accounts_new().
+ * @param account_id The ID of the account to remove.
+ * @return 1=success, 0=error
+ */
+int             dc_accounts_remove_account   (dc_accounts_t* accounts, uint32_t account_id);
+
+
+/**
+ * Select an account.
+ * The selected account is remembered and returned by dc_accounts_get_selected_account()
+ * also after the next dc_accounts_new().
+ *
+ * @memberof dc_accounts_t
+ * @param accounts The accounts object as created by dc_accounts_new().
+ * @param account_id The ID of the account to select.
+ * @return 1=success, 0=error
+ */
+int             dc_accounts_select_account   (dc_accounts_t* accounts, uint32_t account_id);
+
+
+/**
+ * Get the IDs of all accounts.
+ *
+ * @memberof dc_accounts_t
+ * @param accounts The accounts object as created by dc_accounts_new().
+ * @return An array containing all account IDs, must be dc_array_unref()'d after usage.
+ */
+dc_array_t*     dc_accounts_get_all          (dc_accounts_t* accounts);
+
+
+/**
+ * Get an account.
+ * The returned context is already open, its events are passed
+ * to the callback given to dc_accounts_new().
+ *
+ * @memberof dc_accounts_t
+ * @param accounts The accounts object as created by dc_accounts_new().
+ * @param account_id The ID of the account.
+ * @return The context of the account, must be freed using dc_context_unref() after usage.
+ *     NULL if there is no account with the given ID.
+ */
+dc_context_t*   dc_accounts_get_account      (dc_accounts_t* accounts, uint32_t account_id);
+
+
+/**
+ * Get the selected account, see dc_accounts_get_account().
+ *
+ * @memberof dc_accounts_t
+ * @param accounts The accounts object as created by dc_accounts_new().
+ * @return The context of the selected account, must be freed using dc_context_unref() after usage.
+ *     NULL if there are no accounts.
+ */
+dc_context_t*   dc_accounts_get_selected_account (dc_accounts_t* accounts);
+
+
+/**
+ * Get the ID of the selected account.
+ *
+ * @memberof dc_accounts_t
+ * @param accounts The accounts object as created by dc_accounts_new().
+ * @return The ID of the selected account, 0 if there are no accounts.
+ */
+uint32_t        dc_accounts_get_selected_account_id (dc_accounts_t* accounts);
+
+
+/**
+ * Start the IO of all accounts, see dc_start_io().
+ * Accounts added later are started as well.
+ *
+ * @memberof dc_accounts_t
+ * @param accounts The accounts object as created by dc_accounts_new().
+ * @return None.
+ */
+void            dc_accounts_start_io         (dc_accounts_t* accounts);
+
+
+/**
+ * Stop the IO of all accounts
+ * and wait until it is stopped, see dc_stop_io().
+ *
+ * @memberof dc_accounts_t
+ * @param accounts The accounts object as created by dc_accounts_new().
+ * @return None.
+ */
+void            dc_accounts_stop_io          (dc_accounts_t* accounts);
+
+
+/**
+ * @class dc_event_emitter_t
+ *
+ * Opaque object that receives the events of a context,
+ * see dc_get_event_emitter().
+ */
 
 
 /**
- * Get shared locations from the database.
- * The locations can be filtered by the chat-id, the contact-id
- * and by a timespan.
- *
- * The number of returned locations can be retrieved using dc_array_get_cnt().
- * To get information for each location,
- * use dc_array_get_latitude(), dc_array_get_longitude(),
- * dc_array_get_accuracy(), dc_array_get_timestamp(), dc_array_get_contact_id()
- * and dc_array_get_msg_id().
- * The latter returns 0 if there is no message bound to the location.
+ * Free an event emitter.
  *
- * Note that only if dc_array_is_independent() returns 0,
- * the location is the current or a past position of the user.
- * If dc_array_is_independent() returns 1,
- * the location is any location on earth that is marked by the user.
+ * @memberof dc_event_emitter_t
+ * @param emitter The event emitter object as returned by dc_get_event_emitter().
+ * @return None.
+ */
+void            dc_event_emitter_unref       (dc_event_emitter_t* emitter);
+
+
+/**
+ * Wait for the next event.
+ * The function blocks until an event is available.
  *
- * @memberof dc_context_t
- * @param context The context object.
- * @param chat_id Chat-id to get location information for.
- *     0 to get locations independently of the chat.
- * @param contact_id Contact-id to get location information for.
- *     If also a chat-id is given, this should be a member of the given chat.
- *     0 to get locations independently of the contact.
- * @param timestamp_begin Start of timespan to return.
- *     Must be given in number of seconds since 00:00 hours, Jan 1, 1970 UTC.
- *     0 for "start from the beginning".
- * @param timestamp_end End of timespan to return.
- *     Must be given in number of seconds since 00:00 hours, Jan 1, 1970 UTC.
- *     0 for "all up to now".
- * @return Array of locations, NULL is never returned.
- *     The array is sorted decending;
- *     the first entry in the array is the location with the newest timestamp.
- *     Note that this is only realated to the recent postion of the user
- *     if dc_array_is_independent() returns 0.
- *     The returned array must be freed using dc_array_unref().
+ * @memberof dc_event_emitter_t
+ * @param emitter The event emitter object as returned by dc_get_event_emitter().
+ * @return The next event, must be freed using dc_event_unref() after usage.
+ *     NULL if the context is closed and all events are received.
+ */
+dc_event_t*     dc_get_next_event            (dc_event_emitter_t* emitter);
+
+
+/**
+ * @class dc_event_t
  *
- * Examples:
- * ~~~
- * // get locations from the last hour for a global map
- * dc_array_t* loc = dc_get_locations(context, 0, 0, time(NULL)-60*60, 0);
- * for (int i=0; i<dc_array_get_cnt(); i++) {
- *     double lat = dc_array_get_latitude(loc, i);
- *     ...
- * }
- * dc_array_unref(loc);
+ * Opaque object describing a single event,
+ * the values are the same as passed to the callback given to dc_context_new().
+ */
+
+
+/**
+ * Free an event.
  *
- * // get locations from a contact for a global map
- * dc_array_t* loc = dc_get_locations(context, 0, contact_id, 0, 0);
- * ...
+ * @memberof dc_event_t
+ * @param event The event object as returned by dc_get_next_event().
+ * @return None.
+ */
+void            dc_event_unref               (dc_event_t* event);
+
+
+/**
+ * Get the ID of an event.
  *
- * // get all locations known for a given chat
- * dc_array_t* loc = dc_get_locations(context, chat_id, 0, 0, 0);
- * ...
+ * @memberof dc_event_t
+ * @param event The event object as returned by dc_get_next_event().
+ * @return one of the @ref DC_EVENT constants, 0 on errors.
+ */
+int             dc_event_get_id              (dc_event_t* event);
+
+
+/**
+ * Get the first data value of an event as an integer.
  *
- * // get locations from a single contact for a given chat
- * dc_array_t* loc = dc_get_locations(context, chat_id, contact_id, 0, 0);
- * ...
- * ~~~
+ * @memberof dc_event_t
+ * @param event The event object as returned by dc_get_next_event().
+ * @return The value, see @ref DC_EVENT for the meaning,
+ *     0 if the event has no integer as first value.
  */
-dc_array_t* dc_get_locations                (dc_context_t* context, uint32_t chat_id, uint32_t contact_id, int64_t timestamp_begin, int64_t timestamp_end);
+int             dc_event_get_data1_int       (dc_event_t* event);
 
 
 /**
- * Delete all locations on the current device.
- * Locations already sent cannot be deleted.
+ * Get the second data value of an event as an integer.
  *
- * Typically results in the event #DC_EVENT_LOCATION_CHANGED
- * with contact_id set to 0.
+ * @memberof dc_event_t
+ * @param event The event object as returned by dc_get_next_event().
+ * @return The value, see @ref DC_EVENT for the meaning,
+ *     0 if the event has no integer as second value.
+ */
+int             dc_event_get_data2_int       (dc_event_t* event);
+
+
+/**
+ * Get the first data value of an event as a string,
+ * used eg. by #DC_EVENT_IMEX_FILE_WRITTEN.
  *
- * @memberof dc_context_t
- * @param context The context object.
- * @return None.
+ * @memberof dc_event_t
+ * @param event The event object as returned by dc_get_next_event().
+ * @return The string, must be released using dc_str_unref() after usage.
+ *     NULL if the event has no string as first value.
  */
-void        dc_delete_all_locations         (dc_context_t* context);
+char*           dc_event_get_data1_str       (dc_event_t* event);
 
 
 /**
- * Release a string returned by another deltachat-core function.
- * - Strings returned by any deltachat-core-function
- *   MUST NOT be released by the standard free() function;
- *   always use dc_str_unref() for this purpose.
- * - dc_str_unref() MUST NOT be called for strings not returned by deltachat-core.
- * - dc_str_unref() MUST NOT be called for other objectes returned by deltachat-core.
+ * Get the second data value of an event as a string,
+ * used eg. by #DC_EVENT_INFO.
  *
- * @memberof dc_context_t
- * @param str The string to release.
- * @return None.
+ * @memberof dc_event_t
+ * @param event The event object as returned by dc_get_next_event().
+ * @return The string, must be released using dc_str_unref() after usage.
+ *     NULL if the event has no string as second value.
  */
-void dc_str_unref (char* str);
+char*           dc_event_get_data2_str       (dc_event_t* event);
 
 
 /**
@@ -2734,6 +3210,7 @@ char*            dc_chat_get_info_json       (dc_context_t* context, size_t chat
 #define         DC_CHAT_TYPE_SINGLE          100
 #define         DC_CHAT_TYPE_GROUP           120
 #define         DC_CHAT_TYPE_VERIFIED_GROUP  130
+#define         DC_CHAT_TYPE_MAILINGLIST     140
 
 
 /**
@@ -2779,6 +3256,10 @@ uint32_t        dc_chat_get_id               (const dc_chat_t* chat);
  * - DC_CHAT_TYPE_VERIFIED_GROUP  (130) - a verified group chat. In verified groups,
  *   all members are verified and encryption is always active and cannot be disabled.
  *
+ * - DC_CHAT_TYPE_MAILINGLIST  (140) - a mailing list, created per `List-Id` of received messages.
+ *   New mailing lists are shown in the deaddrop until they are accepted.
+ *   Mailing lists are read-only unless sending is enabled using dc_set_mailinglist_sending().
+ *
  * @memberof dc_chat_t
  * @param chat The chat object.
  * @return Chat type.
@@ -2908,7 +3389,8 @@ int             dc_chat_is_device_talk       (const dc_chat_t* chat);
 
 /**
  * Check if messages can be sent to a give chat.
- * This is not true eg. for the deaddrop or for the device-talk, cmp. dc_chat_is_device_talk().
+ * This is not true eg. for the deaddrop, for the device-talk, cmp. dc_chat_is_device_talk(),
+ * or for mailing lists sending is not enabled for, cmp. dc_set_mailinglist_sending().
  *
  * Calling dc_send_msg() for these chats will fail
  * and the ui may decide to hide input controls therefore.
@@ -2920,6 +3402,31 @@ int             dc_chat_is_device_talk       (const dc_chat_t* chat);
 int             dc_chat_can_send              (const dc_chat_t* chat);
 
 
+/**
+ * Get the address messages to a mailing list are sent to.
+ * The address is taken from the `List-Post` header of the message that created the chat.
+ *
+ * @memberof dc_chat_t
+ * @param chat The chat object.
+ * @return The address as a string, must be released using dc_str_unref().
+ *     NULL if the chat is no mailing list or the mailing list does not allow posting.
+ */
+char*           dc_chat_get_mailinglist_addr (const dc_chat_t* chat);
+
+
+/**
+ * Get the URI to unsubscribe from a mailing list.
+ * The URI is taken from the `List-Unsubscribe` header of the message that created the chat,
+ * typically, it is a `mailto:` or `https:` URI the UI can open.
+ *
+ * @memberof dc_chat_t
+ * @param chat The chat object.
+ * @return The URI as a string, must be released using dc_str_unref().
+ *     NULL if the chat is no mailing list or the list offers no way to unsubscribe.
+ */
+char*           dc_chat_get_mailinglist_unsubscribe (const dc_chat_t* chat);
+
+
 /**
  * Check if a chat is verified.  Verified chats contain only verified members
  * and encryption is alwasy enabled.  Verified chats are created using
@@ -2986,6 +3493,7 @@ int64_t          dc_chat_get_remaining_mute_duration (const dc_chat_t* chat);
 #define         DC_STATE_OUT_PREPARING       18
 #define         DC_STATE_OUT_DRAFT           19
 #define         DC_STATE_OUT_PENDING         20
+#define         DC_STATE_OUT_SCHEDULED       22
 #define         DC_STATE_OUT_FAILED          24
 #define         DC_STATE_OUT_DELIVERED       26 // to check if a mail was sent, use dc_msg_is_sent()
 #define         DC_STATE_OUT_MDN_RCVD        28
@@ -3087,6 +3595,8 @@ int             dc_msg_get_viewtype           (const dc_msg_t* msg);
  * - DC_STATE_OUT_DRAFT (19) - Message saved as draft using dc_set_draft()
  * - DC_STATE_OUT_PENDING (20) - The user has pressed the "send" button but the
  *   message is not yet sent and is pending in some way. Maybe we're offline (no checkmark).
+ * - DC_STATE_OUT_SCHEDULED (22) - Message scheduled by dc_send_msg_at() to be sent later,
+ *   the state changes to DC_STATE_OUT_PENDING when the message is due.
  * - DC_STATE_OUT_FAILED (24) - _Unrecoverable_ error (_recoverable_ errors result in pending messages), you'll receive the event #DC_EVENT_MSG_FAILED.
  * - DC_STATE_OUT_DELIVERED (26) - Outgoing message successfully delivered to server (one checkmark). Note, that already delivered messages may get into the state DC_STATE_OUT_FAILED if we get such a hint from the server.
  *   If a sent message changes to this state, you'll receive the event #DC_EVENT_MSG_DELIVERED.
@@ -3232,6 +3742,50 @@ char*           dc_msg_get_filemime           (const dc_msg_t* msg);
 uint64_t        dc_msg_get_filebytes          (const dc_msg_t* msg);
 
 
+/**
+ * Get the error of the last failed try to send the message.
+ *
+ * The error is set for failed messages (DC_STATE_OUT_FAILED)
+ * and for pending messages (DC_STATE_OUT_PENDING) that are tried again later.
+ *
+ * The error also describes bounces reported by the mail server
+ * after the message was sent:
+ * permanent failures mark the message as failed,
+ * temporary delivery problems only set the error.
+ *
+ * @memberof dc_msg_t
+ * @param msg The message object.
+ * @return The error message, NULL if there is no error.
+ *     The returned string must be released using dc_str_unref() after usage.
+ */
+char*           dc_msg_get_error              (const dc_msg_t* msg);
+
+
+/**
+ * Get the number of failed tries to send a pending message.
+ *
+ * @memberof dc_msg_t
+ * @param msg The message object.
+ * @return Number of failed tries,
+ *     0 if the message is not waiting for a retry.
+ */
+int             dc_msg_get_send_tries         (const dc_msg_t* msg);
+
+
+/**
+ * Get the time of the next try to send a pending message.
+ * The time is returned as a unix timestamp in seconds.
+ *
+ * To try sending at once, use dc_resend_msgs().
+ *
+ * @memberof dc_msg_t
+ * @param msg The message object.
+ * @return The time of the next try,
+ *     0 if the message is not waiting for a retry.
+ */
+int64_t         dc_msg_get_next_send_timestamp (const dc_msg_t* msg);
+
+
 /**
  * Get width of image or video.  The width is returned in pixels.
  * If the width is unknown or if the associated file is no image or video file,
@@ -3280,6 +3834,52 @@ int             dc_msg_get_height             (const dc_msg_t* msg);
 int             dc_msg_get_duration           (const dc_msg_t* msg);
 
 
+/**
+ * Get the ephemeral timer the message was sent with.
+ *
+ * @memberof dc_msg_t
+ * @param msg The message object.
+ * @return The timer in seconds, 0 if the message is not ephemeral.
+ */
+uint32_t        dc_msg_get_ephemeral_timer    (const dc_msg_t* msg);
+
+
+/**
+ * Get the time the message will be deleted at.
+ *
+ * @memberof dc_msg_t
+ * @param msg The message object.
+ * @return Unix timestamp of the deletion, 0 if the ephemeral timer is not started yet.
+ */
+int64_t         dc_msg_get_ephemeral_timestamp (const dc_msg_t* msg);
+
+
+/**
+ * Get the excerpt of the quoted message.
+ * For outgoing messages, this is set by dc_msg_set_quote(),
+ * for incoming messages, this is the quote found at the top or the bottom of the text.
+ *
+ * @memberof dc_msg_t
+ * @param msg The message object.
+ * @return The quoted text or NULL if there is no quote.
+ *     Returned strings must be released using dc_str_unref().
+ */
+char*           dc_msg_get_quoted_text        (const dc_msg_t* msg);
+
+
+/**
+ * Get the quoted message, if it is available locally.
+ * Even if a quoted text is available, the quoted message may be unknown,
+ * eg. because it was deleted or never received.
+ *
+ * @memberof dc_msg_t
+ * @param msg The message object.
+ * @return The quoted message or NULL.
+ *     Must be freed using dc_msg_unref() after usage.
+ */
+dc_msg_t*       dc_msg_get_quoted_msg         (const dc_msg_t* msg);
+
+
 /**
  * Check if a padlock should be shown beside the message.
  *
@@ -3405,6 +4005,19 @@ int             dc_msg_is_starred             (const dc_msg_t* msg);
 int             dc_msg_is_forwarded           (const dc_msg_t* msg);
 
 
+/**
+ * Check if the text of the message was edited by the sender,
+ * see dc_edit_msg().
+ *
+ * Typically, the UI shows a little "edited" hint beside the message.
+ *
+ * @memberof dc_msg_t
+ * @param msg The message object.
+ * @return 1=message text was edited, 0=message text is unchanged.
+ */
+int             dc_msg_is_edited              (const dc_msg_t* msg);
+
+
 /**
  * Check if the message is an informational message, created by the
  * device or by another users. Such messages are not "typed" by the user but
@@ -3528,6 +4141,20 @@ void            dc_msg_set_dimension          (dc_msg_t* msg, int width, int hei
 void            dc_msg_set_duration           (dc_msg_t* msg, int duration);
 
 
+/**
+ * Set the message replying to.
+ * The quoted message is referenced by the In-Reply-To header
+ * and an excerpt of its text is sent along, see dc_msg_get_quoted_text().
+ * This does not alter any information in the database; this may be done by dc_send_msg() later.
+ *
+ * @memberof dc_msg_t
+ * @param msg The message object.
+ * @param quote The message to quote, must be sent or received already.
+ * @return None.
+ */
+void            dc_msg_set_quote              (dc_msg_t* msg, const dc_msg_t* quote);
+
+
 /**
  * Set any location that should be bound to the message object.
  * The function is useful to add a marker to the map
@@ -3706,6 +4333,21 @@ char*           dc_contact_get_first_name    (const dc_contact_t* contact);
 char*           dc_contact_get_profile_image (const dc_contact_t* contact);
 
 
+/**
+ * Get the contact's status.
+ * The status is the signature set by the remote user
+ * using dc_set_config(context, "selfstatus", status)
+ * and is taken from the footer of the last chat message received from the contact.
+ *
+ * @memberof dc_contact_t
+ * @param contact The contact object.
+ * @return The status, an empty string if unset or on errors.
+ *     Never returns NULL.
+ *     Must be released using dc_str_unref() after usage.
+ */
+char*           dc_contact_get_status        (const dc_contact_t* contact);
+
+
 /**
  * Get a color for the contact.
  * The color is calculated from the contact's email address
@@ -3821,6 +4463,81 @@ int             dc_provider_get_status                (const dc_provider_t* prov
 void            dc_provider_unref                     (dc_provider_t* provider);
 
 
+/**
+ * @class dc_backup_provider_t
+ *
+ * Opaque object serving a backup of the account to another device in the local network,
+ * see dc_receive_backup().
+ */
+
+
+/**
+ * Create a backup provider.
+ *
+ * A backup of the account is written and served in the local network
+ * until it is received once by another device or until the object is released.
+ * Writing the backup may take a while, DC_EVENT_IMEX_PROGRESS events are emitted meanwhile,
+ * so it is recommended to call this function in a separate thread.
+ *
+ * @memberof dc_backup_provider_t
+ * @param context The context object.
+ * @return The backup provider, must be released using dc_backup_provider_unref() after usage.
+ *     On errors, NULL is returned.
+ */
+dc_backup_provider_t* dc_backup_provider_new          (dc_context_t* context);
+
+
+/**
+ * Get the QR code to show to the other device.
+ *
+ * The QR code contains the address of this device and a one-time secret
+ * the keys for authentication and for the encryption of the backup are derived from.
+ * The secret itself is never sent over the network.
+ * On the other device, the QR code is passed to dc_receive_backup().
+ *
+ * @memberof dc_backup_provider_t
+ * @param provider The backup provider object as created by dc_backup_provider_new().
+ * @return The QR code string, must be released using dc_str_unref() after usage.
+ */
+char*           dc_backup_provider_get_qr             (const dc_backup_provider_t* provider);
+
+
+/**
+ * Wait until the backup was received by the other device.
+ *
+ * The function blocks until the transfer is done
+ * or until dc_backup_provider_cancel() is called from another thread.
+ *
+ * @memberof dc_backup_provider_t
+ * @param provider The backup provider object as created by dc_backup_provider_new().
+ * @return 1=backup was received, 0=error or cancelled.
+ */
+int             dc_backup_provider_wait               (dc_backup_provider_t* provider);
+
+
+/**
+ * Stop serving the backup.
+ *
+ * A running dc_backup_provider_wait() returns 0 then,
+ * the object must still be released using dc_backup_provider_unref().
+ * Use this function if the user abandons the transfer.
+ *
+ * @memberof dc_backup_provider_t
+ * @param provider The backup provider object as created by dc_backup_provider_new().
+ */
+void            dc_backup_provider_cancel             (dc_backup_provider_t* provider);
+
+
+/**
+ * Stop serving the backup and free the backup provider object.
+ * The written backup file is removed.
+ *
+ * @memberof dc_backup_provider_t
+ * @param provider The backup provider object as created by dc_backup_provider_new().
+ */
+void            dc_backup_provider_unref              (dc_backup_provider_t* provider);
+
+
 /**
  * @class dc_lot_t
  *
@@ -4346,6 +5063,17 @@ int64_t          dc_lot_get_timestamp     (const dc_lot_t* lot);
 #define DC_EVENT_MSGS_CHANGED             2000
 
 
+/**
+ * Reactions to a message changed.
+ * Use dc_get_reactions() to get the current reactions.
+ *
+ * @param data1 (int) chat_id
+ * @param data2 (int) msg_id
+ * @return 0
+ */
+#define DC_EVENT_REACTIONS_CHANGED        2001
+
+
 /**
  * There is a fresh message. Typically, the user will show an notification
  * when receiving this message.
@@ -4382,7 +5110,10 @@ int64_t          dc_lot_get_timestamp     (const dc_lot_t* lot);
 
 
 /**
- * A single message is read by the receiver. State changed from DC_STATE_OUT_DELIVERED to
+ * A single message is read by a receiver.
+ * The event is emitted for every contact that sent a read receipt,
+ * see dc_get_msg_read_receipts().
+ * When the message is read by enough receivers, the state changed from DC_STATE_OUT_DELIVERED to
  * DC_STATE_OUT_MDN_RCVD, see dc_msg_get_state().
  *
  * @param data1 (int) chat_id
@@ -4485,13 +5216,14 @@ int64_t          dc_lot_get_timestamp     (const dc_lot_t* lot);
  * Progress information of a secure-join handshake from the view of the joiner
  * (Bob, the person who scans the QR code).
  *
- * The events are typically sent while dc_join_securejoin(), which
- * may take some time, is executed.
+ * The events are sent for handshakes started by dc_join_securejoin().
  *
  * @param data1 (int) ID of the inviting contact.
  * @param data2 (int) Progress as:
+ *     0=Error, the handshake was aborted.
  *     400=vg-/vc-request-with-auth sent, typically shown as "alice@addr verified, introducing myself."
  *     (Bob has verified alice and waits until Alice does the same for him)
+ *     1000=Protocol finished, the contact is verified and, for the verified-group-protocol, the group joined.
  * @return 0
  */
 #define DC_EVENT_SECUREJOIN_JOINER_PROGRESS       2061
@@ -4507,6 +5239,30 @@ int64_t          dc_lot_get_timestamp     (const dc_lot_t* lot);
 #define DC_EVENT_SECUREJOIN_MEMBER_ADDED 2062
 
 
+/**
+ * A contact started typing in a chat, see dc_send_typing().
+ *
+ * While typing, the contact repeats the notification every few seconds,
+ * the typing indicator should be hidden if no new event arrives within 15 seconds
+ * or if #DC_EVENT_CONTACT_STOPPED_TYPING is received.
+ *
+ * @param data1 (int) chat_id
+ * @param data2 (int) contact_id
+ * @return 0
+ */
+#define DC_EVENT_CONTACT_TYPING                   2070
+
+
+/**
+ * A contact stopped typing in a chat, eg. because the message was sent.
+ *
+ * @param data1 (int) chat_id
+ * @param data2 (int) contact_id
+ * @return 0
+ */
+#define DC_EVENT_CONTACT_STOPPED_TYPING           2071
+
+
 /**
  * @}
  */
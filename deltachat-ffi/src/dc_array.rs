@@ -5,6 +5,8 @@ use crate::location::Location;
 pub enum dc_array_t {
     Locations(Vec<Location>),
     Uint(Vec<u32>),
+    /// Contact IDs with timestamps, eg. of read receipts.
+    Timestamps(Vec<(u32, i64)>),
 }
 
 impl dc_array_t {
@@ -37,6 +39,15 @@ impl dc_array_t {
         match self {
             Self::Locations(array) => array[index].location_id,
             Self::Uint(array) => array[index] as u32,
+            Self::Timestamps(array) => array[index].0,
+        }
+    }
+
+    pub fn get_timestamp(&self, index: usize) -> i64 {
+        match self {
+            Self::Locations(array) => array[index].timestamp,
+            Self::Timestamps(array) => array[index].1,
+            Self::Uint(_) => 0,
         }
     }
 
@@ -52,6 +63,7 @@ impl dc_array_t {
         match self {
             Self::Locations(array) => array.is_empty(),
             Self::Uint(array) => array.is_empty(),
+            Self::Timestamps(array) => array.is_empty(),
         }
     }
 
@@ -60,6 +72,7 @@ impl dc_array_t {
         match self {
             Self::Locations(array) => array.len(),
             Self::Uint(array) => array.len(),
+            Self::Timestamps(array) => array.len(),
         }
     }
 
@@ -67,6 +80,7 @@ impl dc_array_t {
         match self {
             Self::Locations(array) => array.clear(),
             Self::Uint(array) => array.clear(),
+            Self::Timestamps(array) => array.clear(),
         }
     }
 
@@ -112,6 +126,12 @@ impl From<Vec<Location>> for dc_array_t {
     }
 }
 
+impl From<Vec<(u32, i64)>> for dc_array_t {
+    fn from(array: Vec<(u32, i64)>) -> Self {
+        dc_array_t::Timestamps(array)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -19,13 +19,14 @@ use std::ffi::CString;
 use std::fmt::Write;
 use std::ptr;
 use std::str::FromStr;
-use std::sync::RwLock;
+use std::sync::{Arc, RwLock};
 use std::time::{Duration, SystemTime};
 
 use libc::uintptr_t;
 use num_traits::{FromPrimitive, ToPrimitive};
 
-use deltachat::chat::{ChatId, ChatVisibility, MuteDuration};
+use deltachat::accounts::Accounts;
+use deltachat::chat::{ChatId, ChatVisibility, EphemeralTimer, MuteDuration};
 use deltachat::constants::DC_MSG_ID_LAST_SPECIAL;
 use deltachat::contact::Contact;
 use deltachat::context::Context;
@@ -62,7 +63,7 @@ pub struct ContextWrapper {
     cb: Option<dc_callback_t>,
     userdata: *mut libc::c_void,
     os_name: String,
-    inner: RwLock<Option<context::Context>>,
+    inner: RwLock<Option<Arc<context::Context>>>,
 }
 
 unsafe impl Send for ContextWrapper {}
@@ -137,81 +138,116 @@ impl ContextWrapper {
     /// Translates the callback from the rust style to the C-style version.
     unsafe fn translate_cb(&self, event: Event) {
         if let Some(ffi_cb) = self.cb {
-            let event_id = event.as_id();
-            match event {
-                Event::Info(msg)
-                | Event::SmtpConnected(msg)
-                | Event::ImapConnected(msg)
-                | Event::SmtpMessageSent(msg)
-                | Event::ImapMessageDeleted(msg)
-                | Event::ImapMessageMoved(msg)
-                | Event::ImapFolderEmptied(msg)
-                | Event::NewBlobFile(msg)
-                | Event::DeletedBlobFile(msg)
-                | Event::Warning(msg)
-                | Event::Error(msg)
-                | Event::ErrorNetwork(msg)
-                | Event::ErrorSelfNotInGroup(msg) => {
-                    let data2 = CString::new(msg).unwrap_or_default();
-                    ffi_cb(self, event_id, 0, data2.as_ptr() as uintptr_t);
-                }
-                Event::MsgsChanged { chat_id, msg_id }
-                | Event::IncomingMsg { chat_id, msg_id }
-                | Event::MsgDelivered { chat_id, msg_id }
-                | Event::MsgFailed { chat_id, msg_id }
-                | Event::MsgRead { chat_id, msg_id } => {
-                    ffi_cb(
-                        self,
-                        event_id,
-                        chat_id.to_u32() as uintptr_t,
-                        msg_id.to_u32() as uintptr_t,
-                    );
-                }
-                Event::ChatModified(chat_id) => {
-                    ffi_cb(self, event_id, chat_id.to_u32() as uintptr_t, 0);
-                }
-                Event::ContactsChanged(id) | Event::LocationChanged(id) => {
-                    let id = id.unwrap_or_default();
-                    ffi_cb(self, event_id, id as uintptr_t, 0);
-                }
-                Event::ConfigureProgress(progress) | Event::ImexProgress(progress) => {
-                    ffi_cb(self, event_id, progress as uintptr_t, 0);
-                }
-                Event::ImexFileWritten(file) => {
-                    let data1 = file.to_c_string().unwrap_or_default();
-                    ffi_cb(self, event_id, data1.as_ptr() as uintptr_t, 0);
-                }
-                Event::SecurejoinInviterProgress {
-                    contact_id,
-                    progress,
-                }
-                | Event::SecurejoinJoinerProgress {
-                    contact_id,
-                    progress,
-                } => {
-                    ffi_cb(
-                        self,
-                        event_id,
-                        contact_id as uintptr_t,
-                        progress as uintptr_t,
-                    );
-                }
-                Event::SecurejoinMemberAdded {
-                    chat_id,
-                    contact_id,
-                } => {
-                    ffi_cb(
-                        self,
-                        event_id,
-                        chat_id.to_u32() as uintptr_t,
-                        contact_id as uintptr_t,
-                    );
-                }
-            }
+            translate_event(event, |event_id, data1, data2| {
+                ffi_cb(self, event_id, data1, data2)
+            });
+        }
+    }
+}
+
+/// Data passed along with an event on the FFI.
+enum EventData {
+    Int(uintptr_t),
+    Str(CString),
+}
+
+impl EventData {
+    fn as_uintptr(&self) -> uintptr_t {
+        match self {
+            EventData::Int(data) => *data,
+            EventData::Str(data) => data.as_ptr() as uintptr_t,
         }
     }
 }
 
+/// Splits an event into the C-style event id and the two data values of the event.
+fn split_event(event: Event) -> (i32, EventData, EventData) {
+    let event_id = event.as_id();
+    let (data1, data2) = match event {
+        Event::Info(msg)
+        | Event::SmtpConnected(msg)
+        | Event::ImapConnected(msg)
+        | Event::SmtpMessageSent(msg)
+        | Event::ImapMessageDeleted(msg)
+        | Event::ImapMessageMoved(msg)
+        | Event::ImapFolderEmptied(msg)
+        | Event::NewBlobFile(msg)
+        | Event::DeletedBlobFile(msg)
+        | Event::Warning(msg)
+        | Event::Error(msg)
+        | Event::ErrorNetwork(msg)
+        | Event::ErrorSelfNotInGroup(msg) => (
+            EventData::Int(0),
+            EventData::Str(CString::new(msg).unwrap_or_default()),
+        ),
+        Event::MsgsChanged { chat_id, msg_id }
+        | Event::ReactionsChanged { chat_id, msg_id }
+        | Event::IncomingMsg { chat_id, msg_id }
+        | Event::MsgDelivered { chat_id, msg_id }
+        | Event::MsgFailed { chat_id, msg_id }
+        | Event::MsgRead {
+            chat_id, msg_id, ..
+        } => (
+            EventData::Int(chat_id.to_u32() as uintptr_t),
+            EventData::Int(msg_id.to_u32() as uintptr_t),
+        ),
+        Event::ChatModified(chat_id) => (
+            EventData::Int(chat_id.to_u32() as uintptr_t),
+            EventData::Int(0),
+        ),
+        Event::ContactsChanged(id) | Event::LocationChanged(id) => (
+            EventData::Int(id.unwrap_or_default() as uintptr_t),
+            EventData::Int(0),
+        ),
+        Event::ConfigureProgress(progress) | Event::ImexProgress(progress) => {
+            (EventData::Int(progress as uintptr_t), EventData::Int(0))
+        }
+        Event::ImexFileWritten(file) => (
+            EventData::Str(file.to_c_string().unwrap_or_default()),
+            EventData::Int(0),
+        ),
+        Event::SecurejoinInviterProgress {
+            contact_id,
+            progress,
+        }
+        | Event::SecurejoinJoinerProgress {
+            contact_id,
+            progress,
+        } => (
+            EventData::Int(contact_id as uintptr_t),
+            EventData::Int(progress as uintptr_t),
+        ),
+        Event::SecurejoinMemberAdded {
+            chat_id,
+            contact_id,
+        }
+        | Event::ContactTyping {
+            chat_id,
+            contact_id,
+        }
+        | Event::ContactStoppedTyping {
+            chat_id,
+            contact_id,
+        } => (
+            EventData::Int(chat_id.to_u32() as uintptr_t),
+            EventData::Int(contact_id as uintptr_t),
+        ),
+    };
+    (event_id, data1, data2)
+}
+
+/// Translates an event from the rust style to the C-style version.
+///
+/// `ffi_cb` is called with the event id and the two data values of the event,
+/// strings passed as data are only valid during the call.
+fn translate_event<F>(event: Event, ffi_cb: F)
+where
+    F: FnOnce(i32, uintptr_t, uintptr_t) -> uintptr_t,
+{
+    let (event_id, data1, data2) = split_event(event);
+    ffi_cb(event_id, data1.as_uintptr(), data2.as_uintptr());
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_context_new(
     cb: Option<dc_callback_t>,
@@ -244,6 +280,9 @@ pub unsafe extern "C" fn dc_context_unref(context: *mut dc_context_t) {
         return;
     }
     let ffi_context = &mut *context;
+    // dropping the last handle of the context stops IO,
+    // accounts owned by dc_accounts_t keep running
+    ffi_context.inner.write().unwrap().take();
     Box::from_raw(ffi_context);
 }
 
@@ -267,33 +306,91 @@ pub unsafe extern "C" fn dc_open(
         eprintln!("ignoring careless call to dc_open()");
         return 0;
     }
-    let ffi_context = &*context;
+    open_context(&*context, dbfile, blobdir, None)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_open_encrypted(
+    context: *mut dc_context_t,
+    dbfile: *const libc::c_char,
+    blobdir: *const libc::c_char,
+    passphrase: *const libc::c_char,
+) -> libc::c_int {
+    if context.is_null() || dbfile.is_null() || passphrase.is_null() {
+        eprintln!("ignoring careless call to dc_open_encrypted()");
+        return 0;
+    }
+    open_context(
+        &*context,
+        dbfile,
+        blobdir,
+        Some(to_string_lossy(passphrase)),
+    )
+}
+
+unsafe fn open_context(
+    ffi_context: &'static ContextWrapper,
+    dbfile: *const libc::c_char,
+    blobdir: *const libc::c_char,
+    passphrase: Option<String>,
+) -> libc::c_int {
     let rust_cb = move |_ctx: &Context, evt: Event| ffi_context.translate_cb(evt);
 
-    let ctx = if blobdir.is_null() || *blobdir == 0 {
-        Context::new(
-            Box::new(rust_cb),
-            ffi_context.os_name.clone(),
-            as_path(dbfile).to_path_buf(),
-        )
+    let blobdir = if blobdir.is_null() || *blobdir == 0 {
+        None
     } else {
-        Context::with_blobdir(
-            Box::new(rust_cb),
-            ffi_context.os_name.clone(),
-            as_path(dbfile).to_path_buf(),
-            as_path(blobdir).to_path_buf(),
-        )
+        Some(as_path(blobdir).to_path_buf())
     };
+    let ctx = Context::with_passphrase(
+        Some(Box::new(rust_cb)),
+        ffi_context.os_name.clone(),
+        as_path(dbfile).to_path_buf(),
+        blobdir,
+        passphrase,
+    );
     match ctx {
         Ok(ctx) => {
             let mut inner_guard = ffi_context.inner.write().unwrap();
-            *inner_guard = Some(ctx);
+            *inner_guard = Some(Arc::new(ctx));
             1
         }
         Err(_) => 0,
     }
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_change_passphrase(
+    context: *mut dc_context_t,
+    passphrase: *const libc::c_char,
+) -> libc::c_int {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_change_passphrase()");
+        return 0;
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| {
+            ctx.change_passphrase(to_opt_string_lossy(passphrase).as_deref())
+                .log_err(ffi_context, "Failed to change passphrase")
+                .is_ok() as libc::c_int
+        })
+        .unwrap_or(0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_event_emitter(
+    context: *mut dc_context_t,
+) -> *mut dc_event_emitter_t {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_get_event_emitter()");
+        return ptr::null_mut();
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| Box::into_raw(Box::new(ctx.get_event_emitter())))
+        .unwrap_or_else(|_| ptr::null_mut())
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_close(context: *mut dc_context_t) {
     if context.is_null() {
@@ -444,6 +541,34 @@ pub unsafe extern "C" fn dc_get_info(context: *mut dc_context_t) -> *mut libc::c
     render_info(info).unwrap_or_default().strdup()
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_quota_json(context: *mut dc_context_t) -> *mut libc::c_char {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_get_quota_json()");
+        return "".strdup();
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| {
+            serde_json::to_string(&ctx.get_quota())
+                .unwrap_or_log_default(ctx, "dc_get_quota_json() failed to serialise to json")
+                .strdup()
+        })
+        .unwrap_or_else(|_| "".strdup())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_connectivity_info(context: *mut dc_context_t) -> *mut libc::c_char {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_get_connectivity_info()");
+        return "".strdup();
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| ctx.get_connectivity_info().strdup())
+        .unwrap_or_else(|_| "".strdup())
+}
+
 fn render_info(
     info: HashMap<&'static str, String>,
 ) -> std::result::Result<String, std::fmt::Error> {
@@ -504,184 +629,35 @@ pub unsafe extern "C" fn dc_is_configured(context: *mut dc_context_t) -> libc::c
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn dc_perform_imap_jobs(context: *mut dc_context_t) {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_perform_imap_jobs()");
-        return;
-    }
-    let ffi_context = &*context;
-    ffi_context
-        .with_inner(|ctx| job::perform_inbox_jobs(ctx))
-        .unwrap_or(())
-}
-
-#[no_mangle]
-pub unsafe extern "C" fn dc_perform_imap_fetch(context: *mut dc_context_t) {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_perform_imap_fetch()");
-        return;
-    }
-    let ffi_context = &*context;
-    ffi_context
-        .with_inner(|ctx| job::perform_inbox_fetch(ctx))
-        .unwrap_or(())
-}
-
-#[no_mangle]
-pub unsafe extern "C" fn dc_perform_imap_idle(context: *mut dc_context_t) {
-    // TODO rename function in co-ordination with UIs
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_perform_imap_idle()");
-        return;
-    }
-    let ffi_context = &*context;
-    ffi_context
-        .with_inner(|ctx| job::perform_inbox_idle(ctx))
-        .unwrap_or(())
-}
-
-#[no_mangle]
-pub unsafe extern "C" fn dc_interrupt_imap_idle(context: *mut dc_context_t) {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_interrupt_imap_idle()");
-        return;
-    }
-    let ffi_context = &*context;
-    ffi_context
-        .with_inner(|ctx| job::interrupt_inbox_idle(ctx))
-        .unwrap_or(())
-}
-
-#[no_mangle]
-pub unsafe extern "C" fn dc_perform_mvbox_fetch(context: *mut dc_context_t) {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_perform_mvbox_fetch()");
-        return;
-    }
-    let ffi_context = &*context;
-    ffi_context
-        .with_inner(|ctx| job::perform_mvbox_fetch(ctx))
-        .unwrap_or(())
-}
-
-#[no_mangle]
-pub unsafe extern "C" fn dc_perform_mvbox_jobs(context: *mut dc_context_t) {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_perform_mvbox_jobs()");
-        return;
-    }
-    let ffi_context = &*context;
-    ffi_context
-        .with_inner(|ctx| job::perform_mvbox_jobs(ctx))
-        .unwrap_or(())
-}
-
-#[no_mangle]
-pub unsafe extern "C" fn dc_perform_mvbox_idle(context: *mut dc_context_t) {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_perform_mvbox_idle()");
-        return;
-    }
-    let ffi_context = &*context;
-    ffi_context
-        .with_inner(|ctx| job::perform_mvbox_idle(ctx))
-        .unwrap_or(())
-}
-
-#[no_mangle]
-pub unsafe extern "C" fn dc_interrupt_mvbox_idle(context: *mut dc_context_t) {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_interrupt_mvbox_idle()");
-        return;
-    }
-    let ffi_context = &*context;
-    ffi_context
-        .with_inner(|ctx| job::interrupt_mvbox_idle(ctx))
-        .unwrap_or(())
-}
-
-#[no_mangle]
-pub unsafe extern "C" fn dc_perform_sentbox_fetch(context: *mut dc_context_t) {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_perform_sentbox_fetch()");
-        return;
-    }
-    let ffi_context = &*context;
-    ffi_context
-        .with_inner(|ctx| job::perform_sentbox_fetch(ctx))
-        .unwrap_or(())
-}
-
-#[no_mangle]
-pub unsafe extern "C" fn dc_perform_sentbox_jobs(context: *mut dc_context_t) {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_perform_sentbox_jobs()");
-        return;
-    }
-    let ffi_context = &*context;
-    ffi_context
-        .with_inner(|ctx| job::perform_sentbox_jobs(ctx))
-        .unwrap_or(())
-}
-
-#[no_mangle]
-pub unsafe extern "C" fn dc_perform_sentbox_idle(context: *mut dc_context_t) {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_perform_sentbox_idle()");
-        return;
-    }
-    let ffi_context = &*context;
-    ffi_context
-        .with_inner(|ctx| job::perform_sentbox_idle(ctx))
-        .unwrap_or(())
-}
-
-#[no_mangle]
-pub unsafe extern "C" fn dc_interrupt_sentbox_idle(context: *mut dc_context_t) {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_interrupt_sentbox_idle()");
-        return;
-    }
-    let ffi_context = &*context;
-    ffi_context
-        .with_inner(|ctx| job::interrupt_sentbox_idle(ctx))
-        .unwrap_or(())
-}
-
-#[no_mangle]
-pub unsafe extern "C" fn dc_perform_smtp_jobs(context: *mut dc_context_t) {
+pub unsafe extern "C" fn dc_start_io(context: *mut dc_context_t) {
     if context.is_null() {
-        eprintln!("ignoring careless call to dc_perform_smtp_jobs()");
+        eprintln!("ignoring careless call to dc_start_io()");
         return;
     }
     let ffi_context = &*context;
-    ffi_context
-        .with_inner(|ctx| job::perform_smtp_jobs(ctx))
-        .unwrap_or(())
+    ffi_context.with_inner(|ctx| ctx.start_io()).unwrap_or(())
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn dc_perform_smtp_idle(context: *mut dc_context_t) {
+pub unsafe extern "C" fn dc_stop_io(context: *mut dc_context_t) {
     if context.is_null() {
-        eprintln!("ignoring careless call to dc_perform_smtp_idle()");
+        eprintln!("ignoring careless call to dc_stop_io()");
         return;
     }
     let ffi_context = &*context;
-    ffi_context
-        .with_inner(|ctx| job::perform_smtp_idle(ctx))
-        .unwrap_or(())
+    ffi_context.with_inner(|ctx| ctx.stop_io()).unwrap_or(())
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn dc_interrupt_smtp_idle(context: *mut dc_context_t) {
+pub unsafe extern "C" fn dc_is_io_running(context: *mut dc_context_t) -> libc::c_int {
     if context.is_null() {
-        eprintln!("ignoring careless call to dc_interrupt_smtp_idle()");
-        return;
+        eprintln!("ignoring careless call to dc_is_io_running()");
+        return 0;
     }
     let ffi_context = &*context;
     ffi_context
-        .with_inner(|ctx| job::interrupt_smtp_idle(ctx))
-        .unwrap_or(())
+        .with_inner(|ctx| ctx.is_io_running() as libc::c_int)
+        .unwrap_or(0)
 }
 
 #[no_mangle]
@@ -858,56 +834,163 @@ pub unsafe extern "C" fn dc_send_msg(
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn dc_send_text_msg(
+pub unsafe extern "C" fn dc_send_msg_at(
     context: *mut dc_context_t,
     chat_id: u32,
-    text_to_send: *const libc::c_char,
+    msg: *mut dc_msg_t,
+    timestamp: i64,
 ) -> u32 {
-    if context.is_null() || text_to_send.is_null() {
-        eprintln!("ignoring careless call to dc_send_text_msg()");
+    if context.is_null() || msg.is_null() {
+        eprintln!("ignoring careless call to dc_send_msg_at()");
         return 0;
     }
-    let ffi_context = &*context;
-    let text_to_send = to_string_lossy(text_to_send);
+    let ffi_context = &mut *context;
+    let ffi_msg = &mut *msg;
     ffi_context
         .with_inner(|ctx| {
-            chat::send_text_msg(ctx, ChatId::new(chat_id), text_to_send)
-                .map(|msg_id| msg_id.to_u32())
-                .unwrap_or_log_default(ctx, "Failed to send text message")
+            chat::send_msg_at(ctx, ChatId::new(chat_id), &mut ffi_msg.message, timestamp)
+                .unwrap_or_log_default(ctx, "Failed to schedule message")
         })
+        .map(|msg_id| msg_id.to_u32())
         .unwrap_or(0)
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn dc_set_draft(
+pub unsafe extern "C" fn dc_get_scheduled_msgs(
     context: *mut dc_context_t,
     chat_id: u32,
-    msg: *mut dc_msg_t,
-) {
+) -> *mut dc_array::dc_array_t {
     if context.is_null() {
-        eprintln!("ignoring careless call to dc_set_draft()");
-        return;
+        eprintln!("ignoring careless call to dc_get_scheduled_msgs()");
+        return ptr::null_mut();
     }
     let ffi_context = &*context;
-    let msg = if msg.is_null() {
-        None
-    } else {
-        let ffi_msg: &mut MessageWrapper = &mut *msg;
-        Some(&mut ffi_msg.message)
-    };
     ffi_context
-        .with_inner(|ctx| ChatId::new(chat_id).set_draft(ctx, msg))
-        .unwrap_or(())
+        .with_inner(|ctx| {
+            let arr = dc_array_t::from(
+                chat::get_scheduled_msgs(ctx, ChatId::new(chat_id))
+                    .unwrap_or_log_default(ctx, "Failed to get scheduled messages")
+                    .iter()
+                    .map(|msg_id| msg_id.to_u32())
+                    .collect::<Vec<u32>>(),
+            );
+            Box::into_raw(Box::new(arr))
+        })
+        .unwrap_or_else(|_| ptr::null_mut())
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn dc_add_device_msg(
+pub unsafe extern "C" fn dc_reschedule_msg(
     context: *mut dc_context_t,
-    label: *const libc::c_char,
-    msg: *mut dc_msg_t,
-) -> u32 {
-    if context.is_null() || (label.is_null() && msg.is_null()) {
-        eprintln!("ignoring careless call to dc_add_device_msg()");
+    msg_id: u32,
+    timestamp: i64,
+) -> libc::c_int {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_reschedule_msg()");
+        return 0;
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| {
+            chat::reschedule_msg(ctx, MsgId::new(msg_id), timestamp)
+                .map(|_| 1)
+                .unwrap_or_log_default(ctx, "Failed to reschedule message")
+        })
+        .unwrap_or(0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_cancel_scheduled_msgs(
+    context: *mut dc_context_t,
+    msg_ids: *const u32,
+    msg_cnt: libc::c_int,
+) -> libc::c_int {
+    if context.is_null() || msg_ids.is_null() || msg_cnt <= 0 {
+        eprintln!("ignoring careless call to dc_cancel_scheduled_msgs()");
+        return 0;
+    }
+    let ffi_context = &*context;
+    let msg_ids = convert_and_prune_message_ids(msg_ids, msg_cnt);
+    ffi_context
+        .with_inner(|ctx| {
+            chat::cancel_scheduled_msgs(ctx, &msg_ids[..])
+                .map(|_| 1)
+                .unwrap_or_log_default(ctx, "Failed to cancel scheduled messages")
+        })
+        .unwrap_or(0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_send_typing(
+    context: *mut dc_context_t,
+    chat_id: u32,
+    typing: libc::c_int,
+) -> libc::c_int {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_send_typing()");
+        return 0;
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| {
+            chat::send_typing(ctx, ChatId::new(chat_id), typing != 0)
+                .map(|_| 1)
+                .unwrap_or_log_default(ctx, "Failed to send typing notification")
+        })
+        .unwrap_or(0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_send_text_msg(
+    context: *mut dc_context_t,
+    chat_id: u32,
+    text_to_send: *const libc::c_char,
+) -> u32 {
+    if context.is_null() || text_to_send.is_null() {
+        eprintln!("ignoring careless call to dc_send_text_msg()");
+        return 0;
+    }
+    let ffi_context = &*context;
+    let text_to_send = to_string_lossy(text_to_send);
+    ffi_context
+        .with_inner(|ctx| {
+            chat::send_text_msg(ctx, ChatId::new(chat_id), text_to_send)
+                .map(|msg_id| msg_id.to_u32())
+                .unwrap_or_log_default(ctx, "Failed to send text message")
+        })
+        .unwrap_or(0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_set_draft(
+    context: *mut dc_context_t,
+    chat_id: u32,
+    msg: *mut dc_msg_t,
+) {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_set_draft()");
+        return;
+    }
+    let ffi_context = &*context;
+    let msg = if msg.is_null() {
+        None
+    } else {
+        let ffi_msg: &mut MessageWrapper = &mut *msg;
+        Some(&mut ffi_msg.message)
+    };
+    ffi_context
+        .with_inner(|ctx| ChatId::new(chat_id).set_draft(ctx, msg))
+        .unwrap_or(())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_add_device_msg(
+    context: *mut dc_context_t,
+    label: *const libc::c_char,
+    msg: *mut dc_msg_t,
+) -> u32 {
+    if context.is_null() || (label.is_null() && msg.is_null()) {
+        eprintln!("ignoring careless call to dc_add_device_msg()");
         return 0;
     }
     let ffi_context = &mut *context;
@@ -1067,6 +1150,28 @@ pub unsafe extern "C" fn dc_get_fresh_msgs(
         .unwrap_or_else(|_| ptr::null_mut())
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_outbox_msgs(
+    context: *mut dc_context_t,
+) -> *mut dc_array::dc_array_t {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_get_outbox_msgs()");
+        return ptr::null_mut();
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| {
+            let arr = dc_array_t::from(
+                ctx.get_outbox_msgs()
+                    .iter()
+                    .map(|msg_id| msg_id.to_u32())
+                    .collect::<Vec<u32>>(),
+            );
+            Box::into_raw(Box::new(arr))
+        })
+        .unwrap_or_else(|_| ptr::null_mut())
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_marknoticed_chat(context: *mut dc_context_t, chat_id: u32) {
     if context.is_null() {
@@ -1276,6 +1381,34 @@ pub unsafe extern "C" fn dc_search_msgs(
         .unwrap_or_else(|_| ptr::null_mut())
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_search_snippet(
+    context: *mut dc_context_t,
+    msg_id: u32,
+    query: *const libc::c_char,
+    start_mark: *const libc::c_char,
+    end_mark: *const libc::c_char,
+) -> *mut libc::c_char {
+    if context.is_null() || query.is_null() {
+        eprintln!("ignoring careless call to dc_get_search_snippet()");
+        return ptr::null_mut();
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| {
+            match ctx.get_search_snippet(
+                MsgId::new(msg_id),
+                to_string_lossy(query),
+                &to_string_lossy(start_mark),
+                &to_string_lossy(end_mark),
+            ) {
+                Some(snippet) => snippet.strdup(),
+                None => ptr::null_mut(),
+            }
+        })
+        .unwrap_or_else(|_| ptr::null_mut())
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_get_chat(context: *mut dc_context_t, chat_id: u32) -> *mut dc_chat_t {
     if context.is_null() {
@@ -1448,6 +1581,66 @@ pub unsafe extern "C" fn dc_set_chat_mute_duration(
         .unwrap_or(0)
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_set_chat_ephemeral_timer(
+    context: *mut dc_context_t,
+    chat_id: u32,
+    timer: u32,
+) -> libc::c_int {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_set_chat_ephemeral_timer()");
+        return 0;
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| {
+            chat::set_ephemeral_timer(ctx, ChatId::new(chat_id), EphemeralTimer::from_u32(timer))
+                .map(|_| 1)
+                .unwrap_or_log_default(ctx, "Failed to set ephemeral timer")
+        })
+        .unwrap_or(0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_set_mailinglist_sending(
+    context: *mut dc_context_t,
+    chat_id: u32,
+    enabled: libc::c_int,
+) -> libc::c_int {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_set_mailinglist_sending()");
+        return 0;
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| {
+            chat::set_mailinglist_sending(ctx, ChatId::new(chat_id), enabled != 0)
+                .map(|_| 1)
+                .unwrap_or_log_default(ctx, "Failed to set mailing list sending")
+        })
+        .unwrap_or(0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_chat_ephemeral_timer(
+    context: *mut dc_context_t,
+    chat_id: u32,
+) -> u32 {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_get_chat_ephemeral_timer()");
+        return 0;
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| {
+            ChatId::new(chat_id)
+                .get_ephemeral_timer(ctx)
+                .map(|timer| timer.to_u32())
+                .unwrap_or_log_default(ctx, "Failed to get ephemeral timer")
+        })
+        .unwrap_or(0)
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_get_msg_info(
     context: *mut dc_context_t,
@@ -1463,6 +1656,66 @@ pub unsafe extern "C" fn dc_get_msg_info(
         .unwrap_or_else(|_| ptr::null_mut())
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_send_reaction(
+    context: *mut dc_context_t,
+    msg_id: u32,
+    reaction: *const libc::c_char,
+) -> u32 {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_send_reaction()");
+        return 0;
+    }
+    let ffi_context = &*context;
+    let reaction = to_opt_string_lossy(reaction).unwrap_or_default();
+    ffi_context
+        .with_inner(|ctx| {
+            reaction::send_reaction(ctx, MsgId::new(msg_id), reaction)
+                .map(|msg_id| msg_id.to_u32())
+                .unwrap_or_log_default(ctx, "Failed to send reaction")
+        })
+        .unwrap_or(0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_reactions_json(
+    context: *mut dc_context_t,
+    msg_id: u32,
+) -> *mut libc::c_char {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_get_reactions_json()");
+        return "".strdup();
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| {
+            let reactions = reaction::get_reactions(ctx, MsgId::new(msg_id))
+                .unwrap_or_log_default(ctx, "dc_get_reactions_json() failed to load reactions");
+            serde_json::to_string(&reactions)
+                .unwrap_or_log_default(ctx, "dc_get_reactions_json() failed to serialise to json")
+                .strdup()
+        })
+        .unwrap_or_else(|_| "".strdup())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_msg_read_receipts(
+    context: *mut dc_context_t,
+    msg_id: u32,
+) -> *mut dc_array::dc_array_t {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_get_msg_read_receipts()");
+        return ptr::null_mut();
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| {
+            let arr = dc_array_t::from(message::get_read_receipts(ctx, MsgId::new(msg_id)));
+            Box::into_raw(Box::new(arr))
+        })
+        .unwrap_or_else(|_| ptr::null_mut())
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_get_mime_headers(
     context: *mut dc_context_t,
@@ -1500,37 +1753,100 @@ pub unsafe extern "C" fn dc_delete_msgs(
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn dc_empty_server(context: *mut dc_context_t, flags: u32) {
-    if context.is_null() || flags == 0 {
-        eprintln!("ignoring careless call to dc_empty_server()");
-        return;
+pub unsafe extern "C" fn dc_delete_msgs_for_everyone(
+    context: *mut dc_context_t,
+    msg_ids: *const u32,
+    msg_cnt: libc::c_int,
+) -> libc::c_int {
+    if context.is_null() || msg_ids.is_null() || msg_cnt <= 0 {
+        eprintln!("ignoring careless call to dc_delete_msgs_for_everyone()");
+        return 0;
     }
     let ffi_context = &*context;
+    let msg_ids = convert_and_prune_message_ids(msg_ids, msg_cnt);
     ffi_context
-        .with_inner(|ctx| message::dc_empty_server(ctx, flags))
-        .unwrap_or(())
+        .with_inner(|ctx| {
+            chat::delete_msgs_for_everyone(ctx, &msg_ids[..])
+                .map(|_| 1)
+                .unwrap_or_log_default(ctx, "Failed to delete messages for everyone")
+        })
+        .unwrap_or(0)
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn dc_forward_msgs(
+pub unsafe extern "C" fn dc_resend_msgs(
     context: *mut dc_context_t,
     msg_ids: *const u32,
     msg_cnt: libc::c_int,
-    chat_id: u32,
-) {
-    if context.is_null()
-        || msg_ids.is_null()
-        || msg_cnt <= 0
-        || chat_id <= constants::DC_CHAT_ID_LAST_SPECIAL as u32
-    {
-        eprintln!("ignoring careless call to dc_forward_msgs()");
-        return;
+) -> libc::c_int {
+    if context.is_null() || msg_ids.is_null() || msg_cnt <= 0 {
+        eprintln!("ignoring careless call to dc_resend_msgs()");
+        return 0;
     }
-    let msg_ids = convert_and_prune_message_ids(msg_ids, msg_cnt);
     let ffi_context = &*context;
+    let msg_ids = convert_and_prune_message_ids(msg_ids, msg_cnt);
     ffi_context
         .with_inner(|ctx| {
-            chat::forward_msgs(ctx, &msg_ids[..], ChatId::new(chat_id))
+            chat::resend_msgs(ctx, &msg_ids[..])
+                .map(|_| 1)
+                .unwrap_or_log_default(ctx, "Failed to resend messages")
+        })
+        .unwrap_or(0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_edit_msg(
+    context: *mut dc_context_t,
+    msg_id: u32,
+    text: *const libc::c_char,
+) -> libc::c_int {
+    if context.is_null() || text.is_null() {
+        eprintln!("ignoring careless call to dc_edit_msg()");
+        return 0;
+    }
+    let ffi_context = &*context;
+    let text = to_string_lossy(text);
+    ffi_context
+        .with_inner(|ctx| {
+            chat::edit_msg(ctx, MsgId::new(msg_id), text)
+                .map(|_| 1)
+                .unwrap_or_log_default(ctx, "Failed to edit message")
+        })
+        .unwrap_or(0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_empty_server(context: *mut dc_context_t, flags: u32) {
+    if context.is_null() || flags == 0 {
+        eprintln!("ignoring careless call to dc_empty_server()");
+        return;
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| message::dc_empty_server(ctx, flags))
+        .unwrap_or(())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_forward_msgs(
+    context: *mut dc_context_t,
+    msg_ids: *const u32,
+    msg_cnt: libc::c_int,
+    chat_id: u32,
+) {
+    if context.is_null()
+        || msg_ids.is_null()
+        || msg_cnt <= 0
+        || chat_id <= constants::DC_CHAT_ID_LAST_SPECIAL as u32
+    {
+        eprintln!("ignoring careless call to dc_forward_msgs()");
+        return;
+    }
+    let msg_ids = convert_and_prune_message_ids(msg_ids, msg_cnt);
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| {
+            chat::forward_msgs(ctx, &msg_ids[..], ChatId::new(chat_id))
                 .unwrap_or_log_default(ctx, "Failed to forward message")
         })
         .unwrap_or_default()
@@ -1684,6 +2000,49 @@ pub unsafe extern "C" fn dc_add_address_book(
         .unwrap_or(0)
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_make_vcard(
+    context: *mut dc_context_t,
+    contact_ids: *const u32,
+    contact_cnt: libc::c_int,
+) -> *mut libc::c_char {
+    if context.is_null() || contact_ids.is_null() || contact_cnt <= 0 {
+        eprintln!("ignoring careless call to dc_make_vcard()");
+        return ptr::null_mut();
+    }
+    let ffi_context = &*context;
+    let contact_ids = std::slice::from_raw_parts(contact_ids, contact_cnt as usize);
+    ffi_context
+        .with_inner(|ctx| {
+            Contact::export_vcard(ctx, contact_ids)
+                .map(|vcard| vcard.strdup())
+                .unwrap_or_else(|err| {
+                    error!(ctx, "Cannot make vCard: {}", err);
+                    ptr::null_mut()
+                })
+        })
+        .unwrap_or_else(|_| ptr::null_mut())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_import_vcard(
+    context: *mut dc_context_t,
+    vcard: *const libc::c_char,
+) -> *mut dc_array::dc_array_t {
+    if context.is_null() || vcard.is_null() {
+        eprintln!("ignoring careless call to dc_import_vcard()");
+        return ptr::null_mut();
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| {
+            let contact_ids = Contact::import_vcard(ctx, &to_string_lossy(vcard))
+                .unwrap_or_log_default(ctx, "Cannot import vCard");
+            Box::into_raw(Box::new(dc_array_t::from(contact_ids)))
+        })
+        .unwrap_or_else(|_| ptr::null_mut())
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_get_contacts(
     context: *mut dc_context_t,
@@ -1816,7 +2175,7 @@ pub unsafe extern "C" fn dc_imex(
     context: *mut dc_context_t,
     what: libc::c_int,
     param1: *const libc::c_char,
-    _param2: *const libc::c_char,
+    param2: *const libc::c_char,
 ) {
     if context.is_null() {
         eprintln!("ignoring careless call to dc_imex()");
@@ -1832,7 +2191,14 @@ pub unsafe extern "C" fn dc_imex(
 
     let ffi_context = &*context;
     ffi_context
-        .with_inner(|ctx| imex::imex(ctx, what, to_opt_string_lossy(param1)))
+        .with_inner(|ctx| {
+            imex::imex(
+                ctx,
+                what,
+                to_opt_string_lossy(param1),
+                to_opt_string_lossy(param2),
+            )
+        })
         .ok();
 }
 
@@ -1905,6 +2271,29 @@ pub unsafe extern "C" fn dc_continue_key_transfer(
         .unwrap_or(0)
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_receive_backup(
+    context: *mut dc_context_t,
+    qr: *const libc::c_char,
+) -> libc::c_int {
+    if context.is_null() || qr.is_null() {
+        eprintln!("ignoring careless call to dc_receive_backup()");
+        return 0;
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(
+            |ctx| match imex::receive_backup(ctx, &to_string_lossy(qr)) {
+                Ok(()) => 1,
+                Err(err) => {
+                    error!(ctx, "dc_receive_backup: {}", err);
+                    0
+                }
+            },
+        )
+        .unwrap_or(0)
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_stop_ongoing_process(context: *mut dc_context_t) {
     if context.is_null() {
@@ -2149,7 +2538,7 @@ pub unsafe extern "C" fn dc_array_get_timestamp(
         return 0;
     }
 
-    (*array).get_location(index).timestamp
+    (*array).get_timestamp(index)
 }
 #[no_mangle]
 pub unsafe extern "C" fn dc_array_get_chat_id(
@@ -2508,6 +2897,34 @@ pub unsafe extern "C" fn dc_chat_can_send(chat: *mut dc_chat_t) -> libc::c_int {
     ffi_chat.chat.can_send() as libc::c_int
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_chat_get_mailinglist_addr(chat: *mut dc_chat_t) -> *mut libc::c_char {
+    if chat.is_null() {
+        eprintln!("ignoring careless call to dc_chat_get_mailinglist_addr()");
+        return ptr::null_mut();
+    }
+    let ffi_chat = &*chat;
+    match ffi_chat.chat.get_mailinglist_addr() {
+        Some(addr) => addr.strdup(),
+        None => ptr::null_mut(),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_chat_get_mailinglist_unsubscribe(
+    chat: *mut dc_chat_t,
+) -> *mut libc::c_char {
+    if chat.is_null() {
+        eprintln!("ignoring careless call to dc_chat_get_mailinglist_unsubscribe()");
+        return ptr::null_mut();
+    }
+    let ffi_chat = &*chat;
+    match ffi_chat.chat.get_mailinglist_unsubscribe() {
+        Some(uri) => uri.strdup(),
+        None => ptr::null_mut(),
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_chat_is_verified(chat: *mut dc_chat_t) -> libc::c_int {
     if chat.is_null() {
@@ -2791,6 +3208,57 @@ pub unsafe extern "C" fn dc_msg_get_filebytes(msg: *mut dc_msg_t) -> u64 {
         .unwrap_or(0)
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_msg_get_error(msg: *mut dc_msg_t) -> *mut libc::c_char {
+    if msg.is_null() {
+        eprintln!("ignoring careless call to dc_msg_get_error()");
+        return ptr::null_mut();
+    }
+    let ffi_msg = &*msg;
+    match ffi_msg.message.get_error() {
+        Some(error) => error.strdup(),
+        None => ptr::null_mut(),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_msg_get_send_tries(msg: *mut dc_msg_t) -> libc::c_int {
+    if msg.is_null() {
+        eprintln!("ignoring careless call to dc_msg_get_send_tries()");
+        return 0;
+    }
+    let ffi_msg = &*msg;
+    let ffi_context = &*ffi_msg.context;
+    ffi_context
+        .with_inner(|ctx| {
+            ffi_msg
+                .message
+                .get_send_retry(ctx)
+                .map(|retry| retry.tries as libc::c_int)
+                .unwrap_or_default()
+        })
+        .unwrap_or(0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_msg_get_next_send_timestamp(msg: *mut dc_msg_t) -> i64 {
+    if msg.is_null() {
+        eprintln!("ignoring careless call to dc_msg_get_next_send_timestamp()");
+        return 0;
+    }
+    let ffi_msg = &*msg;
+    let ffi_context = &*ffi_msg.context;
+    ffi_context
+        .with_inner(|ctx| {
+            ffi_msg
+                .message
+                .get_send_retry(ctx)
+                .map(|retry| retry.next_try)
+                .unwrap_or_default()
+        })
+        .unwrap_or(0)
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_width(msg: *mut dc_msg_t) -> libc::c_int {
     if msg.is_null() {
@@ -2821,6 +3289,26 @@ pub unsafe extern "C" fn dc_msg_get_duration(msg: *mut dc_msg_t) -> libc::c_int
     ffi_msg.message.get_duration()
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_msg_get_ephemeral_timer(msg: *mut dc_msg_t) -> u32 {
+    if msg.is_null() {
+        eprintln!("ignoring careless call to dc_msg_get_ephemeral_timer()");
+        return 0;
+    }
+    let ffi_msg = &*msg;
+    ffi_msg.message.get_ephemeral_timer().to_u32()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_msg_get_ephemeral_timestamp(msg: *mut dc_msg_t) -> i64 {
+    if msg.is_null() {
+        eprintln!("ignoring careless call to dc_msg_get_ephemeral_timestamp()");
+        return 0;
+    }
+    let ffi_msg = &*msg;
+    ffi_msg.message.get_ephemeral_timestamp()
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_showpadlock(msg: *mut dc_msg_t) -> libc::c_int {
     if msg.is_null() {
@@ -2927,6 +3415,16 @@ pub unsafe extern "C" fn dc_msg_is_forwarded(msg: *mut dc_msg_t) -> libc::c_int
     ffi_msg.message.is_forwarded().into()
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_msg_is_edited(msg: *mut dc_msg_t) -> libc::c_int {
+    if msg.is_null() {
+        eprintln!("ignoring careless call to dc_msg_is_edited()");
+        return 0;
+    }
+    let ffi_msg = &*msg;
+    ffi_msg.message.is_edited().into()
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_is_info(msg: *mut dc_msg_t) -> libc::c_int {
     if msg.is_null() {
@@ -3022,6 +3520,65 @@ pub unsafe extern "C" fn dc_msg_set_duration(msg: *mut dc_msg_t, duration: libc:
     ffi_msg.message.set_duration(duration)
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_msg_set_quote(msg: *mut dc_msg_t, quote: *const dc_msg_t) {
+    if msg.is_null() || quote.is_null() {
+        eprintln!("ignoring careless call to dc_msg_set_quote()");
+        return;
+    }
+    let ffi_msg = &mut *msg;
+    let ffi_quote = &*quote;
+    let ffi_context = &*ffi_msg.context;
+    ffi_context
+        .with_inner(|ctx| {
+            ffi_msg
+                .message
+                .set_quote(ctx, &ffi_quote.message)
+                .unwrap_or_log_default(ctx, "Failed to set quote")
+        })
+        .unwrap_or(())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_msg_get_quoted_text(msg: *const dc_msg_t) -> *mut libc::c_char {
+    if msg.is_null() {
+        eprintln!("ignoring careless call to dc_msg_get_quoted_text()");
+        return ptr::null_mut();
+    }
+    let ffi_msg: &MessageWrapper = &*msg;
+    ffi_msg
+        .message
+        .quoted_text()
+        .map(|s| s.strdup())
+        .unwrap_or_else(ptr::null_mut)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_msg_get_quoted_msg(msg: *const dc_msg_t) -> *mut dc_msg_t {
+    if msg.is_null() {
+        eprintln!("ignoring careless call to dc_msg_get_quoted_msg()");
+        return ptr::null_mut();
+    }
+    let ffi_msg: &MessageWrapper = &*msg;
+    let context = ffi_msg.context;
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| {
+            match ffi_msg
+                .message
+                .get_quoted_msg(ctx)
+                .unwrap_or_log_default(ctx, "Failed to get quoted message")
+            {
+                Some(message) => {
+                    let ffi_msg = MessageWrapper { context, message };
+                    Box::into_raw(Box::new(ffi_msg))
+                }
+                None => ptr::null_mut(),
+            }
+        })
+        .unwrap_or_else(|_| ptr::null_mut())
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_set_location(
     msg: *mut dc_msg_t,
@@ -3171,6 +3728,16 @@ pub unsafe extern "C" fn dc_contact_get_profile_image(
         .unwrap_or_else(|_| ptr::null_mut())
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn dc_contact_get_status(contact: *mut dc_contact_t) -> *mut libc::c_char {
+    if contact.is_null() {
+        eprintln!("ignoring careless call to dc_contact_get_status()");
+        return "".strdup();
+    }
+    let ffi_contact = &*contact;
+    ffi_contact.contact.get_status().strdup()
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn dc_contact_get_color(contact: *mut dc_contact_t) -> u32 {
     if contact.is_null() {
@@ -3347,6 +3914,119 @@ fn convert_and_prune_message_ids(msg_ids: *const u32, msg_cnt: libc::c_int) -> V
     msg_ids
 }
 
+// dc_event_emitter_t
+
+#[no_mangle]
+pub type dc_event_emitter_t = EventEmitter;
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_event_emitter_unref(emitter: *mut dc_event_emitter_t) {
+    if emitter.is_null() {
+        eprintln!("ignoring careless call to dc_event_emitter_unref()");
+        return;
+    }
+    Box::from_raw(emitter);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_next_event(emitter: *mut dc_event_emitter_t) -> *mut dc_event_t {
+    if emitter.is_null() {
+        eprintln!("ignoring careless call to dc_get_next_event()");
+        return ptr::null_mut();
+    }
+    let emitter = &*emitter;
+    match emitter.recv_sync() {
+        Some(event) => {
+            let (id, data1, data2) = split_event(event);
+            Box::into_raw(Box::new(EventWrapper { id, data1, data2 }))
+        }
+        None => ptr::null_mut(),
+    }
+}
+
+// dc_event_t
+
+/// An event received from a [dc_event_emitter_t], already split into the C-style values.
+pub struct EventWrapper {
+    id: i32,
+    data1: EventData,
+    data2: EventData,
+}
+
+#[no_mangle]
+pub type dc_event_t = EventWrapper;
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_event_unref(event: *mut dc_event_t) {
+    if event.is_null() {
+        eprintln!("ignoring careless call to dc_event_unref()");
+        return;
+    }
+    Box::from_raw(event);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_event_get_id(event: *mut dc_event_t) -> libc::c_int {
+    if event.is_null() {
+        eprintln!("ignoring careless call to dc_event_get_id()");
+        return 0;
+    }
+    let event = &*event;
+    event.id
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_event_get_data1_int(event: *mut dc_event_t) -> libc::c_int {
+    if event.is_null() {
+        eprintln!("ignoring careless call to dc_event_get_data1_int()");
+        return 0;
+    }
+    let event = &*event;
+    match event.data1 {
+        EventData::Int(data) => data as libc::c_int,
+        EventData::Str(_) => 0,
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_event_get_data2_int(event: *mut dc_event_t) -> libc::c_int {
+    if event.is_null() {
+        eprintln!("ignoring careless call to dc_event_get_data2_int()");
+        return 0;
+    }
+    let event = &*event;
+    match event.data2 {
+        EventData::Int(data) => data as libc::c_int,
+        EventData::Str(_) => 0,
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_event_get_data1_str(event: *mut dc_event_t) -> *mut libc::c_char {
+    if event.is_null() {
+        eprintln!("ignoring careless call to dc_event_get_data1_str()");
+        return ptr::null_mut();
+    }
+    let event = &*event;
+    match event.data1 {
+        EventData::Str(ref data) => dc_strdup(data.as_ptr()),
+        EventData::Int(_) => ptr::null_mut(),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_event_get_data2_str(event: *mut dc_event_t) -> *mut libc::c_char {
+    if event.is_null() {
+        eprintln!("ignoring careless call to dc_event_get_data2_str()");
+        return ptr::null_mut();
+    }
+    let event = &*event;
+    match event.data2 {
+        EventData::Str(ref data) => dc_strdup(data.as_ptr()),
+        EventData::Int(_) => ptr::null_mut(),
+    }
+}
+
 // dc_provider_t
 
 #[no_mangle]
@@ -3411,3 +4091,432 @@ pub unsafe extern "C" fn dc_provider_unref(provider: *mut dc_provider_t) {
     // currently, there is nothing to free, the provider info is a static object.
     // this may change once we start localizing string.
 }
+
+// dc_backup_provider_t
+
+/// FFI backup provider wrapper.
+///
+/// This is the structure behind [dc_backup_provider_t], it keeps a reference
+/// to the context for logging errors.
+pub struct BackupProviderWrapper {
+    context: *const dc_context_t,
+    provider: imex::BackupProvider,
+}
+
+#[no_mangle]
+pub type dc_backup_provider_t = BackupProviderWrapper;
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_backup_provider_new(
+    context: *mut dc_context_t,
+) -> *mut dc_backup_provider_t {
+    if context.is_null() {
+        eprintln!("ignoring careless call to dc_backup_provider_new()");
+        return ptr::null_mut();
+    }
+    let ffi_context = &*context;
+    ffi_context
+        .with_inner(|ctx| match imex::BackupProvider::prepare(ctx) {
+            Ok(provider) => Box::into_raw(Box::new(BackupProviderWrapper { context, provider })),
+            Err(err) => {
+                error!(ctx, "dc_backup_provider_new: {}", err);
+                ptr::null_mut()
+            }
+        })
+        .unwrap_or_else(|_| ptr::null_mut())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_backup_provider_get_qr(
+    provider: *const dc_backup_provider_t,
+) -> *mut libc::c_char {
+    if provider.is_null() {
+        eprintln!("ignoring careless call to dc_backup_provider_get_qr()");
+        return "".strdup();
+    }
+    let ffi_provider = &*provider;
+    ffi_provider.provider.get_qr().strdup()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_backup_provider_wait(
+    provider: *mut dc_backup_provider_t,
+) -> libc::c_int {
+    if provider.is_null() {
+        eprintln!("ignoring careless call to dc_backup_provider_wait()");
+        return 0;
+    }
+    let ffi_provider = &*provider;
+    let ffi_context = &*ffi_provider.context;
+    let res = ffi_provider.provider.wait();
+    ffi_context
+        .with_inner(|ctx| match res {
+            Ok(()) => 1,
+            Err(err) => {
+                error!(ctx, "dc_backup_provider_wait: {}", err);
+                0
+            }
+        })
+        .unwrap_or(0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_backup_provider_cancel(provider: *mut dc_backup_provider_t) {
+    if provider.is_null() {
+        eprintln!("ignoring careless call to dc_backup_provider_cancel()");
+        return;
+    }
+    let ffi_provider = &*provider;
+    ffi_provider.provider.cancel();
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_backup_provider_unref(provider: *mut dc_backup_provider_t) {
+    if provider.is_null() {
+        eprintln!("ignoring careless call to dc_backup_provider_unref()");
+        return;
+    }
+    Box::from_raw(provider);
+}
+
+// dc_accounts_t
+
+/// The FFI accounts struct.
+///
+/// This structure represents the [Accounts] on the FFI interface.
+/// Events of all accounts are passed to the callback given to [dc_accounts_new],
+/// tagged with the ID of the account.
+pub struct AccountsWrapper {
+    cb: Option<dc_accounts_callback_t>,
+    userdata: *mut libc::c_void,
+    os_name: String,
+    inner: RwLock<Option<Accounts>>,
+}
+
+unsafe impl Send for AccountsWrapper {}
+unsafe impl Sync for AccountsWrapper {}
+
+/// Callback function that should be given to [dc_accounts_new].
+///
+/// @memberof [dc_accounts_t]
+/// @param accounts The accounts object as returned by [dc_accounts_new].
+/// @param account_id The ID of the account the event belongs to.
+/// @param event one of the @ref DC_EVENT constants
+/// @param data1 depends on the event parameter
+/// @param data2 depends on the event parameter
+/// @return return 0 unless stated otherwise in the event parameter documentation
+pub type dc_accounts_callback_t = unsafe extern "C" fn(
+    _: &dc_accounts_t,
+    _: u32,
+    _: i32,
+    _: uintptr_t,
+    _: uintptr_t,
+) -> uintptr_t;
+
+/// Struct representing the deltachat accounts.
+///
+/// See [AccountsWrapper] for implementation details.
+pub type dc_accounts_t = AccountsWrapper;
+
+impl AccountsWrapper {
+    /// Unlock the accounts and execute a closure with them.
+    ///
+    /// Like [ContextWrapper::with_inner], errors are only printed
+    /// as there is no account to log them to.
+    fn with_inner<T, F>(&self, accountsfn: F) -> Result<T, ()>
+    where
+        F: FnOnce(&Accounts) -> T,
+    {
+        let guard = self.inner.read().unwrap();
+        match guard.as_ref() {
+            Some(ref accounts) => Ok(accountsfn(accounts)),
+            None => {
+                eprintln!("accounts not open");
+                Err(())
+            }
+        }
+    }
+
+    /// Translates the callback from the rust style to the C-style version.
+    unsafe fn translate_cb(&self, account_id: u32, event: Event) {
+        if let Some(ffi_cb) = self.cb {
+            translate_event(event, |event_id, data1, data2| {
+                ffi_cb(self, account_id, event_id, data1, data2)
+            });
+        }
+    }
+
+    /// Creates a [dc_context_t] for an account.
+    ///
+    /// The returned context does not have a callback,
+    /// events are passed to the callback of the accounts object.
+    fn new_context_wrapper(&self, context: Arc<Context>) -> *mut dc_context_t {
+        let ffi_ctx = ContextWrapper {
+            cb: None,
+            userdata: ptr::null_mut(),
+            os_name: self.os_name.clone(),
+            inner: RwLock::new(Some(context)),
+        };
+        Box::into_raw(Box::new(ffi_ctx))
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_accounts_new(
+    cb: Option<dc_accounts_callback_t>,
+    userdata: *mut libc::c_void,
+    os_name: *const libc::c_char,
+    dir: *const libc::c_char,
+) -> *mut dc_accounts_t {
+    setup_panic!();
+
+    if dir.is_null() {
+        eprintln!("ignoring careless call to dc_accounts_new()");
+        return ptr::null_mut();
+    }
+    let os_name = if os_name.is_null() {
+        String::from("DcFFI")
+    } else {
+        to_string_lossy(os_name)
+    };
+    let ffi_accounts = Box::into_raw(Box::new(AccountsWrapper {
+        cb,
+        userdata,
+        os_name: os_name.clone(),
+        inner: RwLock::new(None),
+    }));
+
+    let wrapper = &*ffi_accounts;
+    let rust_cb =
+        move |account_id: u32, _ctx: &Context, evt: Event| wrapper.translate_cb(account_id, evt);
+    match Accounts::new(Box::new(rust_cb), os_name, as_path(dir).to_path_buf()) {
+        Ok(accounts) => {
+            *wrapper.inner.write().unwrap() = Some(accounts);
+            ffi_accounts
+        }
+        Err(err) => {
+            eprintln!("failed to open accounts: {}", err);
+            Box::from_raw(ffi_accounts);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Release the accounts structure.
+///
+/// The threads of all accounts are stopped before.
+#[no_mangle]
+pub unsafe extern "C" fn dc_accounts_unref(accounts: *mut dc_accounts_t) {
+    if accounts.is_null() {
+        eprintln!("ignoring careless call to dc_accounts_unref()");
+        return;
+    }
+    let ffi_accounts = &mut *accounts;
+    // drop the accounts first, their callback refers to the wrapper
+    ffi_accounts.inner.write().unwrap().take();
+    Box::from_raw(ffi_accounts);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_accounts_get_userdata(
+    accounts: *mut dc_accounts_t,
+) -> *mut libc::c_void {
+    if accounts.is_null() {
+        eprintln!("ignoring careless call to dc_accounts_get_userdata()");
+        return ptr::null_mut();
+    }
+    let ffi_accounts = &*accounts;
+    ffi_accounts.userdata
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_accounts_add_account(accounts: *mut dc_accounts_t) -> u32 {
+    if accounts.is_null() {
+        eprintln!("ignoring careless call to dc_accounts_add_account()");
+        return 0;
+    }
+    let ffi_accounts = &*accounts;
+    ffi_accounts
+        .with_inner(|accounts| {
+            accounts.add_account().unwrap_or_else(|err| {
+                eprintln!("failed to add account: {}", err);
+                0
+            })
+        })
+        .unwrap_or(0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_accounts_migrate_account(
+    accounts: *mut dc_accounts_t,
+    dbfile: *const libc::c_char,
+) -> u32 {
+    if accounts.is_null() || dbfile.is_null() {
+        eprintln!("ignoring careless call to dc_accounts_migrate_account()");
+        return 0;
+    }
+    let ffi_accounts = &*accounts;
+    let dbfile = as_path(dbfile).to_path_buf();
+    ffi_accounts
+        .with_inner(|accounts| {
+            accounts.migrate_account(dbfile).unwrap_or_else(|err| {
+                eprintln!("failed to migrate account: {}", err);
+                0
+            })
+        })
+        .unwrap_or(0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_accounts_remove_account(
+    accounts: *mut dc_accounts_t,
+    account_id: u32,
+) -> libc::c_int {
+    if accounts.is_null() {
+        eprintln!("ignoring careless call to dc_accounts_remove_account()");
+        return 0;
+    }
+    let ffi_accounts = &*accounts;
+    ffi_accounts
+        .with_inner(|accounts| match accounts.remove_account(account_id) {
+            Ok(()) => 1,
+            Err(err) => {
+                eprintln!("failed to remove account {}: {}", account_id, err);
+                0
+            }
+        })
+        .unwrap_or(0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_accounts_select_account(
+    accounts: *mut dc_accounts_t,
+    account_id: u32,
+) -> libc::c_int {
+    if accounts.is_null() {
+        eprintln!("ignoring careless call to dc_accounts_select_account()");
+        return 0;
+    }
+    let ffi_accounts = &*accounts;
+    ffi_accounts
+        .with_inner(|accounts| match accounts.select_account(account_id) {
+            Ok(()) => 1,
+            Err(err) => {
+                eprintln!("failed to select account {}: {}", account_id, err);
+                0
+            }
+        })
+        .unwrap_or(0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_accounts_get_all(accounts: *mut dc_accounts_t) -> *mut dc_array_t {
+    if accounts.is_null() {
+        eprintln!("ignoring careless call to dc_accounts_get_all()");
+        return ptr::null_mut();
+    }
+    let ffi_accounts = &*accounts;
+    let ids = ffi_accounts
+        .with_inner(|accounts| accounts.get_all())
+        .unwrap_or_default();
+    Box::into_raw(Box::new(dc_array_t::from(ids)))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_accounts_get_account(
+    accounts: *mut dc_accounts_t,
+    account_id: u32,
+) -> *mut dc_context_t {
+    if accounts.is_null() {
+        eprintln!("ignoring careless call to dc_accounts_get_account()");
+        return ptr::null_mut();
+    }
+    let ffi_accounts = &*accounts;
+    ffi_accounts
+        .with_inner(|accounts| match accounts.get_account(account_id) {
+            Some(context) => ffi_accounts.new_context_wrapper(context),
+            None => ptr::null_mut(),
+        })
+        .unwrap_or_else(|_| ptr::null_mut())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_accounts_get_selected_account(
+    accounts: *mut dc_accounts_t,
+) -> *mut dc_context_t {
+    if accounts.is_null() {
+        eprintln!("ignoring careless call to dc_accounts_get_selected_account()");
+        return ptr::null_mut();
+    }
+    let ffi_accounts = &*accounts;
+    ffi_accounts
+        .with_inner(|accounts| match accounts.get_selected_account() {
+            Some(context) => ffi_accounts.new_context_wrapper(context),
+            None => ptr::null_mut(),
+        })
+        .unwrap_or_else(|_| ptr::null_mut())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_accounts_get_selected_account_id(accounts: *mut dc_accounts_t) -> u32 {
+    if accounts.is_null() {
+        eprintln!("ignoring careless call to dc_accounts_get_selected_account_id()");
+        return 0;
+    }
+    let ffi_accounts = &*accounts;
+    ffi_accounts
+        .with_inner(|accounts| accounts.get_selected_account_id())
+        .unwrap_or(0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_accounts_start_io(accounts: *mut dc_accounts_t) {
+    if accounts.is_null() {
+        eprintln!("ignoring careless call to dc_accounts_start_io()");
+        return;
+    }
+    let ffi_accounts = &*accounts;
+    ffi_accounts
+        .with_inner(|accounts| accounts.start_io())
+        .unwrap_or(())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_accounts_stop_io(accounts: *mut dc_accounts_t) {
+    if accounts.is_null() {
+        eprintln!("ignoring careless call to dc_accounts_stop_io()");
+        return;
+    }
+    let ffi_accounts = &*accounts;
+    ffi_accounts
+        .with_inner(|accounts| accounts.stop_io())
+        .unwrap_or(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_account_unref_keeps_io_running() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = CString::new(dir.path().join("accounts").to_str().unwrap()).unwrap();
+        unsafe {
+            let accounts = dc_accounts_new(None, ptr::null_mut(), ptr::null(), dir.as_ptr());
+            assert!(!accounts.is_null());
+            let account_id = dc_accounts_add_account(accounts);
+            assert_ne!(account_id, 0);
+            dc_accounts_start_io(accounts);
+
+            let context = dc_accounts_get_account(accounts, account_id);
+            assert!(!context.is_null());
+            dc_context_unref(context);
+
+            let is_io_running = (*accounts)
+                .with_inner(|accounts| accounts.get_account(account_id).unwrap().is_io_running())
+                .unwrap();
+            assert!(is_io_running);
+            dc_accounts_unref(accounts);
+        }
+    }
+}
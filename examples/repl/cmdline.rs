@@ -320,8 +320,8 @@ pub fn dc_cmdline(context: &Context, line: &str) -> Result<(), failure::Error> {
                  get-setupcodebegin <msg-id>\n\
                  continue-key-transfer <msg-id> <setup-code>\n\
                  has-backup\n\
-                 export-backup\n\
-                 import-backup <backup-file>\n\
+                 export-backup <passphrase>\n\
+                 import-backup <backup-file> [<passphrase>]\n\
                  export-keys\n\
                  import-keys\n\
                  export-setup\n\
@@ -341,7 +341,6 @@ pub fn dc_cmdline(context: &Context, line: &str) -> Result<(), failure::Error> {
                  configure\n\
                  connect\n\
                  disconnect\n\
-                 interrupt\n\
                  maybenetwork\n\
                  housekeeping\n\
                  help imex (Import/Export)\n\
@@ -436,17 +435,28 @@ pub fn dc_cmdline(context: &Context, line: &str) -> Result<(), failure::Error> {
             has_backup(context, blobdir)?;
         }
         "export-backup" => {
-            imex(context, ImexMode::ExportBackup, Some(blobdir));
+            ensure!(!arg1.is_empty(), "Argument <passphrase> missing.");
+            imex(
+                context,
+                ImexMode::ExportBackup,
+                Some(blobdir),
+                Some(arg1.to_string()),
+            );
         }
         "import-backup" => {
             ensure!(!arg1.is_empty(), "Argument <backup-file> missing.");
-            imex(context, ImexMode::ImportBackup, Some(arg1));
+            imex(
+                context,
+                ImexMode::ImportBackup,
+                Some(arg1),
+                Some(arg2.to_string()),
+            );
         }
         "export-keys" => {
-            imex(context, ImexMode::ExportSelfKeys, Some(blobdir));
+            imex(context, ImexMode::ExportSelfKeys, Some(blobdir), None);
         }
         "import-keys" => {
-            imex(context, ImexMode::ImportSelfKeys, Some(blobdir));
+            imex(context, ImexMode::ImportSelfKeys, Some(blobdir), None);
         }
         "export-setup" => {
             let setup_code = create_setup_code(context);
@@ -486,9 +496,6 @@ pub fn dc_cmdline(context: &Context, line: &str) -> Result<(), failure::Error> {
         "info" => {
             println!("{:#?}", context.get_info());
         }
-        "interrupt" => {
-            interrupt_inbox_idle(context);
-        }
         "maybenetwork" => {
             maybe_network(context);
         }
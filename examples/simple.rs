@@ -1,6 +1,5 @@
 extern crate deltachat;
 
-use std::sync::{Arc, RwLock};
 use std::{thread, time};
 use tempfile::tempdir;
 
@@ -9,13 +8,9 @@ use deltachat::chatlist::*;
 use deltachat::config;
 use deltachat::contact::*;
 use deltachat::context::*;
-use deltachat::job::{
-    perform_inbox_fetch, perform_inbox_idle, perform_inbox_jobs, perform_smtp_idle,
-    perform_smtp_jobs,
-};
 use deltachat::Event;
 
-fn cb(_ctx: &Context, event: Event) {
+fn cb(event: Event) {
     print!("[{:?}]", event);
 
     match event {
@@ -35,39 +30,18 @@ fn main() {
     let dir = tempdir().unwrap();
     let dbfile = dir.path().join("db.sqlite");
     println!("creating database {:?}", dbfile);
-    let ctx =
-        Context::new(Box::new(cb), "FakeOs".into(), dbfile).expect("Failed to create context");
-    let running = Arc::new(RwLock::new(true));
+    let ctx = Context::new(None, "FakeOs".into(), dbfile).expect("Failed to create context");
+    let events = ctx.get_event_emitter();
+    thread::spawn(move || {
+        while let Some(event) = events.recv_sync() {
+            cb(event);
+        }
+    });
     let info = ctx.get_info();
     let duration = time::Duration::from_millis(4000);
     println!("info: {:#?}", info);
 
-    let ctx = Arc::new(ctx);
-    let ctx1 = ctx.clone();
-    let r1 = running.clone();
-    let t1 = thread::spawn(move || {
-        while *r1.read().unwrap() {
-            perform_inbox_jobs(&ctx1);
-            if *r1.read().unwrap() {
-                perform_inbox_fetch(&ctx1);
-
-                if *r1.read().unwrap() {
-                    perform_inbox_idle(&ctx1);
-                }
-            }
-        }
-    });
-
-    let ctx1 = ctx.clone();
-    let r1 = running.clone();
-    let t2 = thread::spawn(move || {
-        while *r1.read().unwrap() {
-            perform_smtp_jobs(&ctx1);
-            if *r1.read().unwrap() {
-                perform_smtp_idle(&ctx1);
-            }
-        }
-    });
+    ctx.start_io();
 
     println!("configuring");
     let args = std::env::args().collect::<Vec<String>>();
@@ -97,15 +71,8 @@ fn main() {
 
     thread::sleep(duration);
 
-    println!("stopping threads");
-
-    *running.write().unwrap() = false;
-    deltachat::job::interrupt_inbox_idle(&ctx);
-    deltachat::job::interrupt_smtp_idle(&ctx);
-
-    println!("joining");
-    t1.join().unwrap();
-    t2.join().unwrap();
+    println!("stopping IO");
+    ctx.stop_io();
 
     println!("closing");
 }
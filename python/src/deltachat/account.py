@@ -2,9 +2,9 @@
 
 from __future__ import print_function
 import atexit
-import threading
+import json
 import os
-import time
+from contextlib import contextmanager
 from array import array
 from queue import Queue
 
@@ -24,7 +24,7 @@ class Account(object):
     by the underlying deltachat core library.  All public Account methods are
     meant to be memory-safe and return memory-safe objects.
     """
-    def __init__(self, db_path, logid=None, os_name=None, debug=True):
+    def __init__(self, db_path, logid=None, os_name=None, debug=True, passphrase=None):
         """ initialize account object.
 
         :param db_path: a path to the account database. The database
@@ -33,13 +33,14 @@ class Account(object):
                       the default internal logging.
         :param os_name: this will be put to the X-Mailer header in outgoing messages
         :param debug: turn on debug logging for events.
+        :param passphrase: an optional passphrase to encrypt the database with,
+                           needs deltachat-core built with SQLCipher.
         """
         self._dc_context = ffi.gc(
             lib.dc_context_new(lib.py_dc_callback, ffi.NULL, as_dc_charpointer(os_name)),
             _destroy_dc_context,
         )
         self._evlogger = EventLogger(self, logid, debug)
-        self._threads = IOThreads(self._dc_context, self._evlogger._log_event)
 
         # register event call back and initialize plugin system
         def _ll_event(ctx, evt_name, data1, data2):
@@ -55,8 +56,13 @@ class Account(object):
         # open database
         if hasattr(db_path, "encode"):
             db_path = db_path.encode("utf8")
-        if not lib.dc_open(self._dc_context, db_path, ffi.NULL):
-            raise ValueError("Could not dc_open: {}".format(db_path))
+        if passphrase is None:
+            if not lib.dc_open(self._dc_context, db_path, ffi.NULL):
+                raise ValueError("Could not dc_open: {}".format(db_path))
+        else:
+            if not lib.dc_open_encrypted(self._dc_context, db_path, ffi.NULL,
+                                         as_dc_charpointer(passphrase)):
+                raise ValueError("Could not dc_open_encrypted: {}".format(db_path))
         self._configkeys = self.get_config("sys.config_keys").split()
         atexit.register(self.shutdown)
 
@@ -79,6 +85,22 @@ class Account(object):
             d[key.lower()] = value
         return d
 
+    def get_quota(self):
+        """ return dictionary with the storage quota of the account.
+
+        :returns: dictionary with "resources", a list of dictionaries
+            with "root", "name", "usage" and "limit", and "timestamp";
+            None if the quota was not requested from the server yet.
+        """
+        s = from_dc_charpointer(lib.dc_get_quota_json(self._dc_context))
+        return json.loads(s)
+
+    def get_connectivity_info(self):
+        """ return human readable summary of the server connections
+        and the storage quota, one item per line.
+        """
+        return from_dc_charpointer(lib.dc_get_connectivity_info(self._dc_context))
+
     def set_stock_translation(self, id, string):
         """ set stock translation string.
 
@@ -252,6 +274,34 @@ class Account(object):
         )
         return list(iter_array(dc_array, lambda x: Contact(self._dc_context, x)))
 
+    def make_vcard(self, contacts):
+        """ export contacts as a vCard.
+
+        :param contacts: list of contact ids or Contact instances.
+        :returns: vCard as text.
+        """
+        arr = array("i")
+        for contact in contacts:
+            contact = getattr(contact, "id", contact)
+            arr.append(contact)
+        contact_ids = ffi.cast("uint32_t*", ffi.from_buffer(arr))
+        res = lib.dc_make_vcard(self._dc_context, contact_ids, len(contacts))
+        if res == ffi.NULL:
+            raise ValueError("could not export contacts as vCard")
+        return from_dc_charpointer(res)
+
+    def import_vcard(self, vcard):
+        """ import contacts from a vCard.
+
+        :param vcard: vCard as text, may contain several contacts.
+        :returns: list of imported :class:`deltachat.contact.Contact` objects.
+        """
+        dc_array = ffi.gc(
+            lib.dc_import_vcard(self._dc_context, as_dc_charpointer(vcard)),
+            lib.dc_array_unref
+        )
+        return list(iter_array(dc_array, lambda x: Contact(self._dc_context, x)))
+
     def create_chat_by_contact(self, contact):
         """ create or get an existing 1:1 chat object for the specified contact or contact id.
 
@@ -367,26 +417,48 @@ class Account(object):
         msg_ids = [msg.id for msg in messages]
         lib.dc_delete_msgs(self._dc_context, msg_ids, len(msg_ids))
 
+    def resend_messages(self, messages):
+        """ send own messages again that could not be sent yet.
+
+        :param messages: list of pending or failed :class:`deltachat.message.Message` objects.
+        :returns: None
+        """
+        msg_ids = [msg.id for msg in messages]
+        if not lib.dc_resend_msgs(self._dc_context, msg_ids, len(msg_ids)):
+            raise ValueError("could not resend messages")
+
+    def get_outbox_messages(self):
+        """ return own messages of all chats that are pending or failed.
+
+        :returns: list of :class:`deltachat.message.Message` objects, most recent first.
+        """
+        dc_array = ffi.gc(
+            lib.dc_get_outbox_msgs(self._dc_context),
+            lib.dc_array_unref
+        )
+        return list(iter_array(dc_array, lambda x: Message.from_db(self, x)))
+
     def export_self_keys(self, path):
         """ export public and private keys to the specified directory. """
         return self._export(path, imex_cmd=1)
 
-    def export_all(self, path):
+    def export_all(self, path, passphrase=None):
         """return new file containing a backup of all database state
         (chats, contacts, keys, media, ...). The file is created in the
-        the `path` directory.
+        the `path` directory and encrypted with `passphrase`,
+        without passphrase the backup is not encrypted.
         """
-        export_files = self._export(path, 11)
+        export_files = self._export(path, 11, passphrase)
         if len(export_files) != 1:
             raise RuntimeError("found more than one new file")
         return export_files[0]
 
-    def _export(self, path, imex_cmd):
+    def _export(self, path, imex_cmd, passphrase=None):
         with ImexTracker(self) as imex_tracker:
-            lib.dc_imex(self._dc_context, imex_cmd, as_dc_charpointer(path), ffi.NULL)
-            if not self._threads.is_started():
-                lib.dc_perform_imap_jobs(self._dc_context)
-            return imex_tracker.wait_finish()
+            lib.dc_imex(self._dc_context, imex_cmd, as_dc_charpointer(path),
+                        as_dc_charpointer(passphrase) if passphrase else ffi.NULL)
+            with self._temporary_io():
+                return imex_tracker.wait_finish()
 
     def import_self_keys(self, path):
         """ Import private keys found in the `path` directory.
@@ -395,20 +467,21 @@ class Account(object):
         """
         self._import(path, imex_cmd=2)
 
-    def import_all(self, path):
-        """import delta chat state from the specified backup `path` (a file).
+    def import_all(self, path, passphrase=None):
+        """import delta chat state from the specified backup `path` (a file)
+        encrypted with `passphrase`, None for unencrypted backups.
 
         The account must be in unconfigured state for import to attempted.
         """
         assert not self.is_configured(), "cannot import into configured account"
-        self._import(path, imex_cmd=12)
+        self._import(path, imex_cmd=12, passphrase=passphrase)
 
-    def _import(self, path, imex_cmd):
+    def _import(self, path, imex_cmd, passphrase=None):
         with ImexTracker(self) as imex_tracker:
-            lib.dc_imex(self._dc_context, imex_cmd, as_dc_charpointer(path), ffi.NULL)
-            if not self._threads.is_started():
-                lib.dc_perform_imap_jobs(self._dc_context)
-            imex_tracker.wait_finish()
+            lib.dc_imex(self._dc_context, imex_cmd, as_dc_charpointer(path),
+                        as_dc_charpointer(passphrase) if passphrase else ffi.NULL)
+            with self._temporary_io():
+                imex_tracker.wait_finish()
 
     def initiate_key_transfer(self):
         """return setup code after a Autocrypt setup message
@@ -416,8 +489,8 @@ class Account(object):
         If sending out was unsuccessful, a RuntimeError is raised.
         """
         self.check_is_configured()
-        if not self._threads.is_started():
-            raise RuntimeError("threads not running, can not send out")
+        if not self.is_io_running():
+            raise RuntimeError("IO not running, can not send out")
         res = lib.dc_initiate_key_transfer(self._dc_context)
         if res == ffi.NULL:
             raise RuntimeError("could not send out autocrypt setup message")
@@ -445,11 +518,11 @@ class Account(object):
         return ScannedQRCode(lot)
 
     def qr_setup_contact(self, qr):
-        """ setup contact and return a Chat after contact is established.
+        """ start the setup contact protocol and return the Chat with the contact.
 
-        Note that this function may block for a long time as messages are exchanged
-        with the emitter of the QR code.  On success a :class:`deltachat.chat.Chat` instance
-        is returned.
+        The function returns immediately, the messages with the emitter of the QR code
+        are exchanged in the background.  When the contact is established,
+        DC_EVENT_SECUREJOIN_JOINER_PROGRESS is emitted with a progress of 1000.
         :param qr: valid "setup contact" QR code (all other QR codes will result in an exception)
         """
         assert self.check_qr(qr).is_ask_verifycontact()
@@ -459,11 +532,11 @@ class Account(object):
         return Chat(self, chat_id)
 
     def qr_join_chat(self, qr):
-        """ join a chat group through a QR code.
+        """ join a chat group through a QR code and return the group Chat.
 
-        Note that this function may block for a long time as messages are exchanged
-        with the emitter of the QR code.  On success a :class:`deltachat.chat.Chat` instance
-        is returned which is the chat that we just joined.
+        The function returns immediately, the messages with the emitter of the QR code
+        are exchanged in the background.  When the group is joined,
+        DC_EVENT_SECUREJOIN_JOINER_PROGRESS is emitted with a progress of 1000.
 
         :param qr: valid "join-group" QR code (all other QR codes will result in an exception)
         """
@@ -485,29 +558,63 @@ class Account(object):
         ev = self._evlogger.get_matching("DC_EVENT_INCOMING_MSG")
         return self.get_message_by_id(ev[2])
 
-    def start_threads(self, mvbox=False, sentbox=False):
-        """ start IMAP/SMTP threads (and configure account if it hasn't happened).
+    def start_io(self):
+        """ start IMAP/SMTP IO (and configure account if it hasn't happened).
+
+        The folders watched besides the INBOX are defined by the
+        `mvbox_watch` and `sentbox_watch` config options.
 
         :raises: ValueError if 'addr' or 'mail_pw' are not configured.
         :returns: None
         """
         if not self.is_configured():
             self.configure()
-        self._threads.start(mvbox=mvbox, sentbox=sentbox)
+        lib.dc_start_io(self._dc_context)
 
-    def stop_threads(self, wait=True):
-        """ stop IMAP/SMTP threads. """
-        if self._threads.is_started():
-            self.stop_ongoing()
-            self._threads.stop(wait=wait)
+    def change_passphrase(self, passphrase):
+        """ change the passphrase the database is encrypted with.
+
+        IO must be stopped before.
 
-    def shutdown(self, wait=True):
-        """ stop threads and close and remove underlying dc_context and callbacks. """
-        if hasattr(self, "_dc_context") and hasattr(self, "_threads"):
+        :param passphrase: the new passphrase, None removes the encryption.
+        :returns: None
+        """
+        if not lib.dc_change_passphrase(self._dc_context, as_dc_charpointer(passphrase)):
+            raise ValueError("could not change passphrase")
+
+    def stop_io(self):
+        """ stop IMAP/SMTP IO and wait until it is stopped. """
+        if self.is_io_running():
+            self.stop_ongoing()
+            lib.dc_stop_io(self._dc_context)
+
+    def is_io_running(self):
+        """ return True if the IMAP/SMTP IO is running. """
+        return bool(lib.dc_is_io_running(self._dc_context))
+
+    def maybe_network(self):
+        """ hint that the network is available again,
+        pending messages are sent and new messages are fetched. """
+        lib.dc_maybe_network(self._dc_context)
+
+    @contextmanager
+    def _temporary_io(self):
+        """ run IO while in the context, unless it is running already. """
+        if self.is_io_running():
+            yield
+            return
+        lib.dc_start_io(self._dc_context)
+        try:
+            yield
+        finally:
+            lib.dc_stop_io(self._dc_context)
+
+    def shutdown(self):
+        """ stop IO and close and remove underlying dc_context and callbacks. """
+        if hasattr(self, "_dc_context"):
             # print("SHUTDOWN", self)
-            self.stop_threads(wait=False)
+            self.stop_io()
             lib.dc_close(self._dc_context)
-            self.stop_threads(wait=wait)  # to wait for threads
             deltachat.clear_context_callback(self._dc_context)
             del self._dc_context
             atexit.unregister(self.shutdown)
@@ -563,86 +670,6 @@ class ImexTracker:
                 return files_written
 
 
-class IOThreads:
-    def __init__(self, dc_context, log_event=lambda *args: None):
-        self._dc_context = dc_context
-        self._thread_quitflag = False
-        self._name2thread = {}
-        self._log_event = log_event
-
-    def is_started(self):
-        return len(self._name2thread) > 0
-
-    def start(self, imap=True, smtp=True, mvbox=False, sentbox=False):
-        assert not self.is_started()
-        if imap:
-            self._start_one_thread("inbox", self.imap_thread_run)
-        if mvbox:
-            self._start_one_thread("mvbox", self.mvbox_thread_run)
-        if sentbox:
-            self._start_one_thread("sentbox", self.sentbox_thread_run)
-        if smtp:
-            self._start_one_thread("smtp", self.smtp_thread_run)
-
-    def _start_one_thread(self, name, func):
-        self._name2thread[name] = t = threading.Thread(target=func, name=name)
-        t.setDaemon(1)
-        t.start()
-
-    def stop(self, wait=False):
-        self._thread_quitflag = True
-
-        # Workaround for a race condition. Make sure that thread is
-        # not in between checking for quitflag and entering idle.
-        time.sleep(0.5)
-
-        lib.dc_interrupt_imap_idle(self._dc_context)
-        lib.dc_interrupt_smtp_idle(self._dc_context)
-        lib.dc_interrupt_mvbox_idle(self._dc_context)
-        lib.dc_interrupt_sentbox_idle(self._dc_context)
-        if wait:
-            for name, thread in self._name2thread.items():
-                thread.join()
-
-    def imap_thread_run(self):
-        self._log_event("py-bindings-info", 0, "INBOX THREAD START")
-        while not self._thread_quitflag:
-            lib.dc_perform_imap_jobs(self._dc_context)
-            if not self._thread_quitflag:
-                lib.dc_perform_imap_fetch(self._dc_context)
-            if not self._thread_quitflag:
-                lib.dc_perform_imap_idle(self._dc_context)
-        self._log_event("py-bindings-info", 0, "INBOX THREAD FINISHED")
-
-    def mvbox_thread_run(self):
-        self._log_event("py-bindings-info", 0, "MVBOX THREAD START")
-        while not self._thread_quitflag:
-            lib.dc_perform_mvbox_jobs(self._dc_context)
-            if not self._thread_quitflag:
-                lib.dc_perform_mvbox_fetch(self._dc_context)
-            if not self._thread_quitflag:
-                lib.dc_perform_mvbox_idle(self._dc_context)
-        self._log_event("py-bindings-info", 0, "MVBOX THREAD FINISHED")
-
-    def sentbox_thread_run(self):
-        self._log_event("py-bindings-info", 0, "SENTBOX THREAD START")
-        while not self._thread_quitflag:
-            lib.dc_perform_sentbox_jobs(self._dc_context)
-            if not self._thread_quitflag:
-                lib.dc_perform_sentbox_fetch(self._dc_context)
-            if not self._thread_quitflag:
-                lib.dc_perform_sentbox_idle(self._dc_context)
-        self._log_event("py-bindings-info", 0, "SENTBOX THREAD FINISHED")
-
-    def smtp_thread_run(self):
-        self._log_event("py-bindings-info", 0, "SMTP THREAD START")
-        while not self._thread_quitflag:
-            lib.dc_perform_smtp_jobs(self._dc_context)
-            if not self._thread_quitflag:
-                lib.dc_perform_smtp_idle(self._dc_context)
-        self._log_event("py-bindings-info", 0, "SMTP THREAD FINISHED")
-
-
 def _destroy_dc_context(dc_context, dc_context_unref=lib.dc_context_unref):
     # destructor for dc_context
     dc_context_unref(dc_context)
@@ -81,6 +81,43 @@ class Chat(object):
         """
         return lib.dc_chat_is_verified(self._dc_chat)
 
+    def is_mailinglist(self):
+        """ return True if this chat is a mailing list.
+
+        :returns: True if chat is a mailing list, False otherwise.
+        """
+        return lib.dc_chat_get_type(self._dc_chat) == const.DC_CHAT_TYPE_MAILINGLIST
+
+    def get_mailinglist_addr(self):
+        """ return the address messages to this mailing list are sent to.
+
+        :returns: address, None if this chat is no mailing list or the list is read-only.
+        """
+        dc_res = lib.dc_chat_get_mailinglist_addr(self._dc_chat)
+        if dc_res == ffi.NULL:
+            return None
+        return from_dc_charpointer(dc_res)
+
+    def get_mailinglist_unsubscribe(self):
+        """ return the URI to unsubscribe from this mailing list.
+
+        :returns: mailto: or https: URI, None if the list offers no way to unsubscribe.
+        """
+        dc_res = lib.dc_chat_get_mailinglist_unsubscribe(self._dc_chat)
+        if dc_res == ffi.NULL:
+            return None
+        return from_dc_charpointer(dc_res)
+
+    def set_mailinglist_sending(self, enabled):
+        """ allow or forbid sending messages to this mailing list.
+
+        :param enabled: True to allow sending, False to make the mailing list read-only.
+        :returns: None
+        """
+        ret = lib.dc_set_mailinglist_sending(self._dc_context, self.id, int(enabled))
+        if not bool(ret):
+            raise ValueError("Call to dc_set_mailinglist_sending failed")
+
     def get_name(self):
         """ return name of this chat.
 
@@ -184,6 +221,45 @@ class Chat(object):
             raise ValueError("message could not be send, does chat exist?")
         return Message.from_db(self.account, msg_id)
 
+    def send_text_at(self, text, send_at):
+        """ schedule a text message to be sent later and return the resulting Message instance.
+
+        :param text: unicode text
+        :param send_at: naive UTC datetime.datetime() object, must be in the future.
+        :raises ValueError: if message can not be scheduled.
+        :returns: the resulting :class:`deltachat.message.Message` instance
+        """
+        msg = Message.new_empty(self.account, "text")
+        msg.set_text(text)
+        timestamp = calendar.timegm(send_at.utctimetuple())
+        msg_id = lib.dc_send_msg_at(self._dc_context, self.id, msg._dc_msg, timestamp)
+        if msg_id == 0:
+            raise ValueError("message could not be scheduled")
+        return Message.from_db(self.account, msg_id)
+
+    def get_scheduled_messages(self):
+        """ return messages of this chat scheduled to be sent later.
+
+        :returns: list of :class:`deltachat.message.Message` objects,
+            ordered by the time they are sent at.
+        """
+        dc_array = ffi.gc(
+            lib.dc_get_scheduled_msgs(self._dc_context, self.id),
+            lib.dc_array_unref
+        )
+        return list(iter_array(dc_array, lambda x: Message.from_db(self.account, x)))
+
+    def send_typing(self, typing=True):
+        """ tell the other chat members that we started or stopped typing.
+
+        Nothing is sent unless the "typing_notifications" config option is enabled.
+
+        :param typing: True if we started typing, False if we stopped typing.
+        :raises ValueError: if the chat does not accept typing notifications.
+        """
+        if not lib.dc_send_typing(self._dc_context, self.id, 1 if typing else 0):
+            raise ValueError("could not send typing notification")
+
     def send_file(self, path, mime_type="application/octet-stream"):
         """ send a file and return the resulting Message instance.
 
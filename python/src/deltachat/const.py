@@ -19,6 +19,7 @@ DC_QR_FPR_OK = 210
 DC_QR_FPR_MISMATCH = 220
 DC_QR_FPR_WITHOUT_ADDR = 230
 DC_QR_ACCOUNT = 250
+DC_QR_BACKUP = 251
 DC_QR_ADDR = 320
 DC_QR_TEXT = 330
 DC_QR_URL = 332
@@ -34,6 +35,7 @@ DC_CHAT_TYPE_UNDEFINED = 0
 DC_CHAT_TYPE_SINGLE = 100
 DC_CHAT_TYPE_GROUP = 120
 DC_CHAT_TYPE_VERIFIED_GROUP = 130
+DC_CHAT_TYPE_MAILINGLIST = 140
 DC_MSG_ID_MARKER1 = 1
 DC_MSG_ID_DAYMARKER = 9
 DC_MSG_ID_LAST_SPECIAL = 9
@@ -44,6 +46,7 @@ DC_STATE_IN_SEEN = 16
 DC_STATE_OUT_PREPARING = 18
 DC_STATE_OUT_DRAFT = 19
 DC_STATE_OUT_PENDING = 20
+DC_STATE_OUT_SCHEDULED = 22
 DC_STATE_OUT_FAILED = 24
 DC_STATE_OUT_DELIVERED = 26
 DC_STATE_OUT_MDN_RCVD = 28
@@ -86,6 +89,7 @@ DC_EVENT_ERROR = 400
 DC_EVENT_ERROR_NETWORK = 401
 DC_EVENT_ERROR_SELF_NOT_IN_GROUP = 410
 DC_EVENT_MSGS_CHANGED = 2000
+DC_EVENT_REACTIONS_CHANGED = 2001
 DC_EVENT_INCOMING_MSG = 2005
 DC_EVENT_MSG_DELIVERED = 2010
 DC_EVENT_MSG_FAILED = 2012
@@ -99,6 +103,8 @@ DC_EVENT_IMEX_FILE_WRITTEN = 2052
 DC_EVENT_SECUREJOIN_INVITER_PROGRESS = 2060
 DC_EVENT_SECUREJOIN_JOINER_PROGRESS = 2061
 DC_EVENT_SECUREJOIN_MEMBER_ADDED = 2062
+DC_EVENT_CONTACT_TYPING = 2070
+DC_EVENT_CONTACT_STOPPED_TYPING = 2071
 DC_EVENT_FILE_COPIED = 2055
 DC_EVENT_IS_OFFLINE = 2081
 DC_EVENT_GET_STRING = 2091
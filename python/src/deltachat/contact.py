@@ -57,3 +57,10 @@ class Contact(object):
         if dc_res == ffi.NULL:
             return None
         return from_dc_charpointer(dc_res)
+
+    def get_status(self):
+        """Get contact status, the signature of the contact's last message.
+
+        :returns: status text, empty string if the contact has no status.
+        """
+        return from_dc_charpointer(lib.dc_contact_get_status(self._dc_contact))
@@ -104,6 +104,23 @@ class Message(object):
         """
         return from_dc_charpointer(lib.dc_get_msg_info(self._dc_context, self.id))
 
+    def get_read_receipts(self):
+        """ Return the read receipts of an own message.
+
+        :returns: list of (:class:`deltachat.contact.Contact`, naive datetime.datetime())
+            tuples, the earliest first.
+        """
+        from .contact import Contact
+        dc_array = ffi.gc(
+            lib.dc_get_msg_read_receipts(self._dc_context, self.id),
+            lib.dc_array_unref
+        )
+        return [
+            (Contact(self._dc_context, lib.dc_array_get_id(dc_array, i)),
+             datetime.utcfromtimestamp(lib.dc_array_get_timestamp(dc_array, i)))
+            for i in range(lib.dc_array_get_cnt(dc_array))
+        ]
+
     def continue_key_transfer(self, setup_code):
         """ extract key and use it as primary key for this account. """
         res = lib.dc_continue_key_transfer(
@@ -217,11 +234,29 @@ class Message(object):
         """
         return self._msgstate == const.DC_STATE_OUT_PENDING
 
+    def is_out_scheduled(self):
+        """Return True if Message is scheduled to be sent later.
+        """
+        return self._msgstate == const.DC_STATE_OUT_SCHEDULED
+
     def is_out_failed(self):
         """Return True if Message is unrecoverably failed.
         """
         return self._msgstate == const.DC_STATE_OUT_FAILED
 
+    def get_error(self):
+        """Return the error of the last failed try to send the message or None.
+        """
+        res = lib.dc_msg_get_error(self._dc_msg)
+        if res != ffi.NULL:
+            return from_dc_charpointer(res)
+
+    def get_send_tries(self):
+        """Return the number of failed tries to send the message,
+        0 if the message is not waiting for a retry.
+        """
+        return lib.dc_msg_get_send_tries(self._dc_msg)
+
     def is_out_delivered(self):
         """Return True if Message was successfully delivered to the server (one checkmark).
 
@@ -222,8 +222,10 @@ def acfactory(pytestconfig, tmpdir, request, session_liveconfig, datadir):
             ac, configdict = self.get_online_config(
                 pre_generated_key=pre_generated_key)
             configdict.update(config)
+            configdict.setdefault("mvbox_watch", str(int(mvbox)))
+            configdict.setdefault("sentbox_watch", str(int(sentbox)))
             ac.configure(**configdict)
-            ac.start_threads(mvbox=mvbox, sentbox=sentbox)
+            ac.start_io()
             return ac
 
         def get_one_online_account(self, pre_generated_key=True):
@@ -251,7 +253,7 @@ def acfactory(pytestconfig, tmpdir, request, session_liveconfig, datadir):
             ac._evlogger.init_time = self.init_time
             ac._evlogger.set_timeout(30)
             ac.configure(addr=account.get_config("addr"), mail_pw=account.get_config("mail_pw"))
-            ac.start_threads()
+            ac.start_io()
             return ac
 
     am = AccountMaker()
@@ -295,6 +297,16 @@ def wait_securejoin_inviter_progress(account, target):
             break
 
 
+def wait_securejoin_joiner_progress(account, target):
+    while 1:
+        evt_name, data1, data2 = \
+            account._evlogger.get_matching("DC_EVENT_SECUREJOIN_JOINER_PROGRESS")
+        assert data2 != 0, "securejoin handshake failed"
+        if data2 >= target:
+            print("** SECUREJOIN-JOINER PROGRESS {}".format(target), account)
+            break
+
+
 def wait_successful_IMAP_SMTP_connection(account):
     imap_ok = smtp_ok = False
     while not imap_ok or not smtp_ok:
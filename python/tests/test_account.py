@@ -7,7 +7,8 @@ from deltachat import const, Account
 from deltachat.message import Message
 from datetime import datetime, timedelta
 from conftest import (wait_configuration_progress,
-                      wait_securejoin_inviter_progress)
+                      wait_securejoin_inviter_progress,
+                      wait_securejoin_joiner_progress)
 
 
 class TestOfflineAccountBasic:
@@ -38,6 +39,11 @@ class TestOfflineAccountBasic:
         assert d["number_of_chats"] == "0"
         assert d["bcc_self"] == "0"
 
+    def test_get_quota_not_requested(self, acfactory):
+        ac1 = acfactory.get_unconfigured_account()
+        assert ac1.get_quota() is None
+        assert "Storage: not requested yet" in ac1.get_connectivity_info()
+
     def test_is_not_configured(self, acfactory):
         ac1 = acfactory.get_unconfigured_account()
         assert not ac1.is_configured()
@@ -365,10 +371,10 @@ class TestOfflineChat:
         assert contact == ac1.get_self_contact()
         assert not backupdir.listdir()
 
-        path = ac1.export_all(backupdir.strpath)
+        path = ac1.export_all(backupdir.strpath, "secret")
         assert os.path.exists(path)
         ac2 = acfactory.get_unconfigured_account()
-        ac2.import_all(path)
+        ac2.import_all(path, "secret")
         contacts = ac2.get_contacts(query="some1")
         assert len(contacts) == 1
         contact2 = contacts[0]
@@ -983,7 +989,7 @@ class TestOnlineAccount:
         backupdir = tmpdir.mkdir("backup")
 
         lp.sec("export all to {}".format(backupdir))
-        path = ac1.export_all(backupdir.strpath)
+        path = ac1.export_all(backupdir.strpath, "secret")
         assert os.path.exists(path)
         t = time.time()
 
@@ -995,7 +1001,7 @@ class TestOnlineAccount:
         assert path2 == path
 
         lp.sec("import backup and check it's proper")
-        ac2.import_all(path)
+        ac2.import_all(path, "secret")
         contacts = ac2.get_contacts(query="some1")
         assert len(contacts) == 1
         contact2 = contacts[0]
@@ -1011,7 +1017,7 @@ class TestOnlineAccount:
         # backups in one second.
         time.sleep(max(0, 1 - (time.time() - t)))
         lp.sec("Second-time export all to {}".format(backupdir))
-        path2 = ac1.export_all(backupdir.strpath)
+        path2 = ac1.export_all(backupdir.strpath, "secret")
         assert os.path.exists(path2)
         assert path2 != path
         assert ac2.get_latest_backupfile(backupdir.strpath) == path2
@@ -1072,6 +1078,7 @@ class TestOnlineAccount:
         ch = ac2.qr_setup_contact(qr)
         assert ch.id >= 10
         wait_securejoin_inviter_progress(ac1, 1000)
+        wait_securejoin_joiner_progress(ac2, 1000)
 
     def test_qr_join_chat(self, acfactory, lp):
         ac1, ac2 = acfactory.get_two_online_accounts()
@@ -1097,6 +1104,8 @@ class TestOnlineAccount:
         chat2 = ac2.qr_join_chat(qr)
         assert chat2.id >= 10
         wait_securejoin_inviter_progress(ac1, 1000)
+        wait_securejoin_joiner_progress(ac2, 1000)
+        assert chat2.is_verified()
         ac1._evlogger.get_matching("DC_EVENT_SECUREJOIN_MEMBER_ADDED")
 
         lp.sec("ac2: read member added message")
@@ -1371,7 +1380,7 @@ class TestOnlineConfigureFails:
     def test_invalid_password(self, acfactory):
         ac1, configdict = acfactory.get_online_config()
         ac1.configure(addr=configdict["addr"], mail_pw="123")
-        ac1.start_threads()
+        ac1.start_io()
         wait_configuration_progress(ac1, 500)
         ev1 = ac1._evlogger.get_matching("DC_EVENT_ERROR_NETWORK")
         assert "cannot login" in ev1[2].lower()
@@ -1380,7 +1389,7 @@ class TestOnlineConfigureFails:
     def test_invalid_user(self, acfactory):
         ac1, configdict = acfactory.get_online_config()
         ac1.configure(addr="x" + configdict["addr"], mail_pw=configdict["mail_pw"])
-        ac1.start_threads()
+        ac1.start_io()
         wait_configuration_progress(ac1, 500)
         ev1 = ac1._evlogger.get_matching("DC_EVENT_ERROR_NETWORK")
         assert "cannot login" in ev1[2].lower()
@@ -1389,7 +1398,7 @@ class TestOnlineConfigureFails:
     def test_invalid_domain(self, acfactory):
         ac1, configdict = acfactory.get_online_config()
         ac1.configure(addr=configdict["addr"] + "x", mail_pw=configdict["mail_pw"])
-        ac1.start_threads()
+        ac1.start_io()
         wait_configuration_progress(ac1, 500)
         ev1 = ac1._evlogger.get_matching("DC_EVENT_ERROR_NETWORK")
         assert "could not connect" in ev1[2].lower()
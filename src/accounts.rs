@@ -0,0 +1,582 @@
+//! # Account manager module
+//!
+//! [Accounts] manages several [Context]s, one per account, in a common directory.
+//! Every account lives in its own subdirectory containing the database and the blobdir,
+//! the list of accounts and the selected account are stored in an index file.
+//!
+//! Events of all accounts are passed to a single callback
+//! together with the ID of the account the event belongs to.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::context::Context;
+use crate::error::Result;
+use crate::events::Event;
+
+/// Callback function type for [Accounts].
+///
+/// Like [crate::context::ContextCallback] but additionally gets the ID of the account.
+pub type AccountsCallback = dyn Fn(u32, &Context, Event) -> () + Send + Sync;
+
+/// Name of the index file in the accounts directory.
+const INDEX_FILE: &str = "accounts.json";
+
+/// Name of the database file in an account directory.
+const DB_NAME: &str = "dc.db";
+
+/// Persistent list of accounts, stored in [INDEX_FILE].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct AccountsIndex {
+    /// ID of the selected account, `0` if no account is selected.
+    selected_account: u32,
+
+    /// ID the next added account will get; IDs are not reused.
+    next_id: u32,
+
+    accounts: Vec<AccountConfig>,
+}
+
+impl Default for AccountsIndex {
+    fn default() -> Self {
+        AccountsIndex {
+            selected_account: 0,
+            next_id: 1,
+            accounts: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct AccountConfig {
+    id: u32,
+
+    /// Directory of the account, relative to the accounts directory.
+    dir: PathBuf,
+}
+
+/// A set of accounts stored in one directory.
+#[derive(DebugStub)]
+pub struct Accounts {
+    /// Directory containing the index file and the account directories.
+    dir: PathBuf,
+    os_name: String,
+    index: RwLock<AccountsIndex>,
+    accounts: RwLock<BTreeMap<u32, Arc<Context>>>,
+    /// Whether the IO of newly added accounts is started.
+    running: AtomicBool,
+    #[debug_stub = "Callback"]
+    cb: Arc<AccountsCallback>,
+}
+
+impl Accounts {
+    /// Opens the accounts in `dir`, the directory is created if it does not exist.
+    pub fn new(cb: Box<AccountsCallback>, os_name: String, dir: PathBuf) -> Result<Accounts> {
+        if !dir.exists() {
+            fs::create_dir_all(&dir)?;
+        }
+        ensure!(
+            dir.is_dir(),
+            "Accounts directory is not a directory: {}",
+            dir.display()
+        );
+
+        let index_file = dir.join(INDEX_FILE);
+        let index: AccountsIndex = if index_file.exists() {
+            serde_json::from_slice(&fs::read(&index_file)?)
+                .map_err(|err| format_err!("Failed to parse {}: {}", index_file.display(), err))?
+        } else {
+            AccountsIndex::default()
+        };
+
+        let accounts = Accounts {
+            dir,
+            os_name,
+            index: RwLock::new(index.clone()),
+            accounts: RwLock::new(BTreeMap::new()),
+            running: AtomicBool::new(false),
+            cb: Arc::from(cb),
+        };
+        for account in &index.accounts {
+            let context = accounts.open_context(account.id, &account.dir)?;
+            accounts
+                .accounts
+                .write()
+                .unwrap()
+                .insert(account.id, Arc::new(context));
+        }
+        accounts.write_index()?;
+
+        Ok(accounts)
+    }
+
+    /// Returns the directory containing all accounts.
+    pub fn get_dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the account with the given ID.
+    pub fn get_account(&self, id: u32) -> Option<Arc<Context>> {
+        self.accounts.read().unwrap().get(&id).cloned()
+    }
+
+    /// Returns the IDs of all accounts.
+    pub fn get_all(&self) -> Vec<u32> {
+        self.accounts.read().unwrap().keys().copied().collect()
+    }
+
+    /// Returns the selected account, if any.
+    pub fn get_selected_account(&self) -> Option<Arc<Context>> {
+        let id = self.index.read().unwrap().selected_account;
+        self.get_account(id)
+    }
+
+    /// Returns the ID of the selected account, `0` if no account is selected.
+    pub fn get_selected_account_id(&self) -> u32 {
+        self.index.read().unwrap().selected_account
+    }
+
+    /// Selects the account with the given ID.
+    pub fn select_account(&self, id: u32) -> Result<()> {
+        ensure!(
+            self.accounts.read().unwrap().contains_key(&id),
+            "Account {} does not exist",
+            id
+        );
+        self.index.write().unwrap().selected_account = id;
+        self.write_index()
+    }
+
+    /// Adds a new, unconfigured account and selects it.
+    ///
+    /// Returns the ID of the new account.
+    pub fn add_account(&self) -> Result<u32> {
+        let id = self.index.read().unwrap().next_id;
+        let dir = PathBuf::from(format!("account-{}", id));
+        self.insert_account(id, dir)
+    }
+
+    /// Moves an existing database and its blobdir into the accounts directory
+    /// and adds it as a new account that is selected.
+    ///
+    /// The blobdir is expected next to the database,
+    /// as created by [Context::new].
+    /// If the account cannot be opened, the database and the blobdir are moved back.
+    ///
+    /// Returns the ID of the new account.
+    pub fn migrate_account(&self, dbfile: PathBuf) -> Result<u32> {
+        ensure!(
+            dbfile.is_file(),
+            "Database file does not exist: {}",
+            dbfile.display()
+        );
+        let blobdir = blobdir_of(&dbfile);
+
+        let id = self.index.read().unwrap().next_id;
+        let dir = PathBuf::from(format!("account-{}", id));
+        let account_dir = self.dir.join(&dir);
+        ensure!(
+            !account_dir.exists(),
+            "Account directory {} already exists",
+            account_dir.display()
+        );
+        fs::create_dir_all(&account_dir)?;
+        let new_dbfile = account_dir.join(DB_NAME);
+
+        let mut moved = Vec::new();
+        let res = move_path(&dbfile, &new_dbfile)
+            .map(|m| moved.push(m))
+            .and_then(|_| {
+                if blobdir.is_dir() {
+                    moved.push(move_path(&blobdir, &blobdir_of(&new_dbfile))?);
+                }
+                Ok(())
+            })
+            .and_then(|_| self.insert_account(id, dir));
+        match res {
+            Ok(id) => {
+                for m in moved {
+                    m.finish();
+                }
+                Ok(id)
+            }
+            Err(err) => {
+                for m in moved.into_iter().rev() {
+                    m.rollback();
+                }
+                fs::remove_dir_all(&account_dir).ok();
+                Err(err)
+            }
+        }
+    }
+
+    /// Removes an account, its IO is stopped and all its data is deleted.
+    ///
+    /// If the removed account was selected, another account is selected.
+    pub fn remove_account(&self, id: u32) -> Result<()> {
+        ensure!(
+            self.accounts.read().unwrap().contains_key(&id),
+            "Account {} does not exist",
+            id
+        );
+        let account_dir = {
+            let mut index = self.index.write().unwrap();
+            let pos = index
+                .accounts
+                .iter()
+                .position(|account| account.id == id)
+                .ok_or_else(|| format_err!("Account {} is not in the index", id))?;
+            let account_dir = self.checked_account_dir(&index.accounts[pos].dir)?;
+            index.accounts.remove(pos);
+            if index.selected_account == id {
+                index.selected_account = index
+                    .accounts
+                    .first()
+                    .map(|account| account.id)
+                    .unwrap_or_default();
+            }
+            account_dir
+        };
+
+        if let Some(context) = self.accounts.write().unwrap().remove(&id) {
+            // the database is closed when the last reference to the context is gone
+            context.stop_io();
+        }
+        self.write_index()?;
+        if let Some(account_dir) = account_dir {
+            fs::remove_dir_all(account_dir)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the absolute path of an existing account directory, `None` if it does not exist.
+    ///
+    /// Fails if the directory is not a direct subdirectory of the accounts directory,
+    /// so that a modified index cannot make us delete other directories.
+    fn checked_account_dir(&self, dir: &Path) -> Result<Option<PathBuf>> {
+        let mut components = dir.components();
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(_)), None) => {}
+            _ => bail!("Invalid account directory {}", dir.display()),
+        }
+        let account_dir = self.dir.join(dir);
+        if !account_dir.exists() {
+            return Ok(None);
+        }
+        let account_dir = account_dir.canonicalize()?;
+        ensure!(
+            account_dir.starts_with(self.dir.canonicalize()?),
+            "Account directory {} is outside of {}",
+            account_dir.display(),
+            self.dir.display()
+        );
+        Ok(Some(account_dir))
+    }
+
+    /// Starts the IO of all accounts, see [Context::start_io].
+    ///
+    /// Accounts added later are started as well
+    /// until [Accounts::stop_io] is called.
+    pub fn start_io(&self) {
+        self.running.store(true, Ordering::Relaxed);
+        for context in self.accounts.read().unwrap().values() {
+            context.start_io();
+        }
+    }
+
+    /// Stops the IO of all accounts and waits until it is stopped.
+    pub fn stop_io(&self) {
+        self.running.store(false, Ordering::Relaxed);
+        for context in self.accounts.read().unwrap().values() {
+            context.stop_io();
+        }
+    }
+
+    fn insert_account(&self, id: u32, dir: PathBuf) -> Result<u32> {
+        let context = Arc::new(self.open_context(id, &dir)?);
+        let old_index = {
+            let mut index = self.index.write().unwrap();
+            let old_index = index.clone();
+            index.accounts.push(AccountConfig { id, dir });
+            index.next_id = id + 1;
+            index.selected_account = id;
+            old_index
+        };
+        if let Err(err) = self.write_index() {
+            *self.index.write().unwrap() = old_index;
+            return Err(err);
+        }
+
+        self.accounts
+            .write()
+            .unwrap()
+            .insert(id, Arc::clone(&context));
+        if self.running.load(Ordering::Relaxed) {
+            context.start_io();
+        }
+        Ok(id)
+    }
+
+    fn open_context(&self, id: u32, dir: &Path) -> Result<Context> {
+        let account_dir = self.dir.join(dir);
+        if !account_dir.exists() {
+            fs::create_dir_all(&account_dir)?;
+        }
+        let cb = Arc::clone(&self.cb);
+        Context::new(
+            Some(Box::new(move |context: &Context, event: Event| {
+                (*cb)(id, context, event)
+            })),
+            self.os_name.clone(),
+            account_dir.join(DB_NAME),
+        )
+    }
+
+    fn write_index(&self) -> Result<()> {
+        let index = self.index.read().unwrap();
+        let data = serde_json::to_vec_pretty(&*index)
+            .map_err(|err| format_err!("Failed to serialize accounts index: {}", err))?;
+        // write to a temporary file first so that a crash does not leave a truncated index
+        let index_file = self.dir.join(INDEX_FILE);
+        let tmp_file = self.dir.join(format!("{}.tmp", INDEX_FILE));
+        fs::write(&tmp_file, data)?;
+        fs::rename(&tmp_file, &index_file)?;
+        Ok(())
+    }
+}
+
+/// Returns the blobdir belonging to a database, see [Context::new].
+fn blobdir_of(dbfile: &Path) -> PathBuf {
+    let mut blobdir_name = dbfile.file_name().unwrap_or_default().to_os_string();
+    blobdir_name.push("-blobs");
+    dbfile.with_file_name(blobdir_name)
+}
+
+/// A file or directory moved by [move_path].
+struct MovedPath {
+    from: PathBuf,
+    to: PathBuf,
+    /// Whether the path was renamed or copied.
+    renamed: bool,
+}
+
+impl MovedPath {
+    /// Removes the source of a copied path.
+    fn finish(self) {
+        if !self.renamed {
+            // the data is at its new place, a leftover is no harm
+            remove_path(&self.from).ok();
+        }
+    }
+
+    /// Restores the original state.
+    fn rollback(self) {
+        if self.renamed {
+            fs::rename(&self.to, &self.from).ok();
+        } else {
+            remove_path(&self.to).ok();
+        }
+    }
+}
+
+/// Moves a file or directory.
+///
+/// If the path cannot be renamed, eg. because the destination is on another filesystem,
+/// it is copied, the source is removed only by [MovedPath::finish].
+fn move_path(from: &Path, to: &Path) -> Result<MovedPath> {
+    let renamed = if fs::rename(from, to).is_ok() {
+        true
+    } else {
+        if let Err(err) = copy_path(from, to) {
+            remove_path(to).ok();
+            return Err(err);
+        }
+        false
+    };
+    Ok(MovedPath {
+        from: from.to_path_buf(),
+        to: to.to_path_buf(),
+        renamed,
+    })
+}
+
+fn copy_path(from: &Path, to: &Path) -> Result<()> {
+    if from.is_dir() {
+        fs::create_dir_all(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_path(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else {
+        fs::copy(from, to)?;
+    }
+    Ok(())
+}
+
+fn remove_path(path: &Path) -> Result<()> {
+    if path.is_dir() {
+        fs::remove_dir_all(path)?;
+    } else if path.exists() {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+impl Drop for Accounts {
+    fn drop(&mut self) {
+        self.stop_io();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::config::Config;
+
+    fn accounts_in(dir: &Path) -> Accounts {
+        Accounts::new(
+            Box::new(|_, _, _| ()),
+            "FakeOS".into(),
+            dir.join("accounts"),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_add_select_remove() {
+        let tmp = tempfile::tempdir().unwrap();
+        let accounts = accounts_in(tmp.path());
+        assert!(accounts.get_all().is_empty());
+        assert!(accounts.get_selected_account().is_none());
+
+        let id1 = accounts.add_account().unwrap();
+        let id2 = accounts.add_account().unwrap();
+        assert_ne!(id1, id2);
+        assert_eq!(accounts.get_all(), vec![id1, id2]);
+        assert_eq!(accounts.get_selected_account_id(), id2);
+
+        accounts.select_account(id1).unwrap();
+        assert_eq!(accounts.get_selected_account_id(), id1);
+        assert!(accounts.select_account(100).is_err());
+
+        accounts.remove_account(id1).unwrap();
+        assert_eq!(accounts.get_all(), vec![id2]);
+        assert_eq!(accounts.get_selected_account_id(), id2);
+        assert!(accounts.remove_account(id1).is_err());
+
+        // an account missing in the index does not remove another account
+        accounts.index.write().unwrap().accounts.clear();
+        assert!(accounts.remove_account(id2).is_err());
+        assert!(accounts.get_account(id2).is_some());
+        assert!(accounts.dir.join(format!("account-{}", id2)).exists());
+
+        // directories outside of the accounts directory are never removed
+        let outside = tmp.path().join("outside");
+        fs::create_dir_all(&outside).unwrap();
+        for dir in &[PathBuf::new(), PathBuf::from(".."), outside.clone()] {
+            accounts.index.write().unwrap().accounts = vec![AccountConfig {
+                id: id2,
+                dir: dir.clone(),
+            }];
+            assert!(accounts.remove_account(id2).is_err());
+        }
+        assert!(accounts.dir.exists());
+        assert!(outside.exists());
+        accounts.index.write().unwrap().accounts = vec![AccountConfig {
+            id: id2,
+            dir: PathBuf::from(format!("account-{}", id2)),
+        }];
+
+        // ids are not reused
+        let id3 = accounts.add_account().unwrap();
+        assert!(id3 > id2);
+    }
+
+    #[test]
+    fn test_reopen() {
+        let tmp = tempfile::tempdir().unwrap();
+        let id = {
+            let accounts = accounts_in(tmp.path());
+            let id = accounts.add_account().unwrap();
+            let context = accounts.get_account(id).unwrap();
+            context
+                .set_config(Config::Addr, Some("alice@example.org"))
+                .unwrap();
+            id
+        };
+
+        let accounts = accounts_in(tmp.path());
+        assert_eq!(accounts.get_all(), vec![id]);
+        assert_eq!(accounts.get_selected_account_id(), id);
+        let context = accounts.get_selected_account().unwrap();
+        assert_eq!(
+            context.get_config(Config::Addr),
+            Some("alice@example.org".to_string())
+        );
+    }
+
+    #[test]
+    fn test_migrate_account() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dbfile = tmp.path().join("db.sqlite");
+        {
+            let context = Context::new(None, "FakeOS".into(), dbfile.clone()).unwrap();
+            context
+                .set_config(Config::Addr, Some("bob@example.org"))
+                .unwrap();
+        }
+
+        let accounts = accounts_in(tmp.path());
+        let id = accounts.migrate_account(dbfile.clone()).unwrap();
+        assert!(!dbfile.exists());
+        assert!(!tmp.path().join("db.sqlite-blobs").exists());
+        let context = accounts.get_account(id).unwrap();
+        assert!(context.get_blobdir().is_dir());
+        assert_eq!(
+            context.get_config(Config::Addr),
+            Some("bob@example.org".to_string())
+        );
+    }
+
+    #[test]
+    fn test_migrate_account_rollback() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dbfile = tmp.path().join("db.sqlite");
+        fs::write(&dbfile, b"no database").unwrap();
+        let blobdir = tmp.path().join("db.sqlite-blobs");
+        fs::create_dir_all(&blobdir).unwrap();
+        fs::write(blobdir.join("image.jpg"), b"image").unwrap();
+
+        // a database that cannot be opened is moved back
+        let accounts = accounts_in(tmp.path());
+        assert!(accounts.migrate_account(dbfile.clone()).is_err());
+        assert_eq!(fs::read(&dbfile).unwrap(), b"no database");
+        assert_eq!(fs::read(blobdir.join("image.jpg")).unwrap(), b"image");
+        assert!(accounts.get_all().is_empty());
+        assert!(!accounts.dir.join("account-1").exists());
+    }
+
+    #[test]
+    fn test_move_path_copy() {
+        let tmp = tempfile::tempdir().unwrap();
+        let from = tmp.path().join("from");
+        fs::create_dir_all(from.join("sub")).unwrap();
+        fs::write(from.join("sub").join("file"), b"data").unwrap();
+        let to = tmp.path().join("to");
+        copy_path(&from, &to).unwrap();
+        MovedPath {
+            from: from.clone(),
+            to: to.clone(),
+            renamed: false,
+        }
+        .finish();
+        assert!(!from.exists());
+        assert_eq!(fs::read(to.join("sub").join("file")).unwrap(), b"data");
+    }
+}
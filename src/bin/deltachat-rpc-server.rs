@@ -0,0 +1,65 @@
+//! JSON-RPC server exposing the core API over stdio, see [deltachat::rpc].
+//!
+//! Usage: `deltachat-rpc-server <accounts-dir>`
+
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use deltachat::accounts::Accounts;
+use deltachat::rpc::{event_notification, RpcServer};
+
+/// Writes a line to stdout, the lock keeps responses and event notifications
+/// written from other threads apart.
+fn write_line(line: &str) {
+    let out = io::stdout();
+    let mut out = out.lock();
+    if let Err(err) = writeln!(out, "{}", line).and_then(|_| out.flush()) {
+        eprintln!("Failed to write to stdout: {}", err);
+    }
+}
+
+fn main() {
+    let dir = match std::env::args_os().nth(1) {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            eprintln!("Usage: deltachat-rpc-server <accounts-dir>");
+            std::process::exit(1);
+        }
+    };
+
+    let accounts = match Accounts::new(
+        Box::new(|account_id, _context, event| {
+            write_line(&event_notification(account_id, &event).to_string());
+        }),
+        "deltachat-rpc-server".into(),
+        dir,
+    ) {
+        Ok(accounts) => Arc::new(accounts),
+        Err(err) => {
+            eprintln!("Failed to open accounts: {}", err);
+            std::process::exit(1);
+        }
+    };
+    accounts.start_io();
+
+    let server = RpcServer::new(Arc::clone(&accounts));
+    let stdin = io::stdin();
+    for line in stdin.lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(err) => {
+                eprintln!("Failed to read from stdin: {}", err);
+                break;
+            }
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = server.handle_request(&line) {
+            write_line(&response);
+        }
+    }
+
+    accounts.stop_io();
+}
@@ -1,5 +1,6 @@
 //! # Chat module
 
+use std::collections::BTreeMap;
 use std::convert::TryFrom;
 use std::path::{Path, PathBuf};
 use std::time::{Duration, SystemTime};
@@ -362,6 +363,35 @@ impl ChatId {
             .unwrap_or_default() as usize
     }
 
+    /// Returns the ephemeral timer of the chat.
+    pub fn get_ephemeral_timer(self, context: &Context) -> Result<EphemeralTimer, Error> {
+        let timer = context
+            .sql
+            .query_get_value_result(
+                "SELECT ephemeral_timer FROM chats WHERE id=?;",
+                params![self],
+            )?
+            .unwrap_or_default();
+        Ok(timer)
+    }
+
+    /// Sets the ephemeral timer of the chat without sending a message.
+    ///
+    /// Used when the timer is changed by another chat member.
+    pub(crate) fn inner_set_ephemeral_timer(
+        self,
+        context: &Context,
+        timer: EphemeralTimer,
+    ) -> Result<(), Error> {
+        ensure!(!self.is_special(), "Invalid chat ID");
+        context.sql.execute(
+            "UPDATE chats SET ephemeral_timer=? WHERE id=?;",
+            params![timer, self],
+        )?;
+        context.call_cb(Event::ChatModified(self));
+        Ok(())
+    }
+
     /// Bad evil escape hatch.
     ///
     /// Avoid using this, eventually types should be cleaned up enough
@@ -431,6 +461,7 @@ pub struct Chat {
     pub param: Params,
     is_sending_locations: bool,
     pub mute_duration: MuteDuration,
+    pub ephemeral_timer: EphemeralTimer,
 }
 
 impl Chat {
@@ -438,7 +469,7 @@ impl Chat {
     pub fn load_from_db(context: &Context, chat_id: ChatId) -> Result<Self, Error> {
         let res = context.sql.query_row(
             "SELECT c.type, c.name, c.grpid, c.param, c.archived,
-                    c.blocked, c.locations_send_until, c.muted_until
+                    c.blocked, c.locations_send_until, c.muted_until, c.ephemeral_timer
              FROM chats c
              WHERE c.id=?;",
             params![chat_id],
@@ -453,6 +484,7 @@ impl Chat {
                     blocked: row.get::<_, Option<_>>(5)?.unwrap_or_default(),
                     is_sending_locations: row.get(6)?,
                     mute_duration: row.get(7)?,
+                    ephemeral_timer: row.get(8)?,
                 };
                 Ok(c)
             },
@@ -510,8 +542,43 @@ impl Chat {
     }
 
     /// Returns true if user can send messages to this chat.
+    ///
+    /// Mailing lists are read-only unless the user enabled sending,
+    /// see [set_mailinglist_sending].
     pub fn can_send(&self) -> bool {
-        !self.id.is_special() && !self.is_device_talk()
+        !self.id.is_special()
+            && !self.is_device_talk()
+            && (self.typ != Chattype::Mailinglist
+                || (self.param.exists(Param::ListPost)
+                    && self
+                        .param
+                        .get_bool(Param::ListSendingEnabled)
+                        .unwrap_or_default()))
+    }
+
+    /// Returns true if the chat is a mailing list.
+    pub fn is_mailing_list(&self) -> bool {
+        self.typ == Chattype::Mailinglist
+    }
+
+    /// Returns the address messages to the mailing list are sent to,
+    /// `None` for other chats and mailing lists that do not allow posting.
+    pub fn get_mailinglist_addr(&self) -> Option<&str> {
+        if self.is_mailing_list() {
+            self.param.get(Param::ListPost)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the URI to unsubscribe from the mailing list as given by the list,
+    /// typically a `mailto:` or `https:` URI the UI can open.
+    pub fn get_mailinglist_unsubscribe(&self) -> Option<&str> {
+        if self.is_mailing_list() {
+            self.param.get(Param::ListUnsubscribe)
+        } else {
+            None
+        }
     }
 
     pub fn update_param(&mut self, context: &Context) -> Result<(), Error> {
@@ -568,6 +635,13 @@ impl Chat {
             return context.stock_string_repl_int(StockMessage::Member, cnt as i32);
         }
 
+        if self.typ == Chattype::Mailinglist {
+            return self
+                .get_mailinglist_addr()
+                .unwrap_or(&self.grpid)
+                .to_string();
+        }
+
         "Err".to_string()
     }
 
@@ -716,11 +790,17 @@ impl Chat {
 
         if !(self.typ == Chattype::Single
             || self.typ == Chattype::Group
-            || self.typ == Chattype::VerifiedGroup)
+            || self.typ == Chattype::VerifiedGroup
+            || self.typ == Chattype::Mailinglist)
         {
             error!(context, "Cannot send to chat type #{}.", self.typ,);
             bail!("Cannot set to chat type #{}", self.typ);
         }
+        ensure!(
+            !self.is_mailing_list() || self.can_send(),
+            "Cannot send to read-only mailing list {}",
+            self.id
+        );
 
         if (self.typ == Chattype::Group || self.typ == Chattype::VerifiedGroup)
             && !is_contact_in_chat(context, self.id, DC_CONTACT_ID_SELF)
@@ -765,8 +845,11 @@ impl Chat {
 
             /* check if we want to encrypt this message.  If yes and circumstances change
             so that E2EE is no longer available at a later point (reset, changed settings),
-            we might not send the message out at all */
-            if msg.param.get_int(Param::ForcePlaintext).unwrap_or_default() == 0 {
+            we might not send the message out at all;
+            mailing lists have no member keys and are never encrypted */
+            if msg.param.get_int(Param::ForcePlaintext).unwrap_or_default() == 0
+                && !self.is_mailing_list()
+            {
                 let mut can_encrypt = true;
                 let mut all_mutual = context.get_config_bool(Config::E2eeEnabled);
 
@@ -857,6 +940,17 @@ impl Chat {
                 }
             }
 
+            // quotes and reactions refer to the message they quote or react to,
+            // not to the last message of the chat
+            if msg.param.exists(Param::Quote) {
+                if let Some(ref quoted_rfc724_mid) = msg.in_reply_to {
+                    new_in_reply_to = quoted_rfc724_mid.clone();
+                }
+            }
+            if let Some(reaction_target) = msg.param.get(Param::Reaction) {
+                new_in_reply_to = reaction_target.to_string();
+            }
+
             // add independent location to database
 
             if msg.param.exists(Param::SetLatitude)
@@ -892,7 +986,7 @@ impl Chat {
             if sql::execute(
                         context,
                         &context.sql,
-                        "INSERT INTO msgs (rfc724_mid, chat_id, from_id, to_id, timestamp, type, state, txt, param, hidden, mime_in_reply_to, mime_references, location_id) VALUES (?,?,?,?,?, ?,?,?,?,?, ?,?,?);",
+                        "INSERT INTO msgs (rfc724_mid, chat_id, from_id, to_id, timestamp, type, state, txt, param, hidden, mime_in_reply_to, mime_references, location_id, ephemeral_timer) VALUES (?,?,?,?,?, ?,?,?,?,?, ?,?,?,?);",
                         params![
                             new_rfc724_mid,
                             self.id,
@@ -907,6 +1001,7 @@ impl Chat {
                             new_in_reply_to,
                             new_references,
                             location_id as i32,
+                            self.ephemeral_timer,
                         ]
                     ).is_ok() {
                         msg_id = sql::get_rowid(
@@ -1444,6 +1539,273 @@ pub fn send_text_msg(
     send_msg(context, chat_id, &mut msg)
 }
 
+/// Seconds after which a typing indicator is hidden if no new notification arrives.
+pub const TYPING_TIMEOUT: i64 = 15;
+
+/// Seconds between typing notifications sent to a chat while the user is typing.
+const TYPING_INTERVAL: i64 = 5;
+
+/// Tells the other members of the chat that the user started or stopped typing.
+///
+/// UIs call this with `typing=true` on every keystroke and with `typing=false`
+/// when the text field is cleared or the message is sent.
+/// Notifications are only sent if the config option `typing_notifications` is enabled,
+/// at most every few seconds and only end-to-end-encrypted;
+/// the other members get #DC_EVENT_CONTACT_TYPING or #DC_EVENT_CONTACT_STOPPED_TYPING.
+pub fn send_typing(context: &Context, chat_id: ChatId, typing: bool) -> Result<(), Error> {
+    if !context.get_config_bool(Config::TypingNotifications) {
+        return Ok(());
+    }
+    ensure!(!chat_id.is_special(), "Invalid chat ID");
+    let chat = Chat::load_from_db(context, chat_id)?;
+    ensure!(
+        chat.can_send() && !chat.is_self_talk(),
+        "Cannot send typing notifications to chat {}",
+        chat_id
+    );
+
+    let now = time();
+    let mut typing_sent = context.typing_sent.lock().unwrap();
+    if typing {
+        if let Some(timestamp) = typing_sent.get(&chat_id) {
+            if now < timestamp + TYPING_INTERVAL {
+                return Ok(());
+            }
+        }
+        typing_sent.insert(chat_id, now);
+    } else if typing_sent.remove(&chat_id).is_none() {
+        // the other members do not know that the user was typing
+        return Ok(());
+    }
+    job_send_typing(context, chat_id, typing)
+}
+
+/// Schedules a message to be sent at `timestamp`.
+///
+/// Until then, the message is shown in the chat in the state [MessageState::OutScheduled]
+/// and can be changed by [edit_msg] and [reschedule_msg] or cancelled by [cancel_scheduled_msgs].
+/// The message is rendered and encrypted only when it is sent.
+/// Sends the event #DC_EVENT_MSGS_CHANGED on success.
+pub fn send_msg_at(
+    context: &Context,
+    chat_id: ChatId,
+    msg: &mut Message,
+    timestamp: i64,
+) -> Result<MsgId, Error> {
+    ensure!(
+        timestamp > time(),
+        "Cannot schedule message for {}, time is in the past",
+        timestamp
+    );
+    ensure!(
+        msg.state != MessageState::OutPreparing,
+        "Cannot schedule prepared message {}",
+        msg.id
+    );
+    msg.id = MsgId::new_unset();
+    prepare_msg_blob(context, msg)?;
+    chat_id.unarchive(context)?;
+
+    let mut chat = Chat::load_from_db(context, chat_id)?;
+    ensure!(chat.can_send(), "cannot send to {}", chat_id);
+
+    msg.state = MessageState::OutScheduled;
+    msg.id = chat.prepare_msg_raw(context, msg, timestamp)?;
+    msg.chat_id = chat_id;
+    job_send_scheduled_msg(context, msg.id, timestamp);
+
+    context.call_cb(Event::MsgsChanged {
+        chat_id: msg.chat_id,
+        msg_id: msg.id,
+    });
+    Ok(msg.id)
+}
+
+/// Returns the scheduled messages of a chat, ordered by the time they are sent at.
+///
+/// If `chat_id` is unset, the scheduled messages of all chats are returned.
+pub fn get_scheduled_msgs(context: &Context, chat_id: ChatId) -> Result<Vec<MsgId>, Error> {
+    let msg_ids = context.sql.query_map(
+        "SELECT id FROM msgs WHERE state=? AND (chat_id=? OR ?) ORDER BY timestamp, id;",
+        params![MessageState::OutScheduled, chat_id, chat_id.is_unset()],
+        |row| row.get::<_, MsgId>(0),
+        |ids| ids.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+    )?;
+    Ok(msg_ids)
+}
+
+/// Changes the time a scheduled message is sent at.
+pub fn reschedule_msg(context: &Context, msg_id: MsgId, timestamp: i64) -> Result<(), Error> {
+    let msg = load_scheduled_msg(context, msg_id)?;
+    ensure!(
+        timestamp > time(),
+        "Cannot schedule message for {}, time is in the past",
+        timestamp
+    );
+    context.sql.execute(
+        "UPDATE msgs SET timestamp=? WHERE id=?;",
+        params![timestamp, msg_id],
+    )?;
+    job_kill_scheduled_msg(context, msg_id)?;
+    job_send_scheduled_msg(context, msg_id, timestamp);
+    context.call_cb(Event::MsgsChanged {
+        chat_id: msg.chat_id,
+        msg_id,
+    });
+    Ok(())
+}
+
+/// Cancels scheduled messages, the messages are deleted.
+pub fn cancel_scheduled_msgs(context: &Context, msg_ids: &[MsgId]) -> Result<(), Error> {
+    for msg_id in msg_ids {
+        load_scheduled_msg(context, *msg_id)?;
+    }
+    for msg_id in msg_ids {
+        job_kill_scheduled_msg(context, *msg_id)?;
+    }
+    message::delete_msgs(context, msg_ids);
+    Ok(())
+}
+
+fn load_scheduled_msg(context: &Context, msg_id: MsgId) -> Result<Message, Error> {
+    let msg = Message::load_from_db(context, msg_id)?;
+    ensure!(
+        msg.state == MessageState::OutScheduled,
+        "Message {} is not scheduled",
+        msg_id
+    );
+    Ok(msg)
+}
+
+/// Replaces the text of an own message, also for all other chat members.
+///
+/// The new text is sent as a hidden message
+/// that refers to the edited message by its Message-ID.
+/// Scheduled messages are not sent yet, so only the message itself is changed.
+pub fn edit_msg(context: &Context, msg_id: MsgId, new_text: String) -> Result<(), Error> {
+    let mut msg = Message::load_from_db(context, msg_id)?;
+    ensure!(
+        msg.from_id == DC_CONTACT_ID_SELF,
+        "Cannot edit message {} of another contact",
+        msg_id
+    );
+    ensure!(!msg.is_info(), "Cannot edit info message {}", msg_id);
+    ensure!(
+        !msg.rfc724_mid.is_empty(),
+        "Cannot edit message {} without Message-ID",
+        msg_id
+    );
+    let chat = Chat::load_from_db(context, msg.chat_id)?;
+    ensure!(chat.can_send(), "Cannot send to chat {}", chat.id);
+
+    if msg.state == MessageState::OutScheduled {
+        msg.text = Some(new_text);
+        context.sql.execute(
+            "UPDATE msgs SET txt=? WHERE id=?;",
+            params![msg.text, msg.id],
+        )?;
+        context.call_cb(Event::MsgsChanged {
+            chat_id: msg.chat_id,
+            msg_id: msg.id,
+        });
+        return Ok(());
+    }
+
+    message::set_edited_text(context, &mut msg, &new_text)?;
+
+    let mut edit = Message::new(Viewtype::Text);
+    edit.text = Some(new_text);
+    edit.hidden = true;
+    edit.param.set(Param::TextEditFor, &msg.rfc724_mid);
+    send_msg(context, chat.id, &mut edit)?;
+    Ok(())
+}
+
+/// Deletes own messages locally, on the server and for all other chat members.
+///
+/// For every affected chat, a hidden message is sent
+/// that lists the Message-IDs of the deleted messages.
+pub fn delete_msgs_for_everyone(context: &Context, msg_ids: &[MsgId]) -> Result<(), Error> {
+    let mut rfc724_mids_by_chat: BTreeMap<ChatId, Vec<String>> = BTreeMap::new();
+    for msg_id in msg_ids {
+        let msg = Message::load_from_db(context, *msg_id)?;
+        ensure!(
+            msg.from_id == DC_CONTACT_ID_SELF,
+            "Cannot delete message {} of another contact for everyone",
+            msg_id
+        );
+        ensure!(
+            !msg.rfc724_mid.is_empty(),
+            "Cannot delete message {} without Message-ID for everyone",
+            msg_id
+        );
+        rfc724_mids_by_chat
+            .entry(msg.chat_id)
+            .or_default()
+            .push(msg.rfc724_mid);
+    }
+
+    for (chat_id, rfc724_mids) in rfc724_mids_by_chat {
+        let chat = Chat::load_from_db(context, chat_id)?;
+        ensure!(chat.can_send(), "Cannot send to chat {}", chat.id);
+
+        let mut request = Message::new(Viewtype::Text);
+        request.text = Some(
+            context
+                .stock_str(StockMessage::MsgDeletedForEveryone)
+                .into(),
+        );
+        request.hidden = true;
+        request
+            .param
+            .set(Param::DeleteRequestFor, rfc724_mids.join(" "));
+        send_msg(context, chat.id, &mut request)?;
+    }
+
+    message::delete_msgs(context, msg_ids);
+    Ok(())
+}
+
+/// Sends own messages again that could not be sent yet.
+///
+/// Failed messages are queued for sending again,
+/// messages waiting for a retry are tried at once.
+/// The error of the last try is removed from the messages.
+pub fn resend_msgs(context: &Context, msg_ids: &[MsgId]) -> Result<(), Error> {
+    let mut msgs = Vec::with_capacity(msg_ids.len());
+    for msg_id in msg_ids {
+        let msg = Message::load_from_db(context, *msg_id)?;
+        ensure!(
+            msg.from_id == DC_CONTACT_ID_SELF,
+            "Cannot resend message {} of another contact",
+            msg_id
+        );
+        ensure!(
+            msg.state == MessageState::OutPending || msg.state == MessageState::OutFailed,
+            "Cannot resend message {} in state {}",
+            msg_id,
+            msg.state
+        );
+        let chat = Chat::load_from_db(context, msg.chat_id)?;
+        ensure!(chat.can_send(), "Cannot send to chat {}", chat.id);
+        msgs.push(msg);
+    }
+
+    for mut msg in msgs {
+        msg.param.remove(Param::Error);
+        msg.save_param_to_disk(context);
+        if !retry_send_msg_now(context, msg.id) {
+            message::update_msg_state(context, msg.id, MessageState::OutPending);
+            job_send_msg(context, msg.id)?;
+        }
+        context.call_cb(Event::MsgsChanged {
+            chat_id: msg.chat_id,
+            msg_id: msg.id,
+        });
+    }
+    Ok(())
+}
+
 pub fn get_chat_msgs(
     context: &Context,
     chat_id: ChatId,
@@ -2034,6 +2396,158 @@ pub fn set_muted(context: &Context, chat_id: ChatId, duration: MuteDuration) ->
     Ok(())
 }
 
+/// Allows or forbids sending messages to the mailing list `chat_id`.
+///
+/// Mailing lists are read-only by default,
+/// sending can only be enabled if the list announced an address to post to.
+pub fn set_mailinglist_sending(
+    context: &Context,
+    chat_id: ChatId,
+    enabled: bool,
+) -> Result<(), Error> {
+    ensure!(!chat_id.is_special(), "Invalid chat ID");
+    let mut chat = Chat::load_from_db(context, chat_id)?;
+    ensure!(chat.is_mailing_list(), "{} is no mailing list", chat_id);
+    ensure!(
+        !enabled || chat.param.exists(Param::ListPost),
+        "Mailing list {} does not allow posting",
+        chat_id
+    );
+    chat.param
+        .set_int(Param::ListSendingEnabled, enabled as i32);
+    chat.update_param(context)?;
+    context.call_cb(Event::ChatModified(chat_id));
+    Ok(())
+}
+
+/// Ephemeral timer of a chat.
+///
+/// If enabled, messages are deleted locally and from the server
+/// `duration` seconds after they are seen (incoming) or sent (outgoing).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EphemeralTimer {
+    Disabled,
+    Enabled { duration: u32 },
+}
+
+impl Default for EphemeralTimer {
+    fn default() -> Self {
+        EphemeralTimer::Disabled
+    }
+}
+
+impl EphemeralTimer {
+    /// Converts the timer to the number of seconds used in the database
+    /// and in the `Ephemeral-Timer` header, `0` means disabled.
+    pub fn to_u32(self) -> u32 {
+        match self {
+            EphemeralTimer::Disabled => 0,
+            EphemeralTimer::Enabled { duration } => duration,
+        }
+    }
+
+    pub fn from_u32(duration: u32) -> Self {
+        match duration {
+            0 => EphemeralTimer::Disabled,
+            duration => EphemeralTimer::Enabled { duration },
+        }
+    }
+}
+
+impl std::str::FromStr for EphemeralTimer {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(input: &str) -> Result<EphemeralTimer, Self::Err> {
+        input.trim().parse::<u32>().map(EphemeralTimer::from_u32)
+    }
+}
+
+impl rusqlite::types::ToSql for EphemeralTimer {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput> {
+        let val = rusqlite::types::Value::Integer(self.to_u32() as i64);
+        let out = rusqlite::types::ToSqlOutput::Owned(val);
+        Ok(out)
+    }
+}
+
+impl rusqlite::types::FromSql for EphemeralTimer {
+    fn column_result(value: rusqlite::types::ValueRef) -> rusqlite::types::FromSqlResult<Self> {
+        // Negative values should not be in the database.
+        // If found they'll be Disabled.
+        match i64::column_result(value)? {
+            n if n > 0 => u32::try_from(n)
+                .map(EphemeralTimer::from_u32)
+                .map_err(|_| rusqlite::types::FromSqlError::OutOfRange(n)),
+            _ => Ok(EphemeralTimer::Disabled),
+        }
+    }
+}
+
+/// Sets the ephemeral timer of a chat and informs the other chat members about the change.
+///
+/// The timer applies to messages sent or received after this call.
+pub fn set_ephemeral_timer(
+    context: &Context,
+    chat_id: ChatId,
+    timer: EphemeralTimer,
+) -> Result<(), Error> {
+    ensure!(!chat_id.is_special(), "Invalid chat ID");
+
+    let chat = Chat::load_from_db(context, chat_id)?;
+    ensure!(chat.can_send(), "Cannot set ephemeral timer in this chat");
+    if chat.ephemeral_timer == timer {
+        return Ok(());
+    }
+    if (chat.typ == Chattype::Group || chat.typ == Chattype::VerifiedGroup)
+        && !is_contact_in_chat(context, chat_id, DC_CONTACT_ID_SELF)
+    {
+        emit_event!(
+            context,
+            Event::ErrorSelfNotInGroup("Cannot set ephemeral timer; self not in group".into())
+        );
+        bail!("Cannot set ephemeral timer; self not in group");
+    }
+
+    chat_id.inner_set_ephemeral_timer(context, timer)?;
+
+    if chat.is_promoted() && !chat.is_self_talk() {
+        let mut msg = Message::new(Viewtype::Text);
+        msg.text = Some(stock_ephemeral_timer_changed(
+            context,
+            timer,
+            DC_CONTACT_ID_SELF,
+        ));
+        msg.param.set_cmd(SystemMessage::EphemeralTimerChanged);
+        msg.id = send_msg(context, chat_id, &mut msg)?;
+        context.call_cb(Event::MsgsChanged {
+            chat_id,
+            msg_id: msg.id,
+        });
+    }
+    context.call_cb(Event::ChatModified(chat_id));
+
+    Ok(())
+}
+
+/// Returns the stock string announcing an ephemeral timer change done by `from_id`.
+pub(crate) fn stock_ephemeral_timer_changed(
+    context: &Context,
+    timer: EphemeralTimer,
+    from_id: u32,
+) -> String {
+    match timer {
+        EphemeralTimer::Disabled => {
+            context.stock_system_msg(StockMessage::MsgEphemeralTimerDisabled, "", "", from_id)
+        }
+        EphemeralTimer::Enabled { duration } => context.stock_system_msg(
+            StockMessage::MsgEphemeralTimerEnabled,
+            duration.to_string(),
+            "",
+            from_id,
+        ),
+    }
+}
+
 pub fn remove_contact_from_chat(
     context: &Context,
     chat_id: ChatId,
@@ -2306,6 +2820,7 @@ pub fn forward_msgs(context: &Context, msg_ids: &[MsgId], chat_id: ChatId) -> Re
             msg.param.remove(Param::GuaranteeE2ee);
             msg.param.remove(Param::ForcePlaintext);
             msg.param.remove(Param::Cmd);
+            msg.param.remove(Param::Quote);
 
             let new_msg_id: MsgId;
             if msg.state == MessageState::OutPreparing {
@@ -2467,6 +2982,15 @@ pub fn was_device_msg_ever_added(context: &Context, label: &str) -> Result<bool,
     Ok(false)
 }
 
+/// Forgets that a device message with the given label was added,
+/// so that it can be added again.
+pub(crate) fn reset_device_msg_label(context: &Context, label: &str) -> Result<(), Error> {
+    context
+        .sql
+        .execute("DELETE FROM devmsglabels WHERE label=?;", params![label])?;
+    Ok(())
+}
+
 // needed on device-switches during export/import;
 // - deletion in `msgs` with `DC_CONTACT_ID_DEVICE` makes sure,
 //   no wrong information are shown in the device chat
@@ -3051,4 +3575,188 @@ mod tests {
             false
         );
     }
+
+    #[test]
+    fn test_ephemeral_timer_from_str() {
+        assert_eq!("0".parse::<EphemeralTimer>(), Ok(EphemeralTimer::Disabled));
+        assert_eq!(
+            "60".parse::<EphemeralTimer>(),
+            Ok(EphemeralTimer::Enabled { duration: 60 })
+        );
+        assert!("-1".parse::<EphemeralTimer>().is_err());
+        assert!("foo".parse::<EphemeralTimer>().is_err());
+    }
+
+    #[test]
+    fn test_set_ephemeral_timer() {
+        let t = dummy_context();
+        let chat_id = create_group_chat(&t.ctx, VerifiedStatus::Unverified, "foo").unwrap();
+        assert_eq!(
+            chat_id.get_ephemeral_timer(&t.ctx).unwrap(),
+            EphemeralTimer::Disabled
+        );
+
+        set_ephemeral_timer(&t.ctx, chat_id, EphemeralTimer::Enabled { duration: 60 }).unwrap();
+        assert_eq!(
+            chat_id.get_ephemeral_timer(&t.ctx).unwrap(),
+            EphemeralTimer::Enabled { duration: 60 }
+        );
+        assert_eq!(
+            Chat::load_from_db(&t.ctx, chat_id).unwrap().ephemeral_timer,
+            EphemeralTimer::Enabled { duration: 60 }
+        );
+
+        set_ephemeral_timer(&t.ctx, chat_id, EphemeralTimer::Disabled).unwrap();
+        assert_eq!(
+            chat_id.get_ephemeral_timer(&t.ctx).unwrap(),
+            EphemeralTimer::Disabled
+        );
+
+        assert!(set_ephemeral_timer(
+            &t.ctx,
+            ChatId::new(DC_CHAT_ID_TRASH),
+            EphemeralTimer::Disabled
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_edit_msg() {
+        let t = dummy_context();
+        t.ctx
+            .set_config(Config::ConfiguredAddr, Some("self@example.com"))
+            .unwrap();
+        let chat_id = create_group_chat(&t.ctx, VerifiedStatus::Unverified, "foo").unwrap();
+        let msg_id = send_text_msg(&t.ctx, chat_id, "foo".to_string()).unwrap();
+        assert!(!Message::load_from_db(&t.ctx, msg_id).unwrap().is_edited());
+
+        edit_msg(&t.ctx, msg_id, "bar".to_string()).unwrap();
+        let msg = Message::load_from_db(&t.ctx, msg_id).unwrap();
+        assert_eq!(msg.get_text().unwrap(), "bar");
+        assert!(msg.is_edited());
+
+        // the edit request itself is not shown in the chat
+        assert_eq!(get_chat_msgs(&t.ctx, chat_id, 0, None), vec![msg_id]);
+    }
+
+    #[test]
+    fn test_resend_msgs() {
+        let t = dummy_context();
+        t.ctx
+            .set_config(Config::ConfiguredAddr, Some("self@example.com"))
+            .unwrap();
+        let chat_id = create_group_chat(&t.ctx, VerifiedStatus::Unverified, "foo").unwrap();
+        let msg_id = send_text_msg(&t.ctx, chat_id, "foo".to_string()).unwrap();
+        assert!(t.ctx.get_outbox_msgs().is_empty());
+        assert!(resend_msgs(&t.ctx, &[msg_id]).is_err());
+
+        message::set_msg_failed(&t.ctx, msg_id, Some("Permanent SMTP error"));
+        let msg = Message::load_from_db(&t.ctx, msg_id).unwrap();
+        assert_eq!(msg.get_state(), MessageState::OutFailed);
+        assert_eq!(msg.get_error(), Some("Permanent SMTP error".to_string()));
+        assert_eq!(msg.get_send_retry(&t.ctx), None);
+        assert_eq!(t.ctx.get_outbox_msgs(), vec![msg_id]);
+
+        // the group has no other members, so the message is delivered at once
+        resend_msgs(&t.ctx, &[msg_id]).unwrap();
+        let msg = Message::load_from_db(&t.ctx, msg_id).unwrap();
+        assert_eq!(msg.get_state(), MessageState::OutDelivered);
+        assert_eq!(msg.get_error(), None);
+        assert!(t.ctx.get_outbox_msgs().is_empty());
+    }
+
+    #[test]
+    fn test_send_msg_at() {
+        let t = dummy_context();
+        configure_alice_keypair(&t.ctx);
+        t.ctx.set_config(Config::BccSelf, Some("1")).unwrap();
+        let chat_id = create_group_chat(&t.ctx, VerifiedStatus::Unverified, "foo").unwrap();
+        let send_at = time() + 3600;
+        let desired_timestamp = |msg_id: MsgId| -> Option<i64> {
+            t.ctx.sql.query_get_value(
+                &t.ctx,
+                "SELECT desired_timestamp FROM jobs WHERE action=? AND foreign_id=?;",
+                params![Action::SendScheduledMsg, msg_id],
+            )
+        };
+
+        let mut msg = Message::new(Viewtype::Text);
+        msg.text = Some("foo".to_string());
+        assert!(send_msg_at(&t.ctx, chat_id, &mut msg, time() - 1).is_err());
+        let msg_id = send_msg_at(&t.ctx, chat_id, &mut msg, send_at).unwrap();
+        let msg = Message::load_from_db(&t.ctx, msg_id).unwrap();
+        assert_eq!(msg.get_state(), MessageState::OutScheduled);
+        assert_eq!(msg.get_timestamp(), send_at);
+        assert_eq!(get_chat_msgs(&t.ctx, chat_id, 0, None), vec![msg_id]);
+        assert_eq!(get_scheduled_msgs(&t.ctx, chat_id).unwrap(), vec![msg_id]);
+        assert_eq!(
+            get_scheduled_msgs(&t.ctx, ChatId::new(0)).unwrap(),
+            vec![msg_id]
+        );
+        assert!(desired_timestamp(msg_id).unwrap() >= send_at);
+        // nothing is rendered before the message is due
+        let smtp_jobs: i32 = t
+            .ctx
+            .sql
+            .query_get_value(
+                &t.ctx,
+                "SELECT COUNT(*) FROM jobs WHERE action=?;",
+                params![Action::SendMsgToSmtp],
+            )
+            .unwrap();
+        assert_eq!(smtp_jobs, 0);
+
+        // scheduled messages are changed without sending an edit request
+        edit_msg(&t.ctx, msg_id, "bar".to_string()).unwrap();
+        let msg = Message::load_from_db(&t.ctx, msg_id).unwrap();
+        assert_eq!(msg.get_text().unwrap(), "bar");
+        assert!(!msg.is_edited());
+        assert_eq!(get_chat_msgs(&t.ctx, chat_id, 0, None), vec![msg_id]);
+
+        reschedule_msg(&t.ctx, msg_id, send_at + 3600).unwrap();
+        let msg = Message::load_from_db(&t.ctx, msg_id).unwrap();
+        assert_eq!(msg.get_timestamp(), send_at + 3600);
+        assert!(desired_timestamp(msg_id).unwrap() >= send_at + 3600);
+
+        cancel_scheduled_msgs(&t.ctx, &[msg_id]).unwrap();
+        assert!(get_scheduled_msgs(&t.ctx, chat_id).unwrap().is_empty());
+        assert!(desired_timestamp(msg_id).is_none());
+        assert!(reschedule_msg(&t.ctx, msg_id, send_at).is_err());
+    }
+
+    #[test]
+    fn test_send_typing() {
+        let t = dummy_context();
+        let chat_id = create_group_chat(&t.ctx, VerifiedStatus::Unverified, "foo").unwrap();
+        let typing_jobs = || -> Vec<String> {
+            t.ctx
+                .sql
+                .query_map(
+                    "SELECT param FROM jobs WHERE action=? AND foreign_id=?;",
+                    params![Action::SendTyping, chat_id],
+                    |row| row.get::<_, String>(0),
+                    |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+                )
+                .unwrap()
+        };
+
+        // typing notifications are opt-in
+        send_typing(&t.ctx, chat_id, true).unwrap();
+        assert!(typing_jobs().is_empty());
+
+        t.ctx
+            .set_config(Config::TypingNotifications, Some("1"))
+            .unwrap();
+        assert!(send_typing(&t.ctx, ChatId::new(DC_CHAT_ID_TRASH), true).is_err());
+        send_typing(&t.ctx, chat_id, false).unwrap();
+        assert!(typing_jobs().is_empty());
+
+        send_typing(&t.ctx, chat_id, true).unwrap();
+        send_typing(&t.ctx, chat_id, true).unwrap();
+        assert_eq!(typing_jobs(), vec!["Y=1".to_string()]);
+
+        // stopping replaces the pending notification
+        send_typing(&t.ctx, chat_id, false).unwrap();
+        assert_eq!(typing_jobs(), vec!["Y=0".to_string()]);
+    }
 }
@@ -300,7 +300,9 @@ impl Chatlist {
 
         let lastmsg = if let Ok(lastmsg) = Message::load_from_db(context, lastmsg_id) {
             if lastmsg.from_id != DC_CONTACT_ID_SELF
-                && (chat.typ == Chattype::Group || chat.typ == Chattype::VerifiedGroup)
+                && (chat.typ == Chattype::Group
+                    || chat.typ == Chattype::VerifiedGroup
+                    || chat.typ == Chattype::Mailinglist)
             {
                 lastcontact = Contact::load_from_db(context, lastmsg.from_id).ok();
             }
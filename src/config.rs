@@ -9,6 +9,7 @@ use crate::context::Context;
 use crate::dc_tools::*;
 use crate::job::*;
 use crate::mimefactory::RECOMMENDED_FILE_SIZE;
+use crate::param::Params;
 use crate::stock::StockMessage;
 use rusqlite::NO_PARAMS;
 
@@ -47,6 +48,9 @@ pub enum Config {
     #[strum(props(default = "1"))]
     MdnsEnabled,
 
+    #[strum(props(default = "0"))]
+    TypingNotifications,
+
     #[strum(props(default = "1"))]
     InboxWatch,
 
@@ -59,6 +63,14 @@ pub enum Config {
     #[strum(props(default = "1"))]
     MvboxMove,
 
+    WatchFolders,
+
+    #[strum(props(default = "0"))]
+    DeleteServerAfter,
+
+    #[strum(props(default = "0"))]
+    DeleteDeviceAfter,
+
     #[strum(props(default = "0"))] // also change ShowEmails.default() on changes
     ShowEmails,
 
@@ -161,6 +173,17 @@ impl Context {
                 interrupt_mvbox_idle(self);
                 ret
             }
+            Config::WatchFolders => {
+                let ret = self.sql.set_raw_config(self, key, value);
+                interrupt_watch_idle(self);
+                ret
+            }
+            Config::DeleteServerAfter | Config::DeleteDeviceAfter => {
+                let ret = self.sql.set_raw_config(self, key, value);
+                job_kill_action(self, Action::Housekeeping);
+                job_add(self, Action::Housekeeping, 0, Params::new(), 0);
+                ret
+            }
             Config::Selfstatus => {
                 let def = self.stock_str(StockMessage::StatusLine);
                 let val = if value.is_none() || value.unwrap() == def {
@@ -174,6 +197,41 @@ impl Context {
             _ => self.sql.set_raw_config(self, key, value),
         }
     }
+
+    /// Returns the folders set by [Config::WatchFolders], one folder per line.
+    ///
+    /// Folders watched by the INBOX, SENTBOX or MVBOX thread are left out
+    /// so that no folder is fetched twice.
+    pub(crate) fn get_watch_folders(&self) -> Vec<String> {
+        let mut watched = Vec::new();
+        for (config_name, watch) in &[
+            ("configured_inbox_folder", Config::InboxWatch),
+            ("configured_sentbox_folder", Config::SentboxWatch),
+            ("configured_mvbox_folder", Config::MvboxWatch),
+        ] {
+            if self.get_config_bool(*watch) {
+                if let Some(folder) = self.sql.get_raw_config(self, config_name) {
+                    watched.push(folder);
+                }
+            }
+        }
+
+        let mut folders: Vec<String> = Vec::new();
+        for folder in self
+            .get_config(Config::WatchFolders)
+            .unwrap_or_default()
+            .lines()
+            .map(|folder| folder.trim())
+        {
+            if !folder.is_empty()
+                && !watched.iter().any(|w| w == folder)
+                && !folders.iter().any(|f| f == folder)
+            {
+                folders.push(folder.to_string());
+            }
+        }
+        folders
+    }
 }
 
 /// Returns all available configuration keys concated together.
@@ -217,6 +275,30 @@ mod tests {
         assert_eq!(Config::ImapFolder.get_str("default"), Some("INBOX"));
     }
 
+    #[test]
+    fn test_get_watch_folders() {
+        let t = dummy_context();
+        assert!(t.ctx.get_watch_folders().is_empty());
+
+        t.ctx
+            .sql
+            .set_raw_config(&t.ctx, "configured_inbox_folder", Some("INBOX"))
+            .unwrap();
+        t.ctx
+            .set_config(
+                Config::WatchFolders,
+                Some("Lists\n INBOX\n\nWork/Projects \nLists\n"),
+            )
+            .unwrap();
+        assert_eq!(t.ctx.get_watch_folders(), vec!["Lists", "Work/Projects"]);
+
+        t.ctx.set_config(Config::InboxWatch, Some("0")).unwrap();
+        assert_eq!(
+            t.ctx.get_watch_folders(),
+            vec!["Lists", "INBOX", "Work/Projects"]
+        );
+    }
+
     #[test]
     fn test_selfavatar_outside_blobdir() {
         let t = dummy_context();
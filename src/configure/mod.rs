@@ -87,6 +87,12 @@ pub(crate) fn JobConfigureImap(context: &Context) -> job::Status {
         .unwrap()
         .imap
         .disconnect(context);
+    context
+        .watch_thread
+        .read()
+        .unwrap()
+        .imap
+        .disconnect(context);
     context.smtp.clone().lock().unwrap().disconnect();
     info!(context, "Configure ...",);
 
@@ -125,6 +125,7 @@ pub enum Chattype {
     Single = 100,
     Group = 120,
     VerifiedGroup = 130,
+    Mailinglist = 140,
 }
 
 impl Default for Chattype {
@@ -207,8 +208,6 @@ pub const DC_LP_SMTP_SOCKET_FLAGS: usize =
 // QR code scanning (view from Bob, the joiner)
 pub const DC_VC_AUTH_REQUIRED: i32 = 2;
 pub const DC_VC_CONTACT_CONFIRM: i32 = 6;
-pub const DC_BOB_ERROR: i32 = 0;
-pub const DC_BOB_SUCCESS: i32 = 1;
 
 // max. width/height of an avatar
 pub const AVATAR_SIZE: u32 = 192;
@@ -7,6 +7,7 @@ use itertools::Itertools;
 use rusqlite;
 
 use crate::aheader::EncryptPreference;
+use crate::blob::BlobObject;
 use crate::chat::ChatId;
 use crate::config::Config;
 use crate::constants::*;
@@ -23,10 +24,14 @@ use crate::param::*;
 use crate::peerstate::*;
 use crate::sql;
 use crate::stock::StockMessage;
+use crate::vcard::{self, VcardContact};
 
 /// Contacts with at least this origin value are shown in the contact list.
 const DC_ORIGIN_MIN_CONTACT_LIST: i32 = 0x100;
 
+/// Statuses taken from message footers are cut to about this number of characters.
+const STATUS_MAX_CHARS: usize = 200;
+
 /// An object representing a single contact in memory.
 ///
 /// The contact object is not updated.
@@ -518,6 +523,109 @@ impl Contact {
         Ok(modify_cnt)
     }
 
+    /// Returns a vCard with the given contacts.
+    ///
+    /// The vCard contains the name, the address, the profile image
+    /// and, if known, the Autocrypt key of each contact.
+    /// For DC_CONTACT_ID_SELF, the own name, avatar and key are exported.
+    pub fn export_vcard(context: &Context, contact_ids: &[u32]) -> Result<String> {
+        let mut vcard_contacts = Vec::new();
+        for &contact_id in contact_ids {
+            ensure!(
+                contact_id == DC_CONTACT_ID_SELF || contact_id > DC_CONTACT_ID_LAST_SPECIAL,
+                "Cannot export special contact {}",
+                contact_id
+            );
+            let contact = Contact::load_from_db(context, contact_id)?;
+            let (display_name, key) = if contact_id == DC_CONTACT_ID_SELF {
+                (
+                    context.get_config(Config::Displayname).unwrap_or_default(),
+                    Key::from_self_public(context, &contact.addr, &context.sql),
+                )
+            } else {
+                let name = if !contact.name.is_empty() {
+                    &contact.name
+                } else {
+                    &contact.authname
+                };
+                (
+                    name.to_string(),
+                    Peerstate::from_addr(context, &context.sql, &contact.addr).and_then(
+                        |peerstate| {
+                            peerstate
+                                .peek_key(PeerstateVerifiedStatus::Unverified)
+                                .cloned()
+                        },
+                    ),
+                )
+            };
+            let profile_image = match contact.get_profile_image(context) {
+                Some(path) => Some(base64::encode(&dc_read_file(context, path)?)),
+                None => None,
+            };
+            vcard_contacts.push(VcardContact {
+                addr: contact.addr,
+                display_name,
+                key: key.map(|key| key.to_base64()),
+                profile_image,
+            });
+        }
+        Ok(vcard::make_vcard(&vcard_contacts))
+    }
+
+    /// Imports the contacts of a vCard as created by [Contact::export_vcard]
+    /// or by address books.
+    ///
+    /// Profile images are only imported for contacts without profile image,
+    /// keys are only imported for contacts without known key.
+    ///
+    /// Returns the IDs of the imported contacts.
+    pub fn import_vcard(context: &Context, vcard: &str) -> Result<Vec<u32>> {
+        let mut contact_ids = Vec::new();
+        for vcard_contact in vcard::parse_vcard(vcard) {
+            let contact_id = match Contact::add_or_lookup(
+                context,
+                &vcard_contact.display_name,
+                &vcard_contact.addr,
+                Origin::AddressBook,
+            ) {
+                Ok((contact_id, _)) if contact_id != 0 => contact_id,
+                Ok(_) => continue,
+                Err(err) => {
+                    warn!(
+                        context,
+                        "Cannot import {} from vCard: {}", vcard_contact.addr, err
+                    );
+                    continue;
+                }
+            };
+            if contact_id != DC_CONTACT_ID_SELF {
+                if let Some(ref key) = vcard_contact.key {
+                    if let Err(err) = import_vcard_key(context, &vcard_contact.addr, key) {
+                        warn!(
+                            context,
+                            "Cannot import key of {}: {}", vcard_contact.addr, err
+                        );
+                    }
+                }
+                if let Some(ref profile_image) = vcard_contact.profile_image {
+                    if let Err(err) = import_vcard_profile_image(context, contact_id, profile_image)
+                    {
+                        warn!(
+                            context,
+                            "Cannot import profile image of {}: {}", vcard_contact.addr, err
+                        );
+                    }
+                }
+            }
+            contact_ids.push(contact_id);
+        }
+        if !contact_ids.is_empty() {
+            context.call_cb(Event::ContactsChanged(None));
+        }
+        Ok(contact_ids)
+    }
+
     /// Returns known and unblocked contacts.
     ///
     /// To get information about a single contact, see dc_get_contact().
@@ -869,6 +977,16 @@ impl Contact {
         None
     }
 
+    /// Get the status of the contact, this is the footer of the last chat message
+    /// received from the contact, for Delta Chat users this is the status
+    /// set by dc_set_config(context, "selfstatus", status).
+    ///
+    /// Empty if the contact has no status.
+    /// For DC_CONTACT_ID_SELF, the status is not returned here, use the "selfstatus" config.
+    pub fn get_status(&self) -> &str {
+        self.param.get(Param::Status).unwrap_or_default()
+    }
+
     /// Get a color for the contact.
     /// The color is calculated from the contact's email address
     /// and can be used for an fallback avatar with white initials
@@ -1053,6 +1171,97 @@ pub(crate) fn set_profile_image(
     Ok(())
 }
 
+/// Sets the status of a contact from the footer of a message sent at `timestamp`,
+/// `None` removes the status.
+///
+/// Messages older than the one the current status was taken from are ignored.
+/// The status is cut at the first quoted line and limited in length.
+/// The contact is only written if the status changes.
+pub(crate) fn set_status(
+    context: &Context,
+    contact_id: u32,
+    status: Option<&str>,
+    timestamp: i64,
+) -> Result<()> {
+    let mut contact = Contact::load_from_db(context, contact_id)?;
+    let status_timestamp = contact
+        .param
+        .get(Param::StatusTimestamp)
+        .and_then(|ts| ts.parse::<i64>().ok())
+        .unwrap_or_default();
+    if timestamp < status_timestamp {
+        return Ok(());
+    }
+
+    let status = status
+        .unwrap_or_default()
+        .lines()
+        .take_while(|line| !line.starts_with('>'))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let status = dc_truncate(status.trim(), STATUS_MAX_CHARS);
+    if contact.get_status() == status {
+        return Ok(());
+    }
+    if status.is_empty() {
+        contact.param.remove(Param::Status);
+    } else {
+        contact.param.set(Param::Status, status.as_ref());
+    }
+    contact
+        .param
+        .set(Param::StatusTimestamp, timestamp.to_string());
+    contact.update_param(context)?;
+    context.call_cb(Event::ContactsChanged(Some(contact_id)));
+    Ok(())
+}
+
+/// Uses a key from a vCard as gossip key if no key of the contact is known yet.
+///
+/// The key must contain the address of the contact,
+/// so that a vCard cannot assign the key of one contact to another one.
+fn import_vcard_key(context: &Context, addr: &str, key_base64: &str) -> Result<()> {
+    let key = Key::from_slice(&base64::decode(key_base64)?, KeyType::Public)
+        .filter(|key| key.verify())
+        .ok_or_else(|| format_err!("Invalid key"))?;
+    ensure!(key.has_addr(addr), "Key does not belong to {}", addr);
+    let addr = addr_normalize(addr);
+    let (mut peerstate, create) = match Peerstate::from_addr(context, &context.sql, addr) {
+        Some(peerstate) => {
+            if peerstate
+                .peek_key(PeerstateVerifiedStatus::Unverified)
+                .is_some()
+            {
+                return Ok(());
+            }
+            (peerstate, false)
+        }
+        None => (Peerstate::new(context, addr.to_string()), true),
+    };
+    peerstate.gossip_key = Some(key);
+    peerstate.gossip_timestamp = time();
+    peerstate.to_save = Some(ToSave::All);
+    peerstate.recalc_fingerprint();
+    peerstate.save_to_db(&context.sql, create)?;
+    Ok(())
+}
+
+/// Sets a profile image from a vCard if the contact has no profile image yet.
+fn import_vcard_profile_image(
+    context: &Context,
+    contact_id: u32,
+    image_base64: &str,
+) -> Result<()> {
+    let mut contact = Contact::load_from_db(context, contact_id)?;
+    if contact.param.exists(Param::ProfileImage) {
+        return Ok(());
+    }
+    let blob = BlobObject::create(context, "avatar.jpg", &base64::decode(image_base64)?)?;
+    contact.param.set(Param::ProfileImage, blob.as_name());
+    contact.update_param(context)?;
+    Ok(())
+}
+
 /// Normalize a name.
 ///
 /// - Remove quotes (come from some bad MUA implementations)
@@ -1454,6 +1663,69 @@ mod tests {
         assert_eq!(contact.get_display_name(), "dave2");
     }
 
+    #[test]
+    fn test_vcard_export_import() {
+        let alice = dummy_context();
+        configure_alice_keypair(&alice.ctx);
+        alice
+            .ctx
+            .set_config(Config::Displayname, Some("Alice"))
+            .unwrap();
+        let vcard = Contact::export_vcard(&alice.ctx, &[DC_CONTACT_ID_SELF]).unwrap();
+        assert!(Contact::export_vcard(&alice.ctx, &[DC_CONTACT_ID_DEVICE]).is_err());
+
+        // keys are only imported for the address they belong to
+        let bob = dummy_context();
+        let wrong_vcard = vcard::make_vcard(&[VcardContact {
+            addr: "mallory@example.org".to_string(),
+            ..vcard::parse_vcard(&vcard)[0].clone()
+        }]);
+        assert_eq!(
+            Contact::import_vcard(&bob.ctx, &wrong_vcard).unwrap().len(),
+            1
+        );
+        assert!(Peerstate::from_addr(&bob.ctx, &bob.ctx.sql, "mallory@example.org").is_none());
+
+        let contact_ids = Contact::import_vcard(&bob.ctx, &vcard).unwrap();
+        assert_eq!(contact_ids.len(), 1);
+        let contact = Contact::load_from_db(&bob.ctx, contact_ids[0]).unwrap();
+        assert_eq!(contact.get_addr(), "alice@example.com");
+        assert_eq!(contact.get_name(), "Alice");
+        assert!(contact.get_profile_image(&bob.ctx).is_none());
+
+        // the key is used for encryption, but not verified
+        let peerstate = Peerstate::from_addr(&bob.ctx, &bob.ctx.sql, "alice@example.com").unwrap();
+        let alice_key = Key::from(alice_keypair().public);
+        assert_eq!(
+            peerstate.peek_key(PeerstateVerifiedStatus::Unverified),
+            Some(&alice_key)
+        );
+        assert!(peerstate
+            .peek_key(PeerstateVerifiedStatus::BidirectVerified)
+            .is_none());
+
+        // profile images are imported, exported vCards contain keys
+        let vcard = vcard::make_vcard(&[VcardContact {
+            addr: "alice@example.com".to_string(),
+            display_name: "Alice".to_string(),
+            key: None,
+            profile_image: Some(base64::encode(b"image data")),
+        }]);
+        Contact::import_vcard(&bob.ctx, &vcard).unwrap();
+        let contact = Contact::load_from_db(&bob.ctx, contact_ids[0]).unwrap();
+        let profile_image = contact.get_profile_image(&bob.ctx).unwrap();
+        assert_eq!(std::fs::read(profile_image).unwrap(), b"image data");
+
+        let vcard = Contact::export_vcard(&bob.ctx, &contact_ids).unwrap();
+        let vcard_contacts = vcard::parse_vcard(&vcard);
+        assert_eq!(vcard_contacts.len(), 1);
+        assert_eq!(vcard_contacts[0].key, Some(alice_key.to_base64()));
+        assert_eq!(
+            vcard_contacts[0].profile_image,
+            Some(base64::encode(b"image data"))
+        );
+    }
+
     #[test]
     fn test_addr_cmp() {
         assert!(addr_cmp("AA@AA.ORG", "aa@aa.ORG"));
@@ -2,7 +2,9 @@
 
 use std::collections::HashMap;
 use std::ffi::OsString;
+use std::ops::Deref;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Condvar, Mutex, RwLock};
 
 use crate::chat::*;
@@ -10,20 +12,24 @@ use crate::config::Config;
 use crate::constants::*;
 use crate::contact::*;
 use crate::error::*;
-use crate::events::Event;
+use crate::events::{Event, EventEmitter, Events};
 use crate::imap::*;
 use crate::job::*;
 use crate::job_thread::JobThread;
 use crate::key::Key;
 use crate::login_param::LoginParam;
-use crate::lot::Lot;
-use crate::message::{self, Message, MessengerMessage, MsgId};
+use crate::message::{self, Message, MessageState, MessengerMessage, MsgId};
 use crate::param::Params;
+use crate::quota::Quota;
+use crate::scheduler::Scheduler;
 use crate::smtp::Smtp;
 use crate::sql::Sql;
 
 /// Callback function type for [Context]
 ///
+/// The callback is optional, events can also be received using [Context::get_event_emitter].
+/// It is called synchronously from the thread emitting the event.
+///
 /// # Parameters
 ///
 /// * `context` - The context object as returned by [Context::new].
@@ -32,8 +38,21 @@ use crate::sql::Sql;
 /// * `data2` - Depends on the event parameter, see [Event].
 pub type ContextCallback = dyn Fn(&Context, Event) -> () + Send + Sync;
 
-#[derive(DebugStub)]
+/// The context of an account.
+///
+/// The context is a handle to the account, clones of it refer to the same account.
+/// When the last handle is dropped, the IO is stopped and the database is closed.
+#[derive(Debug)]
 pub struct Context {
+    inner: Arc<InnerContext>,
+
+    /// Internal handles, eg. of the IO loops, do not keep the account open,
+    /// see [Context::clone_internal].
+    internal: bool,
+}
+
+#[derive(DebugStub)]
+pub struct InnerContext {
     /// Database file path
     dbfile: PathBuf,
     /// Blob directory path
@@ -44,19 +63,30 @@ pub struct Context {
     pub inbox_thread: Arc<RwLock<JobThread>>,
     pub sentbox_thread: Arc<RwLock<JobThread>>,
     pub mvbox_thread: Arc<RwLock<JobThread>>,
+    pub watch_thread: Arc<RwLock<JobThread>>,
     pub smtp: Arc<Mutex<Smtp>>,
     pub smtp_state: Arc<(Mutex<SmtpState>, Condvar)>,
     pub oauth2_critical: Arc<Mutex<()>>,
     #[debug_stub = "Callback"]
-    cb: Box<ContextCallback>,
+    cb: Option<Box<ContextCallback>>,
+    events: Events,
     pub os_name: Option<String>,
     pub cmdline_sel_chat_id: Arc<RwLock<ChatId>>,
-    pub(crate) bob: Arc<RwLock<BobStatus>>,
     pub last_smeared_timestamp: RwLock<i64>,
     pub running_state: Arc<RwLock<RunningState>>,
     /// Mutex to avoid generating the key for the user more than once.
     pub generating_key_mutex: Mutex<()>,
     pub translated_stockstrings: RwLock<HashMap<usize, String>>,
+    pub(crate) scheduler: RwLock<Scheduler>,
+    /// Timestamps of the last typing notifications sent per chat, see [send_typing].
+    pub(crate) typing_sent: Mutex<HashMap<ChatId, i64>>,
+    /// The last quota reported by the server, see [Context::get_quota].
+    pub(crate) quota: RwLock<Option<Quota>>,
+    /// Passphrase of the pending import or export of a backup,
+    /// kept out of the job parameters which are stored in the database.
+    pub(crate) imex_passphrase: Mutex<Option<String>>,
+    /// Number of handles that are no internal handles.
+    user_handles: AtomicUsize,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -82,41 +112,74 @@ pub fn get_info() -> HashMap<&'static str, String> {
 
 impl Context {
     /// Creates new context.
-    pub fn new(cb: Box<ContextCallback>, os_name: String, dbfile: PathBuf) -> Result<Context> {
-        pretty_env_logger::try_init_timed().ok();
-
-        let mut blob_fname = OsString::new();
-        blob_fname.push(dbfile.file_name().unwrap_or_default());
-        blob_fname.push("-blobs");
-        let blobdir = dbfile.with_file_name(blob_fname);
-        if !blobdir.exists() {
-            std::fs::create_dir_all(&blobdir)?;
-        }
-        Context::with_blobdir(cb, os_name, dbfile, blobdir)
+    ///
+    /// Events are queued for the emitters returned by [Context::get_event_emitter]
+    /// and additionally passed to the callback, if any.
+    pub fn new(
+        cb: Option<Box<ContextCallback>>,
+        os_name: String,
+        dbfile: PathBuf,
+    ) -> Result<Context> {
+        Context::with_passphrase(cb, os_name, dbfile, None, None)
     }
 
     pub fn with_blobdir(
-        cb: Box<ContextCallback>,
+        cb: Option<Box<ContextCallback>>,
         os_name: String,
         dbfile: PathBuf,
         blobdir: PathBuf,
     ) -> Result<Context> {
+        Context::with_passphrase(cb, os_name, dbfile, Some(blobdir), None)
+    }
+
+    /// Creates a context whose database is encrypted with `passphrase`.
+    ///
+    /// Encrypted databases need SQLite built with SQLCipher and FTS5,
+    /// an existing unencrypted database is encrypted on opening.
+    /// The passphrase must not be empty.
+    /// Without `blobdir`, the blobdir is placed next to the database file.
+    pub fn with_passphrase(
+        cb: Option<Box<ContextCallback>>,
+        os_name: String,
+        dbfile: PathBuf,
+        blobdir: Option<PathBuf>,
+        passphrase: Option<String>,
+    ) -> Result<Context> {
+        pretty_env_logger::try_init_timed().ok();
+        ensure!(
+            passphrase.as_deref() != Some(""),
+            "Empty database passphrase"
+        );
+
+        let blobdir = match blobdir {
+            Some(blobdir) => blobdir,
+            None => {
+                let mut blob_fname = OsString::new();
+                blob_fname.push(dbfile.file_name().unwrap_or_default());
+                blob_fname.push("-blobs");
+                let blobdir = dbfile.with_file_name(blob_fname);
+                if !blobdir.exists() {
+                    std::fs::create_dir_all(&blobdir)?;
+                }
+                blobdir
+            }
+        };
         ensure!(
             blobdir.is_dir(),
             "Blobdir does not exist: {}",
             blobdir.display()
         );
-        let ctx = Context {
+        let inner = InnerContext {
             blobdir,
             dbfile,
             cb,
+            events: Events::new(),
             os_name: Some(os_name),
             running_state: Arc::new(RwLock::new(Default::default())),
             sql: Sql::new(),
             smtp: Arc::new(Mutex::new(Smtp::new())),
             smtp_state: Arc::new((Mutex::new(Default::default()), Condvar::new())),
             oauth2_critical: Arc::new(Mutex::new(())),
-            bob: Arc::new(RwLock::new(Default::default())),
             last_smeared_timestamp: RwLock::new(0),
             cmdline_sel_chat_id: Arc::new(RwLock::new(ChatId::new(0))),
             inbox_thread: Arc::new(RwLock::new(JobThread::new(
@@ -134,20 +197,49 @@ impl Context {
                 "configured_mvbox_folder",
                 Imap::new(),
             ))),
+            watch_thread: Arc::new(RwLock::new(JobThread::new(
+                "WATCH",
+                "watch_folders",
+                Imap::new(),
+            ))),
             probe_imap_network: Arc::new(RwLock::new(false)),
             perform_inbox_jobs_needed: Arc::new(RwLock::new(false)),
             generating_key_mutex: Mutex::new(()),
             translated_stockstrings: RwLock::new(HashMap::new()),
+            scheduler: RwLock::new(Scheduler::Stopped),
+            typing_sent: Mutex::new(HashMap::new()),
+            quota: RwLock::new(None),
+            imex_passphrase: Mutex::new(None),
+            user_handles: AtomicUsize::new(1),
+        };
+        let ctx = Context {
+            inner: Arc::new(inner),
+            internal: false,
         };
 
         ensure!(
-            ctx.sql.open(&ctx, &ctx.dbfile, false),
+            ctx.sql
+                .open(&ctx, &ctx.dbfile, false, passphrase.as_deref()),
             "Failed opening sqlite database"
         );
 
         Ok(ctx)
     }
 
+    /// Changes the passphrase of the database, `None` removes the encryption.
+    ///
+    /// IO must be stopped while the database is rewritten.
+    pub fn change_passphrase(&self, passphrase: Option<&str>) -> Result<()> {
+        ensure!(
+            !self.is_io_running(),
+            "Cannot change passphrase while IO is running"
+        );
+        ensure!(self.alloc_ongoing(), "Cannot allocate ongoing process");
+        let res = self.sql.change_passphrase(self, &self.dbfile, passphrase);
+        self.free_ongoing();
+        res
+    }
+
     /// Returns database file path.
     pub fn get_dbfile(&self) -> &Path {
         self.dbfile.as_path()
@@ -159,7 +251,48 @@ impl Context {
     }
 
     pub fn call_cb(&self, event: Event) {
-        (*self.cb)(self, event);
+        if let Some(ref cb) = self.cb {
+            cb(self, event.clone());
+        }
+        self.events.emit(event);
+    }
+
+    /// Returns an emitter to receive the events of this context.
+    pub fn get_event_emitter(&self) -> EventEmitter {
+        self.events.get_emitter()
+    }
+
+    /// Starts the IMAP and SMTP loops.
+    ///
+    /// The loops run in the background, perform jobs, fetch and idle
+    /// until [Context::stop_io] is called.
+    /// Calling this function while the loops are already running does nothing.
+    pub fn start_io(&self) {
+        let mut scheduler = self.scheduler.write().unwrap();
+        if scheduler.is_running() {
+            info!(self, "IO is already running.");
+            return;
+        }
+        info!(self, "Starting IO.");
+        *scheduler = Scheduler::start(self);
+    }
+
+    /// Stops the IMAP and SMTP loops and waits until they are finished.
+    ///
+    /// This is also done when the last handle to the context is dropped.
+    pub fn stop_io(&self) {
+        let scheduler =
+            std::mem::replace(&mut *self.scheduler.write().unwrap(), Scheduler::Stopped);
+        if scheduler.is_running() {
+            info!(self, "Stopping IO.");
+            scheduler.stop(self);
+            info!(self, "IO stopped.");
+        }
+    }
+
+    /// Returns true if the IMAP and SMTP loops are running.
+    pub fn is_io_running(&self) -> bool {
+        self.scheduler.read().unwrap().is_running()
     }
 
     /*******************************************************************************
@@ -299,6 +432,27 @@ impl Context {
         res.insert("folders_configured", folders_configured.to_string());
         res.insert("configured_sentbox_folder", configured_sentbox_folder);
         res.insert("configured_mvbox_folder", configured_mvbox_folder);
+        res.insert("watch_folders", self.get_watch_folders().join(","));
+        res.insert(
+            "quota",
+            match self.get_quota() {
+                Some(ref quota) if !quota.resources.is_empty() => quota
+                    .resources
+                    .iter()
+                    .map(|resource| {
+                        format!(
+                            "{} {}/{} ({}%)",
+                            resource.name,
+                            resource.usage,
+                            resource.limit,
+                            resource.get_usage_percentage()
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                _ => "<unknown>".to_string(),
+            },
+        );
         res.insert("mdns_enabled", mdns_enabled.to_string());
         res.insert("e2ee_enabled", e2ee_enabled.to_string());
         res.insert("bcc_self", bcc_self.to_string());
@@ -346,31 +500,71 @@ impl Context {
             .unwrap_or_default()
     }
 
-    #[allow(non_snake_case)]
+    /// Returns the own messages of all chats that are not sent yet,
+    /// either pending or failed, starting with the most recent message.
+    pub fn get_outbox_msgs(&self) -> Vec<MsgId> {
+        self.sql
+            .query_map(
+                "SELECT id FROM msgs \
+                 WHERE from_id=? AND state IN (?, ?) AND hidden=0 AND chat_id>? \
+                 ORDER BY timestamp DESC, id DESC;",
+                params![
+                    DC_CONTACT_ID_SELF,
+                    MessageState::OutPending,
+                    MessageState::OutFailed,
+                    DC_CHAT_ID_LAST_SPECIAL
+                ],
+                |row| row.get::<_, MsgId>(0),
+                |rows| {
+                    rows.collect::<std::result::Result<Vec<_>, _>>()
+                        .map_err(Into::into)
+                },
+            )
+            .unwrap_or_default()
+    }
+
+    /// Searches message texts and attachment filenames using the full-text index
+    /// as well as the names of the senders.
+    ///
+    /// All words of the query have to match, the last characters of a word may be missing;
+    /// sender names have to start with the query.
+    /// If `chat_id` is set, the messages of this chat are returned in chat order,
+    /// otherwise messages of all chats are returned, the best text matches first.
     pub fn search_msgs(&self, chat_id: ChatId, query: impl AsRef<str>) -> Vec<MsgId> {
-        let real_query = query.as_ref().trim();
-        if real_query.is_empty() {
-            return Vec::new();
-        }
-        let strLikeInText = format!("%{}%", real_query);
-        let strLikeBeg = format!("{}%", real_query);
+        let fts_query = match fts_query(query.as_ref()) {
+            Some(fts_query) => fts_query,
+            None => return Vec::new(),
+        };
+        let name_like = format!("{}%", query.as_ref().trim());
 
+        // the subquery only touches the messages matching the text or sent by a matching contact
         let query = if !chat_id.is_unset() {
             concat!(
-                "SELECT m.id AS id, m.timestamp AS timestamp",
-                " FROM msgs m",
+                "SELECT m.id AS id",
+                " FROM (SELECT rowid AS id FROM msgs_fts WHERE msgs_fts MATCH ?",
+                "       UNION",
+                "       SELECT id FROM msgs",
+                "        WHERE from_id IN (SELECT id FROM contacts WHERE name LIKE ?)) f",
+                " INNER JOIN msgs m",
+                "        ON m.id=f.id",
                 " LEFT JOIN contacts ct",
                 "        ON m.from_id=ct.id",
                 " WHERE m.chat_id=?",
                 "   AND m.hidden=0",
                 "   AND ct.blocked=0",
-                "   AND (txt LIKE ? OR ct.name LIKE ?)",
                 " ORDER BY m.timestamp,m.id;"
             )
         } else {
             concat!(
-                "SELECT m.id AS id, m.timestamp AS timestamp",
-                " FROM msgs m",
+                "SELECT m.id AS id",
+                " FROM (SELECT id, MIN(rank) AS rank",
+                "         FROM (SELECT rowid AS id, rank FROM msgs_fts WHERE msgs_fts MATCH ?",
+                "               UNION ALL",
+                "               SELECT id, NULL AS rank FROM msgs",
+                "                WHERE from_id IN (SELECT id FROM contacts WHERE name LIKE ?))",
+                "        GROUP BY id) f",
+                " INNER JOIN msgs m",
+                "        ON m.id=f.id",
                 " LEFT JOIN contacts ct",
                 "        ON m.from_id=ct.id",
                 " LEFT JOIN chats c",
@@ -379,15 +573,14 @@ impl Context {
                 "   AND m.hidden=0",
                 "   AND (c.blocked=0 OR c.blocked=?)",
                 "   AND ct.blocked=0",
-                "   AND (m.txt LIKE ? OR ct.name LIKE ?)",
-                " ORDER BY m.timestamp DESC,m.id DESC;"
+                " ORDER BY f.rank IS NULL,f.rank,m.timestamp DESC,m.id DESC;"
             )
         };
 
         self.sql
             .query_map(
                 query,
-                params![chat_id, &strLikeInText, &strLikeBeg],
+                params![fts_query, name_like, chat_id],
                 |row| row.get::<_, MsgId>("id"),
                 |rows| {
                     let mut ret = Vec::new();
@@ -400,6 +593,27 @@ impl Context {
             .unwrap_or_default()
     }
 
+    /// Returns the part of the message text or filename matching a query of [Context::search_msgs].
+    ///
+    /// The matching words are enclosed by `start_mark` and `end_mark`,
+    /// omitted text is replaced by an ellipsis.
+    /// Returns `None` if the message does not match the query.
+    pub fn get_search_snippet(
+        &self,
+        msg_id: MsgId,
+        query: impl AsRef<str>,
+        start_mark: &str,
+        end_mark: &str,
+    ) -> Option<String> {
+        let fts_query = fts_query(query.as_ref())?;
+        self.sql.query_get_value(
+            self,
+            "SELECT snippet(msgs_fts, -1, ?, ?, '...', 16) FROM msgs_fts \
+             WHERE msgs_fts MATCH ? AND rowid=?;",
+            params![start_mark, end_mark, fts_query, msg_id],
+        )
+    }
+
     pub fn is_inbox(&self, folder_name: impl AsRef<str>) -> bool {
         folder_name.as_ref() == "INBOX"
     }
@@ -454,14 +668,54 @@ impl Context {
     }
 }
 
+impl Context {
+    /// Returns a handle to the account that does not keep it open.
+    ///
+    /// The IO loops use internal handles, so dropping the last handle
+    /// of the user stops the loops instead of waiting for them forever.
+    pub(crate) fn clone_internal(&self) -> Context {
+        Context {
+            inner: Arc::clone(&self.inner),
+            internal: true,
+        }
+    }
+}
+
+impl Clone for Context {
+    fn clone(&self) -> Self {
+        self.user_handles.fetch_add(1, Ordering::SeqCst);
+        Context {
+            inner: Arc::clone(&self.inner),
+            internal: false,
+        }
+    }
+}
+
+impl Deref for Context {
+    type Target = InnerContext;
+
+    fn deref(&self) -> &InnerContext {
+        &self.inner
+    }
+}
+
 impl Drop for Context {
     fn drop(&mut self) {
+        // the counter is decremented atomically,
+        // so exactly one of several concurrently dropped handles is the last one
+        if self.internal || self.user_handles.fetch_sub(1, Ordering::SeqCst) > 1 {
+            // other handles to the account are still in use
+            return;
+        }
+        self.stop_io();
         info!(self, "disconnecting inbox-thread",);
         self.inbox_thread.read().unwrap().imap.disconnect(self);
         info!(self, "disconnecting sentbox-thread",);
         self.sentbox_thread.read().unwrap().imap.disconnect(self);
         info!(self, "disconnecting mvbox-thread",);
         self.mvbox_thread.read().unwrap().imap.disconnect(self);
+        info!(self, "disconnecting watch-thread",);
+        self.watch_thread.read().unwrap().imap.disconnect(self);
         info!(self, "disconnecting SMTP");
         self.smtp.clone().lock().unwrap().disconnect();
         self.sql.close(self);
@@ -477,13 +731,6 @@ impl Default for RunningState {
     }
 }
 
-#[derive(Debug, Default)]
-pub(crate) struct BobStatus {
-    pub expects: i32,
-    pub status: i32,
-    pub qr_scan: Option<Lot>,
-}
-
 #[derive(Debug, PartialEq)]
 pub(crate) enum PerformJobsNeeded {
     Not,
@@ -510,6 +757,22 @@ pub fn get_version_str() -> &'static str {
     &DC_VERSION_STR
 }
 
+/// Converts a search query entered by the user to an FTS5 query.
+///
+/// Every word is quoted so that FTS5 operators are not interpreted
+/// and is matched as a prefix, the words are implicitly combined by AND.
+fn fts_query(query: &str) -> Option<String> {
+    let words: Vec<String> = query
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+    if words.is_empty() {
+        None
+    } else {
+        Some(words.join(" "))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -521,7 +784,7 @@ mod tests {
         let tmp = tempfile::tempdir().unwrap();
         let dbfile = tmp.path().join("db.sqlite");
         std::fs::write(&dbfile, b"123").unwrap();
-        let res = Context::new(Box::new(|_, _| ()), "FakeOs".into(), dbfile);
+        let res = Context::new(None, "FakeOs".into(), dbfile);
         assert!(res.is_err());
     }
 
@@ -536,7 +799,7 @@ mod tests {
     fn test_blobdir_exists() {
         let tmp = tempfile::tempdir().unwrap();
         let dbfile = tmp.path().join("db.sqlite");
-        Context::new(Box::new(|_, _| ()), "FakeOS".into(), dbfile).unwrap();
+        Context::new(None, "FakeOS".into(), dbfile).unwrap();
         let blobdir = tmp.path().join("db.sqlite-blobs");
         assert!(blobdir.is_dir());
     }
@@ -547,7 +810,7 @@ mod tests {
         let dbfile = tmp.path().join("db.sqlite");
         let blobdir = tmp.path().join("db.sqlite-blobs");
         std::fs::write(&blobdir, b"123").unwrap();
-        let res = Context::new(Box::new(|_, _| ()), "FakeOS".into(), dbfile);
+        let res = Context::new(None, "FakeOS".into(), dbfile);
         assert!(res.is_err());
     }
 
@@ -557,7 +820,7 @@ mod tests {
         let subdir = tmp.path().join("subdir");
         let dbfile = subdir.join("db.sqlite");
         let dbfile2 = dbfile.clone();
-        Context::new(Box::new(|_, _| ()), "FakeOS".into(), dbfile).unwrap();
+        Context::new(None, "FakeOS".into(), dbfile).unwrap();
         assert!(subdir.is_dir());
         assert!(dbfile2.is_file());
     }
@@ -567,7 +830,7 @@ mod tests {
         let tmp = tempfile::tempdir().unwrap();
         let dbfile = tmp.path().join("db.sqlite");
         let blobdir = PathBuf::new();
-        let res = Context::with_blobdir(Box::new(|_, _| ()), "FakeOS".into(), dbfile, blobdir);
+        let res = Context::with_blobdir(None, "FakeOS".into(), dbfile, blobdir);
         assert!(res.is_err());
     }
 
@@ -576,8 +839,81 @@ mod tests {
         let tmp = tempfile::tempdir().unwrap();
         let dbfile = tmp.path().join("db.sqlite");
         let blobdir = tmp.path().join("blobs");
-        let res = Context::with_blobdir(Box::new(|_, _| ()), "FakeOS".into(), dbfile, blobdir);
+        let res = Context::with_blobdir(None, "FakeOS".into(), dbfile, blobdir);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    #[cfg(not(feature = "sqlcipher"))]
+    fn test_passphrase_without_sqlcipher() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dbfile = tmp.path().join("db.sqlite");
+        Context::new(None, "FakeOS".into(), dbfile.clone()).unwrap();
+        let res = Context::with_passphrase(
+            None,
+            "FakeOS".into(),
+            dbfile.clone(),
+            None,
+            Some("secret".into()),
+        );
+        assert!(res.is_err());
+
+        // an empty key would not encrypt the database
+        let res =
+            Context::with_passphrase(None, "FakeOS".into(), dbfile.clone(), None, Some("".into()));
         assert!(res.is_err());
+
+        // the unencrypted database is left untouched
+        Context::new(None, "FakeOS".into(), dbfile).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "sqlcipher")]
+    fn test_change_passphrase() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dbfile = tmp.path().join("db.sqlite");
+        let ctx = Context::new(None, "FakeOS".into(), dbfile.clone()).unwrap();
+        ctx.set_config(Config::Displayname, Some("Alice")).unwrap();
+        std::mem::drop(ctx);
+
+        let ctx = Context::with_passphrase(
+            None,
+            "FakeOS".into(),
+            dbfile.clone(),
+            None,
+            Some("secret".into()),
+        )
+        .unwrap();
+        assert!(ctx.sql.is_encrypted());
+        assert_eq!(ctx.get_config(Config::Displayname).unwrap(), "Alice");
+        assert!(ctx.change_passphrase(Some("")).is_err());
+        ctx.change_passphrase(Some("other")).unwrap();
+        std::mem::drop(ctx);
+
+        assert!(Context::new(None, "FakeOS".into(), dbfile.clone()).is_err());
+        let res = Context::with_passphrase(
+            None,
+            "FakeOS".into(),
+            dbfile.clone(),
+            None,
+            Some("secret".into()),
+        );
+        assert!(res.is_err());
+
+        let ctx = Context::with_passphrase(
+            None,
+            "FakeOS".into(),
+            dbfile.clone(),
+            None,
+            Some("other".into()),
+        )
+        .unwrap();
+        ctx.change_passphrase(None).unwrap();
+        assert!(!ctx.sql.is_encrypted());
+        std::mem::drop(ctx);
+
+        let ctx = Context::new(None, "FakeOS".into(), dbfile).unwrap();
+        assert_eq!(ctx.get_config(Config::Displayname).unwrap(), "Alice");
     }
 
     #[test]
@@ -601,4 +937,98 @@ mod tests {
         assert!(info.get("database_dir").is_none());
         assert_eq!(info.get("level").unwrap(), "awesome");
     }
+
+    #[test]
+    fn test_fts_query() {
+        assert_eq!(fts_query("  "), None);
+        assert_eq!(fts_query("foo"), Some(r#""foo"*"#.to_string()));
+        assert_eq!(
+            fts_query("foo \"bar OR"),
+            Some(r#""foo"* """bar"* "OR"*"#.to_string())
+        );
+    }
+
+    #[test]
+    fn test_search_msgs() {
+        let t = dummy_context();
+        let contact_id = Contact::create(&t.ctx, "Bob", "bob@example.org").unwrap();
+        let chat_id = create_by_contact_id(&t.ctx, contact_id).unwrap();
+        for (timestamp, txt, param) in &[
+            (1, "hello world", ""),
+            (2, "hello again", ""),
+            (3, "the photos", "f=$BLOBDIR/holiday.jpg\nm=image/jpeg"),
+        ] {
+            t.ctx
+                .sql
+                .execute(
+                    "INSERT INTO msgs (chat_id, from_id, timestamp, txt, param) VALUES (?,?,?,?,?);",
+                    params![chat_id, contact_id, timestamp, txt, param],
+                )
+                .unwrap();
+        }
+
+        assert_eq!(t.ctx.search_msgs(chat_id, "hel").len(), 2);
+        assert_eq!(t.ctx.search_msgs(ChatId::new(0), "hello wor").len(), 1);
+        assert!(t.ctx.search_msgs(chat_id, "world again").is_empty());
+        assert!(t.ctx.search_msgs(chat_id, "   ").is_empty());
+
+        // messages are found by the name of the sender as well
+        assert_eq!(t.ctx.search_msgs(chat_id, "Bo").len(), 3);
+        assert_eq!(t.ctx.search_msgs(ChatId::new(0), "bob").len(), 3);
+        assert!(t.ctx.search_msgs(chat_id, "ob").is_empty());
+
+        // attachment filenames are searched as well
+        let msg_ids = t.ctx.search_msgs(chat_id, "holiday");
+        assert_eq!(msg_ids.len(), 1);
+        assert_eq!(
+            t.ctx
+                .get_search_snippet(msg_ids[0], "holiday", "<b>", "</b>"),
+            Some("<b>holiday</b>.jpg".to_string())
+        );
+
+        // the index follows changes and deletions
+        t.ctx
+            .sql
+            .execute(
+                "UPDATE msgs SET txt='bye world' WHERE txt='hello world';",
+                params![],
+            )
+            .unwrap();
+        assert_eq!(t.ctx.search_msgs(chat_id, "hello").len(), 1);
+        t.ctx
+            .sql
+            .execute("DELETE FROM msgs WHERE txt='hello again';", params![])
+            .unwrap();
+        assert!(t.ctx.search_msgs(chat_id, "hello").is_empty());
+        assert_eq!(t.ctx.search_msgs(chat_id, "world").len(), 1);
+    }
+
+    #[test]
+    fn test_start_stop_io() {
+        let t = dummy_context();
+        assert!(!t.ctx.is_io_running());
+        t.ctx.start_io();
+        assert!(t.ctx.is_io_running());
+        // starting again does not start a second scheduler
+        t.ctx.start_io();
+        t.ctx.stop_io();
+        assert!(!t.ctx.is_io_running());
+        t.ctx.stop_io();
+    }
+
+    #[test]
+    fn test_drop_stops_io() {
+        let t = dummy_context();
+        t.ctx.start_io();
+        let ctx = t.ctx.clone();
+        let inner = Arc::downgrade(&ctx.inner);
+
+        // other handles keep the account open
+        std::mem::drop(t);
+        assert!(ctx.is_io_running());
+
+        // the loops do not keep the account open
+        std::mem::drop(ctx);
+        assert!(inner.upgrade().is_none());
+    }
 }
@@ -3,7 +3,7 @@ use sha2::{Digest, Sha256};
 
 use num_traits::FromPrimitive;
 
-use crate::chat::{self, Chat, ChatId};
+use crate::chat::{self, Chat, ChatId, EphemeralTimer};
 use crate::config::Config;
 use crate::constants::*;
 use crate::contact::*;
@@ -20,7 +20,7 @@ use crate::peerstate::*;
 use crate::securejoin::{self, handle_securejoin_handshake};
 use crate::sql;
 use crate::stock::StockMessage;
-use crate::{contact, location};
+use crate::{contact, location, reaction};
 
 // IndexSet is like HashSet but maintains order of insertion
 type ContactIds = indexmap::IndexSet<u32>;
@@ -192,6 +192,27 @@ pub fn dc_receive_imf(
         };
     }
 
+    // for chat messages, the footer is the status of the sender and a missing footer removes the status,
+    // footers of other messages are mostly signatures or quoted threads and are not used
+    if from_id > DC_CONTACT_ID_LAST_SPECIAL
+        && mime_parser.has_chat_version()
+        && mime_parser.get(HeaderDef::ListId).is_none()
+        && mime_parser.reports.is_empty()
+        && mime_parser
+            .parts
+            .iter()
+            .any(|part| part.typ == Viewtype::Text)
+    {
+        if let Err(err) = contact::set_status(
+            context,
+            from_id,
+            mime_parser.footer.as_deref(),
+            sent_timestamp,
+        ) {
+            warn!(context, "receive_imf cannot update status: {}", err);
+        }
+    }
+
     // if we delete we don't need to try moving messages
     if needs_delete_job && !created_db_entries.is_empty() {
         job_add(
@@ -308,10 +329,65 @@ fn add_parts(
     } else {
         MessengerMessage::No
     };
+    // reactions are not shown as messages but attached to the message they refer to;
+    // the reaction message itself goes to the trash so that it is recognized when seen again.
+    if mime_parser.is_reaction() {
+        let in_reply_to = mime_parser
+            .get(HeaderDef::InReplyTo)
+            .cloned()
+            .unwrap_or_default();
+        let reaction = mime_parser
+            .parts
+            .first()
+            .map(|part| part.msg.clone())
+            .unwrap_or_default();
+        if let Err(err) =
+            reaction::set_reaction_by_in_reply_to(context, &in_reply_to, from_id, &reaction)
+        {
+            warn!(context, "Cannot apply reaction: {}", err);
+        }
+        *chat_id = ChatId::new(DC_CHAT_ID_TRASH);
+        *hidden = true;
+    }
+
+    // edits and deletions are applied to the messages they refer to,
+    // the requests themselves go to the trash just as reactions.
+    if let Some(value) = mime_parser.get(HeaderDef::ChatEdit) {
+        let new_text = mime_parser
+            .parts
+            .first()
+            .map(|part| part.msg.clone())
+            .unwrap_or_default();
+        if let Err(err) = parse_message_id(value)
+            .and_then(|rfc724_mid| message::apply_edit(context, &rfc724_mid, from_id, &new_text))
+        {
+            warn!(context, "Cannot apply edit: {}", err);
+        }
+        *chat_id = ChatId::new(DC_CHAT_ID_TRASH);
+        *hidden = true;
+    }
+    if let Some(value) = mime_parser.get(HeaderDef::ChatDelete) {
+        let rfc724_mids: Vec<String> = mailparse::msgidparse(value)
+            .map(|ids| {
+                ids.iter()
+                    .map(|id| id.trim_start_matches('<').trim_end_matches('>').to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        if let Err(err) = message::apply_delete_request(context, &rfc724_mids, from_id) {
+            warn!(context, "Cannot apply deletion: {}", err);
+        }
+        *chat_id = ChatId::new(DC_CHAT_ID_TRASH);
+        *hidden = true;
+    }
+
     // incoming non-chat messages may be discarded
     let mut allow_creation = true;
     let show_emails =
         ShowEmails::from_i32(context.get_config_int(Config::ShowEmails)).unwrap_or_default();
+    // mailing lists get their own chats, see create_or_lookup_mailinglist()
+    let is_mailinglist = mime_parser.get(HeaderDef::ListId).is_some()
+        && mime_parser.get(HeaderDef::ChatGroupId).is_none();
     if mime_parser.is_system_message != SystemMessage::AutocryptSetupMessage
         && msgrmsg == MessengerMessage::No
     {
@@ -326,6 +402,12 @@ fn add_parts(
         }
     }
 
+    // typing notifications never introduce a chat
+    let typing = mime_parser.get_typing();
+    if typing.is_some() {
+        allow_creation = false;
+    }
+
     // check if the message introduces a new chat:
     // - outgoing messages introduce a chat with the first to: address if they are sent by a messenger
     // - incoming messages introduce a chat only for known contacts if they are sent by a messenger
@@ -363,8 +445,6 @@ fn add_parts(
                 }
                 Err(err) => {
                     *hidden = true;
-                    context.bob.write().unwrap().status = 0; // secure-join failed
-                    context.stop_ongoing();
                     error!(context, "Error in Secure-Join message handling: {}", err);
                 }
             }
@@ -396,9 +476,11 @@ fn add_parts(
             )?;
             *chat_id = new_chat_id;
             chat_id_blocked = new_chat_id_blocked;
+            // mailing lists are accepted explicitly, also if the sender is known
             if !chat_id.is_unset()
                 && chat_id_blocked != Blocked::Not
                 && create_blocked == Blocked::Not
+                && !is_mailinglist
             {
                 new_chat_id.unblock(context);
                 chat_id_blocked = Blocked::Not;
@@ -462,6 +544,7 @@ fn add_parts(
             && !incoming_origin.is_known()
             && msgrmsg == MessengerMessage::No
             && show_emails != ShowEmails::All
+            && !is_mailinglist
         {
             state = MessageState::InNoticed;
         }
@@ -534,6 +617,44 @@ fn add_parts(
             *chat_id = ChatId::new(DC_CHAT_ID_TRASH);
         }
     }
+    // typing notifications are passed to the UI only,
+    // the mail is not added to the database and is deleted from the server.
+    // as anyone can send typing notifications in the name of a contact,
+    // only encrypted and signed ones are accepted.
+    if let Some(typing) = typing {
+        if !mime_parser.was_encrypted() || mime_parser.signatures.is_empty() {
+            info!(
+                context,
+                "Ignoring typing notification not encrypted and signed."
+            );
+        } else if incoming
+            && !chat_id.is_special()
+            && chat_id_blocked == Blocked::Not
+            && !from_id_blocked
+        {
+            let (chat_id, contact_id) = (*chat_id, from_id);
+            context.call_cb(if typing {
+                Event::ContactTyping {
+                    chat_id,
+                    contact_id,
+                }
+            } else {
+                Event::ContactStoppedTyping {
+                    chat_id,
+                    contact_id,
+                }
+            });
+        }
+        let mut param = Params::new();
+        param.set(Param::ServerFolder, server_folder.as_ref());
+        param.set_int(Param::ServerUid, server_uid as i32);
+        param.set(Param::Arg, rfc724_mid);
+        job_add(context, Action::DeleteMsgOnImap, 0, param, 0);
+        *chat_id = ChatId::new(DC_CHAT_ID_TRASH);
+        *hidden = true;
+        return Ok(());
+    }
+
     // correct message_timestamp, it should not be used before,
     // however, we cannot do this earlier as we need from_id to be set
     calc_timestamps(
@@ -550,6 +671,39 @@ fn add_parts(
     // unarchive chat
     chat_id.unarchive(context)?;
 
+    // apply the ephemeral timer of the sender to the chat;
+    // messages without the header (eg. from classic MUAs) do not disable the timer.
+    let ephemeral_timer = match mime_parser.get(HeaderDef::EphemeralTimer) {
+        Some(value) => match value.parse::<EphemeralTimer>() {
+            Ok(timer) => {
+                if !chat_id.is_special() && chat_id.get_ephemeral_timer(context)? != timer {
+                    info!(context, "received new ephemeral timer value {:?}", timer);
+                    chat_id.inner_set_ephemeral_timer(context, timer)?;
+                }
+                timer
+            }
+            Err(err) => {
+                warn!(
+                    context,
+                    "can't parse ephemeral timer \"{}\": {}", value, err
+                );
+                EphemeralTimer::Disabled
+            }
+        },
+        None => EphemeralTimer::Disabled,
+    };
+    if mime_parser.is_system_message == SystemMessage::EphemeralTimerChanged {
+        let better_msg = chat::stock_ephemeral_timer_changed(context, ephemeral_timer, from_id);
+        set_better_msg(mime_parser, &better_msg);
+    }
+    // the timer of outgoing messages and of messages already seen starts immediately
+    let ephemeral_timestamp = match ephemeral_timer {
+        EphemeralTimer::Enabled { duration } if !incoming || state == MessageState::InSeen => {
+            rcvd_timestamp + i64::from(duration)
+        }
+        _ => 0,
+    };
+
     // if the mime-headers should be saved, find out its size
     // (the mime-header ends with an empty line)
     let save_mime_headers = context.get_config_bool(Config::SaveMimeHeaders);
@@ -573,8 +727,9 @@ fn add_parts(
         "INSERT INTO msgs \
          (rfc724_mid, server_folder, server_uid, chat_id, from_id, to_id, timestamp, \
          timestamp_sent, timestamp_rcvd, type, state, msgrmsg,  txt, txt_raw, param, \
-         bytes, hidden, mime_headers,  mime_in_reply_to, mime_references) \
-         VALUES (?,?,?,?,?,?, ?,?,?,?,?,?, ?,?,?,?,?,?, ?,?);",
+         bytes, hidden, mime_headers,  mime_in_reply_to, mime_references, \
+         ephemeral_timer, ephemeral_timestamp) \
+         VALUES (?,?,?,?,?,?, ?,?,?,?,?,?, ?,?,?,?,?,?, ?,?,?,?);",
         |mut stmt, conn| {
             let subject = mime_parser.get_subject().unwrap_or_default();
 
@@ -624,6 +779,8 @@ fn add_parts(
                     },
                     mime_in_reply_to,
                     mime_references,
+                    ephemeral_timer,
+                    ephemeral_timestamp,
                 ])?;
 
                 txt_raw = None;
@@ -641,6 +798,10 @@ fn add_parts(
         "Message has {} parts and is assigned to chat #{}.", icnt, *chat_id,
     );
 
+    if ephemeral_timestamp != 0 {
+        message::schedule_ephemeral_task(context);
+    }
+
     // check event to send
     if chat_id.is_trash() {
         *create_event_to_send = None;
@@ -785,6 +946,20 @@ fn create_or_lookup_group(
     }
 
     if grpid.is_empty() {
+        // a mailing list software may add a List-Id to group messages,
+        // the Chat-Group-ID takes precedence then
+        if let Some(list_id) = mime_parser.get(HeaderDef::ListId) {
+            if let Some((listid, listname)) = parse_list_id(list_id) {
+                return create_or_lookup_mailinglist(
+                    context,
+                    mime_parser,
+                    allow_creation,
+                    &listid,
+                    &listname,
+                );
+            }
+        }
+
         if let Some(value) = mime_parser.get(HeaderDef::MessageId) {
             if let Some(extracted_grpid) = dc_extract_grpid_from_rfc724_mid(&value) {
                 grpid = extracted_grpid.to_string();
@@ -1079,8 +1254,6 @@ fn create_or_lookup_adhoc_group(
     // (we do not want to heuristically look at the likely mangled Subject)
 
     if mime_parser.is_mailinglist_message() {
-        // XXX we could parse List-* headers and actually create and
-        // manage a mailing list group, eventually
         info!(
             context,
             "not creating ad-hoc group for mailing list message"
@@ -1172,7 +1345,124 @@ fn create_or_lookup_adhoc_group(
     Ok((new_chat_id, create_blocked))
 }
 
-fn create_group_record(
+/// Returns the chat of the mailing list `listid`, creating it if needed.
+///
+/// New mailing list chats are shown as contact requests in the deaddrop
+/// until they are accepted by the user.
+/// The `List-Post` and `List-Unsubscribe` headers of the message creating the chat
+/// are saved to the chat.
+/// They are not updated by later messages as anyone can send messages with a `List-Id`.
+fn create_or_lookup_mailinglist(
+    context: &Context,
+    mime_parser: &MimeMessage,
+    allow_creation: bool,
+    listid: &str,
+    listname: &str,
+) -> Result<(ChatId, Blocked)> {
+    let (chat_id, blocked) = match chat::get_chat_id_by_grpid(context, listid) {
+        Ok((chat_id, _, blocked)) => {
+            // anyone can send a List-Id equal to the ID of a group,
+            // this must not bypass the membership checks of the group.
+            if Chat::load_from_db(context, chat_id)?.get_type() != Chattype::Mailinglist {
+                warn!(
+                    context,
+                    "List-Id {} is the ID of a chat that is no mailing list", listid
+                );
+                return Ok((ChatId::new(0), Blocked::Not));
+            }
+            (chat_id, blocked)
+        }
+        Err(_) => {
+            if !allow_creation {
+                info!(context, "creating mailing list forbidden by caller");
+                return Ok((ChatId::new(0), Blocked::Not));
+            }
+            let mut param = Params::new();
+            if let Some(addr) = mime_parser
+                .get(HeaderDef::ListPost)
+                .and_then(|value| parse_list_post(value))
+            {
+                param.set(Param::ListPost, addr);
+            }
+            if let Some(uri) = mime_parser
+                .get(HeaderDef::ListUnsubscribe)
+                .and_then(|value| parse_list_uris(value).into_iter().next())
+            {
+                param.set(Param::ListUnsubscribe, uri);
+            }
+            context.sql.execute(
+                "INSERT INTO chats (type, name, grpid, blocked, created_timestamp, param) \
+                 VALUES(?, ?, ?, ?, ?, ?);",
+                params![
+                    Chattype::Mailinglist,
+                    listname,
+                    listid,
+                    Blocked::Deaddrop,
+                    time(),
+                    param.to_string()
+                ],
+            )?;
+            let chat_id = ChatId::new(sql::get_rowid(
+                context,
+                &context.sql,
+                "chats",
+                "grpid",
+                listid,
+            ));
+            info!(
+                context,
+                "Created mailing list '{}' listid={} as {}", listname, listid, chat_id
+            );
+            context.call_cb(Event::ChatModified(chat_id));
+            (chat_id, Blocked::Deaddrop)
+        }
+    };
+
+    Ok((chat_id, blocked))
+}
+
+/// Splits a `List-Id` header value as `List Name <list-id>` into the list ID and the list name.
+///
+/// The list ID is used as name if the header contains no name.
+fn parse_list_id(value: &str) -> Option<(String, String)> {
+    let (name, listid) = match (value.rfind('<'), value.rfind('>')) {
+        (Some(start), Some(end)) if start < end => (&value[..start], &value[start + 1..end]),
+        _ => ("", value),
+    };
+    let listid = listid.trim();
+    if listid.is_empty() {
+        return None;
+    }
+    let name = name.trim().trim_matches('"').trim();
+    let name = if name.is_empty() { listid } else { name };
+    Some((listid.to_string(), name.to_string()))
+}
+
+/// Returns the URIs in angle brackets of a `List-Post` or `List-Unsubscribe` header.
+fn parse_list_uris(value: &str) -> Vec<String> {
+    value
+        .split('<')
+        .skip(1)
+        .filter_map(|part| part.find('>').map(|end| part[..end].trim().to_string()))
+        .filter(|uri| !uri.is_empty())
+        .collect()
+}
+
+/// Returns the address of the first `mailto:` URI of a `List-Post` header,
+/// `None` if posting is not allowed.
+fn parse_list_post(value: &str) -> Option<String> {
+    parse_list_uris(value).into_iter().find_map(|uri| {
+        if uri.len() > 7 && uri[..7].eq_ignore_ascii_case("mailto:") {
+            let addr = uri[7..].split('?').next().unwrap_or_default();
+            if may_be_valid_addr(addr) {
+                return Some(addr.to_string());
+            }
+        }
+        None
+    })
+}
+
+pub(crate) fn create_group_record(
     context: &Context,
     grpid: impl AsRef<str>,
     grpname: impl AsRef<str>,
@@ -1612,7 +1902,10 @@ fn dc_create_incoming_rfc724_mid(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::test_utils::dummy_context;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use crate::test_utils::{configure_alice_keypair, dummy_context, test_context, TestContext};
 
     #[test]
     fn test_hex_hash() {
@@ -1652,6 +1945,531 @@ mod tests {
         assert_eq!(extract_grpid(&mimeparser, HeaderDef::References), grpid);
     }
 
+    #[test]
+    fn test_parse_list_headers() {
+        assert_eq!(
+            parse_list_id("\"Delta Chat\" <delta.example.org>"),
+            Some(("delta.example.org".to_string(), "Delta Chat".to_string()))
+        );
+        assert_eq!(
+            parse_list_id("<delta.example.org>"),
+            Some((
+                "delta.example.org".to_string(),
+                "delta.example.org".to_string()
+            ))
+        );
+        assert_eq!(parse_list_id("<>"), None);
+
+        assert_eq!(
+            parse_list_post("<mailto:delta@example.org?subject=post>"),
+            Some("delta@example.org".to_string())
+        );
+        assert_eq!(parse_list_post("NO (posting not allowed)"), None);
+
+        assert_eq!(
+            parse_list_uris("<mailto:leave@example.org>, <https://example.org/leave>"),
+            vec!["mailto:leave@example.org", "https://example.org/leave"]
+        );
+    }
+
+    #[test]
+    fn test_mailing_list() {
+        let t = dummy_context();
+        configure_alice_keypair(&t.ctx);
+        t.ctx.set_config(Config::ShowEmails, Some("2")).unwrap();
+
+        let mail = |msgid: &str, list_post: &str| {
+            format!(
+                "From: Bob <bob@example.net>\n\
+                 To: Delta <delta@example.org>\n\
+                 Subject: Release\n\
+                 Message-ID: <{}>\n\
+                 Date: Sun, 22 Mar 2020 22:37:57 +0000\n\
+                 List-Id: \"Delta Chat\" <delta.example.org>\n\
+                 List-Post: {}\n\
+                 List-Unsubscribe: <https://example.org/leave>\n\
+                 \n\
+                 hello\n",
+                msgid, list_post
+            )
+        };
+
+        dc_receive_imf(
+            &t.ctx,
+            mail("1@example.net", "<mailto:delta@example.org>").as_bytes(),
+            "INBOX",
+            1,
+            false,
+        )
+        .unwrap();
+        let (chat_id, _, blocked) =
+            chat::get_chat_id_by_grpid(&t.ctx, "delta.example.org").unwrap();
+        assert_eq!(blocked, Blocked::Deaddrop);
+        let chat = Chat::load_from_db(&t.ctx, chat_id).unwrap();
+        assert!(chat.is_mailing_list());
+        assert_eq!(chat.get_name(), "Delta Chat");
+        assert_eq!(chat.get_mailinglist_addr(), Some("delta@example.org"));
+        assert_eq!(
+            chat.get_mailinglist_unsubscribe(),
+            Some("https://example.org/leave")
+        );
+        assert_eq!(chat::get_chat_msgs(&t.ctx, chat_id, 0, None).len(), 1);
+
+        // mailing lists are read-only until sending is enabled
+        assert!(!chat.can_send());
+        chat::set_mailinglist_sending(&t.ctx, chat_id, true).unwrap();
+        assert!(Chat::load_from_db(&t.ctx, chat_id).unwrap().can_send());
+
+        // later messages do not change the list headers, anyone can send them
+        dc_receive_imf(
+            &t.ctx,
+            mail("2@example.net", "NO").as_bytes(),
+            "INBOX",
+            2,
+            false,
+        )
+        .unwrap();
+        let chat = Chat::load_from_db(&t.ctx, chat_id).unwrap();
+        assert!(chat.can_send());
+        assert_eq!(chat.get_mailinglist_addr(), Some("delta@example.org"));
+        assert_eq!(chat::get_chat_msgs(&t.ctx, chat_id, 0, None).len(), 2);
+
+        // mailing lists are classic emails and honour the ShowEmails setting
+        t.ctx.set_config(Config::ShowEmails, Some("0")).unwrap();
+        dc_receive_imf(
+            &t.ctx,
+            mail("3@example.net", "<mailto:delta@example.org>").as_bytes(),
+            "INBOX",
+            3,
+            false,
+        )
+        .unwrap();
+        assert_eq!(chat::get_chat_msgs(&t.ctx, chat_id, 0, None).len(), 2);
+    }
+
+    #[test]
+    fn test_unencrypted_typing_notification() {
+        let typing_events = Arc::new(AtomicUsize::new(0));
+        let t = {
+            let typing_events = Arc::clone(&typing_events);
+            test_context(Some(Box::new(move |_, event| {
+                if let Event::ContactTyping { .. } = event {
+                    typing_events.fetch_add(1, Ordering::Relaxed);
+                }
+            })))
+        };
+        configure_alice_keypair(&t.ctx);
+        let contact_id = Contact::create(&t.ctx, "Bob", "bob@example.net").unwrap();
+        let chat_id = chat::create_by_contact_id(&t.ctx, contact_id).unwrap();
+
+        dc_receive_imf(
+            &t.ctx,
+            b"From: Bob <bob@example.net>\n\
+              To: Alice <alice@example.com>\n\
+              Subject: Chat: typing\n\
+              Message-ID: <typing@example.net>\n\
+              Chat-Version: 1.0\n\
+              Chat-Content: typing\n\
+              Date: Sun, 22 Mar 2020 22:37:57 +0000\n\
+              \n\
+              \n",
+            "INBOX",
+            1,
+            false,
+        )
+        .unwrap();
+
+        // anyone could have sent the notification, it is ignored
+        // and deleted from the server without being added to the database
+        assert_eq!(typing_events.load(Ordering::Relaxed), 0);
+        assert!(message::rfc724_mid_exists(&t.ctx, "typing@example.net").is_err());
+        assert_eq!(chat::get_chat_msgs(&t.ctx, chat_id, 0, None).len(), 0);
+        let param: String = t
+            .ctx
+            .sql
+            .query_get_value(
+                &t.ctx,
+                "SELECT param FROM jobs WHERE action=?;",
+                params![Action::DeleteMsgOnImap],
+            )
+            .unwrap();
+        let param: Params = param.parse().unwrap();
+        assert_eq!(param.get(Param::ServerFolder), Some("INBOX"));
+        assert_eq!(param.get(Param::Arg), Some("typing@example.net"));
+    }
+
+    #[test]
+    fn test_mailing_list_chat_group_id() {
+        let t = dummy_context();
+        configure_alice_keypair(&t.ctx);
+
+        // the Chat-Group-ID of a group message takes precedence over a List-Id
+        dc_receive_imf(
+            &t.ctx,
+            b"From: Bob <bob@example.net>\n\
+              To: Alice <alice@example.com>, Claire <claire@example.org>\n\
+              Subject: Chat: Group\n\
+              Message-ID: <Gr.abcdefghijk.1@example.net>\n\
+              Chat-Version: 1.0\n\
+              Chat-Group-ID: abcdefghijk\n\
+              Chat-Group-Name: Group\n\
+              Date: Sun, 22 Mar 2020 22:37:57 +0000\n\
+              List-Id: <delta.example.org>\n\
+              \n\
+              hello\n",
+            "INBOX",
+            1,
+            false,
+        )
+        .unwrap();
+        let (chat_id, _, _) = chat::get_chat_id_by_grpid(&t.ctx, "abcdefghijk").unwrap();
+        assert_eq!(
+            Chat::load_from_db(&t.ctx, chat_id).unwrap().get_type(),
+            Chattype::Group
+        );
+        assert!(chat::get_chat_id_by_grpid(&t.ctx, "delta.example.org").is_err());
+    }
+
+    #[test]
+    fn test_mailing_list_with_group_id() {
+        let t = dummy_context();
+        configure_alice_keypair(&t.ctx);
+        let group_id =
+            chat::create_group_chat(&t.ctx, VerifiedStatus::Unverified, "Group").unwrap();
+        let grpid = Chat::load_from_db(&t.ctx, group_id).unwrap().grpid;
+        let msg_cnt = chat::get_chat_msgs(&t.ctx, group_id, 0, None).len();
+
+        // a List-Id equal to the ID of a group does not add the message to the group
+        dc_receive_imf(
+            &t.ctx,
+            format!(
+                "From: Mallory <mallory@example.net>\n\
+                 To: Alice <alice@example.com>\n\
+                 Subject: Release\n\
+                 Message-ID: <1@example.net>\n\
+                 Date: Sun, 22 Mar 2020 22:37:57 +0000\n\
+                 List-Id: <{}>\n\
+                 \n\
+                 hello\n",
+                grpid
+            )
+            .as_bytes(),
+            "INBOX",
+            1,
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            chat::get_chat_msgs(&t.ctx, group_id, 0, None).len(),
+            msg_cnt
+        );
+        assert_eq!(
+            Chat::load_from_db(&t.ctx, group_id).unwrap().get_type(),
+            Chattype::Group
+        );
+    }
+
+    #[test]
+    fn test_contact_status() {
+        let t = dummy_context();
+        configure_alice_keypair(&t.ctx);
+
+        let mail = |msgid: &str, chat_version: &str, hour: u32, text: &str| {
+            format!(
+                "From: Bob <bob@example.net>\n\
+                 To: Alice <alice@example.com>\n\
+                 Subject: Chat: hi\n\
+                 Message-ID: <{}>\n\
+                 {}\
+                 Date: Sun, 22 Mar 2020 {:02}:37:57 +0000\n\
+                 \n\
+                 {}\n",
+                msgid, chat_version, hour, text
+            )
+        };
+        let receive = |raw: String, uid: u32| {
+            dc_receive_imf(&t.ctx, raw.as_bytes(), "INBOX", uid, false).unwrap();
+        };
+        let status = |t: &TestContext| {
+            let contact_id = Contact::lookup_id_by_addr(&t.ctx, "bob@example.net");
+            Contact::load_from_db(&t.ctx, contact_id)
+                .unwrap()
+                .get_status()
+                .to_string()
+        };
+
+        receive(
+            mail(
+                "1@example.net",
+                "Chat-Version: 1.0\n",
+                10,
+                "hi\n\n-- \nBob's status",
+            ),
+            1,
+        );
+        assert_eq!(status(&t), "Bob's status");
+
+        // an unchanged status is not written again
+        let status_timestamp = |t: &TestContext| {
+            let contact_id = Contact::lookup_id_by_addr(&t.ctx, "bob@example.net");
+            Contact::load_from_db(&t.ctx, contact_id)
+                .unwrap()
+                .param
+                .get(Param::StatusTimestamp)
+                .map(|ts| ts.to_string())
+        };
+        let old_status_timestamp = status_timestamp(&t);
+        receive(
+            mail(
+                "1b@example.net",
+                "Chat-Version: 1.0\n",
+                11,
+                "hi again\n\n-- \nBob's status",
+            ),
+            7,
+        );
+        assert_eq!(status_timestamp(&t), old_status_timestamp);
+
+        // classic emails do not change the status, their footers are mostly signatures
+        receive(mail("2@example.net", "", 11, "hi from the web"), 2);
+        assert_eq!(status(&t), "Bob's status");
+        receive(
+            mail(
+                "3@example.net",
+                "",
+                12,
+                "hi from the web\n\n-- \nBob\n\n> quoted thread",
+            ),
+            3,
+        );
+        assert_eq!(status(&t), "Bob's status");
+
+        // messages older than the current status are ignored
+        receive(
+            mail(
+                "4@example.net",
+                "Chat-Version: 1.0\n",
+                9,
+                "hi\n\n-- \nBob's old status",
+            ),
+            4,
+        );
+        assert_eq!(status(&t), "Bob's status");
+
+        // quotes are cut and long statuses are truncated
+        receive(
+            mail(
+                "5@example.net",
+                "Chat-Version: 1.0\n",
+                13,
+                &format!("hi\n\n-- \n{}\n> quote", "status ".repeat(100)),
+            ),
+            5,
+        );
+        let new_status = status(&t);
+        assert!(new_status.starts_with("status status"));
+        assert!(new_status.ends_with("[...]"));
+        assert!(!new_status.contains("quote"));
+        assert!(new_status.chars().count() < 220);
+
+        // chat messages without footer remove the status
+        receive(
+            mail("6@example.net", "Chat-Version: 1.0\n", 14, "hi again"),
+            6,
+        );
+        assert_eq!(status(&t), "");
+    }
+
+    #[test]
+    fn test_delivery_status_notification() {
+        let t = dummy_context();
+        let alice = configure_alice_keypair(&t.ctx);
+
+        let outgoing = |msgid: &str| {
+            format!(
+                "From: Alice <{}>\n\
+                 To: Bob <bob@example.net>\n\
+                 Subject: Chat: hi\n\
+                 Message-ID: <{}>\n\
+                 Chat-Version: 1.0\n\
+                 Date: Sun, 22 Mar 2020 22:37:57 +0000\n\
+                 \n\
+                 hi\n",
+                alice, msgid
+            )
+        };
+        let dsn = |msgid: &str, recipient: &str, action: &str, status: &str, diagnostic: &str| {
+            format!(
+                "From: Mail Delivery System <MAILER-DAEMON@example.com>\n\
+                 To: {}\n\
+                 Subject: Undelivered Mail Returned to Sender\n\
+                 Message-ID: <dsn-{}>\n\
+                 Date: Sun, 22 Mar 2020 22:38:57 +0000\n\
+                 Content-Type: multipart/report; report-type=delivery-status;\n\
+                 \tboundary=\"RPT\"\n\
+                 \n\
+                 --RPT\n\
+                 Content-Type: text/plain\n\
+                 \n\
+                 Your message could not be delivered.\n\
+                 --RPT\n\
+                 Content-Type: message/delivery-status\n\
+                 \n\
+                 Reporting-MTA: dns; mail.example.com\n\
+                 \n\
+                 Final-Recipient: rfc822; {}\n\
+                 Action: {}\n\
+                 Status: {}\n\
+                 Diagnostic-Code: smtp; {}\n\
+                 \n\
+                 --RPT\n\
+                 Content-Type: text/rfc822-headers\n\
+                 \n\
+                 From: Alice <{}>\n\
+                 To: Bob <bob@example.net>\n\
+                 Message-ID: <{}>\n\
+                 \n\
+                 --RPT--\n",
+                alice, msgid, recipient, action, status, diagnostic, alice, msgid
+            )
+        };
+        let receive = |raw: String, uid: u32| {
+            dc_receive_imf(&t.ctx, raw.as_bytes(), "INBOX", uid, false).unwrap();
+        };
+        let load = |msgid: &str| {
+            let (_, _, msg_id) = message::rfc724_mid_exists(&t.ctx, msgid).unwrap();
+            message::Message::load_from_db(&t.ctx, msg_id).unwrap()
+        };
+
+        receive(outgoing("1@example.com"), 1);
+        receive(outgoing("2@example.com"), 2);
+        receive(outgoing("3@example.com"), 3);
+        receive(outgoing("4@example.com"), 4);
+        receive(outgoing("5@example.com"), 8);
+        assert_eq!(
+            load("1@example.com").get_state(),
+            MessageState::OutDelivered
+        );
+
+        // a delay only records the reason
+        receive(
+            dsn(
+                "2@example.com",
+                "bob@example.net",
+                "delayed",
+                "4.4.1",
+                "451 4.4.1 try again later",
+            ),
+            14,
+        );
+        let msg = load("2@example.com");
+        assert_eq!(msg.get_state(), MessageState::OutDelivered);
+        assert_eq!(
+            msg.get_error(),
+            Some(
+                "Temporary delivery problem to bob@example.net: 451 4.4.1 try again later"
+                    .to_string()
+            )
+        );
+
+        // a permanent failure marks the message as failed
+        receive(
+            dsn(
+                "1@example.com",
+                "bob@example.net",
+                "failed",
+                "5.1.1",
+                "550 5.1.1 user unknown",
+            ),
+            15,
+        );
+        let msg = load("1@example.com");
+        assert_eq!(msg.get_state(), MessageState::OutFailed);
+        assert_eq!(
+            msg.get_error(),
+            Some(
+                "Permanent delivery failure to bob@example.net: 550 5.1.1 user unknown".to_string()
+            )
+        );
+
+        // reports naming recipients outside of the chat are ignored
+        receive(
+            dsn(
+                "3@example.com",
+                "mallory@example.net",
+                "failed",
+                "5.1.1",
+                "550 5.1.1 user unknown",
+            ),
+            16,
+        );
+        let msg = load("3@example.com");
+        assert_eq!(msg.get_state(), MessageState::OutDelivered);
+        assert_eq!(msg.get_error(), None);
+
+        // the server gave up, even if the status code is a temporary one
+        receive(
+            dsn(
+                "3@example.com",
+                "bob@example.net",
+                "failed",
+                "4.4.7",
+                "450 4.4.7 delivery time expired",
+            ),
+            17,
+        );
+        let msg = load("3@example.com");
+        assert_eq!(msg.get_state(), MessageState::OutFailed);
+        assert_eq!(
+            msg.get_error(),
+            Some(
+                "Permanent delivery failure to bob@example.net: 450 4.4.7 delivery time expired"
+                    .to_string()
+            )
+        );
+
+        // reports are only accepted from the own provider or the provider of the recipient
+        let failure = dsn(
+            "4@example.com",
+            "bob@example.net",
+            "failed",
+            "5.1.1",
+            "550 5.1.1 user unknown",
+        );
+        receive(
+            failure.replace("MAILER-DAEMON@example.com", "mallory@example.org"),
+            18,
+        );
+        receive(
+            failure.replace("MAILER-DAEMON@example.com", "alice@example.com"),
+            19,
+        );
+        let msg = load("4@example.com");
+        assert_eq!(msg.get_state(), MessageState::OutDelivered);
+        assert_eq!(msg.get_error(), None);
+
+        receive(
+            failure.replace("MAILER-DAEMON@example.com", "MAILER-DAEMON@example.net"),
+            20,
+        );
+        let msg = load("4@example.com");
+        assert_eq!(msg.get_state(), MessageState::OutFailed);
+
+        // read messages were delivered
+        let msg_id = load("5@example.com").id;
+        t.ctx
+            .sql
+            .execute(
+                "UPDATE msgs SET state=? WHERE id=?;",
+                params![MessageState::OutMdnRcvd, msg_id],
+            )
+            .unwrap();
+        receive(failure.replace("4@example.com", "5@example.com"), 21);
+        let msg = load("5@example.com");
+        assert_eq!(msg.get_state(), MessageState::OutMdnRcvd);
+        assert_eq!(msg.get_error(), None);
+    }
+
     #[test]
     fn test_dc_create_incoming_rfc724_mid() {
         let mut members = ContactIds::new();
@@ -1,12 +1,163 @@
 //! # Events specification
 
+use std::collections::VecDeque;
+use std::future::Future;
 use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, Weak};
+use std::task::{Context, Poll, Waker};
 
+use serde::Serialize;
 use strum::EnumProperty;
 
 use crate::chat::ChatId;
 use crate::message::MsgId;
 
+/// Number of events kept for each [EventEmitter] of a context,
+/// when the queue is full, the oldest events are dropped.
+const EVENT_QUEUE_CAPACITY: usize = 1000;
+
+/// Distributes the events of a context to its [EventEmitter]s.
+///
+/// Every emitter has its own bounded queue,
+/// events emitted while there is no emitter are not kept.
+#[derive(Debug, Default)]
+pub struct Events {
+    queues: Mutex<Vec<Weak<EventQueue>>>,
+}
+
+impl Events {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds an event to the queues of all emitters,
+    /// the oldest event of a full queue is dropped.
+    ///
+    /// Never blocks on the emitters.
+    pub fn emit(&self, event: Event) {
+        let mut queues = self.queues.lock().unwrap();
+        queues.retain(|queue| match queue.upgrade() {
+            Some(queue) => {
+                queue.push(event.clone());
+                true
+            }
+            None => false,
+        });
+    }
+
+    /// Returns a new emitter receiving the events emitted from now on.
+    pub fn get_emitter(&self) -> EventEmitter {
+        let queue = Arc::new(EventQueue::default());
+        self.queues.lock().unwrap().push(Arc::downgrade(&queue));
+        EventEmitter(queue)
+    }
+}
+
+impl Drop for Events {
+    fn drop(&mut self) {
+        for queue in self.queues.lock().unwrap().iter() {
+            if let Some(queue) = queue.upgrade() {
+                queue.close();
+            }
+        }
+    }
+}
+
+/// Bounded queue of the events of one [EventEmitter].
+#[derive(Debug, Default)]
+struct EventQueue {
+    state: Mutex<EventQueueState>,
+    available: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct EventQueueState {
+    events: VecDeque<Event>,
+    /// Set when the context is gone, no more events are added then.
+    closed: bool,
+    /// Tasks waiting in [EventEmitter::recv].
+    wakers: Vec<Waker>,
+}
+
+impl EventQueue {
+    fn push(&self, event: Event) {
+        let mut state = self.state.lock().unwrap();
+        if state.events.len() >= EVENT_QUEUE_CAPACITY {
+            state.events.pop_front();
+        }
+        state.events.push_back(event);
+        self.notify(&mut state);
+    }
+
+    fn close(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.closed = true;
+        self.notify(&mut state);
+    }
+
+    fn notify(&self, state: &mut EventQueueState) {
+        for waker in state.wakers.drain(..) {
+            waker.wake();
+        }
+        self.available.notify_all();
+    }
+}
+
+/// Receives the events of a context.
+///
+/// Events are received in the order they are emitted,
+/// when the context is dropped, the remaining events can still be received
+/// before `None` is returned.
+/// Each emitter receives all events, independently of other emitters,
+/// an emitter is meant to be used by a single consumer.
+#[derive(Debug)]
+pub struct EventEmitter(Arc<EventQueue>);
+
+impl EventEmitter {
+    /// Blocks until the next event is available.
+    ///
+    /// Returns `None` if the context is gone and all events are received.
+    pub fn recv_sync(&self) -> Option<Event> {
+        let mut state = self.0.state.lock().unwrap();
+        loop {
+            if let Some(event) = state.events.pop_front() {
+                return Some(event);
+            }
+            if state.closed {
+                return None;
+            }
+            state = self.0.available.wait(state).unwrap();
+        }
+    }
+
+    /// Waits for the next event.
+    ///
+    /// Returns `None` if the context is gone and all events are received.
+    pub async fn recv(&self) -> Option<Event> {
+        Recv(&self.0).await
+    }
+}
+
+/// Future returned by [EventEmitter::recv].
+struct Recv<'a>(&'a EventQueue);
+
+impl Future for Recv<'_> {
+    type Output = Option<Event>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.0.state.lock().unwrap();
+        if let Some(event) = state.events.pop_front() {
+            Poll::Ready(Some(event))
+        } else if state.closed {
+            Poll::Ready(None)
+        } else {
+            state.wakers.push(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
 impl Event {
     /// Returns the corresponding Event id.
     pub fn as_id(&self) -> i32 {
@@ -17,7 +168,8 @@ impl Event {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, EnumProperty)]
+#[derive(Debug, Clone, PartialEq, Eq, EnumProperty, Serialize)]
+#[serde(tag = "type", content = "data")]
 pub enum Event {
     /// The library-user may write an informational string to the log.
     /// Passed to the callback given to dc_context_new().
@@ -110,6 +262,11 @@ pub enum Event {
     #[strum(props(id = "2000"))]
     MsgsChanged { chat_id: ChatId, msg_id: MsgId },
 
+    /// Reactions to a message changed,
+    /// use get_reactions() to get the current reactions.
+    #[strum(props(id = "2001"))]
+    ReactionsChanged { chat_id: ChatId, msg_id: MsgId },
+
     /// There is a fresh message. Typically, the user will show an notification
     /// when receiving this message.
     ///
@@ -127,10 +284,17 @@ pub enum Event {
     #[strum(props(id = "2012"))]
     MsgFailed { chat_id: ChatId, msg_id: MsgId },
 
-    /// A single message is read by the receiver. State changed from DC_STATE_OUT_DELIVERED to
+    /// A single message is read by a receiver.
+    /// The event is emitted for every contact that sent a read receipt,
+    /// see dc_get_msg_read_receipts().
+    /// When the message is read by enough receivers, the state changed from DC_STATE_OUT_DELIVERED to
     /// DC_STATE_OUT_MDN_RCVD, see dc_msg_get_state().
     #[strum(props(id = "2015"))]
-    MsgRead { chat_id: ChatId, msg_id: MsgId },
+    MsgRead {
+        chat_id: ChatId,
+        msg_id: MsgId,
+        contact_id: u32,
+    },
 
     /// Chat changed.  The name or the image of a chat group was changed or members were added or removed.
     /// Or the verify state of a chat has changed.
@@ -193,12 +357,13 @@ pub enum Event {
 
     /// Progress information of a secure-join handshake from the view of the joiner
     /// (Bob, the person who scans the QR code).
-    /// The events are typically sent while dc_join_securejoin(), which
-    /// may take some time, is executed.
+    /// The events are sent for handshakes started by dc_join_securejoin().
     /// @param data1 (int) ID of the inviting contact.
     /// @param data2 (int) Progress as:
+    ///     0=Error, the handshake was aborted.
     ///     400=vg-/vc-request-with-auth sent, typically shown as "alice@addr verified, introducing myself."
     ///     (Bob has verified alice and waits until Alice does the same for him)
+    ///     1000=Protocol finished, the contact is verified and, for the verified-group-protocol, the group joined.
     #[strum(props(id = "2061"))]
     SecurejoinJoinerProgress { contact_id: u32, progress: usize },
 
@@ -207,4 +372,83 @@ pub enum Event {
     /// @param data2 (int) contact_id
     #[strum(props(id = "2062"))]
     SecurejoinMemberAdded { chat_id: ChatId, contact_id: u32 },
+
+    /// A contact started typing in a chat.
+    ///
+    /// While typing, the contact repeats the notification every few seconds,
+    /// the indicator should be hidden if no new notification arrives
+    /// for [crate::chat::TYPING_TIMEOUT] seconds.
+    /// @param data1 (int) chat_id
+    /// @param data2 (int) contact_id
+    #[strum(props(id = "2070"))]
+    ContactTyping { chat_id: ChatId, contact_id: u32 },
+
+    /// A contact stopped typing in a chat, eg. because the message was sent.
+    /// @param data1 (int) chat_id
+    /// @param data2 (int) contact_id
+    #[strum(props(id = "2071"))]
+    ContactStoppedTyping { chat_id: ChatId, contact_id: u32 },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_emitter() {
+        let events = Events::new();
+        let emitter = events.get_emitter();
+        events.emit(Event::Info("first".to_string()));
+        events.emit(Event::Info("second".to_string()));
+        assert_eq!(emitter.recv_sync(), Some(Event::Info("first".to_string())));
+        assert_eq!(emitter.recv_sync(), Some(Event::Info("second".to_string())));
+
+        // events emitted before the queue is dropped can still be received
+        events.emit(Event::Info("last".to_string()));
+        drop(events);
+        assert_eq!(emitter.recv_sync(), Some(Event::Info("last".to_string())));
+        assert_eq!(emitter.recv_sync(), None);
+    }
+
+    #[test]
+    fn test_event_queue_full() {
+        let events = Events::new();
+        let emitter = events.get_emitter();
+        for i in 0..=EVENT_QUEUE_CAPACITY {
+            events.emit(Event::ConfigureProgress(i));
+        }
+        // the oldest event is dropped
+        assert_eq!(emitter.recv_sync(), Some(Event::ConfigureProgress(1)));
+    }
+
+    #[test]
+    fn test_event_emitters() {
+        let events = Events::new();
+        // events are not kept while nobody listens
+        events.emit(Event::Info("unheard".to_string()));
+
+        let emitter1 = events.get_emitter();
+        let emitter2 = events.get_emitter();
+        events.emit(Event::Info("first".to_string()));
+        drop(emitter2);
+        events.emit(Event::Info("second".to_string()));
+        assert_eq!(events.queues.lock().unwrap().len(), 1);
+
+        // every emitter receives all events
+        let emitter3 = events.get_emitter();
+        events.emit(Event::Info("third".to_string()));
+        drop(events);
+        assert_eq!(emitter1.recv_sync(), Some(Event::Info("first".to_string())));
+        assert_eq!(
+            emitter1.recv_sync(),
+            Some(Event::Info("second".to_string()))
+        );
+        assert_eq!(
+            async_std::task::block_on(emitter1.recv()),
+            Some(Event::Info("third".to_string()))
+        );
+        assert_eq!(emitter1.recv_sync(), None);
+        assert_eq!(emitter3.recv_sync(), Some(Event::Info("third".to_string())));
+        assert_eq!(async_std::task::block_on(emitter3.recv()), None);
+    }
 }
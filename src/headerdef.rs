@@ -17,7 +17,15 @@ pub enum HeaderDef {
     /// Delta Chat extension for message IDs in combined MDNs
     AdditionalMessageIds,
 
+    /// Per-recipient fields of delivery status notifications, see RFC 3464
+    FinalRecipient,
+    Action,
+    Status,
+    DiagnosticCode,
+
     ListId,
+    ListPost,
+    ListUnsubscribe,
     References,
     InReplyTo,
     Precedence,
@@ -34,6 +42,16 @@ pub enum HeaderDef {
     ChatContent,
     ChatDuration,
     ChatDispositionNotificationTo,
+
+    /// Ephemeral timer of the chat in seconds, `0` if disabled
+    EphemeralTimer,
+
+    /// Message-ID of the message whose text is replaced by the text of this message
+    ChatEdit,
+
+    /// Message-IDs of the messages to delete for everyone
+    ChatDelete,
+
     Autocrypt,
     AutocryptSetupMessage,
     SecureJoin,
@@ -0,0 +1,417 @@
+//! # Synchronization of flags and deletions
+//!
+//! Messages marked as seen by another mail client are marked as seen locally,
+//! messages deleted by another mail client are moved to the trash.
+//!
+//! If the server supports CONDSTORE, only the flags changed since
+//! the last synchronization are fetched, see
+//! [RFC 7162](https://tools.ietf.org/html/rfc7162).
+//! Otherwise the flags of all known messages are fetched,
+//! at most once per [FLAG_SYNC_INTERVAL].
+//!
+//! The `\Deleted` flag is ignored: other devices move messages
+//! by copying them and flagging the original as `\Deleted`.
+//! Messages missing in the folder are not deleted locally right away either:
+//! in IMAP, moving a message to another folder, eg. by a filter or by archiving,
+//! cannot be told apart from deleting it.
+//! The messages are looked up in the other known folders instead,
+//! only if they are not found in any of them, they are deleted locally.
+
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use async_imap::types::Flag;
+
+use super::{Error, Imap, Result};
+use crate::constants::*;
+use crate::context::Context;
+use crate::message::{self, MessageState, MsgId};
+
+/// Minimal interval between two synchronizations of a folder without CONDSTORE.
+const FLAG_SYNC_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug)]
+struct LocalMsg {
+    id: MsgId,
+    state: MessageState,
+    rfc724_mid: String,
+}
+
+/// The flags of a message on the server relevant for the synchronization.
+#[derive(Debug)]
+struct ServerFlags {
+    uid: u32,
+    seen: bool,
+}
+
+/// The changes to apply to the local messages.
+#[derive(Debug, Default, PartialEq)]
+struct FlagChanges {
+    /// Messages seen by another client.
+    seen_ids: Vec<MsgId>,
+
+    /// Messages missing in the folder with their Message-IDs,
+    /// they were moved to another folder or expunged.
+    vanished: Vec<(MsgId, String)>,
+}
+
+impl Imap {
+    /// Applies flag changes and deletions done by other clients in `folder`
+    /// to the local messages.
+    pub(super) async fn sync_flags(&self, context: &Context, folder: &str) -> Result<()> {
+        let can_condstore = self.config.read().await.can_condstore;
+        let synced = get_config_modseq(context, folder);
+        let modseq = synced.map(|(_, modseq)| modseq).unwrap_or_default();
+        if (!can_condstore || modseq == 0) && !self.flag_sync_due(folder).await {
+            return Ok(());
+        }
+
+        let (uid_validity, _) = self.select_with_uidvalidity(context, folder)?;
+        if let Some((synced_uid_validity, _)) = synced {
+            if synced_uid_validity != uid_validity {
+                // the UIDs of the local messages refer to the old UIDVALIDITY,
+                // they cannot be used to identify the messages anymore.
+                info!(
+                    context,
+                    "UIDVALIDITY of \"{}\" changed, forgetting server UIDs.", folder
+                );
+                context.sql.execute(
+                    "UPDATE msgs SET server_uid=0 WHERE server_folder=?;",
+                    params![folder],
+                )?;
+                set_config_modseq(context, folder, uid_validity, 0);
+                return Ok(());
+            }
+        }
+
+        let local = context.sql.query_map(
+            "SELECT id, server_uid, state, rfc724_mid FROM msgs \
+             WHERE server_folder=? AND server_uid>0 AND chat_id!=?;",
+            params![folder, DC_CHAT_ID_TRASH],
+            |row| {
+                let msg = LocalMsg {
+                    id: row.get(0)?,
+                    state: row.get(2)?,
+                    rfc724_mid: row.get(3)?,
+                };
+                Ok((row.get::<_, u32>(1)?, msg))
+            },
+            |rows| {
+                rows.collect::<std::result::Result<HashMap<_, _>, _>>()
+                    .map_err(Into::into)
+            },
+        )?;
+        let (min_uid, max_uid) = match (local.keys().min(), local.keys().max()) {
+            (Some(min_uid), Some(max_uid)) => (*min_uid, *max_uid),
+            _ => {
+                set_config_modseq(context, folder, uid_validity, modseq);
+                return Ok(());
+            }
+        };
+        let uid_set = format!("{}:{}", min_uid, max_uid);
+
+        let (changed, existing_uids) =
+            if let Some(ref mut session) = &mut *self.session.lock().await {
+                if can_condstore && modseq > 0 {
+                    let query = format!("(UID FLAGS) (CHANGEDSINCE {})", modseq);
+                    let changed = session
+                        .uid_fetch(&uid_set, query)
+                        .await
+                        .map_err(Error::FetchFailed)?;
+                    let existing_uids = session
+                        .uid_search(format!("UID {}", uid_set))
+                        .await
+                        .map_err(Error::FetchFailed)?;
+                    (changed, existing_uids)
+                } else {
+                    let query = if can_condstore {
+                        "(UID FLAGS MODSEQ)"
+                    } else {
+                        "(UID FLAGS)"
+                    };
+                    let list = session
+                        .uid_fetch(&uid_set, query)
+                        .await
+                        .map_err(Error::FetchFailed)?;
+                    let existing_uids: HashSet<u32> =
+                        list.iter().filter_map(|fetch| fetch.uid).collect();
+                    (list, existing_uids)
+                }
+            } else {
+                return Err(Error::NoConnection);
+            };
+
+        let new_modseq = changed
+            .iter()
+            .filter_map(|fetch| fetch.modseq)
+            .fold(modseq, std::cmp::max);
+        let server_flags: Vec<ServerFlags> = changed
+            .iter()
+            .filter_map(|fetch| {
+                Some(ServerFlags {
+                    uid: fetch.uid?,
+                    seen: fetch.flags().any(|flag| flag == Flag::Seen),
+                })
+            })
+            .collect();
+        let changes = get_flag_changes(&local, &server_flags, &existing_uids);
+
+        message::markseen_msgs_from_server(context, &changes.seen_ids);
+        if !changes.vanished.is_empty() {
+            self.locate_moved_msgs(context, folder, changes.vanished)
+                .await;
+        }
+        set_config_modseq(context, folder, uid_validity, new_modseq);
+        Ok(())
+    }
+
+    /// Returns true if `folder` was not synchronized within the [FLAG_SYNC_INTERVAL]
+    /// and remembers the synchronization.
+    async fn flag_sync_due(&self, folder: &str) -> bool {
+        let mut last_flag_sync = self.last_flag_sync.lock().await;
+        match last_flag_sync.get(folder) {
+            Some(last) if last.elapsed() < FLAG_SYNC_INTERVAL => false,
+            _ => {
+                last_flag_sync.insert(folder.to_string(), Instant::now());
+                true
+            }
+        }
+    }
+
+    /// Looks up the `vanished` messages in the other known folders
+    /// and updates their location on the server.
+    ///
+    /// Other devices using Delta Chat move chat messages to the DeltaChat folder,
+    /// users and filters may move messages to any folder.
+    /// Messages that are not found in any folder are deleted locally.
+    /// If a folder cannot be searched, the messages are checked again
+    /// on the next synchronization.
+    async fn locate_moved_msgs(
+        &self,
+        context: &Context,
+        folder: &str,
+        vanished: Vec<(MsgId, String)>,
+    ) {
+        let mut folders: Vec<String> = [
+            "configured_mvbox_folder",
+            "configured_inbox_folder",
+            "configured_sentbox_folder",
+        ]
+        .iter()
+        .filter_map(|key| context.sql.get_raw_config(context, key))
+        .chain(context.get_watch_folders())
+        .filter(|other| other != folder)
+        .collect();
+        let mut unique = HashSet::new();
+        folders.retain(|other| unique.insert(other.clone()));
+
+        let mut missing = vanished;
+        for other in &folders {
+            if missing.is_empty() {
+                break;
+            }
+            if let Err(err) = self.select_folder(context, Some(other)).await {
+                warn!(context, "Cannot select \"{}\": {}", other, err);
+                return;
+            }
+            let mut not_found = Vec::new();
+            for (msg_id, rfc724_mid) in missing {
+                if !is_searchable(&rfc724_mid) {
+                    not_found.push((msg_id, rfc724_mid));
+                    continue;
+                }
+                let res = if let Some(ref mut session) = &mut *self.session.lock().await {
+                    session
+                        .uid_search(format!("HEADER MESSAGE-ID \"{}\"", rfc724_mid))
+                        .await
+                } else {
+                    return;
+                };
+                match res {
+                    Ok(uids) => match uids.into_iter().max() {
+                        Some(uid) => {
+                            info!(
+                                context,
+                                "Message {} was moved to \"{}\".", rfc724_mid, other
+                            );
+                            message::update_server_uid(context, &rfc724_mid, other, uid);
+                        }
+                        None => not_found.push((msg_id, rfc724_mid)),
+                    },
+                    Err(err) => {
+                        warn!(context, "Cannot search \"{}\": {}", other, err);
+                        return;
+                    }
+                }
+            }
+            missing = not_found;
+        }
+
+        delete_vanished_msgs(context, &missing);
+    }
+}
+
+/// Returns true if messages can be searched for by the Message-ID `rfc724_mid`.
+fn is_searchable(rfc724_mid: &str) -> bool {
+    !rfc724_mid.is_empty() && !rfc724_mid.contains('"') && !rfc724_mid.contains('\\')
+}
+
+/// Handles messages that were not found in any known folder.
+///
+/// Messages with a Message-ID that cannot be searched for may still exist in another folder,
+/// they are kept and only their location is forgotten,
+/// all other messages were deleted by another client and are deleted locally.
+fn delete_vanished_msgs(context: &Context, missing: &[(MsgId, String)]) {
+    let mut deleted_ids = Vec::new();
+    for (msg_id, rfc724_mid) in missing {
+        if is_searchable(rfc724_mid) {
+            deleted_ids.push(*msg_id);
+        } else {
+            info!(
+                context,
+                "Message {} is not in a known folder anymore, keeping it.", msg_id
+            );
+            context
+                .sql
+                .execute(
+                    "UPDATE msgs SET server_folder='', server_uid=0 WHERE id=?;",
+                    params![*msg_id],
+                )
+                .ok();
+        }
+    }
+    message::delete_msgs_from_server(context, &deleted_ids);
+}
+
+/// Compares the local messages of a folder with the flags on the server.
+///
+/// `changed` are the flags fetched from the server,
+/// `existing_uids` are all UIDs of the local messages that still exist on the server.
+fn get_flag_changes(
+    local: &HashMap<u32, LocalMsg>,
+    changed: &[ServerFlags],
+    existing_uids: &HashSet<u32>,
+) -> FlagChanges {
+    let mut changes = FlagChanges::default();
+    for flags in changed {
+        let msg = match local.get(&flags.uid) {
+            Some(msg) => msg,
+            None => continue,
+        };
+        if flags.seen
+            && (msg.state == MessageState::InFresh || msg.state == MessageState::InNoticed)
+        {
+            changes.seen_ids.push(msg.id);
+        }
+    }
+    for (uid, msg) in local.iter() {
+        if !existing_uids.contains(uid) {
+            changes.vanished.push((msg.id, msg.rfc724_mid.clone()));
+        }
+    }
+    changes
+}
+
+/// Returns the UIDVALIDITY and the highest MODSEQ of the last synchronization of `folder`.
+fn get_config_modseq(context: &Context, folder: &str) -> Option<(u32, u64)> {
+    let key = format!("imap.modseq.{}", folder);
+    // the entry has the format `imap.modseq.<folder>=<uidvalidity>:<modseq>`
+    let entry = context.sql.get_raw_config(context, &key)?;
+    let mut parts = entry.split(':');
+    let uid_validity = parts.next()?.parse().ok()?;
+    let modseq = parts.next()?.parse().ok()?;
+    Some((uid_validity, modseq))
+}
+
+fn set_config_modseq(context: &Context, folder: &str, uid_validity: u32, modseq: u64) {
+    let key = format!("imap.modseq.{}", folder);
+    let val = format!("{}:{}", uid_validity, modseq);
+
+    context.sql.set_raw_config(context, &key, Some(&val)).ok();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::{rfc724_mid_exists, Message};
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_get_flag_changes() {
+        let local_msg = |id: u32, state: MessageState| LocalMsg {
+            id: MsgId::new(id),
+            state,
+            rfc724_mid: format!("{}@example.org", id),
+        };
+        let mut local = HashMap::new();
+        local.insert(1, local_msg(10, MessageState::InFresh));
+        local.insert(2, local_msg(20, MessageState::InSeen));
+        local.insert(3, local_msg(30, MessageState::InNoticed));
+        local.insert(4, local_msg(40, MessageState::InFresh));
+        let changed = vec![
+            ServerFlags { uid: 1, seen: true },
+            ServerFlags { uid: 2, seen: true },
+            ServerFlags {
+                uid: 3,
+                seen: false,
+            },
+            // unknown messages are ignored
+            ServerFlags { uid: 5, seen: true },
+        ];
+        let existing_uids = [1, 2, 3].iter().copied().collect();
+
+        let changes = get_flag_changes(&local, &changed, &existing_uids);
+        assert_eq!(
+            changes,
+            FlagChanges {
+                seen_ids: vec![MsgId::new(10)],
+                // the missing message is moved or expunged, not deleted
+                vanished: vec![(MsgId::new(40), "40@example.org".to_string())],
+            }
+        );
+    }
+
+    #[test]
+    fn test_delete_vanished_msgs() {
+        let t = dummy_context();
+        configure_alice_keypair(&t.ctx);
+        let receive = |server_uid: u32, rfc724_mid: &str| {
+            let mail = format!(
+                "From: Bob <bob@example.net>\n\
+                 To: Alice <alice@example.com>\n\
+                 Subject: Chat: hi\n\
+                 Message-ID: <{}>\n\
+                 Chat-Version: 1.0\n\
+                 Date: Sun, 22 Mar 2020 22:37:57 +0000\n\
+                 \n\
+                 hi\n",
+                rfc724_mid
+            );
+            crate::dc_receive_imf::dc_receive_imf(
+                &t.ctx,
+                mail.as_bytes(),
+                "INBOX",
+                server_uid,
+                false,
+            )
+            .unwrap();
+            rfc724_mid_exists(&t.ctx, rfc724_mid).unwrap().2
+        };
+        let deleted_id = receive(1, "1@example.net");
+        let kept_id = receive(2, "2@example.net");
+
+        // messages that cannot be searched for are kept
+        delete_vanished_msgs(
+            &t.ctx,
+            &[
+                (deleted_id, "1@example.net".to_string()),
+                (kept_id, "".to_string()),
+            ],
+        );
+        let msg = Message::load_from_db(&t.ctx, deleted_id).unwrap();
+        assert!(msg.get_chat_id().is_trash());
+        let msg = Message::load_from_db(&t.ctx, kept_id).unwrap();
+        assert!(!msg.get_chat_id().is_trash());
+        assert_eq!(msg.server_uid, 0);
+    }
+}
@@ -204,7 +204,7 @@ impl Imap {
         })
     }
 
-    pub(crate) fn fake_idle(&self, context: &Context, watch_folder: Option<String>) {
+    pub(crate) fn fake_idle(&self, context: &Context, watch_folders: Vec<String>) {
         // Idle using polling. This is also needed if we're not yet configured -
         // in this case, we're waiting for a configure job (and an interrupt).
         task::block_on(async move {
@@ -234,7 +234,7 @@ impl Imap {
                         warn!(context, "fake_idle: could not connect: {}", err);
                         continue;
                     }
-                    if self.config.read().await.can_idle {
+                    if watch_folders.len() <= 1 && self.config.read().await.can_idle {
                         // we only fake-idled because network was gone during IDLE, probably
                         break;
                     }
@@ -244,10 +244,10 @@ impl Imap {
                     // will have already fetched the messages so perform_*_fetch
                     // will not find any new.
 
-                    if let Some(ref watch_folder) = watch_folder {
-                        match self.fetch_new_messages(context, watch_folder).await {
+                    if !watch_folders.is_empty() {
+                        match self.fetch_folders(context, &watch_folders).await {
                             Ok(res) => {
-                                info!(context, "fetch_new_messages returned {:?}", res);
+                                info!(context, "fetch_folders returned {:?}", res);
                                 if res {
                                     break;
                                 }
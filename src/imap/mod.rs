@@ -3,7 +3,9 @@
 //! uses [async-email/async-imap](https://github.com/async-email/async-imap)
 //! to implement connect, fetch, delete functionality with standard IMAP servers.
 
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
 
 use num_traits::FromPrimitive;
 
@@ -27,9 +29,11 @@ use crate::login_param::{CertificateChecks, LoginParam};
 use crate::message::{self, update_server_uid};
 use crate::oauth2::dc_get_oauth2_access_token;
 use crate::param::Params;
+use crate::quota::{parse_quota_response, QuotaResource};
 use crate::stock::StockMessage;
 
 mod client;
+mod flag_sync;
 mod idle;
 pub mod select_folder;
 mod session;
@@ -74,6 +78,9 @@ pub enum Error {
     #[fail(display = "Mail parse error")]
     MailParseError(#[cause] mailparse::MailParseError),
 
+    #[fail(display = "IMAP GETQUOTAROOT failed")]
+    QuotaFailed(#[cause] async_imap::error::Error),
+
     #[fail(display = "No mailbox selected, folder: {:?}", _0)]
     NoMailbox(String),
 
@@ -145,6 +152,7 @@ pub struct Imap {
     interrupt: Mutex<Option<stop_token::StopSource>>,
     skip_next_idle_wait: AtomicBool,
     should_reconnect: AtomicBool,
+    last_flag_sync: Mutex<HashMap<String, Instant>>,
 }
 
 #[derive(Debug)]
@@ -188,6 +196,14 @@ struct ImapConfig {
     /// True if the server has MOVE capability as defined in
     /// https://tools.ietf.org/html/rfc6851
     pub can_move: bool,
+
+    /// True if the server has CONDSTORE capability as defined in
+    /// https://tools.ietf.org/html/rfc7162
+    pub can_condstore: bool,
+
+    /// True if the server has QUOTA capability as defined in
+    /// https://tools.ietf.org/html/rfc2087
+    pub can_quota: bool,
     pub imap_delimiter: char,
 }
 
@@ -206,6 +222,8 @@ impl Default for ImapConfig {
             selected_folder_needs_expunge: false,
             can_idle: false,
             can_move: false,
+            can_condstore: false,
+            can_quota: false,
             imap_delimiter: '.',
         }
     }
@@ -360,6 +378,8 @@ impl Imap {
 
         cfg.can_idle = false;
         cfg.can_move = false;
+        cfg.can_condstore = false;
+        cfg.can_quota = false;
     }
 
     /// Connects to imap account using already-configured parameters.
@@ -421,6 +441,9 @@ impl Imap {
                     } else {
                         let can_idle = caps.has_str("IDLE");
                         let can_move = caps.has_str("MOVE");
+                        // QRESYNC implies CONDSTORE
+                        let can_condstore = caps.has_str("CONDSTORE") || caps.has_str("QRESYNC");
+                        let can_quota = caps.has_str("QUOTA");
                         let caps_list = caps.iter().fold(String::new(), |s, c| {
                             if let Capability::Atom(x) = c {
                                 s + &format!(" {}", x)
@@ -431,6 +454,8 @@ impl Imap {
 
                         self.config.write().await.can_idle = can_idle;
                         self.config.write().await.can_move = can_move;
+                        self.config.write().await.can_condstore = can_condstore;
+                        self.config.write().await.can_quota = can_quota;
                         *self.connected.lock().await = true;
                         emit_event!(
                             context,
@@ -466,19 +491,78 @@ impl Imap {
         });
     }
 
-    pub async fn fetch(&self, context: &Context, watch_folder: &str) -> Result<()> {
+    pub async fn fetch(&self, context: &Context, watch_folders: &[String]) -> Result<()> {
         if !context.sql.is_open() {
             // probably shutdown
             return Err(Error::InTeardown);
         }
         self.setup_handle_if_needed(context).await?;
 
-        while self.fetch_new_messages(context, &watch_folder).await? {
+        while self.fetch_folders(context, watch_folders).await? {
             // We fetch until no more new messages are there.
         }
+
+        for folder in watch_folders {
+            if let Err(err) = self.sync_flags(context, folder).await {
+                warn!(context, "Cannot sync flags of \"{}\": {}", folder, err);
+            }
+        }
         Ok(())
     }
 
+    /// Fetches new messages from all `folders` once,
+    /// returns true if anything was fetched.
+    ///
+    /// If there are several folders, only the folders reporting
+    /// new messages via STATUS are selected.
+    /// Folders that cannot be checked, eg. because they do not exist,
+    /// are skipped so that they do not stop fetching from the other folders.
+    pub(crate) async fn fetch_folders(
+        &self,
+        context: &Context,
+        folders: &[String],
+    ) -> Result<bool> {
+        let mut read_any = false;
+        for folder in folders {
+            if folders.len() > 1 {
+                match self.folder_has_new_messages(context, folder).await {
+                    Ok(true) => {}
+                    Ok(false) => continue,
+                    Err(err) => {
+                        warn!(context, "Cannot check folder \"{}\": {}", folder, err);
+                        continue;
+                    }
+                }
+            }
+            if self.fetch_new_messages(context, folder).await? {
+                read_any = true;
+            }
+        }
+        Ok(read_any)
+    }
+
+    /// Checks via STATUS, without selecting the folder,
+    /// whether the folder has messages newer than the last seen UID.
+    async fn folder_has_new_messages(&self, context: &Context, folder: &str) -> Result<bool> {
+        let mailbox = if let Some(ref mut session) = &mut *self.session.lock().await {
+            session
+                .status(folder, "(UIDNEXT UIDVALIDITY)")
+                .await
+                .map_err(Error::FetchFailed)?
+        } else {
+            return Err(Error::NoConnection);
+        };
+
+        let (uid_validity, last_seen_uid) = self.get_config_last_seen_uid(context, folder);
+        match (mailbox.uid_validity, mailbox.uid_next) {
+            (Some(new_uid_validity), Some(uid_next)) => {
+                Ok(new_uid_validity != uid_validity || uid_next > last_seen_uid + 1)
+            }
+            // the server did not tell, let fetch_new_messages() find out
+            _ => Ok(true),
+        }
+    }
+
     fn get_config_last_seen_uid<S: AsRef<str>>(&self, context: &Context, folder: S) -> (u32, u32) {
         let key = format!("imap.mailbox.{}", folder.as_ref());
         if let Some(entry) = context.sql.get_raw_config(context, &key) {
@@ -501,6 +585,26 @@ impl Imap {
         }
     }
 
+    /// Returns the quota resources of the INBOX,
+    /// empty if the server does not support quotas.
+    pub(crate) async fn get_quota(&self, context: &Context) -> Result<Vec<QuotaResource>> {
+        let can_quota = self.config.read().await.can_quota;
+        if let Some(ref mut session) = &mut *self.session.lock().await {
+            if !can_quota {
+                return Ok(Vec::new());
+            }
+            let response = session
+                .get_quota_root("INBOX")
+                .await
+                .map_err(Error::QuotaFailed)?;
+            let resources = parse_quota_response(&response);
+            info!(context, "Quota: {:?}", resources);
+            Ok(resources)
+        } else {
+            Err(Error::NoConnection)
+        }
+    }
+
     /// return Result with (uid_validity, last_seen_uid) tuple.
     pub(crate) fn select_with_uidvalidity(
         &self,
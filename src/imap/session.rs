@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+
 use async_imap::{
     error::Result as ImapResult,
     types::{Capabilities, Fetch, Mailbox, Name},
@@ -78,6 +80,19 @@ impl Session {
         Ok(mbox)
     }
 
+    pub async fn status<S1: AsRef<str>, S2: AsRef<str>>(
+        &mut self,
+        mailbox_name: S1,
+        data_items: S2,
+    ) -> ImapResult<Mailbox> {
+        let mbox = match self {
+            Session::Secure(i) => i.status(mailbox_name, data_items).await?,
+            Session::Insecure(i) => i.status(mailbox_name, data_items).await?,
+        };
+
+        Ok(mbox)
+    }
+
     pub async fn fetch<S1, S2>(&mut self, sequence_set: S1, query: S2) -> ImapResult<Vec<Fetch>>
     where
         S1: AsRef<str>,
@@ -123,6 +138,23 @@ impl Session {
         Ok(res)
     }
 
+    pub async fn get_quota_root<S: AsRef<str>>(&mut self, mailbox_name: S) -> ImapResult<String> {
+        let command = format!("GETQUOTAROOT \"{}\"", mailbox_name.as_ref());
+        let response = match self {
+            Session::Secure(i) => i.run_command_and_read_response(command).await?,
+            Session::Insecure(i) => i.run_command_and_read_response(command).await?,
+        };
+        Ok(String::from_utf8_lossy(&response).into_owned())
+    }
+
+    pub async fn uid_search<S: AsRef<str>>(&mut self, query: S) -> ImapResult<HashSet<u32>> {
+        let res = match self {
+            Session::Secure(i) => i.uid_search(query).await?,
+            Session::Insecure(i) => i.uid_search(query).await?,
+        };
+        Ok(res)
+    }
+
     pub async fn uid_store<S1, S2>(&mut self, uid_set: S1, query: S2) -> ImapResult<Vec<Fetch>>
     where
         S1: AsRef<str>,
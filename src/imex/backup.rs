@@ -0,0 +1,574 @@
+//! # Backup file format
+//!
+//! A backup is a single file that is written and read as a stream, it consists of
+//!
+//! - a header with [MAGIC], the format version, the backup time
+//!   and whether the backup is encrypted,
+//! - frames containing a tar archive; every frame is a chunk of the archive of up to
+//!   [CHUNK_SIZE] bytes, encrypted with the passphrase by [pgp::symm_encrypt]
+//!   if the backup is encrypted, and prefixed by its length as big-endian `u32`,
+//! - an empty frame followed by the SHA-256 checksum of all bytes before.
+//!
+//! The archive contains the database as [DBFILE_BACKUP_NAME],
+//! the files of the blobdir in the directory [BLOBS_BACKUP_NAME]
+//! and, as last entry, a manifest with the size and the SHA-256 checksum of all other entries.
+//!
+//! The checksum at the end of the file can be verified without the passphrase,
+//! see [check_backup_file], the manifest is verified by [BackupReader::unpack].
+
+use std::io::{self, Cursor, Read, Write};
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::*;
+use crate::pgp;
+
+/// First bytes of a backup file.
+const MAGIC: &[u8; 8] = b"DCBACKUP";
+
+/// Version of the backup file format.
+const VERSION: u32 = 1;
+
+/// Number of archive bytes encrypted at once.
+const CHUNK_SIZE: usize = 4 * 1024 * 1024;
+
+/// Maximum size of an encrypted frame, larger than [CHUNK_SIZE] because of the armor.
+const MAX_FRAME_SIZE: u32 = 2 * CHUNK_SIZE as u32;
+
+/// Name of the database in the archive.
+pub(crate) const DBFILE_BACKUP_NAME: &str = "dc_database_backup.sqlite";
+
+/// Name of the directory containing the blobs in the archive.
+pub(crate) const BLOBS_BACKUP_NAME: &str = "blobs_backup";
+
+/// Name of the manifest in the archive.
+const MANIFEST_NAME: &str = "manifest.json";
+
+/// Size of tar headers and the unit the content of tar entries is padded to.
+const BLOCK_SIZE: usize = 512;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    backup_time: i64,
+    files: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+struct ManifestEntry {
+    name: String,
+    size: u64,
+    sha256: String,
+}
+
+/// Returns true if `input` starts like a backup file written by [BackupWriter].
+///
+/// Older backups are plain SQLite databases.
+pub(crate) fn is_backup_file(mut input: impl Read) -> Result<bool> {
+    let mut magic = [0u8; MAGIC.len()];
+    match input.read_exact(&mut magic) {
+        Ok(()) => Ok(&magic == MAGIC),
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Verifies the checksum of a backup file without decrypting it.
+///
+/// Returns the time the backup was created.
+pub(crate) fn check_backup_file(input: impl Read) -> Result<i64> {
+    let mut frames = FrameReader::new(input)?;
+    while frames.next_frame()? {}
+    Ok(frames.backup_time)
+}
+
+/// Writes a backup file.
+pub(crate) struct BackupWriter<W: Write> {
+    frames: FrameWriter<W>,
+    entries: Vec<ManifestEntry>,
+}
+
+impl<W: Write> BackupWriter<W> {
+    /// Writes the header of the backup,
+    /// without passphrase the backup is not encrypted.
+    pub fn new(out: W, passphrase: Option<&str>, backup_time: i64) -> Result<Self> {
+        Ok(BackupWriter {
+            frames: FrameWriter::new(out, passphrase, backup_time)?,
+            entries: Vec::new(),
+        })
+    }
+
+    /// Adds an entry of `size` bytes read from `content` to the archive.
+    pub fn append(&mut self, name: &str, size: u64, content: impl Read) -> Result<()> {
+        let entry = self.append_entry(name, size, content)?;
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Adds the manifest, finishes the archive and writes the checksum.
+    pub fn finish(mut self) -> Result<W> {
+        let manifest = Manifest {
+            version: VERSION,
+            backup_time: self.frames.backup_time,
+            files: std::mem::replace(&mut self.entries, Vec::new()),
+        };
+        let manifest = serde_json::to_vec(&manifest)
+            .map_err(|err| format_err!("Cannot serialize manifest: {}", err))?;
+        self.append_entry(MANIFEST_NAME, manifest.len() as u64, &manifest[..])?;
+
+        // the end of a tar archive is marked by two empty blocks
+        self.frames.write_all(&[0u8; 2 * BLOCK_SIZE])?;
+        self.frames.finish()
+    }
+
+    fn append_entry(
+        &mut self,
+        name: &str,
+        size: u64,
+        mut content: impl Read,
+    ) -> Result<ManifestEntry> {
+        self.frames.write_all(&tar_header(name, size)?)?;
+
+        let mut hasher = Sha256::new();
+        let mut buf = vec![0u8; 64 * 1024];
+        let mut written = 0;
+        while written < size {
+            let len = std::cmp::min(buf.len() as u64, size - written) as usize;
+            let read = content.read(&mut buf[..len])?;
+            ensure!(read > 0, "{} is shorter than {} bytes", name, size);
+            hasher.input(&buf[..read]);
+            self.frames.write_all(&buf[..read])?;
+            written += read as u64;
+        }
+        self.frames.write_all(&vec![0u8; padding(size)])?;
+
+        Ok(ManifestEntry {
+            name: name.to_string(),
+            size,
+            sha256: hex::encode(hasher.result()),
+        })
+    }
+}
+
+/// Reads a backup file written by [BackupWriter].
+pub(crate) struct BackupReader<R: Read> {
+    frames: FrameReader<R>,
+}
+
+impl<R: Read> BackupReader<R> {
+    /// Reads the header of the backup, fails if the file is no backup
+    /// or if the backup is encrypted and no passphrase is given.
+    ///
+    /// The passphrase is ignored for unencrypted backups.
+    pub fn new(input: R, passphrase: Option<&str>) -> Result<Self> {
+        let mut frames = FrameReader::new(input)?;
+        if frames.encrypted {
+            ensure!(
+                passphrase.is_some(),
+                "The backup is encrypted, a passphrase is needed"
+            );
+            frames.passphrase = passphrase.map(|p| p.to_string());
+        }
+        frames.read_content = true;
+        Ok(BackupReader { frames })
+    }
+
+    /// Reads all entries of the archive, the content of each entry except the manifest
+    /// is written to the writer `dest` returns for the name of the entry.
+    ///
+    /// `progress` is called with the number of bytes read from the backup file so far,
+    /// an error returned by `progress` cancels unpacking.
+    ///
+    /// If an error is returned, the backup is damaged, incomplete or cannot be decrypted
+    /// and the content written so far must not be used.
+    pub fn unpack<F, W>(
+        mut self,
+        mut dest: F,
+        mut progress: impl FnMut(u64) -> Result<()>,
+    ) -> Result<()>
+    where
+        F: FnMut(&str) -> Result<W>,
+        W: Write,
+    {
+        let mut entries = Vec::new();
+        let mut manifest: Option<Manifest> = None;
+        while let Some((name, size)) = self.next_entry()? {
+            ensure!(manifest.is_none(), "Entry {} after the manifest", name);
+            if name == MANIFEST_NAME {
+                let mut content = Vec::new();
+                self.read_entry(size, &mut content)?;
+                manifest = Some(
+                    serde_json::from_slice(&content)
+                        .map_err(|err| format_err!("Cannot parse manifest: {}", err))?,
+                );
+            } else {
+                let sha256 = self.read_entry(size, dest(&name)?)?;
+                entries.push(ManifestEntry { name, size, sha256 });
+            }
+            progress(self.frames.bytes_read)?;
+        }
+        // the end of the archive has to be the end of the backup
+        ensure!(
+            self.frames.buf_pos == self.frames.buf.len() && !self.frames.next_frame()?,
+            "Unexpected data after the end of the archive"
+        );
+
+        let mut manifest = manifest.ok_or_else(|| format_err!("Backup has no manifest"))?;
+        ensure!(
+            manifest.version == VERSION && manifest.backup_time == self.frames.backup_time,
+            "Manifest does not match the backup"
+        );
+        manifest.files.sort();
+        entries.sort();
+        ensure!(
+            manifest.files == entries,
+            "Files of the backup do not match the manifest"
+        );
+        Ok(())
+    }
+
+    /// Reads the next tar header, returns `None` at the end of the archive.
+    fn next_entry(&mut self) -> Result<Option<(String, u64)>> {
+        let mut header = [0u8; BLOCK_SIZE];
+        self.frames.read_exact(&mut header)?;
+        if header.iter().all(|b| *b == 0) {
+            self.frames.read_exact(&mut header)?;
+            ensure!(header.iter().all(|b| *b == 0), "Invalid end of the archive");
+            return Ok(None);
+        }
+
+        let name = tar_field(&header[0..100])?;
+        let prefix = tar_field(&header[345..500])?;
+        let name = if prefix.is_empty() {
+            name
+        } else {
+            format!("{}/{}", prefix, name)
+        };
+        let size = u64::from_str_radix(tar_field(&header[124..136])?.trim(), 8)
+            .map_err(|err| format_err!("Invalid size of {}: {}", name, err))?;
+        Ok(Some((name, size)))
+    }
+
+    /// Copies the content of the current entry to `out`, returns the SHA-256 checksum.
+    fn read_entry(&mut self, size: u64, mut out: impl Write) -> Result<String> {
+        let mut hasher = Sha256::new();
+        let mut buf = vec![0u8; 64 * 1024];
+        let mut read = 0;
+        while read < size {
+            let len = std::cmp::min(buf.len() as u64, size - read) as usize;
+            self.frames.read_exact(&mut buf[..len])?;
+            hasher.input(&buf[..len]);
+            out.write_all(&buf[..len])?;
+            read += len as u64;
+        }
+        out.flush()?;
+        self.frames.read_exact(&mut vec![0u8; padding(size)])?;
+        Ok(hex::encode(hasher.result()))
+    }
+}
+
+/// Splits the archive into frames, encrypted if there is a passphrase.
+struct FrameWriter<W: Write> {
+    out: W,
+    hasher: Sha256,
+    passphrase: Option<String>,
+    backup_time: i64,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> FrameWriter<W> {
+    fn new(out: W, passphrase: Option<&str>, backup_time: i64) -> Result<Self> {
+        ensure!(passphrase != Some(""), "Empty backup passphrase");
+        let mut frames = FrameWriter {
+            out,
+            hasher: Sha256::new(),
+            passphrase: passphrase.map(|p| p.to_string()),
+            backup_time,
+            buf: Vec::with_capacity(CHUNK_SIZE),
+        };
+        let mut header = MAGIC.to_vec();
+        header.write_u32::<BigEndian>(VERSION)?;
+        header.write_i64::<BigEndian>(backup_time)?;
+        header.write_u8(passphrase.is_some() as u8)?;
+        frames.write_raw(&header)?;
+        Ok(frames)
+    }
+
+    fn write_all(&mut self, mut data: &[u8]) -> Result<()> {
+        while !data.is_empty() {
+            let len = std::cmp::min(CHUNK_SIZE - self.buf.len(), data.len());
+            self.buf.extend_from_slice(&data[..len]);
+            data = &data[len..];
+            if self.buf.len() == CHUNK_SIZE {
+                self.write_frame()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_frame(&mut self) -> Result<()> {
+        let chunk = std::mem::replace(&mut self.buf, Vec::with_capacity(CHUNK_SIZE));
+        let frame = match self.passphrase {
+            Some(ref passphrase) => pgp::symm_encrypt(passphrase, &chunk)?.into_bytes(),
+            None => chunk,
+        };
+        let mut len = Vec::new();
+        len.write_u32::<BigEndian>(frame.len() as u32)?;
+        self.write_raw(&len)?;
+        self.write_raw(&frame)
+    }
+
+    fn write_raw(&mut self, data: &[u8]) -> Result<()> {
+        self.hasher.input(data);
+        self.out.write_all(data)?;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<W> {
+        if !self.buf.is_empty() {
+            self.write_frame()?;
+        }
+        self.write_raw(&[0u8; 4])?;
+        let checksum = self.hasher.result();
+        self.out.write_all(&checksum)?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+/// Reads the frames of a backup file and verifies the checksum at the end.
+///
+/// Unless `read_content` is set, the frames are only checked, not decrypted.
+struct FrameReader<R: Read> {
+    input: R,
+    hasher: Sha256,
+    backup_time: i64,
+    encrypted: bool,
+    passphrase: Option<String>,
+    read_content: bool,
+
+    /// Number of bytes read from `input`.
+    bytes_read: u64,
+
+    /// Decrypted content of the current frame.
+    buf: Vec<u8>,
+    buf_pos: usize,
+}
+
+impl<R: Read> FrameReader<R> {
+    fn new(mut input: R) -> Result<Self> {
+        let mut header = [0u8; MAGIC.len() + 13];
+        input.read_exact(&mut header)?;
+        ensure!(&header[..MAGIC.len()] == MAGIC, "Not a backup file");
+        let mut fields = &header[MAGIC.len()..];
+        let version = fields.read_u32::<BigEndian>()?;
+        ensure!(version == VERSION, "Unsupported backup version {}", version);
+        let backup_time = fields.read_i64::<BigEndian>()?;
+        let encrypted = match fields.read_u8()? {
+            0 => false,
+            1 => true,
+            flag => bail!("Invalid encryption flag {}", flag),
+        };
+
+        let mut hasher = Sha256::new();
+        hasher.input(&header[..]);
+        Ok(FrameReader {
+            input,
+            hasher,
+            backup_time,
+            encrypted,
+            passphrase: None,
+            read_content: false,
+            bytes_read: header.len() as u64,
+            buf: Vec::new(),
+            buf_pos: 0,
+        })
+    }
+
+    /// Reads the next frame, returns false after the last frame if the checksum is valid.
+    fn next_frame(&mut self) -> Result<bool> {
+        let len = Cursor::new(self.read_raw(4)?).read_u32::<BigEndian>()?;
+        if len == 0 {
+            let mut checksum = [0u8; 32];
+            self.input.read_exact(&mut checksum)?;
+            ensure!(
+                self.hasher.clone().result()[..] == checksum[..],
+                "Backup checksum mismatch, the file is damaged"
+            );
+            ensure!(
+                self.input.read(&mut [0u8; 1])? == 0,
+                "Unexpected data after the end of the backup"
+            );
+            return Ok(false);
+        }
+        ensure!(len <= MAX_FRAME_SIZE, "Invalid frame size {}", len);
+
+        let frame = self.read_raw(len as usize)?;
+        if self.read_content {
+            self.buf = match self.passphrase {
+                Some(ref passphrase) if self.encrypted => {
+                    pgp::symm_decrypt(passphrase, Cursor::new(frame))?
+                }
+                _ => frame,
+            };
+            self.buf_pos = 0;
+        }
+        Ok(true)
+    }
+
+    fn read_raw(&mut self, len: usize) -> Result<Vec<u8>> {
+        let mut data = vec![0u8; len];
+        self.input
+            .read_exact(&mut data)
+            .map_err(|err| format_err!("Backup is incomplete: {}", err))?;
+        self.hasher.input(&data);
+        self.bytes_read += len as u64;
+        Ok(data)
+    }
+
+    fn read_exact(&mut self, mut out: &mut [u8]) -> Result<()> {
+        while !out.is_empty() {
+            if self.buf_pos == self.buf.len() {
+                ensure!(self.next_frame()?, "Unexpected end of the archive");
+                continue;
+            }
+            let len = std::cmp::min(self.buf.len() - self.buf_pos, out.len());
+            out[..len].copy_from_slice(&self.buf[self.buf_pos..self.buf_pos + len]);
+            self.buf_pos += len;
+            out = &mut out[len..];
+        }
+        Ok(())
+    }
+}
+
+/// Number of zero bytes following an entry of `size` bytes.
+fn padding(size: u64) -> usize {
+    (BLOCK_SIZE - (size % BLOCK_SIZE as u64) as usize) % BLOCK_SIZE
+}
+
+/// Creates a ustar header for a regular file.
+fn tar_header(path: &str, size: u64) -> Result<[u8; BLOCK_SIZE]> {
+    let (prefix, name) = match path.rfind('/') {
+        Some(pos) => (&path[..pos], &path[pos + 1..]),
+        None => ("", path),
+    };
+    ensure!(
+        name.len() <= 100 && prefix.len() <= 155,
+        "Name too long for backup: {}",
+        path
+    );
+
+    let mut header = [0u8; BLOCK_SIZE];
+    header[0..name.len()].copy_from_slice(name.as_bytes());
+    header[100..108].copy_from_slice(b"0000644\0");
+    header[108..116].copy_from_slice(b"0000000\0");
+    header[116..124].copy_from_slice(b"0000000\0");
+    header[124..136].copy_from_slice(format!("{:011o}\0", size).as_bytes());
+    header[136..148].copy_from_slice(b"00000000000\0");
+    header[156] = b'0';
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    header[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+
+    // the checksum is calculated with the checksum field set to spaces
+    header[148..156].copy_from_slice(b"        ");
+    let checksum: u32 = header.iter().map(|b| u32::from(*b)).sum();
+    header[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+    Ok(header)
+}
+
+/// Returns the NUL-terminated string of a tar header field.
+fn tar_field(field: &[u8]) -> Result<String> {
+    let len = field.iter().position(|b| *b == 0).unwrap_or(field.len());
+    Ok(std::str::from_utf8(&field[..len])?.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_backup(passphrase: Option<&str>) -> Vec<u8> {
+        let mut writer = BackupWriter::new(Vec::new(), passphrase, 1_500_000_000).unwrap();
+        writer.append(DBFILE_BACKUP_NAME, 5, &b"dummy"[..]).unwrap();
+        let blob = vec![42u8; 1000];
+        writer
+            .append(
+                &format!("{}/image.jpg", BLOBS_BACKUP_NAME),
+                blob.len() as u64,
+                &blob[..],
+            )
+            .unwrap();
+        writer.finish().unwrap()
+    }
+
+    fn unpack(backup: &[u8], passphrase: Option<&str>) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+        BackupReader::new(backup, passphrase)?.unpack(
+            |name| {
+                names.push(name.to_string());
+                Ok(io::sink())
+            },
+            |_| Ok(()),
+        )?;
+        Ok(names)
+    }
+
+    #[test]
+    fn test_backup_roundtrip() {
+        let backup = write_backup(Some("secret"));
+        assert!(is_backup_file(&backup[..]).unwrap());
+        assert!(!is_backup_file(&b"SQLite format 3\0"[..]).unwrap());
+        assert_eq!(check_backup_file(&backup[..]).unwrap(), 1_500_000_000);
+
+        let files = unpack(&backup, Some("secret")).unwrap();
+        assert_eq!(
+            files,
+            vec![
+                DBFILE_BACKUP_NAME.to_string(),
+                format!("{}/image.jpg", BLOBS_BACKUP_NAME)
+            ]
+        );
+
+        assert!(unpack(&backup, Some("wrong")).is_err());
+        assert!(unpack(&backup, None).is_err());
+    }
+
+    #[test]
+    fn test_backup_unencrypted() {
+        let backup = write_backup(None);
+        assert_eq!(check_backup_file(&backup[..]).unwrap(), 1_500_000_000);
+        let files = unpack(&backup, None).unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(unpack(&backup, Some("secret")).unwrap(), files);
+
+        assert!(BackupWriter::new(Vec::new(), Some(""), 0).is_err());
+    }
+
+    #[test]
+    fn test_backup_damaged() {
+        let backup = write_backup(Some("secret"));
+
+        let mut damaged = backup.clone();
+        let pos = damaged.len() / 2;
+        damaged[pos] ^= 1;
+        assert!(check_backup_file(&damaged[..]).is_err());
+        assert!(unpack(&damaged, Some("secret")).is_err());
+
+        let truncated = &backup[..backup.len() - 1];
+        assert!(check_backup_file(truncated).is_err());
+        assert!(unpack(truncated, Some("secret")).is_err());
+    }
+
+    #[test]
+    fn test_tar_header() {
+        let header = tar_header("blobs_backup/image.jpg", 1000).unwrap();
+        assert_eq!(tar_field(&header[0..100]).unwrap(), "image.jpg");
+        assert_eq!(tar_field(&header[345..500]).unwrap(), "blobs_backup");
+        assert_eq!(tar_field(&header[124..136]).unwrap(), "00000001750");
+        assert_eq!(padding(1000), 24);
+        assert_eq!(padding(1024), 0);
+        assert!(tar_header(&"a".repeat(101), 0).is_err());
+    }
+}
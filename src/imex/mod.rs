@@ -1,7 +1,10 @@
 //! # Import/export module
 
 use core::cmp::{max, min};
-use std::path::Path;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read};
+use std::path::{Path, PathBuf};
 
 use num_traits::FromPrimitive;
 use rand::{thread_rng, Rng};
@@ -25,6 +28,15 @@ use crate::pgp;
 use crate::sql::{self, Sql};
 use crate::stock::StockMessage;
 
+mod backup;
+mod transfer;
+
+use self::backup::{BackupReader, BackupWriter, BLOBS_BACKUP_NAME, DBFILE_BACKUP_NAME};
+pub use self::transfer::{receive_backup, BackupProvider};
+
+/// Name of the unencrypted copy of an encrypted database in the blobdir while exporting.
+const EXPORT_DBFILE_NAME: &str = "delta-chat-export.db.part";
+
 #[derive(Debug, Display, Copy, Clone, PartialEq, Eq, FromPrimitive, ToPrimitive)]
 #[repr(i32)]
 pub enum ImexMode {
@@ -39,14 +51,18 @@ pub enum ImexMode {
     /// Public keys are not imported.
     ImportSelfKeys = 2,
 
-    /// Export a backup to the directory given as `param1`.
+    /// Export a backup to the directory given as `param1`,
+    /// the backup is encrypted with the passphrase given as `param2`;
+    /// without passphrase, the backup is not encrypted.
+    /// Backups of encrypted databases need a passphrase.
     /// The backup contains all contacts, chats, images and other data and device independent settings.
     /// The backup does not contain device dependent settings as ringtones or LED notification settings.
     /// The name of the backup is typically `delta-chat.<day>.bak`, if more than one backup is create on a day,
     /// the format is `delta-chat.<day>-<number>.bak`
     ExportBackup = 11,
 
-    /// `param1` is the file (not: directory) to import. The file is normally
+    /// `param1` is the file (not: directory) to import, `param2` is the passphrase
+    /// of encrypted backups. The file is normally
     /// created by DC_IMEX_EXPORT_BACKUP and detected by dc_imex_has_backup(). Importing a backup
     /// is only possible as long as the context is not configured or used in another way.
     ImportBackup = 12,
@@ -54,7 +70,7 @@ pub enum ImexMode {
 
 /// Import/export things.
 /// For this purpose, the function creates a job that is executed in the IMAP-thread then;
-/// this requires to call dc_perform_inbox_jobs() regularly.
+/// this requires the IO to be started by [Context::start_io].
 ///
 /// What to do is defined by the *what* parameter.
 ///
@@ -69,18 +85,28 @@ pub enum ImexMode {
 ///
 /// Only one import-/export-progress can run at the same time.
 /// To cancel an import-/export-progress, use dc_stop_ongoing_process().
-pub fn imex(context: &Context, what: ImexMode, param1: Option<impl AsRef<Path>>) {
+pub fn imex(
+    context: &Context,
+    what: ImexMode,
+    param1: Option<impl AsRef<Path>>,
+    param2: Option<String>,
+) {
     let mut param = Params::new();
     param.set_int(Param::Cmd, what as i32);
     if let Some(param1) = param1 {
         param.set(Param::Arg, param1.as_ref().to_string_lossy());
     }
+    // the passphrase is not stored in the job as the jobs are contained in backups,
+    // an empty passphrase stands for no passphrase
+    *context.imex_passphrase.lock().unwrap() = param2.filter(|p| !p.is_empty());
 
     job_kill_action(context, Action::ImexImap);
     job_add(context, Action::ImexImap, 0, param, 0);
 }
 
-/// Returns the filename of the backup found (otherwise an error)
+/// Returns the filename of the newest backup found (otherwise an error).
+///
+/// The checksums of the backups are verified, this does not need the passphrase.
 pub fn has_backup(context: &Context, dir_name: impl AsRef<Path>) -> Result<String> {
     let dir_name = dir_name.as_ref();
     let dir_iter = std::fs::read_dir(dir_name)?;
@@ -92,18 +118,18 @@ pub fn has_backup(context: &Context, dir_name: impl AsRef<Path>) -> Result<Strin
             let name = dirent.file_name();
             let name = name.to_string_lossy();
             if name.starts_with("delta-chat") && name.ends_with(".bak") {
-                let sql = Sql::new();
-                if sql.open(context, &path, true) {
-                    let curr_backup_time = sql
-                        .get_raw_config_int(context, "backup_time")
-                        .unwrap_or_default();
-                    if curr_backup_time > newest_backup_time {
-                        newest_backup_path = Some(path);
-                        newest_backup_time = curr_backup_time;
+                let curr_backup_time = match get_backup_time(context, &path) {
+                    Ok(backup_time) => backup_time,
+                    Err(err) => {
+                        warn!(context, "Ignoring backup {}: {}", name, err);
+                        continue;
                     }
-                    info!(context, "backup_time of {} is {}", name, curr_backup_time);
-                    sql.close(&context);
+                };
+                if curr_backup_time > newest_backup_time {
+                    newest_backup_path = Some(path);
+                    newest_backup_time = curr_backup_time;
                 }
+                info!(context, "backup_time of {} is {}", name, curr_backup_time);
             }
         }
     }
@@ -113,6 +139,22 @@ pub fn has_backup(context: &Context, dir_name: impl AsRef<Path>) -> Result<Strin
     }
 }
 
+/// Verifies a backup file and returns the time it was created.
+fn get_backup_time(context: &Context, path: &Path) -> Result<i64> {
+    if backup::is_backup_file(File::open(path)?)? {
+        backup::check_backup_file(BufReader::new(File::open(path)?))
+    } else {
+        // backups of older versions are plain databases
+        let sql = Sql::new();
+        ensure!(sql.open(context, path, true, None), "Cannot open database");
+        let backup_time = sql
+            .get_raw_config_int(context, "backup_time")
+            .unwrap_or_default();
+        sql.close(&context);
+        Ok(backup_time.into())
+    }
+}
+
 pub fn initiate_key_transfer(context: &Context) -> Result<String> {
     ensure!(context.alloc_ongoing(), "could not allocate ongoing");
     let res = do_initiate_key_transfer(context);
@@ -364,6 +406,8 @@ pub fn JobImexImap(context: &Context, job: &Job) -> Result<()> {
     ensure!(context.alloc_ongoing(), "could not allocate ongoing");
     let what: Option<ImexMode> = job.param.get_int(Param::Cmd).and_then(ImexMode::from_i32);
     let param = job.param.get(Param::Arg).unwrap_or_default();
+    let passphrase = context.imex_passphrase.lock().unwrap().take();
+    let passphrase = passphrase.as_deref();
 
     ensure!(!param.is_empty(), "No Import/export dir/file given.");
     info!(context, "Import/export process started.");
@@ -383,8 +427,8 @@ pub fn JobImexImap(context: &Context, job: &Job) -> Result<()> {
     let success = match what {
         Some(ImexMode::ExportSelfKeys) => export_self_keys(context, path),
         Some(ImexMode::ImportSelfKeys) => import_self_keys(context, path),
-        Some(ImexMode::ExportBackup) => export_backup(context, path),
-        Some(ImexMode::ImportBackup) => import_backup(context, path),
+        Some(ImexMode::ExportBackup) => export_backup(context, path, passphrase).map(|_| ()),
+        Some(ImexMode::ImportBackup) => import_backup(context, path, passphrase),
         None => {
             bail!("unknown IMEX type");
         }
@@ -404,11 +448,16 @@ pub fn JobImexImap(context: &Context, job: &Job) -> Result<()> {
 }
 
 /// Import Backup
-fn import_backup(context: &Context, backup_to_import: impl AsRef<Path>) -> Result<()> {
+fn import_backup(
+    context: &Context,
+    backup_to_import: impl AsRef<Path>,
+    passphrase: Option<&str>,
+) -> Result<()> {
+    let backup_to_import = backup_to_import.as_ref();
     info!(
         context,
         "Import \"{}\" to \"{}\".",
-        backup_to_import.as_ref().display(),
+        backup_to_import.display(),
         context.get_dbfile().display()
     );
 
@@ -416,28 +465,170 @@ fn import_backup(context: &Context, backup_to_import: impl AsRef<Path>) -> Resul
         !context.is_configured(),
         "Cannot import backups to accounts in use."
     );
+    if !backup::is_backup_file(File::open(backup_to_import)?)? {
+        return import_backup_legacy(context, backup_to_import);
+    }
+
+    let file = File::open(backup_to_import)?;
+    let total_size = file.metadata()?.len();
+    import_backup_stream(context, BufReader::new(file), total_size, passphrase)
+}
+
+/// Imports a backup read from `input`, `total_size` is the size of the backup
+/// and is used for the progress events only.
+fn import_backup_stream(
+    context: &Context,
+    input: impl Read,
+    total_size: u64,
+    passphrase: Option<&str>,
+) -> Result<()> {
+    // unpack the database and the blobs next to the live ones,
+    // they are replaced only if the whole backup is intact.
+    let dbfile = context.get_dbfile();
+    let blobdir = context.get_blobdir();
+    let tmp_dbfile = import_tmp_path(dbfile);
+    let tmp_blobdir = import_tmp_path(blobdir);
+    remove_import_tmp(context, &tmp_dbfile, &tmp_blobdir);
+    fs::create_dir_all(&tmp_blobdir)?;
+
+    let total_size = max(total_size, 1);
+    let blobs_prefix = format!("{}/", BLOBS_BACKUP_NAME);
+    let res = BackupReader::new(input, passphrase).and_then(|reader| {
+        reader.unpack(
+            |name| {
+                if name == DBFILE_BACKUP_NAME {
+                    Ok(BufWriter::new(File::create(&tmp_dbfile)?))
+                } else if name.starts_with(&blobs_prefix) {
+                    let blob_name = &name[blobs_prefix.len()..];
+                    ensure!(
+                        is_valid_blob_name(blob_name),
+                        "Invalid file name in backup: {}",
+                        name
+                    );
+                    Ok(BufWriter::new(File::create(tmp_blobdir.join(blob_name))?))
+                } else {
+                    bail!("Unknown file in backup: {}", name);
+                }
+            },
+            |bytes_read| {
+                ensure!(!context.shall_stop_ongoing(), "received stop signal");
+                let permille = max(min(bytes_read * 1000 / total_size, 990), 10);
+                context.call_cb(Event::ImexProgress(permille as usize));
+                Ok(())
+            },
+        )
+    });
+    if let Err(err) = res.and_then(|()| {
+        ensure!(tmp_dbfile.exists(), "Backup contains no database");
+        Ok(())
+    }) {
+        remove_import_tmp(context, &tmp_dbfile, &tmp_blobdir);
+        return Err(err);
+    }
+
+    replace_with_import_tmp(context, &tmp_dbfile, &tmp_blobdir)?;
+    delete_and_reset_all_device_msgs(&context)?;
+    Ok(())
+}
+
+/// Returns true if `name` can be used as the name of a file in the blobdir.
+fn is_valid_blob_name(name: &str) -> bool {
+    !name.is_empty() && !name.starts_with('.') && !name.contains('/') && !name.contains('\\')
+}
+
+/// Replaces the live database and adds the blobs by the verified ones unpacked
+/// to `tmp_dbfile` and `tmp_blobdir`, the database is re-opened afterwards.
+fn replace_with_import_tmp(context: &Context, tmp_dbfile: &Path, tmp_blobdir: &Path) -> Result<()> {
+    let dbfile = context.get_dbfile();
+    let blobdir = context.get_blobdir();
     context.sql.close(&context);
-    dc_delete_file(context, context.get_dbfile());
+    dc_delete_file(context, dbfile);
+    ensure!(!dbfile.exists(), "Cannot delete old database.");
+    fs::rename(tmp_dbfile, dbfile)?;
+    for entry in fs::read_dir(tmp_blobdir)? {
+        let entry = entry?;
+        fs::rename(entry.path(), blobdir.join(entry.file_name()))?;
+    }
+    fs::remove_dir(tmp_blobdir).ok();
+
+    ensure!(context.sql.reopen(&context, dbfile), "could not re-open db");
+    Ok(())
+}
+
+/// Returns the path the database or the blobdir is unpacked to during import.
+fn import_tmp_path(path: &Path) -> PathBuf {
+    let mut tmp_path = OsString::from(path);
+    tmp_path.push("-import");
+    tmp_path.into()
+}
+
+fn remove_import_tmp(context: &Context, tmp_dbfile: &Path, tmp_blobdir: &Path) {
+    if tmp_dbfile.exists() {
+        dc_delete_file(context, tmp_dbfile);
+    }
+    if tmp_blobdir.exists() {
+        if let Err(err) = fs::remove_dir_all(tmp_blobdir) {
+            warn!(context, "Cannot remove {}: {}", tmp_blobdir.display(), err);
+        }
+    }
+}
+
+/// Imports a backup of an older version, which is a database
+/// with all blobs in the table `backup_blobs`.
+///
+/// The backup is copied and verified next to the live database,
+/// which is replaced only if the backup can be opened and all blobs are extracted.
+fn import_backup_legacy(context: &Context, backup_to_import: &Path) -> Result<()> {
+    let tmp_dbfile = import_tmp_path(context.get_dbfile());
+    let tmp_blobdir = import_tmp_path(context.get_blobdir());
+    remove_import_tmp(context, &tmp_dbfile, &tmp_blobdir);
+    fs::create_dir_all(&tmp_blobdir)?;
+
+    let res = fs::copy(backup_to_import, &tmp_dbfile)
+        .map_err(Error::from)
+        .and_then(|_| extract_legacy_backup(context, &tmp_dbfile, &tmp_blobdir))
+        .and_then(|()| replace_with_import_tmp(context, &tmp_dbfile, &tmp_blobdir));
+    if let Err(err) = res {
+        remove_import_tmp(context, &tmp_dbfile, &tmp_blobdir);
+        return Err(err);
+    }
+
+    delete_and_reset_all_device_msgs(&context)?;
+    // the blobs are extracted, they are not needed in the database anymore
+    sql::execute(context, &context.sql, "DROP TABLE backup_blobs;", params![])?;
+    sql::try_execute(context, &context.sql, "VACUUM;").ok();
+    Ok(())
+}
+
+/// Verifies the legacy backup database `dbfile`
+/// and extracts the blobs contained in it to `blobdir`.
+fn extract_legacy_backup(context: &Context, dbfile: &Path, blobdir: &Path) -> Result<()> {
+    let sql = Sql::new();
     ensure!(
-        !context.get_dbfile().exists(),
-        "Cannot delete old database."
+        sql.open(context, dbfile, true, None),
+        "Cannot open backup database"
     );
+    let res = extract_legacy_blobs(context, &sql, blobdir);
+    sql.close(context);
+    res
+}
 
+fn extract_legacy_blobs(context: &Context, sql: &Sql, blobdir: &Path) -> Result<()> {
     ensure!(
-        dc_copy_file(context, backup_to_import.as_ref(), context.get_dbfile()),
-        "could not copy file"
+        sql.table_exists("config") && sql.table_exists("backup_blobs"),
+        "Backup database misses tables"
     );
-    /* error already logged */
-    /* re-open copied database file */
+    let dbversion = sql
+        .get_raw_config_int(context, "dbversion")
+        .ok_or_else(|| format_err!("Backup database has no version"))?;
     ensure!(
-        context.sql.open(&context, &context.get_dbfile(), false),
-        "could not re-open db"
+        dbversion <= sql::latest_version(),
+        "Backup database version {} is newer than the supported version {}",
+        dbversion,
+        sql::latest_version()
     );
 
-    delete_and_reset_all_device_msgs(&context)?;
-
-    let total_files_cnt = context
-        .sql
+    let total_files_cnt = sql
         .query_get_value::<_, isize>(context, "SELECT COUNT(*) FROM backup_blobs;", params![])
         .unwrap_or_default() as usize;
     info!(
@@ -445,7 +636,8 @@ fn import_backup(context: &Context, backup_to_import: impl AsRef<Path>) -> Resul
         "***IMPORT-in-progress: total_files_cnt={:?}", total_files_cnt,
     );
 
-    let res = context.sql.query_map(
+    // errors of the closure have to be sql errors, other errors are returned as result
+    sql.query_map(
         "SELECT file_name, file_content FROM backup_blobs ORDER BY id;",
         params![],
         |row| {
@@ -458,152 +650,147 @@ fn import_backup(context: &Context, backup_to_import: impl AsRef<Path>) -> Resul
             for (processed_files_cnt, file) in files.enumerate() {
                 let (file_name, file_blob) = file?;
                 if context.shall_stop_ongoing() {
-                    return Ok(false);
-                }
-                let mut permille = processed_files_cnt * 1000 / total_files_cnt;
-                if permille < 10 {
-                    permille = 10
+                    return Ok(Err(format_err!("received stop signal")));
                 }
-                if permille > 990 {
-                    permille = 990
-                }
-                context.call_cb(Event::ImexProgress(permille));
+                let permille = processed_files_cnt * 1000 / max(total_files_cnt, 1);
+                context.call_cb(Event::ImexProgress(max(min(permille, 990), 10)));
                 if file_blob.is_empty() {
                     continue;
                 }
-
-                let path_filename = context.get_blobdir().join(file_name);
-                dc_write_file(context, &path_filename, &file_blob)?;
+                if !is_valid_blob_name(&file_name) {
+                    return Ok(Err(format_err!(
+                        "Invalid file name in backup: {}",
+                        file_name
+                    )));
+                }
+                fs::write(blobdir.join(file_name), &file_blob)?;
             }
-            Ok(true)
+            Ok(Ok(()))
         },
-    );
-
-    match res {
-        Ok(all_files_extracted) => {
-            if all_files_extracted {
-                // only delete backup_blobs if all files were successfully extracted
-                sql::execute(context, &context.sql, "DROP TABLE backup_blobs;", params![])?;
-                sql::try_execute(context, &context.sql, "VACUUM;").ok();
-                Ok(())
-            } else {
-                bail!("received stop signal");
-            }
-        }
-        Err(err) => Err(err.into()),
-    }
+    )?
 }
 
 /*******************************************************************************
  * Export backup
  ******************************************************************************/
-/* the FILE_PROGRESS macro calls the callback with the permille of files processed.
-The macro avoids weird values of 0% or 100% while still working. */
-fn export_backup(context: &Context, dir: impl AsRef<Path>) -> Result<()> {
+fn export_backup(
+    context: &Context,
+    dir: impl AsRef<Path>,
+    passphrase: Option<&str>,
+) -> Result<PathBuf> {
+    // backups of encrypted databases contain the unencrypted database
+    ensure!(
+        passphrase.is_some() || !context.sql.is_encrypted(),
+        "Backups of encrypted databases need a passphrase"
+    );
     // get a fine backup file name (the name includes the date so that multiple backup instances are possible)
-    // FIXME: we should write to a temporary file first and rename it on success. this would guarantee the backup is complete.
-    // let dest_path_filename = dc_get_next_backup_file(context, dir, res);
     let now = time();
     let dest_path_filename = dc_get_next_backup_path(dir, now)?;
-    let dest_path_string = dest_path_filename.to_string_lossy().to_string();
+    // the backup is written to a temporary file first and renamed on success,
+    // so a found backup is always complete.
+    let tmp_path_filename = dest_path_filename.with_extension("bak.part");
 
     sql::housekeeping(context);
 
     sql::try_execute(context, &context.sql, "VACUUM;").ok();
 
-    // we close the database during the copy of the dbfile
-    context.sql.close(context);
     info!(
         context,
         "Backup '{}' to '{}'.",
         context.get_dbfile().display(),
         dest_path_filename.display(),
     );
-    let copied = dc_copy_file(context, context.get_dbfile(), &dest_path_filename);
-    context.sql.open(&context, &context.get_dbfile(), false);
-
-    if !copied {
-        bail!(
-            "could not copy file from '{}' to '{}'",
-            context.get_dbfile().display(),
-            dest_path_string
-        );
-    }
-    let dest_sql = Sql::new();
-    ensure!(
-        dest_sql.open(context, &dest_path_filename, false),
-        "could not open exported database {}",
-        dest_path_string
-    );
-    let res = match add_files_to_export(context, &dest_sql) {
+    match write_backup(context, &tmp_path_filename, passphrase, now) {
+        Ok(()) => {
+            fs::rename(&tmp_path_filename, &dest_path_filename)?;
+            context.call_cb(Event::ImexFileWritten(dest_path_filename.clone()));
+            Ok(dest_path_filename)
+        }
         Err(err) => {
-            dc_delete_file(context, &dest_path_filename);
+            dc_delete_file(context, &tmp_path_filename);
             error!(context, "backup failed: {}", err);
             Err(err)
         }
-        Ok(()) => {
-            dest_sql.set_raw_config_int(context, "backup_time", now as i32)?;
-            context.call_cb(Event::ImexFileWritten(dest_path_filename));
-            Ok(())
-        }
-    };
-    dest_sql.close(context);
-
-    Ok(res?)
+    }
 }
 
-fn add_files_to_export(context: &Context, sql: &Sql) -> Result<()> {
-    // add all files as blobs to the database copy (this does not require
-    // the source to be locked, neigher the destination as it is used only here)
-    if !sql.table_exists("backup_blobs") {
-        sql::execute(
-            context,
-            &sql,
-            "CREATE TABLE backup_blobs (id INTEGER PRIMARY KEY, file_name, file_content);",
-            params![],
-        )?
-    }
-    // copy all files from BLOBDIR into backup-db
-    let mut total_files_cnt = 0;
-    let dir = context.get_blobdir();
-    let dir_handle = std::fs::read_dir(&dir)?;
-    total_files_cnt += dir_handle.filter(|r| r.is_ok()).count();
+fn write_backup(context: &Context, path: &Path, passphrase: Option<&str>, now: i64) -> Result<()> {
+    let mut blobs = Vec::new();
+    let mut total_size = 0;
+    for entry in fs::read_dir(context.get_blobdir())? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with("delta-chat")
+            && (name.ends_with(".bak") || name.ends_with(".bak.part") || name == EXPORT_DBFILE_NAME)
+        {
+            continue;
+        }
+        let metadata = entry.metadata()?;
+        if !metadata.is_file() || metadata.len() == 0 {
+            continue;
+        }
+        total_size += metadata.len();
+        blobs.push((entry.path(), name));
+    }
+    let dbfile = context.get_dbfile();
+    total_size = max(total_size + fs::metadata(dbfile)?.len(), 1);
+    info!(context, "EXPORT: total_files_cnt={}", blobs.len() + 1);
+
+    let mut writer = BackupWriter::new(BufWriter::new(File::create(path)?), passphrase, now)?;
+
+    let res = if context.sql.is_encrypted() {
+        // backups contain the unencrypted database, the backup itself is encrypted.
+        // the unencrypted copy is written to the blobdir which is private to the app,
+        // unlike the backup directory, and removed by housekeeping if the export is killed.
+        let tmp_dbfile = context.get_blobdir().join(EXPORT_DBFILE_NAME);
+        if tmp_dbfile.exists() {
+            // left over by a killed export
+            fs::remove_file(&tmp_dbfile)?;
+        }
+        let res = context
+            .sql
+            .export_unencrypted(&tmp_dbfile)
+            .map_err(Error::from)
+            .and_then(|()| {
+                let file = File::open(&tmp_dbfile)?;
+                let size = file.metadata()?.len();
+                writer.append(DBFILE_BACKUP_NAME, size, BufReader::new(file))
+            });
+        dc_delete_file(context, &tmp_dbfile);
+        res
+    } else {
+        // we close the database during the copy of the dbfile
+        context.sql.close(context);
+        let res = File::open(dbfile).map_err(Error::from).and_then(|file| {
+            let size = file.metadata()?.len();
+            writer.append(DBFILE_BACKUP_NAME, size, BufReader::new(file))
+        });
+        context.sql.open(&context, dbfile, false, None);
+        res
+    };
+    res?;
 
-    info!(context, "EXPORT: total_files_cnt={}", total_files_cnt);
-    // scan directory, pass 2: copy files
-    let dir_handle = std::fs::read_dir(&dir)?;
-    let exported_all_files = sql.prepare(
-        "INSERT INTO backup_blobs (file_name, file_content) VALUES (?, ?);",
-        |mut stmt, _| {
-            let mut processed_files_cnt = 0;
-            for entry in dir_handle {
-                let entry = entry?;
-                if context.shall_stop_ongoing() {
-                    return Ok(false);
-                }
-                processed_files_cnt += 1;
-                let permille = max(min(processed_files_cnt * 1000 / total_files_cnt, 990), 10);
-                context.call_cb(Event::ImexProgress(permille));
+    let mut processed_size = fs::metadata(dbfile)?.len();
+    for (path, name) in blobs {
+        ensure!(
+            !context.shall_stop_ongoing(),
+            "canceled during export-files"
+        );
+        info!(context, "EXPORT: copying filename={}", name);
+        let file = File::open(&path)?;
+        let size = file.metadata()?.len();
+        writer.append(
+            &format!("{}/{}", BLOBS_BACKUP_NAME, name),
+            size,
+            BufReader::new(file),
+        )?;
+        processed_size += size;
+        let permille = max(min(processed_size * 1000 / total_size, 990), 10);
+        context.call_cb(Event::ImexProgress(permille as usize));
+    }
 
-                let name_f = entry.file_name();
-                let name = name_f.to_string_lossy();
-                if name.starts_with("delta-chat") && name.ends_with(".bak") {
-                    continue;
-                }
-                info!(context, "EXPORT: copying filename={}", name);
-                let curr_path_filename = context.get_blobdir().join(entry.file_name());
-                if let Ok(buf) = dc_read_file(context, &curr_path_filename) {
-                    if buf.is_empty() {
-                        continue;
-                    }
-                    // bail out if we can't insert
-                    stmt.execute(params![name, buf])?;
-                }
-            }
-            Ok(true)
-        },
-    )?;
-    ensure!(exported_all_files, "canceled during export-files");
+    let out = writer.finish()?;
+    out.get_ref().sync_all()?;
     Ok(())
 }
 
@@ -842,4 +1029,153 @@ mod tests {
         assert_eq!(headers.get(HEADER_AUTOCRYPT), Some(&"mutual".to_string()));
         assert!(headers.get(HEADER_SETUPCODE).is_none());
     }
+
+    #[test]
+    fn test_export_and_import_backup() {
+        let t = dummy_context();
+        t.ctx
+            .set_config(Config::Displayname, Some("Alice"))
+            .unwrap();
+        fs::write(t.ctx.get_blobdir().join("hello.txt"), b"hello").unwrap();
+        let backup_dir = tempfile::tempdir().unwrap();
+        assert!(t.ctx.alloc_ongoing());
+        assert!(export_backup(&t.ctx, backup_dir.path(), Some("")).is_err());
+        export_backup(&t.ctx, backup_dir.path(), Some("secret")).unwrap();
+        t.ctx.free_ongoing();
+        let backup = has_backup(&t.ctx, backup_dir.path()).unwrap();
+
+        // the live database is not touched if the backup cannot be read
+        let t2 = dummy_context();
+        assert!(t2.ctx.alloc_ongoing());
+        assert!(import_backup(&t2.ctx, &backup, Some("wrong")).is_err());
+        assert!(import_backup(&t2.ctx, &backup, None).is_err());
+        assert_eq!(t2.ctx.get_config(Config::Displayname), None);
+
+        import_backup(&t2.ctx, &backup, Some("secret")).unwrap();
+        t2.ctx.free_ongoing();
+        assert_eq!(
+            t2.ctx.get_config(Config::Displayname),
+            Some("Alice".to_string())
+        );
+        assert_eq!(
+            fs::read(t2.ctx.get_blobdir().join("hello.txt")).unwrap(),
+            b"hello"
+        );
+    }
+
+    #[test]
+    fn test_export_and_import_unencrypted_backup() {
+        let t = dummy_context();
+        t.ctx
+            .set_config(Config::Displayname, Some("Alice"))
+            .unwrap();
+        let backup_dir = tempfile::tempdir().unwrap();
+        assert!(t.ctx.alloc_ongoing());
+        export_backup(&t.ctx, backup_dir.path(), None).unwrap();
+        t.ctx.free_ongoing();
+        let backup = has_backup(&t.ctx, backup_dir.path()).unwrap();
+
+        let t2 = dummy_context();
+        assert!(t2.ctx.alloc_ongoing());
+        import_backup(&t2.ctx, &backup, None).unwrap();
+        t2.ctx.free_ongoing();
+        assert_eq!(
+            t2.ctx.get_config(Config::Displayname),
+            Some("Alice".to_string())
+        );
+    }
+
+    #[test]
+    fn test_import_legacy_backup() {
+        let t = dummy_context();
+        t.ctx
+            .set_config(Config::Displayname, Some("Alice"))
+            .unwrap();
+        t.ctx
+            .sql
+            .execute(
+                "CREATE TABLE backup_blobs (id INTEGER PRIMARY KEY, file_name, file_content);",
+                params![],
+            )
+            .unwrap();
+        t.ctx
+            .sql
+            .execute(
+                "INSERT INTO backup_blobs (file_name, file_content) VALUES (?, ?);",
+                params!["hello.txt", b"hello".to_vec()],
+            )
+            .unwrap();
+        let backup_dir = tempfile::tempdir().unwrap();
+        let backup = backup_dir.path().join("delta-chat-2020-01-01.bak");
+        t.ctx.sql.close(&t.ctx);
+        fs::copy(t.ctx.get_dbfile(), &backup).unwrap();
+
+        // damaged backups do not touch the live database
+        let damaged = backup_dir.path().join("damaged.bak");
+        fs::write(&damaged, b"SQLite format 3\0damaged").unwrap();
+        let t2 = dummy_context();
+        t2.ctx.set_config(Config::Displayname, Some("Bob")).unwrap();
+        assert!(t2.ctx.alloc_ongoing());
+        assert!(import_backup(&t2.ctx, &damaged, None).is_err());
+        assert_eq!(
+            t2.ctx.get_config(Config::Displayname),
+            Some("Bob".to_string())
+        );
+
+        import_backup(&t2.ctx, &backup, None).unwrap();
+        t2.ctx.free_ongoing();
+        assert_eq!(
+            t2.ctx.get_config(Config::Displayname),
+            Some("Alice".to_string())
+        );
+        assert_eq!(
+            fs::read(t2.ctx.get_blobdir().join("hello.txt")).unwrap(),
+            b"hello"
+        );
+        assert!(!t2.ctx.sql.table_exists("backup_blobs"));
+    }
+
+    #[test]
+    fn test_imex_passphrase_not_stored() {
+        let t = dummy_context();
+        let dir = tempfile::tempdir().unwrap();
+        let job_cnt = |t: &TestContext| {
+            t.ctx
+                .sql
+                .query_get_value::<_, i32>(
+                    &t.ctx,
+                    "SELECT COUNT(*) FROM jobs WHERE action=?;",
+                    params![Action::ImexImap],
+                )
+                .unwrap_or_default()
+        };
+        imex(&t.ctx, ImexMode::ExportBackup, Some(dir.path()), None);
+        assert_eq!(job_cnt(&t), 1);
+        assert_eq!(*t.ctx.imex_passphrase.lock().unwrap(), None);
+        imex(
+            &t.ctx,
+            ImexMode::ExportBackup,
+            Some(dir.path()),
+            Some("".to_string()),
+        );
+        assert_eq!(*t.ctx.imex_passphrase.lock().unwrap(), None);
+
+        imex(
+            &t.ctx,
+            ImexMode::ExportBackup,
+            Some(dir.path()),
+            Some("secret".to_string()),
+        );
+        assert_eq!(job_cnt(&t), 1);
+        let params: String = t
+            .ctx
+            .sql
+            .query_get_value(
+                &t.ctx,
+                "SELECT param FROM jobs WHERE action=?;",
+                params![Action::ImexImap],
+            )
+            .unwrap();
+        assert!(!params.contains("secret"));
+    }
 }
@@ -0,0 +1,428 @@
+//! # Transfer of a backup to another device in the local network
+//!
+//! The old device creates a [BackupProvider] which exports a backup
+//! and serves it on a TCP port, the QR code of the provider contains the address
+//! and a one-time secret, see [BackupProvider::get_qr].
+//!
+//! The new device scans the QR code and calls [receive_backup],
+//! it authenticates and streams the backup directly into the import.
+//!
+//! Two keys are derived from the secret, see [derive_key]:
+//! the authentication key and the passphrase the backup is encrypted with.
+//! Neither the secret nor the keys are sent over the network,
+//! so others in the network can neither receive nor read the backup.
+//!
+//! Protocol: the server sends a random nonce of [NONCE_LEN] bytes,
+//! the client answers with the HMAC-SHA256 of the nonce using the authentication key.
+//! If the HMAC is correct, the server answers with the size of the backup
+//! as big-endian `u64` followed by the backup, otherwise the connection is closed.
+//! Clients are served concurrently and have to authenticate within [HANDSHAKE_TIMEOUT],
+//! so idle connections of other devices in the network do not block the transfer.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, Shutdown, SocketAddr, TcpListener, TcpStream, UdpSocket};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::context::Context;
+use crate::e2ee;
+use crate::error::*;
+use crate::events::Event;
+use crate::lot::LotState;
+use crate::qr::{self, DCBACKUP_SCHEME};
+
+/// Timeout for connecting and for reading from the other device.
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Time a client has to authenticate after connecting.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often the provider checks if it shall stop while waiting for a connection.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Length of the challenge sent by the provider.
+const NONCE_LEN: usize = 32;
+
+/// Length of HMAC-SHA256 values.
+const MAC_LEN: usize = 32;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Connections of the clients currently served, shut down on cancellation.
+type Clients = Arc<Mutex<HashMap<u64, TcpStream>>>;
+
+/// Serves a backup of the account to another device.
+///
+/// The provider serves the backup until it was transferred once,
+/// until it is cancelled or until it is dropped.
+#[derive(Debug)]
+pub struct BackupProvider {
+    qr: String,
+    backup_file: PathBuf,
+    stop: Arc<AtomicBool>,
+    clients: Clients,
+    handle: Mutex<Option<JoinHandle<Result<()>>>>,
+}
+
+impl BackupProvider {
+    /// Exports a backup and starts serving it on the address of the device in the local network.
+    ///
+    /// This may take a while as the backup is written first, [Event::ImexProgress] is emitted.
+    pub fn prepare(context: &Context) -> Result<Self> {
+        Self::prepare_on(context, get_local_ip()?)
+    }
+
+    /// Exports a backup and starts serving it on the given IP address.
+    pub fn prepare_on(context: &Context, ip: IpAddr) -> Result<Self> {
+        ensure!(context.alloc_ongoing(), "could not allocate ongoing");
+        let secret = hex::encode(rand::random::<[u8; 16]>());
+        let passphrase = hex::encode(derive_key(&secret, "encryption"));
+        let res = e2ee::ensure_secret_key_exists(context)
+            .and_then(|_| super::export_backup(context, context.get_blobdir(), Some(&passphrase)));
+        context.free_ongoing();
+        let backup_file = res?;
+
+        let listener = match TcpListener::bind((ip, 0)).and_then(|listener| {
+            listener.set_nonblocking(true)?;
+            Ok(listener)
+        }) {
+            Ok(listener) => listener,
+            Err(err) => {
+                fs::remove_file(&backup_file).ok();
+                return Err(err.into());
+            }
+        };
+        let qr = format!("{}{}#{}", DCBACKUP_SCHEME, listener.local_addr()?, secret);
+        info!(context, "Serving backup on {}", listener.local_addr()?);
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let clients = Clients::default();
+        let handle = {
+            let stop = Arc::clone(&stop);
+            let clients = Arc::clone(&clients);
+            let backup_file = backup_file.clone();
+            let auth_key = derive_key(&secret, "authentication");
+            thread::spawn(move || serve_backup(listener, backup_file, auth_key, &stop, &clients))
+        };
+        Ok(BackupProvider {
+            qr,
+            backup_file,
+            stop,
+            clients,
+            handle: Mutex::new(Some(handle)),
+        })
+    }
+
+    /// Returns the QR code the other device has to scan to receive the backup.
+    pub fn get_qr(&self) -> &str {
+        &self.qr
+    }
+
+    /// Waits until the backup was transferred or the provider was cancelled.
+    ///
+    /// The backup file is removed when the provider is dropped.
+    pub fn wait(&self) -> Result<()> {
+        let handle = self.handle.lock().unwrap().take();
+        match handle {
+            Some(handle) => handle
+                .join()
+                .unwrap_or_else(|_| Err(format_err!("Backup provider thread panicked"))),
+            None => Ok(()),
+        }
+    }
+
+    /// Stops serving the backup, a running [BackupProvider::wait] returns with an error.
+    ///
+    /// This may be called from another thread while [BackupProvider::wait] blocks.
+    pub fn cancel(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+        shutdown_clients(&self.clients);
+    }
+}
+
+impl Drop for BackupProvider {
+    fn drop(&mut self) {
+        self.cancel();
+        self.wait().ok();
+        fs::remove_file(&self.backup_file).ok();
+    }
+}
+
+fn serve_backup(
+    listener: TcpListener,
+    backup_file: PathBuf,
+    auth_key: Vec<u8>,
+    stop: &AtomicBool,
+    clients: &Clients,
+) -> Result<()> {
+    let backup_file = Arc::new(backup_file);
+    let auth_key = Arc::new(auth_key);
+    // set while an authenticated client receives the backup, it is sent to one client only
+    let sending = Arc::new(AtomicBool::new(false));
+    let (done_sender, done) = mpsc::channel();
+    let mut next_client_id = 0;
+    while !stop.load(Ordering::Relaxed) {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                let id = next_client_id;
+                next_client_id += 1;
+                if let Ok(clone) = stream.try_clone() {
+                    clients.lock().unwrap().insert(id, clone);
+                }
+                let backup_file = Arc::clone(&backup_file);
+                let auth_key = Arc::clone(&auth_key);
+                let sending = Arc::clone(&sending);
+                let clients = Arc::clone(clients);
+                let done_sender = done_sender.clone();
+                thread::spawn(move || {
+                    // a failing client, eg. with a wrong secret, does not stop the provider
+                    let res = send_backup(stream, &backup_file, &auth_key, &sending);
+                    clients.lock().unwrap().remove(&id);
+                    if let Ok(true) = res {
+                        done_sender.send(()).ok();
+                    }
+                });
+            }
+            Err(ref err) if err.kind() == std::io::ErrorKind::WouldBlock => {
+                match done.recv_timeout(POLL_INTERVAL) {
+                    Ok(()) => {
+                        shutdown_clients(clients);
+                        return Ok(());
+                    }
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => unreachable!(),
+                }
+            }
+            Err(err) => {
+                shutdown_clients(clients);
+                return Err(err.into());
+            }
+        }
+    }
+    shutdown_clients(clients);
+    bail!("Backup provider stopped")
+}
+
+/// Unblocks reading from and writing to all clients.
+fn shutdown_clients(clients: &Clients) {
+    for client in clients.lock().unwrap().values() {
+        client.shutdown(Shutdown::Both).ok();
+    }
+}
+
+/// Sends the backup if the client knows the authentication key
+/// and no other client receives the backup, returns false otherwise.
+fn send_backup(
+    mut stream: TcpStream,
+    backup_file: &Path,
+    auth_key: &[u8],
+    sending: &AtomicBool,
+) -> Result<bool> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    stream.set_write_timeout(Some(HANDSHAKE_TIMEOUT))?;
+
+    let nonce = rand::random::<[u8; NONCE_LEN]>();
+    stream.write_all(&nonce)?;
+    stream.flush()?;
+    let mut code = [0u8; MAC_LEN];
+    stream.read_exact(&mut code)?;
+    if hmac_sha256(auth_key, &nonce).verify(&code).is_err() {
+        return Ok(false);
+    }
+    if sending.swap(true, Ordering::SeqCst) {
+        return Ok(false);
+    }
+
+    let res = (|| -> Result<()> {
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+        let mut file = fs::File::open(backup_file)?;
+        stream.write_u64::<BigEndian>(file.metadata()?.len())?;
+        std::io::copy(&mut file, &mut stream)?;
+        stream.flush()?;
+        Ok(())
+    })();
+    if res.is_err() {
+        // another client may try again
+        sending.store(false, Ordering::SeqCst);
+    }
+    res.map(|_| true)
+}
+
+/// Receives a backup from a [BackupProvider] and imports it.
+///
+/// `qr` is the QR code of the provider, [qr::check_qr] returns [LotState::QrBackup] for it.
+/// Like an import by [super::imex], this is only possible as long as the context is not configured.
+/// The function blocks until the backup is imported, [Event::ImexProgress] is emitted.
+pub fn receive_backup(context: &Context, qr: &str) -> Result<()> {
+    let lot = qr::check_qr(context, qr);
+    ensure!(
+        lot.state == LotState::QrBackup,
+        "QR code is no backup transfer"
+    );
+    let addr: SocketAddr = lot
+        .text1
+        .unwrap_or_default()
+        .parse()
+        .map_err(|err| format_err!("Invalid address in QR code: {}", err))?;
+    let secret = lot.auth.unwrap_or_default();
+
+    ensure!(context.alloc_ongoing(), "could not allocate ongoing");
+    let res = do_receive_backup(context, addr, &secret);
+    context.free_ongoing();
+    match res {
+        Ok(()) => {
+            info!(context, "Backup received from {}", addr);
+            context.call_cb(Event::ImexProgress(1000));
+            Ok(())
+        }
+        Err(err) => {
+            context.call_cb(Event::ImexProgress(0));
+            bail!("Receiving backup failed: {}", err);
+        }
+    }
+}
+
+fn do_receive_backup(context: &Context, addr: SocketAddr, secret: &str) -> Result<()> {
+    ensure!(
+        !context.is_configured(),
+        "Cannot import backups to accounts in use."
+    );
+    context.call_cb(Event::ImexProgress(10));
+
+    let mut stream = TcpStream::connect_timeout(&addr, TIMEOUT)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let mut nonce = [0u8; NONCE_LEN];
+    stream.read_exact(&mut nonce)?;
+    stream.write_all(
+        &hmac_sha256(&derive_key(secret, "authentication"), &nonce)
+            .result()
+            .code(),
+    )?;
+    stream.flush()?;
+
+    let size = stream
+        .read_u64::<BigEndian>()
+        .map_err(|err| format_err!("Authentication failed: {}", err))?;
+    let passphrase = hex::encode(derive_key(secret, "encryption"));
+    super::import_backup_stream(
+        context,
+        BufReader::new(stream.take(size)),
+        size,
+        Some(&passphrase),
+    )
+}
+
+/// Derives the key for `purpose` from the secret of the QR code.
+fn derive_key(secret: &str, purpose: &str) -> Vec<u8> {
+    hmac_sha256(
+        secret.as_bytes(),
+        format!("dcbackup-{}", purpose).as_bytes(),
+    )
+    .result()
+    .code()
+    .to_vec()
+}
+
+/// Returns the HMAC-SHA256 of `data`,
+/// [Mac::verify] compares it in constant time.
+fn hmac_sha256(key: &[u8], data: &[u8]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_varkey(key).expect("HMAC accepts keys of any size");
+    mac.input(data);
+    mac
+}
+
+/// Returns the address of the device in the local network.
+///
+/// The address is the source address used to reach an outside address,
+/// no data is sent for this.
+fn get_local_ip() -> Result<IpAddr> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+    socket.connect((Ipv4Addr::new(192, 0, 2, 1), 9))?;
+    Ok(socket.local_addr()?.ip())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::config::Config;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_backup_transfer() {
+        let t = dummy_context();
+        configure_alice_keypair(&t.ctx);
+        t.ctx
+            .set_config(Config::Displayname, Some("Alice"))
+            .unwrap();
+        let provider = BackupProvider::prepare_on(&t.ctx, Ipv4Addr::LOCALHOST.into()).unwrap();
+        let qr = provider.get_qr().to_string();
+        assert!(qr.starts_with("DCBACKUP:127.0.0.1:"));
+
+        // an idle connection does not block the transfer
+        let addr = &qr["DCBACKUP:".len()..qr.find('#').unwrap()];
+        let _idle = TcpStream::connect(addr).unwrap();
+
+        // a wrong secret is rejected, the provider keeps serving
+        let t2 = dummy_context();
+        let wrong_qr = format!("{}#wrong", &qr[..qr.find('#').unwrap()]);
+        assert!(receive_backup(&t2.ctx, &wrong_qr).is_err());
+        assert_eq!(t2.ctx.get_config(Config::Displayname), None);
+
+        receive_backup(&t2.ctx, &qr).unwrap();
+        provider.wait().unwrap();
+        assert_eq!(
+            t2.ctx.get_config(Config::Displayname),
+            Some("Alice".to_string())
+        );
+    }
+
+    #[test]
+    fn test_backup_provider_cancel() {
+        let t = dummy_context();
+        configure_alice_keypair(&t.ctx);
+        let provider =
+            Arc::new(BackupProvider::prepare_on(&t.ctx, Ipv4Addr::LOCALHOST.into()).unwrap());
+        let waiting = {
+            let provider = Arc::clone(&provider);
+            thread::spawn(move || provider.wait())
+        };
+        provider.cancel();
+        assert!(waiting.join().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_hmac_sha256() {
+        // RFC 4231, test case 2
+        let mac = hmac_sha256(b"Jefe", b"what do ya want for nothing?");
+        assert_eq!(
+            hex::encode(mac.clone().result().code()),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert!(mac
+            .clone()
+            .verify(
+                &hex::decode("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843")
+                    .unwrap()
+            )
+            .is_ok());
+        assert!(mac.verify(b"abc").is_err());
+        assert_ne!(
+            derive_key("secret", "authentication"),
+            derive_key("secret", "encryption")
+        );
+    }
+}
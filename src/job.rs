@@ -29,11 +29,15 @@ use crate::message::MsgId;
 use crate::message::{self, Message, MessageState};
 use crate::mimefactory::{MimeFactory, RenderedEmail};
 use crate::param::*;
+use crate::quota;
 use crate::sql;
 
 // results in ~3 weeks for the last backoff timespan
 const JOB_RETRIES: u32 = 17;
 
+/// Interval of the housekeeping if old messages are deleted, in seconds.
+const HOUSEKEEPING_INTERVAL: i64 = 60 * 60;
+
 /// Thread IDs
 #[derive(Debug, Display, Copy, Clone, PartialEq, Eq, FromPrimitive, ToPrimitive, FromSql, ToSql)]
 #[repr(i32)]
@@ -79,6 +83,7 @@ pub enum Action {
 
     // Jobs in the INBOX-thread, range from DC_IMAP_THREAD..DC_IMAP_THREAD+999
     Housekeeping = 105, // low priority ...
+    DeleteExpiredMsgs = 106,
     EmptyServer = 107,
     DeleteMsgOnImap = 110,
     MarkseenMdnOnImap = 120,
@@ -88,9 +93,11 @@ pub enum Action {
     ImexImap = 910, // ... high priority
 
     // Jobs in the SMTP-thread, range from DC_SMTP_THREAD..DC_SMTP_THREAD+999
-    MaybeSendLocations = 5005, // low priority ...
+    SendTyping = 5001, // low priority ...
+    MaybeSendLocations = 5005,
     MaybeSendLocationsEnded = 5007,
     SendMdn = 5010,
+    SendScheduledMsg = 5900,
     SendMsgToSmtp = 5901, // ... high priority
 }
 
@@ -108,6 +115,7 @@ impl From<Action> for Thread {
             Unknown => Thread::Unknown,
 
             Housekeeping => Thread::Imap,
+            DeleteExpiredMsgs => Thread::Imap,
             DeleteMsgOnImap => Thread::Imap,
             EmptyServer => Thread::Imap,
             MarkseenMdnOnImap => Thread::Imap,
@@ -116,9 +124,11 @@ impl From<Action> for Thread {
             ConfigureImap => Thread::Imap,
             ImexImap => Thread::Imap,
 
+            SendTyping => Thread::Smtp,
             MaybeSendLocations => Thread::Smtp,
             MaybeSendLocationsEnded => Thread::Smtp,
             SendMdn => Thread::Smtp,
+            SendScheduledMsg => Thread::Smtp,
             SendMsgToSmtp => Thread::Smtp,
         }
     }
@@ -193,11 +203,12 @@ impl Job {
             Err(crate::smtp::send::Error::SendError(err)) => {
                 // Remote error, retry later.
                 warn!(context, "SMTP failed to send: {}", err);
-                self.pending_error = Some(err.to_string());
+                let description = crate::smtp::send::describe_send_error(&err);
+                self.pending_error = Some(description.clone());
 
                 let res = match err {
                     async_smtp::smtp::error::Error::Permanent(_) => {
-                        Status::Finished(Err(format_err!("Permanent SMTP error: {}", err)))
+                        Status::Finished(Err(format_err!("{}", description)))
                     }
                     async_smtp::smtp::error::Error::Transient(_) => {
                         // We got a transient 4xx response from SMTP server.
@@ -245,6 +256,7 @@ impl Job {
             let loginparam = LoginParam::from_database(context, "configured_");
             if let Err(err) = context.smtp.lock().unwrap().connect(context, &loginparam) {
                 warn!(context, "SMTP connection failure: {:?}", err);
+                self.pending_error = Some(err.to_string());
                 return Status::RetryLater;
             }
         }
@@ -397,6 +409,92 @@ impl Job {
         })
     }
 
+    /// Sends a scheduled message that is due.
+    ///
+    /// The message is rendered only now,
+    /// so that recipients, keys and the message itself are up to date.
+    #[allow(non_snake_case)]
+    fn SendScheduledMsg(&mut self, context: &Context) -> Status {
+        let msg_id = MsgId::new(self.foreign_id);
+        let updated = job_try!(context.sql.execute(
+            "UPDATE msgs SET state=? WHERE id=? AND state=?;",
+            params![MessageState::OutPending, msg_id, MessageState::OutScheduled],
+        ));
+        if updated == 0 {
+            info!(context, "message {} is not scheduled anymore", msg_id);
+            return Status::Finished(Ok(()));
+        }
+        let chat_id = job_try!(Message::load_from_db(context, msg_id)).chat_id;
+        context.call_cb(Event::MsgsChanged { chat_id, msg_id });
+        job_try!(job_send_msg(context, msg_id));
+        Status::Finished(Ok(()))
+    }
+
+    /// Sends a typing notification to the members of the chat.
+    ///
+    /// Typing notifications are outdated quickly and must not delay real messages,
+    /// so they are rendered just before sending and are never retried.
+    #[allow(non_snake_case)]
+    fn SendTyping(&mut self, context: &Context) -> Status {
+        if time() > self.added_timestamp + chat::TYPING_TIMEOUT {
+            return Status::Finished(Err(format_err!("Typing notification is outdated")));
+        }
+        if job_try!(has_due_jobs(context, Action::SendMsgToSmtp)) {
+            return Status::Finished(Err(format_err!(
+                "Typing notification dropped in favour of waiting messages"
+            )));
+        }
+
+        let from = context
+            .get_config(Config::ConfiguredAddr)
+            .unwrap_or_default();
+        let mut msg = Message::new(Viewtype::Text);
+        msg.chat_id = ChatId::new(self.foreign_id);
+        msg.hidden = true;
+        msg.timestamp_sort = time();
+        msg.rfc724_mid = dc_create_outgoing_rfc724_mid(None, &from);
+        msg.param.set_int(
+            Param::Typing,
+            self.param.get_int(Param::Typing).unwrap_or_default(),
+        );
+        // typing notifications are sent encrypted or not at all
+        msg.param.set_int(Param::GuaranteeE2ee, 1);
+
+        let mimefactory = job_try!(MimeFactory::from_msg(context, &msg, false));
+        let recipients_list = mimefactory
+            .recipients()
+            .into_iter()
+            .filter_map(|addr| async_smtp::EmailAddress::new(addr).ok())
+            .collect::<Vec<_>>();
+        if recipients_list.is_empty() {
+            return Status::Finished(Ok(()));
+        }
+        let rendered_msg = job_try!(mimefactory.render());
+        if !rendered_msg.is_encrypted {
+            return Status::Finished(Err(format_err!("Typing notification cannot be encrypted")));
+        }
+
+        if !context.smtp.lock().unwrap().is_connected() {
+            let loginparam = LoginParam::from_database(context, "configured_");
+            if let Err(err) = context.smtp.lock().unwrap().connect(context, &loginparam) {
+                return Status::Finished(Err(format_err!("SMTP connection failure: {:?}", err)));
+            }
+        }
+
+        match self.smtp_send(
+            context,
+            recipients_list,
+            rendered_msg.message,
+            self.job_id,
+            || Ok(()),
+        ) {
+            Status::RetryNow | Status::RetryLater => Status::Finished(Err(format_err!(
+                "Typing notification not sent, not retrying"
+            ))),
+            status => status,
+        }
+    }
+
     #[allow(non_snake_case)]
     fn MoveMsg(&mut self, context: &Context) -> Status {
         let imap_inbox = &context.inbox_thread.read().unwrap().imap;
@@ -441,8 +539,39 @@ impl Job {
     fn DeleteMsgOnImap(&mut self, context: &Context) -> Status {
         let imap_inbox = &context.inbox_thread.read().unwrap().imap;
 
+        if let Some(server_folder) = self.param.get(Param::ServerFolder) {
+            // messages not added to the database, eg. typing notifications,
+            // are identified by their location on the server and their Message-ID
+            let rfc724_mid = self.param.get(Param::Arg).unwrap_or_default();
+            let mut server_uid = self.param.get_int(Param::ServerUid).unwrap_or_default() as u32;
+            return match imap_inbox.delete_msg(context, rfc724_mid, server_folder, &mut server_uid)
+            {
+                ImapActionResult::RetryLater => Status::RetryLater,
+                _ => Status::Finished(Ok(())),
+            };
+        }
+
         let mut msg = job_try!(Message::load_from_db(context, MsgId::new(self.foreign_id)));
 
+        if self.param.get_bool(Param::KeepOnDevice).unwrap_or_default() {
+            if msg.rfc724_mid.is_empty() || msg.server_uid == 0 {
+                // not on the server or already deleted by a job for another part
+                return Status::Finished(Ok(()));
+            }
+            let server_folder = msg.server_folder.clone().unwrap_or_default();
+            let res = imap_inbox.delete_msg(
+                context,
+                &msg.rfc724_mid,
+                &server_folder,
+                &mut msg.server_uid,
+            );
+            if res == ImapActionResult::RetryLater {
+                return Status::RetryLater;
+            }
+            message::update_server_uid(context, &msg.rfc724_mid, "", 0);
+            return Status::Finished(Ok(()));
+        }
+
         if !msg.rfc724_mid.is_empty() {
             if message::rfc724_mid_cnt(context, &msg.rfc724_mid) > 1 {
                 info!(
@@ -575,7 +704,7 @@ pub fn job_kill_ids(context: &Context, job_ids: &[u32]) -> sql::Result<()> {
     )
 }
 
-pub fn perform_inbox_fetch(context: &Context) {
+pub(crate) fn perform_inbox_fetch(context: &Context) {
     let use_network = context.get_config_bool(Config::InboxWatch);
 
     task::block_on(
@@ -585,9 +714,12 @@ pub fn perform_inbox_fetch(context: &Context) {
             .unwrap()
             .fetch(context, use_network),
     );
+    if use_network {
+        quota::update_quota_if_needed(context);
+    }
 }
 
-pub fn perform_mvbox_fetch(context: &Context) {
+pub(crate) fn perform_mvbox_fetch(context: &Context) {
     let use_network = context.get_config_bool(Config::MvboxWatch);
 
     task::block_on(
@@ -599,7 +731,7 @@ pub fn perform_mvbox_fetch(context: &Context) {
     );
 }
 
-pub fn perform_sentbox_fetch(context: &Context) {
+pub(crate) fn perform_sentbox_fetch(context: &Context) {
     let use_network = context.get_config_bool(Config::SentboxWatch);
 
     task::block_on(
@@ -611,7 +743,19 @@ pub fn perform_sentbox_fetch(context: &Context) {
     );
 }
 
-pub fn perform_inbox_idle(context: &Context) {
+pub(crate) fn perform_watch_fetch(context: &Context) {
+    let use_network = !context.get_watch_folders().is_empty();
+
+    task::block_on(
+        context
+            .watch_thread
+            .write()
+            .unwrap()
+            .fetch(context, use_network),
+    );
+}
+
+pub(crate) fn perform_inbox_idle(context: &Context) {
     if *context.perform_inbox_jobs_needed.clone().read().unwrap() {
         info!(
             context,
@@ -628,7 +772,7 @@ pub fn perform_inbox_idle(context: &Context) {
         .idle(context, use_network);
 }
 
-pub fn perform_mvbox_idle(context: &Context) {
+pub(crate) fn perform_mvbox_idle(context: &Context) {
     let use_network = context.get_config_bool(Config::MvboxWatch);
 
     context
@@ -638,7 +782,7 @@ pub fn perform_mvbox_idle(context: &Context) {
         .idle(context, use_network);
 }
 
-pub fn perform_sentbox_idle(context: &Context) {
+pub(crate) fn perform_sentbox_idle(context: &Context) {
     let use_network = context.get_config_bool(Config::SentboxWatch);
 
     context
@@ -648,7 +792,17 @@ pub fn perform_sentbox_idle(context: &Context) {
         .idle(context, use_network);
 }
 
-pub fn interrupt_inbox_idle(context: &Context) {
+pub(crate) fn perform_watch_idle(context: &Context) {
+    let use_network = !context.get_watch_folders().is_empty();
+
+    context
+        .watch_thread
+        .read()
+        .unwrap()
+        .idle(context, use_network);
+}
+
+pub(crate) fn interrupt_inbox_idle(context: &Context) {
     info!(context, "interrupt_inbox_idle called");
     // we do not block on trying to obtain the thread lock
     // because we don't know in which state the thread is.
@@ -665,11 +819,11 @@ pub fn interrupt_inbox_idle(context: &Context) {
     }
 }
 
-pub fn interrupt_mvbox_idle(context: &Context) {
+pub(crate) fn interrupt_mvbox_idle(context: &Context) {
     context.mvbox_thread.read().unwrap().interrupt_idle(context);
 }
 
-pub fn interrupt_sentbox_idle(context: &Context) {
+pub(crate) fn interrupt_sentbox_idle(context: &Context) {
     context
         .sentbox_thread
         .read()
@@ -677,7 +831,11 @@ pub fn interrupt_sentbox_idle(context: &Context) {
         .interrupt_idle(context);
 }
 
-pub fn perform_smtp_jobs(context: &Context) {
+pub(crate) fn interrupt_watch_idle(context: &Context) {
+    context.watch_thread.read().unwrap().interrupt_idle(context);
+}
+
+pub(crate) fn perform_smtp_jobs(context: &Context) {
     let probe_smtp_network = {
         let &(ref lock, _) = &*context.smtp_state.clone();
         let mut state = lock.lock().unwrap();
@@ -706,7 +864,7 @@ pub fn perform_smtp_jobs(context: &Context) {
     }
 }
 
-pub fn perform_smtp_idle(context: &Context) {
+pub(crate) fn perform_smtp_idle(context: &Context) {
     info!(context, "SMTP-idle started...",);
     {
         let &(ref lock, ref cvar) = &*context.smtp_state.clone();
@@ -775,6 +933,7 @@ pub fn maybe_network(context: &Context) {
     interrupt_inbox_idle(context);
     interrupt_mvbox_idle(context);
     interrupt_sentbox_idle(context);
+    interrupt_watch_idle(context);
 }
 
 pub fn job_action_exists(context: &Context, action: Action) -> bool {
@@ -786,6 +945,9 @@ pub fn job_action_exists(context: &Context, action: Action) -> bool {
 
 fn set_delivered(context: &Context, msg_id: MsgId) {
     message::update_msg_state(context, msg_id, MessageState::OutDelivered);
+    if let Err(err) = message::start_ephemeral_timers(context, &[msg_id]) {
+        warn!(context, "failed to start ephemeral timer: {}", err);
+    }
     let chat_id: ChatId = context
         .sql
         .query_get_value(
@@ -797,6 +959,24 @@ fn set_delivered(context: &Context, msg_id: MsgId) {
     context.call_cb(Event::MsgDelivered { chat_id, msg_id });
 }
 
+/// Schedules the job sending the message to be tried at once.
+///
+/// Returns false if there is no such job,
+/// eg. because the job was removed after exhausting its retries.
+pub(crate) fn retry_send_msg_now(context: &Context, msg_id: MsgId) -> bool {
+    let updated = context
+        .sql
+        .execute(
+            "UPDATE jobs SET desired_timestamp=?, tries=0 WHERE action=? AND foreign_id=?;",
+            params![time(), Action::SendMsgToSmtp, msg_id],
+        )
+        .unwrap_or_default();
+    if updated > 0 {
+        interrupt_smtp_idle(context);
+    }
+    updated > 0
+}
+
 /* special case for DC_JOB_SEND_MSG_TO_SMTP */
 pub fn job_send_msg(context: &Context, msg_id: MsgId) -> Result<()> {
     let mut msg = Message::load_from_db(context, msg_id)?;
@@ -897,23 +1077,65 @@ pub fn job_send_msg(context: &Context, msg_id: MsgId) -> Result<()> {
     Ok(())
 }
 
-pub fn perform_inbox_jobs(context: &Context) {
-    info!(context, "dc_perform_inbox_jobs starting.",);
+/// Adds a job sending a typing notification to the chat.
+///
+/// Only the latest typing state of a chat is sent,
+/// a notification that is still waiting to be sent is replaced.
+pub(crate) fn job_send_typing(context: &Context, chat_id: ChatId, typing: bool) -> Result<()> {
+    context.sql.execute(
+        "DELETE FROM jobs WHERE action=? AND foreign_id=?;",
+        params![Action::SendTyping, chat_id],
+    )?;
+    let mut param = Params::new();
+    param.set_int(Param::Typing, typing as i32);
+    job_add(
+        context,
+        Action::SendTyping,
+        chat_id.to_u32() as i32,
+        param,
+        0,
+    );
+    Ok(())
+}
 
-    let probe_imap_network = *context.probe_imap_network.clone().read().unwrap();
-    *context.probe_imap_network.write().unwrap() = false;
-    *context.perform_inbox_jobs_needed.write().unwrap() = false;
+/// Returns true if there are jobs of the given action that are due to be performed.
+fn has_due_jobs(context: &Context, action: Action) -> sql::Result<bool> {
+    context.sql.exists(
+        "SELECT id FROM jobs WHERE action=? AND desired_timestamp<=?;",
+        params![action, time()],
+    )
+}
 
-    job_perform(context, Thread::Imap, probe_imap_network);
-    info!(context, "dc_perform_inbox_jobs ended.",);
+/// Adds a job sending a scheduled message at `timestamp`,
+/// the message is rendered when the job is performed.
+pub(crate) fn job_send_scheduled_msg(context: &Context, msg_id: MsgId, timestamp: i64) {
+    job_add(
+        context,
+        Action::SendScheduledMsg,
+        msg_id.to_u32() as i32,
+        Params::new(),
+        std::cmp::max(timestamp - time(), 0),
+    );
 }
 
-pub fn perform_mvbox_jobs(context: &Context) {
-    info!(context, "dc_perform_mbox_jobs EMPTY (for now).",);
+/// Removes the job sending a scheduled message, eg. to schedule it for another time.
+pub(crate) fn job_kill_scheduled_msg(context: &Context, msg_id: MsgId) -> Result<()> {
+    context.sql.execute(
+        "DELETE FROM jobs WHERE action=? AND foreign_id=?;",
+        params![Action::SendScheduledMsg, msg_id],
+    )?;
+    Ok(())
 }
 
-pub fn perform_sentbox_jobs(context: &Context) {
-    info!(context, "dc_perform_sentbox_jobs EMPTY (for now).",);
+pub(crate) fn perform_inbox_jobs(context: &Context) {
+    info!(context, "INBOX-jobs started...",);
+
+    let probe_imap_network = *context.probe_imap_network.clone().read().unwrap();
+    *context.probe_imap_network.write().unwrap() = false;
+    *context.perform_inbox_jobs_needed.write().unwrap() = false;
+
+    job_perform(context, Thread::Imap, probe_imap_network);
+    info!(context, "INBOX-jobs ended.",);
 }
 
 fn job_perform(context: &Context, thread: Thread, probe_network: bool) {
@@ -938,6 +1160,12 @@ fn job_perform(context: &Context, thread: Thread, probe_network: bool) {
                 .read()
                 .unwrap()
                 .suspend(context);
+            context
+                .watch_thread
+                .clone()
+                .read()
+                .unwrap()
+                .suspend(context);
             suspend_smtp_thread(context, true);
         }
 
@@ -959,6 +1187,12 @@ fn job_perform(context: &Context, thread: Thread, probe_network: bool) {
                 .read()
                 .unwrap()
                 .unsuspend(context);
+            context
+                .watch_thread
+                .clone()
+                .read()
+                .unwrap()
+                .unsuspend(context);
             suspend_smtp_thread(context, false);
             break;
         }
@@ -984,6 +1218,11 @@ fn job_perform(context: &Context, thread: Thread, probe_network: bool) {
                         tries,
                         time_offset
                     );
+                    if job.action == Action::SendMsgToSmtp {
+                        if let Some(ref err) = job.pending_error {
+                            message::set_msg_error(context, MsgId::new(job.foreign_id), err);
+                        }
+                    }
                     if thread == Thread::Smtp && tries < JOB_RETRIES - 1 {
                         context
                             .smtp_state
@@ -1025,6 +1264,14 @@ fn job_perform(context: &Context, thread: Thread, probe_network: bool) {
                         context,
                         "{} removes job {} as it failed with error {:?}", thread, job, err
                     );
+                    if job.action == Action::SendMsgToSmtp || job.action == Action::SendScheduledMsg
+                    {
+                        message::set_msg_failed(
+                            context,
+                            MsgId::new(job.foreign_id),
+                            Some(err.to_string()),
+                        );
+                    }
                 } else {
                     info!(context, "{} removes job {} as it succeeded", thread, job);
                 }
@@ -1050,6 +1297,8 @@ fn perform_job_action(context: &Context, mut job: &mut Job, thread: Thread, trie
         Action::MarkseenMdnOnImap => job.MarkseenMdnOnImap(context),
         Action::MoveMsg => job.MoveMsg(context),
         Action::SendMdn => job.SendMdn(context),
+        Action::SendScheduledMsg => job.SendScheduledMsg(context),
+        Action::SendTyping => job.SendTyping(context),
         Action::ConfigureImap => JobConfigureImap(context),
         Action::ImexImap => match JobImexImap(context, &job) {
             Ok(()) => Status::Finished(Ok(())),
@@ -1061,7 +1310,11 @@ fn perform_job_action(context: &Context, mut job: &mut Job, thread: Thread, trie
         Action::MaybeSendLocations => location::JobMaybeSendLocations(context, &job),
         Action::MaybeSendLocationsEnded => location::JobMaybeSendLocationsEnded(context, &mut job),
         Action::Housekeeping => {
-            sql::housekeeping(context);
+            housekeeping(context);
+            Status::Finished(Ok(()))
+        }
+        Action::DeleteExpiredMsgs => {
+            job_try!(message::delete_expired_messages(context));
             Status::Finished(Ok(()))
         }
     };
@@ -1074,6 +1327,37 @@ fn perform_job_action(context: &Context, mut job: &mut Job, thread: Thread, trie
     try_res
 }
 
+/// Deletes old messages as configured and cleans up the database and the blobdir.
+///
+/// If old messages are deleted, the next housekeeping is scheduled.
+fn housekeeping(context: &Context) {
+    if let Err(err) = message::prune_device_msgs(context) {
+        warn!(context, "Housekeeping: Cannot delete old messages: {}", err);
+    }
+    if let Err(err) = message::schedule_deletion_on_server(context) {
+        warn!(
+            context,
+            "Housekeeping: Cannot schedule deletion from server: {}", err
+        );
+    }
+
+    sql::housekeeping(context);
+
+    if context.get_config_int(Config::DeleteServerAfter) > 0
+        || context.get_config_int(Config::DeleteDeviceAfter) > 0
+    {
+        // check for old messages again later
+        job_kill_action(context, Action::Housekeeping);
+        job_add(
+            context,
+            Action::Housekeeping,
+            0,
+            Params::new(),
+            HOUSEKEEPING_INTERVAL,
+        );
+    }
+}
+
 fn get_backoff_time_offset(tries: u32) -> i64 {
     let n = 2_i32.pow(tries - 1) * 60;
     let mut rng = thread_rng();
@@ -1165,7 +1449,7 @@ pub fn job_add(
     }
 }
 
-pub fn interrupt_smtp_idle(context: &Context) {
+pub(crate) fn interrupt_smtp_idle(context: &Context) {
     info!(context, "Interrupting SMTP-idle...",);
 
     let &(ref lock, ref cvar) = &*context.smtp_state.clone();
@@ -1277,4 +1561,35 @@ mod tests {
         let jobs = load_next_job(&t.ctx, Thread::from(Action::MoveMsg), false);
         assert!(jobs.is_some());
     }
+
+    #[test]
+    fn test_send_scheduled_msg() {
+        let t = dummy_context();
+        configure_alice_keypair(&t.ctx);
+        t.ctx.set_config(Config::BccSelf, Some("1")).unwrap();
+        let chat_id =
+            chat::create_group_chat(&t.ctx, crate::contact::VerifiedStatus::Unverified, "foo")
+                .unwrap();
+        let mut msg = Message::new(Viewtype::Text);
+        msg.set_text(Some("foo".to_string()));
+        let msg_id = chat::send_msg_at(&t.ctx, chat_id, &mut msg, time() + 3600).unwrap();
+        assert!(load_next_job(&t.ctx, Thread::Smtp, false).is_none());
+
+        // the message is rendered when it is due
+        t.ctx
+            .sql
+            .execute("UPDATE jobs SET desired_timestamp=0;", params![])
+            .unwrap();
+        let mut job = load_next_job(&t.ctx, Thread::Smtp, false).unwrap();
+        assert_eq!(job.action, Action::SendScheduledMsg);
+        match perform_job_action(&t.ctx, &mut job, Thread::Smtp, 0) {
+            Status::Finished(Ok(())) => {}
+            _ => panic!("scheduled message not sent"),
+        }
+        let msg = Message::load_from_db(&t.ctx, msg_id).unwrap();
+        assert_eq!(msg.get_state(), MessageState::OutPending);
+        let job = load_next_job(&t.ctx, Thread::Smtp, false).unwrap();
+        assert_eq!(job.action, Action::SendMsgToSmtp);
+        assert_eq!(job.foreign_id, msg_id.to_u32());
+    }
 }
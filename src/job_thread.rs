@@ -1,5 +1,6 @@
 use std::sync::{Arc, Condvar, Mutex};
 
+use crate::config::Config;
 use crate::context::Context;
 use crate::error::{Error, Result};
 use crate::imap::Imap;
@@ -101,12 +102,13 @@ impl JobThread {
         let prefix = format!("{}-fetch", self.name);
         match self.imap.connect_configured(context) {
             Ok(()) => {
-                if let Some(watch_folder) = self.get_watch_folder(context) {
+                let watch_folders = self.get_watch_folders(context);
+                if !watch_folders.is_empty() {
                     let start = std::time::Instant::now();
                     info!(context, "{} started...", prefix);
                     let res = self
                         .imap
-                        .fetch(context, &watch_folder)
+                        .fetch(context, &watch_folders)
                         .await
                         .map_err(Into::into);
                     let elapsed = start.elapsed().as_millis();
@@ -135,6 +137,14 @@ impl JobThread {
         }
     }
 
+    fn get_watch_folders(&self, context: &Context) -> Vec<String> {
+        if self.folder_config_name == Config::WatchFolders.as_ref() {
+            context.get_watch_folders()
+        } else {
+            self.get_watch_folder(context).into_iter().collect()
+        }
+    }
+
     pub fn idle(&self, context: &Context, use_network: bool) {
         {
             let &(ref lock, ref cvar) = &*self.state.clone();
@@ -174,12 +184,12 @@ impl JobThread {
         let prefix = format!("{}-IDLE", self.name);
         let do_fake_idle = match self.imap.connect_configured(context) {
             Ok(()) => {
-                if !self.imap.can_idle() {
-                    true // we have to do fake_idle
+                let mut watch_folders = self.get_watch_folders(context);
+                if !self.imap.can_idle() || watch_folders.len() > 1 {
+                    true // we have to do fake_idle, IDLE watches only one folder
                 } else {
-                    let watch_folder = self.get_watch_folder(context);
                     info!(context, "{} started...", prefix);
-                    let res = self.imap.idle(context, watch_folder);
+                    let res = self.imap.idle(context, watch_folders.pop());
                     info!(context, "{} ended...", prefix);
                     if let Err(err) = res {
                         warn!(context, "{} failed: {} -> reconnecting", prefix, err);
@@ -198,8 +208,8 @@ impl JobThread {
             }
         };
         if do_fake_idle {
-            let watch_folder = self.get_watch_folder(context);
-            self.imap.fake_idle(context, watch_folder);
+            let watch_folders = self.get_watch_folders(context);
+            self.imap.fake_idle(context, watch_folders);
         }
 
         self.state.0.lock().unwrap().using_handle = false;
@@ -9,6 +9,7 @@ use pgp::ser::Serialize;
 use pgp::types::{KeyTrait, SecretKeyTrait};
 
 use crate::constants::*;
+use crate::contact::addr_cmp;
 use crate::context::Context;
 use crate::dc_tools::*;
 use crate::sql::Sql;
@@ -287,6 +288,23 @@ impl Key {
         }
     }
 
+    /// Returns true if a user ID of the key contains the address `addr`.
+    pub fn has_addr(&self, addr: &str) -> bool {
+        let users = match self {
+            Key::Public(k) => &k.details.users,
+            Key::Secret(k) => &k.details.users,
+        };
+        users.iter().any(|user| {
+            // user IDs are typically formatted as `Name <addr>`
+            let id = user.id.id();
+            let id_addr = match (id.rfind('<'), id.rfind('>')) {
+                (Some(start), Some(end)) if start < end => &id[start + 1..end],
+                _ => id,
+            };
+            addr_cmp(id_addr, addr)
+        })
+    }
+
     pub fn formatted_fingerprint(&self) -> String {
         let rawhex = self.fingerprint();
         dc_format_fingerprint(&rawhex)
@@ -578,6 +596,14 @@ i8pcjGO+IZffvyZJVRWfVooBJmWWbPB1pueo3tx8w3+fcuzpxz+RLFKaPyqXO+dD
         assert_eq!(public.primary_key, KEYPAIR.public.primary_key);
     }
 
+    #[test]
+    fn test_has_addr() {
+        let key = Key::from(KEYPAIR.public.clone());
+        assert!(key.has_addr("alice@example.com"));
+        assert!(key.has_addr("Alice@Example.com"));
+        assert!(!key.has_addr("mallory@example.com"));
+    }
+
     #[test]
     fn test_save_self_key_twice() {
         // Saving the same key twice should result in only one row in
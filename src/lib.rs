@@ -26,6 +26,7 @@ pub mod headerdef;
 pub(crate) mod events;
 pub use events::*;
 
+pub mod accounts;
 mod aheader;
 mod blob;
 pub mod chat;
@@ -55,12 +56,17 @@ pub mod peerstate;
 pub mod pgp;
 pub mod provider;
 pub mod qr;
+pub mod quota;
+pub mod reaction;
+pub mod rpc;
+mod scheduler;
 pub mod securejoin;
 mod simplify;
 mod smtp;
 pub mod sql;
 pub mod stock;
 mod token;
+pub mod vcard;
 #[macro_use]
 mod dehtml;
 
@@ -7,7 +7,8 @@ use failure::Fail;
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 
-use crate::chat::{self, Chat, ChatId};
+use crate::chat::{self, Chat, ChatId, EphemeralTimer};
+use crate::config::Config;
 use crate::constants::*;
 use crate::contact::*;
 use crate::context::*;
@@ -208,6 +209,8 @@ pub struct Message {
     pub(crate) starred: bool,
     pub(crate) chat_blocked: Blocked,
     pub(crate) location_id: u32,
+    pub(crate) ephemeral_timer: EphemeralTimer,
+    pub(crate) ephemeral_timestamp: i64,
     pub(crate) param: Params,
 }
 
@@ -248,6 +251,8 @@ impl Message {
                     "    m.starred AS starred,",
                     "    m.hidden AS hidden,",
                     "    m.location_id AS location,",
+                    "    m.ephemeral_timer AS ephemeral_timer,",
+                    "    m.ephemeral_timestamp AS ephemeral_timestamp,",
                     "    c.blocked AS blocked",
                     " FROM msgs m LEFT JOIN chats c ON c.id=m.chat_id",
                     " WHERE m.id=?;"
@@ -295,6 +300,8 @@ impl Message {
                     msg.starred = row.get("starred")?;
                     msg.hidden = row.get("hidden")?;
                     msg.location_id = row.get("location")?;
+                    msg.ephemeral_timer = row.get("ephemeral_timer")?;
+                    msg.ephemeral_timestamp = row.get("ephemeral_timestamp")?;
                     msg.chat_blocked = row
                         .get::<_, Option<Blocked>>("blocked")?
                         .unwrap_or_default();
@@ -321,6 +328,13 @@ impl Message {
                 params![msg.id],
             )
             .ok();
+            sql::execute(
+                context,
+                &context.sql,
+                "DELETE FROM reactions WHERE msg_id=?;",
+                params![msg.id],
+            )
+            .ok();
         }
     }
 
@@ -473,6 +487,17 @@ impl Message {
         self.param.get_int(Param::Duration).unwrap_or_default()
     }
 
+    /// Returns the ephemeral timer the message was sent with.
+    pub fn get_ephemeral_timer(&self) -> EphemeralTimer {
+        self.ephemeral_timer
+    }
+
+    /// Returns the time the message will be deleted at,
+    /// `0` if the ephemeral timer is not started.
+    pub fn get_ephemeral_timestamp(&self) -> i64 {
+        self.ephemeral_timestamp
+    }
+
     pub fn get_showpadlock(&self) -> bool {
         self.param.get_int(Param::GuaranteeE2ee).unwrap_or_default() != 0
     }
@@ -491,7 +516,9 @@ impl Message {
         };
 
         let contact = if self.from_id != DC_CONTACT_ID_SELF as u32
-            && (chat.typ == Chattype::Group || chat.typ == Chattype::VerifiedGroup)
+            && (chat.typ == Chattype::Group
+                || chat.typ == Chattype::VerifiedGroup
+                || chat.typ == Chattype::Mailinglist)
         {
             Contact::get_by_id(context, self.from_id).ok()
         } else {
@@ -533,6 +560,33 @@ impl Message {
         0 != self.param.get_int(Param::Forwarded).unwrap_or_default()
     }
 
+    /// Whether the text of the message was edited after sending.
+    pub fn is_edited(&self) -> bool {
+        self.param.get_bool(Param::IsEdited).unwrap_or_default()
+    }
+
+    /// Returns the error of the last failed try to send the message.
+    pub fn get_error(&self) -> Option<String> {
+        self.param.get(Param::Error).map(|err| err.to_string())
+    }
+
+    /// Returns the retry state if sending the message failed and is tried again later.
+    pub fn get_send_retry(&self, context: &Context) -> Option<SendRetry> {
+        context
+            .sql
+            .query_row(
+                "SELECT tries, desired_timestamp FROM jobs WHERE action=? AND foreign_id=? AND tries>0;",
+                params![Action::SendMsgToSmtp, self.id],
+                |row| {
+                    Ok(SendRetry {
+                        tries: row.get(0)?,
+                        next_try: row.get(1)?,
+                    })
+                },
+            )
+            .ok()
+    }
+
     pub fn is_info(&self) -> bool {
         let cmd = self.param.get_cmd();
         self.from_id == DC_CONTACT_ID_INFO as u32
@@ -598,6 +652,57 @@ impl Message {
         self.param.set_int(Param::Duration, duration);
     }
 
+    /// Sets the message this message replies to.
+    ///
+    /// The quoted message is referenced by `In-Reply-To`
+    /// and an excerpt of it is sent along so that the quote can be shown
+    /// even if the receiver does not have the quoted message.
+    pub fn set_quote(&mut self, context: &Context, quote: &Message) -> Result<(), Error> {
+        ensure!(
+            !quote.rfc724_mid.is_empty(),
+            "Message without Message-ID cannot be quoted"
+        );
+        self.in_reply_to = Some(quote.rfc724_mid.clone());
+        self.param.set(
+            Param::Quote,
+            quote.get_summarytext(context, SUMMARY_CHARACTERS),
+        );
+        Ok(())
+    }
+
+    /// Returns the excerpt of the quoted message, if any.
+    pub fn quoted_text(&self) -> Option<String> {
+        self.param.get(Param::Quote).map(|s| s.to_string())
+    }
+
+    /// Returns the quoted message if it is known locally.
+    pub fn get_quoted_msg(&self, context: &Context) -> Result<Option<Message>, Error> {
+        if !self.param.exists(Param::Quote) {
+            return Ok(None);
+        }
+        let rfc724_mid = match self.in_reply_to {
+            Some(ref in_reply_to) => in_reply_to
+                .trim()
+                .trim_start_matches('<')
+                .trim_end_matches('>')
+                .to_string(),
+            None => return Ok(None),
+        };
+        if rfc724_mid.is_empty() {
+            return Ok(None);
+        }
+        let msg_id: Option<MsgId> = context.sql.query_get_value_result(
+            "SELECT m.id FROM msgs m \
+             LEFT JOIN chats c ON m.chat_id=c.id \
+             WHERE m.rfc724_mid=? AND m.chat_id>9 AND c.blocked=0;",
+            params![rfc724_mid],
+        )?;
+        match msg_id {
+            Some(msg_id) => Ok(Some(Message::load_from_db(context, msg_id)?)),
+            None => Ok(None),
+        }
+    }
+
     pub fn latefiling_mediasize(
         &mut self,
         context: &Context,
@@ -626,6 +731,15 @@ impl Message {
     }
 }
 
+/// Retry state of an outgoing message that could not be sent yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SendRetry {
+    /// Number of failed tries.
+    pub tries: u32,
+    /// Timestamp of the next try.
+    pub next_try: i64,
+}
+
 #[derive(
     Debug,
     Clone,
@@ -669,6 +783,10 @@ pub enum MessageState {
     /// checkmark).
     OutPending = 20,
 
+    /// Message scheduled to be sent later, see [chat::send_msg_at].
+    /// Until then, the message can still be edited or cancelled.
+    OutScheduled = 22,
+
     /// *Unrecoverable* error (*recoverable* errors result in pending
     /// messages).
     OutFailed = 24,
@@ -702,6 +820,7 @@ impl std::fmt::Display for MessageState {
                 Self::OutPreparing => "Preparing",
                 Self::OutDraft => "Draft",
                 Self::OutPending => "Pending",
+                Self::OutScheduled => "Scheduled",
                 Self::OutFailed => "Failed",
                 Self::OutDelivered => "Delivered",
                 Self::OutMdnRcvd => "Read",
@@ -721,6 +840,7 @@ impl From<MessageState> for LotState {
             OutPreparing => LotState::MsgOutPreparing,
             OutDraft => LotState::MsgOutDraft,
             OutPending => LotState::MsgOutPending,
+            OutScheduled => LotState::MsgOutScheduled,
             OutFailed => LotState::MsgOutFailed,
             OutDelivered => LotState::MsgOutDelivered,
             OutMdnRcvd => LotState::MsgOutMdnRcvd,
@@ -731,9 +851,10 @@ impl From<MessageState> for LotState {
 impl MessageState {
     pub fn can_fail(self) -> bool {
         match self {
-            MessageState::OutPreparing | MessageState::OutPending | MessageState::OutDelivered => {
-                true
-            }
+            MessageState::OutPreparing
+            | MessageState::OutPending
+            | MessageState::OutScheduled
+            | MessageState::OutDelivered => true,
             _ => false,
         }
     }
@@ -760,7 +881,10 @@ impl Lot {
                 self.text1 = Some(context.stock_str(StockMessage::SelfMsg).to_owned().into());
                 self.text1_meaning = Meaning::Text1Self;
             }
-        } else if chat.typ == Chattype::Group || chat.typ == Chattype::VerifiedGroup {
+        } else if chat.typ == Chattype::Group
+            || chat.typ == Chattype::VerifiedGroup
+            || chat.typ == Chattype::Mailinglist
+        {
             if msg.is_info() || contact.is_none() {
                 self.text1 = None;
                 self.text1_meaning = Meaning::None;
@@ -841,27 +965,32 @@ pub fn get_msg_info(context: &Context, msg_id: MsgId) -> String {
         return ret;
     }
 
-    if let Ok(rows) = context.sql.query_map(
-        "SELECT contact_id, timestamp_sent FROM msgs_mdns WHERE msg_id=?;",
-        params![msg_id],
-        |row| {
-            let contact_id: i32 = row.get(0)?;
-            let ts: i64 = row.get(1)?;
-            Ok((contact_id, ts))
-        },
-        |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
-    ) {
-        for (contact_id, ts) in rows {
-            let fts = dc_timestamp_to_str(ts);
-            ret += &format!("Read: {}", fts);
+    let read_receipts = get_read_receipts(context, msg_id);
+    if msg.from_id == DC_CONTACT_ID_SELF {
+        if let Ok(chat) = Chat::load_from_db(context, msg.chat_id) {
+            if chat.typ == Chattype::Group || chat.typ == Chattype::VerifiedGroup {
+                let member_cnt = chat::get_chat_contacts(context, chat.id)
+                    .iter()
+                    .filter(|contact_id| **contact_id != DC_CONTACT_ID_SELF)
+                    .count();
+                ret += &format!(
+                    "Read by {} of {} members\n",
+                    read_receipts.len(),
+                    member_cnt
+                );
+            }
+        }
+    }
+    for (contact_id, ts) in read_receipts {
+        let fts = dc_timestamp_to_str(ts);
+        ret += &format!("Read: {}", fts);
 
-            let name = Contact::load_from_db(context, contact_id as u32)
-                .map(|contact| contact.get_name_n_addr())
-                .unwrap_or_default();
+        let name = Contact::load_from_db(context, contact_id)
+            .map(|contact| contact.get_name_n_addr())
+            .unwrap_or_default();
 
-            ret += &format!(" by {}", name);
-            ret += "\n";
-        }
+        ret += &format!(" by {}", name);
+        ret += "\n";
     }
 
     ret += &format!("State: {}", msg.state);
@@ -881,6 +1010,13 @@ pub fn get_msg_info(context: &Context, msg_id: MsgId) -> String {
     }
 
     ret += "\n";
+    if let Some(retry) = msg.get_send_retry(context) {
+        ret += &format!(
+            "Tries: {}, next try: {}\n",
+            retry.tries,
+            dc_timestamp_to_str(retry.next_try)
+        );
+    }
     if let Some(err) = msg.param.get(Param::Error) {
         ret += &format!("Error: {}", err)
     }
@@ -905,6 +1041,15 @@ pub fn get_msg_info(context: &Context, msg_id: MsgId) -> String {
     if duration != 0 {
         ret += &format!("Duration: {} ms\n", duration,);
     }
+    if let EphemeralTimer::Enabled { duration } = msg.ephemeral_timer {
+        ret += &format!("Ephemeral timer: {} s\n", duration);
+    }
+    if msg.ephemeral_timestamp != 0 {
+        ret += &format!(
+            "Expires: {}\n",
+            dc_timestamp_to_str(msg.ephemeral_timestamp)
+        );
+    }
     if !rawtxt.is_empty() {
         ret += &format!("\n{}\n", rawtxt);
     }
@@ -977,6 +1122,81 @@ pub fn delete_msgs(context: &Context, msg_ids: &[MsgId]) {
     };
 }
 
+/// Replaces the text of a message and marks the message as edited.
+pub(crate) fn set_edited_text(
+    context: &Context,
+    msg: &mut Message,
+    new_text: &str,
+) -> Result<(), Error> {
+    msg.text = Some(new_text.to_string());
+    msg.param.set_int(Param::IsEdited, 1);
+    context.sql.execute(
+        "UPDATE msgs SET txt=?, param=? WHERE id=?;",
+        params![new_text, msg.param.to_string(), msg.id],
+    )?;
+    context.call_cb(Event::MsgsChanged {
+        chat_id: msg.chat_id,
+        msg_id: msg.id,
+    });
+    Ok(())
+}
+
+/// Applies an edit received from `from_id` to the message with the Message-ID `rfc724_mid`.
+///
+/// Only the sender of a message can edit it.
+pub(crate) fn apply_edit(
+    context: &Context,
+    rfc724_mid: &str,
+    from_id: u32,
+    new_text: &str,
+) -> Result<(), Error> {
+    let (_, _, msg_id) = rfc724_mid_exists(context, rfc724_mid)?;
+    let mut msg = Message::load_from_db(context, msg_id)?;
+    ensure!(
+        msg.from_id == from_id,
+        "Contact {} cannot edit message {} of contact {}",
+        from_id,
+        msg_id,
+        msg.from_id
+    );
+    ensure!(!msg.chat_id.is_special(), "Message {} is deleted", msg_id);
+    set_edited_text(context, &mut msg, new_text)
+}
+
+/// Applies a request of `from_id` to delete the messages with the given Message-IDs.
+///
+/// Only messages sent by `from_id` are deleted, unknown messages are ignored.
+pub(crate) fn apply_delete_request(
+    context: &Context,
+    rfc724_mids: &[String],
+    from_id: u32,
+) -> Result<(), Error> {
+    let mut msg_ids = Vec::new();
+    for rfc724_mid in rfc724_mids {
+        if let Ok((_, _, msg_id)) = rfc724_mid_exists(context, rfc724_mid) {
+            let msg = Message::load_from_db(context, msg_id)?;
+            if msg.from_id != from_id {
+                warn!(
+                    context,
+                    "Contact {} cannot delete message {} of contact {}.",
+                    from_id,
+                    msg_id,
+                    msg.from_id
+                );
+            } else if !msg.chat_id.is_special() {
+                msg_ids.push(msg_id);
+            }
+        } else {
+            info!(
+                context,
+                "Ignoring deletion of unknown message {}.", rfc724_mid
+            );
+        }
+    }
+    delete_msgs(context, &msg_ids);
+    Ok(())
+}
+
 fn update_msg_chat_id(context: &Context, msg_id: MsgId, chat_id: ChatId) -> bool {
     sql::execute(
         context,
@@ -1036,6 +1256,7 @@ pub fn markseen_msgs(context: &Context, msg_ids: &[MsgId]) -> bool {
         return false;
     }
     let mut send_event = false;
+    let mut seen_ids = Vec::new();
     let msgs = msgs.unwrap_or_default();
 
     for (id, curr_state, curr_blocked) in msgs.into_iter() {
@@ -1043,6 +1264,7 @@ pub fn markseen_msgs(context: &Context, msg_ids: &[MsgId]) -> bool {
             if curr_state == MessageState::InFresh || curr_state == MessageState::InNoticed {
                 update_msg_state(context, *id, MessageState::InSeen);
                 info!(context, "Seen message {}.", id);
+                seen_ids.push(*id);
 
                 job_add(
                     context,
@@ -1059,6 +1281,12 @@ pub fn markseen_msgs(context: &Context, msg_ids: &[MsgId]) -> bool {
         }
     }
 
+    if !seen_ids.is_empty() {
+        if let Err(err) = start_ephemeral_timers(context, &seen_ids) {
+            warn!(context, "failed to start ephemeral timers: {}", err);
+        }
+    }
+
     if send_event {
         context.call_cb(Event::MsgsChanged {
             chat_id: ChatId::new(0),
@@ -1069,6 +1297,264 @@ pub fn markseen_msgs(context: &Context, msg_ids: &[MsgId]) -> bool {
     true
 }
 
+/// Marks messages seen by another mail client as seen.
+///
+/// Unlike [markseen_msgs], no jobs are added
+/// as the messages are already seen on the server.
+pub(crate) fn markseen_msgs_from_server(context: &Context, msg_ids: &[MsgId]) {
+    let mut seen_ids = Vec::new();
+    for msg_id in msg_ids.iter() {
+        match context.sql.execute(
+            "UPDATE msgs SET state=? WHERE id=? AND (state=? OR state=?);",
+            params![
+                MessageState::InSeen,
+                *msg_id,
+                MessageState::InFresh,
+                MessageState::InNoticed
+            ],
+        ) {
+            Ok(0) => {}
+            Ok(_) => seen_ids.push(*msg_id),
+            Err(err) => warn!(context, "failed to mark {} as seen: {}", msg_id, err),
+        }
+    }
+
+    if !seen_ids.is_empty() {
+        info!(
+            context,
+            "{} messages seen by another client.",
+            seen_ids.len()
+        );
+        if let Err(err) = start_ephemeral_timers(context, &seen_ids) {
+            warn!(context, "failed to start ephemeral timers: {}", err);
+        }
+        context.call_cb(Event::MsgsChanged {
+            chat_id: ChatId::new(0),
+            msg_id: MsgId::new(0),
+        });
+    }
+}
+
+/// Moves messages deleted on the server by another mail client to the trash.
+///
+/// Unlike [delete_msgs], the deletion from the server is not scheduled.
+pub(crate) fn delete_msgs_from_server(context: &Context, msg_ids: &[MsgId]) {
+    for msg_id in msg_ids.iter() {
+        if let Ok(msg) = Message::load_from_db(context, *msg_id) {
+            if msg.location_id > 0 {
+                delete_poi_location(context, msg.location_id);
+            }
+        }
+        update_msg_chat_id(context, *msg_id, ChatId::new(DC_CHAT_ID_TRASH));
+        sql::execute(
+            context,
+            &context.sql,
+            "UPDATE msgs SET server_folder='', server_uid=0 WHERE id=?;",
+            params![*msg_id],
+        )
+        .ok();
+    }
+
+    if !msg_ids.is_empty() {
+        info!(
+            context,
+            "{} messages deleted by another client.",
+            msg_ids.len()
+        );
+        context.call_cb(Event::MsgsChanged {
+            chat_id: ChatId::new(0),
+            msg_id: MsgId::new(0),
+        });
+        job_kill_action(context, Action::Housekeeping);
+        job_add(context, Action::Housekeeping, 0, Params::new(), 10);
+    }
+}
+
+/// Starts the ephemeral timer of the given messages.
+///
+/// Messages without ephemeral timer and messages
+/// whose timer is already running are not affected.
+pub(crate) fn start_ephemeral_timers(context: &Context, msg_ids: &[MsgId]) -> sql::Result<()> {
+    let now = time();
+    context.sql.prepare(
+        "UPDATE msgs SET ephemeral_timestamp=?+ephemeral_timer \
+         WHERE id=? AND ephemeral_timer>0 AND ephemeral_timestamp=0;",
+        |mut stmt, _| {
+            for msg_id in msg_ids.iter() {
+                stmt.execute(params![now, *msg_id])?;
+            }
+            Ok(())
+        },
+    )?;
+    schedule_ephemeral_task(context);
+    Ok(())
+}
+
+/// Moves messages with expired ephemeral timer to the trash, removes their content
+/// and schedules their deletion from the server.
+///
+/// Returns true if any message was deleted.
+pub(crate) fn delete_expired_messages(context: &Context) -> Result<bool, Error> {
+    let msg_ids = context.sql.query_map(
+        "SELECT id FROM msgs \
+         WHERE ephemeral_timestamp!=0 AND ephemeral_timestamp<=? AND chat_id!=?;",
+        params![time(), DC_CHAT_ID_TRASH],
+        |row| row.get::<_, MsgId>(0),
+        |ids| ids.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+    )?;
+
+    for msg_id in msg_ids.iter() {
+        info!(context, "Ephemeral timer of message {} expired.", msg_id);
+        trash_msg_content(context, *msg_id)?;
+        job_add(
+            context,
+            Action::DeleteMsgOnImap,
+            msg_id.to_u32() as i32,
+            Params::new(),
+            0,
+        );
+    }
+    if !msg_ids.is_empty() {
+        context.call_cb(Event::MsgsChanged {
+            chat_id: ChatId::new(0),
+            msg_id: MsgId::new(0),
+        });
+    }
+
+    schedule_ephemeral_task(context);
+    Ok(!msg_ids.is_empty())
+}
+
+/// Moves a message to the trash and removes its content.
+///
+/// The files of the message are deleted by the next housekeeping
+/// as they are not referenced anymore, the search index is updated by a trigger.
+/// The Message-ID and the location on the server are kept
+/// so that the message can still be deleted from the server.
+fn trash_msg_content(context: &Context, msg_id: MsgId) -> Result<(), Error> {
+    if let Ok(msg) = Message::load_from_db(context, msg_id) {
+        if msg.location_id > 0 {
+            delete_poi_location(context, msg.location_id);
+        }
+    }
+    context.sql.execute(
+        "UPDATE msgs SET chat_id=?, txt='', txt_raw='', param='', mime_headers='' WHERE id=?;",
+        params![DC_CHAT_ID_TRASH, msg_id],
+    )?;
+    Ok(())
+}
+
+/// Schedules the deletion of messages older than [Config::DeleteServerAfter]
+/// from the server, the messages are kept on the device.
+///
+/// Starred messages and messages in the device chat are not deleted.
+pub(crate) fn schedule_deletion_on_server(context: &Context) -> Result<(), Error> {
+    let delete_server_after = i64::from(context.get_config_int(Config::DeleteServerAfter));
+    if delete_server_after <= 0 {
+        return Ok(());
+    }
+
+    // one job per Message-ID, the parts of a message share the server UID,
+    // so a message is kept if any of its parts is starred
+    let msg_ids = context.sql.query_map(
+        "SELECT MIN(id) FROM msgs \
+         WHERE timestamp<? AND server_uid>0 \
+         AND rfc724_mid NOT IN (SELECT rfc724_mid FROM msgs WHERE starred=1) \
+         AND chat_id NOT IN (SELECT chat_id FROM chats_contacts WHERE contact_id=?) \
+         AND id NOT IN (SELECT foreign_id FROM jobs WHERE action=?) \
+         GROUP BY rfc724_mid;",
+        params![
+            time() - delete_server_after,
+            DC_CONTACT_ID_DEVICE,
+            Action::DeleteMsgOnImap
+        ],
+        |row| row.get::<_, MsgId>(0),
+        |ids| ids.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+    )?;
+
+    if !msg_ids.is_empty() {
+        info!(
+            context,
+            "Deleting {} messages from the server.",
+            msg_ids.len()
+        );
+    }
+    for msg_id in msg_ids.iter() {
+        let mut param = Params::new();
+        param.set_int(Param::KeepOnDevice, 1);
+        job_add(
+            context,
+            Action::DeleteMsgOnImap,
+            msg_id.to_u32() as i32,
+            param,
+            0,
+        );
+    }
+    Ok(())
+}
+
+/// Deletes messages older than [Config::DeleteDeviceAfter] from the device.
+///
+/// The messages are moved to the trash and their content is removed,
+/// the files are removed by the housekeeping afterwards.
+/// Starred messages and messages in the device chat are not deleted.
+///
+/// Returns true if any message was deleted.
+pub(crate) fn prune_device_msgs(context: &Context) -> Result<bool, Error> {
+    let delete_device_after = i64::from(context.get_config_int(Config::DeleteDeviceAfter));
+    if delete_device_after <= 0 {
+        return Ok(false);
+    }
+
+    let msg_ids = context.sql.query_map(
+        "SELECT id FROM msgs \
+         WHERE timestamp<? AND starred=0 AND chat_id!=? \
+         AND (chat_id>? OR chat_id=?) \
+         AND chat_id NOT IN (SELECT chat_id FROM chats_contacts WHERE contact_id=?);",
+        params![
+            time() - delete_device_after,
+            DC_CHAT_ID_TRASH,
+            DC_CHAT_ID_LAST_SPECIAL,
+            DC_CHAT_ID_DEADDROP,
+            DC_CONTACT_ID_DEVICE
+        ],
+        |row| row.get::<_, MsgId>(0),
+        |ids| ids.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+    )?;
+
+    for msg_id in msg_ids.iter() {
+        trash_msg_content(context, *msg_id)?;
+    }
+    if !msg_ids.is_empty() {
+        info!(
+            context,
+            "Deleted {} old messages from the device.",
+            msg_ids.len()
+        );
+        context.call_cb(Event::MsgsChanged {
+            chat_id: ChatId::new(0),
+            msg_id: MsgId::new(0),
+        });
+    }
+    Ok(!msg_ids.is_empty())
+}
+
+/// Schedules a job deleting the message that expires next.
+pub(crate) fn schedule_ephemeral_task(context: &Context) {
+    let next_expiration: Option<i64> = context.sql.query_get_value(
+        context,
+        "SELECT MIN(ephemeral_timestamp) FROM msgs \
+         WHERE ephemeral_timestamp!=0 AND chat_id!=?;",
+        params![DC_CHAT_ID_TRASH],
+    );
+
+    job_kill_action(context, Action::DeleteExpiredMsgs);
+    if let Some(next_expiration) = next_expiration {
+        let delay = std::cmp::max(next_expiration - time(), 0);
+        job_add(context, Action::DeleteExpiredMsgs, 0, Params::new(), delay);
+    }
+}
+
 pub fn update_msg_state(context: &Context, msg_id: MsgId, state: MessageState) -> bool {
     sql::execute(
         context,
@@ -1213,7 +1699,26 @@ pub fn set_msg_failed(context: &Context, msg_id: MsgId, error: Option<impl AsRef
     }
 }
 
-/// returns Some if an event should be send
+/// Stores the error of a failed try to send the message,
+/// the message stays pending and is tried again later.
+pub(crate) fn set_msg_error(context: &Context, msg_id: MsgId, error: impl AsRef<str>) {
+    if let Ok(mut msg) = Message::load_from_db(context, msg_id) {
+        msg.param.set(Param::Error, error.as_ref());
+        if msg.save_param_to_disk(context) {
+            context.call_cb(Event::MsgsChanged {
+                chat_id: msg.chat_id,
+                msg_id,
+            });
+        }
+    }
+}
+
+/// Records a read receipt of `from_id` for the own message with the Message-ID `rfc724_mid`.
+///
+/// Returns the chat and the message if the receipt is new,
+/// [Event::MsgRead] should be emitted then.
+/// The state of the message changes to [MessageState::OutMdnRcvd]
+/// if it was read by enough members of the chat.
 pub fn mdn_from_ext(
     context: &Context,
     from_id: u32,
@@ -1250,33 +1755,35 @@ pub fn mdn_from_ext(
     }
 
     if let Ok((msg_id, chat_id, chat_type, msg_state)) = res {
-        let mut read_by_all = false;
-
-        // if already marked as MDNS_RCVD msgstate_can_fail() returns false.
-        // however, it is important, that ret_msg_id is set above as this
-        // will allow the caller eg. to move the message away
-        if msg_state.can_fail() {
-            let mdn_already_in_table = context
-                .sql
-                .exists(
-                    "SELECT contact_id FROM msgs_mdns WHERE msg_id=? AND contact_id=?;",
-                    params![msg_id, from_id as i32,],
-                )
-                .unwrap_or_default();
+        // receipts are recorded also after the message is marked as read,
+        // so that all members that read the message are known.
+        if !msg_state.can_fail() && msg_state != MessageState::OutMdnRcvd {
+            return None;
+        }
 
-            if !mdn_already_in_table {
-                context.sql.execute(
-                    "INSERT INTO msgs_mdns (msg_id, contact_id, timestamp_sent) VALUES (?, ?, ?);",
-                    params![msg_id, from_id as i32, timestamp_sent],
-                ).unwrap_or_default(); // TODO: better error handling
-            }
+        let mdn_already_in_table = context
+            .sql
+            .exists(
+                "SELECT contact_id FROM msgs_mdns WHERE msg_id=? AND contact_id=?;",
+                params![msg_id, from_id as i32,],
+            )
+            .unwrap_or_default();
+        if mdn_already_in_table {
+            return None;
+        }
+        if let Err(err) = context.sql.execute(
+            "INSERT INTO msgs_mdns (msg_id, contact_id, timestamp_sent) VALUES (?, ?, ?);",
+            params![msg_id, from_id as i32, timestamp_sent],
+        ) {
+            warn!(context, "Failed to record MDN: {}", err);
+            return None;
+        }
 
+        if msg_state != MessageState::OutMdnRcvd {
             // Normal chat? that's quite easy.
-            if chat_type == Chattype::Single {
-                update_msg_state(context, msg_id, MessageState::OutMdnRcvd);
-                read_by_all = true;
+            let read_by_all = if chat_type == Chattype::Single {
+                true
             } else {
-                // send event about new state
                 let ist_cnt = context
                     .sql
                     .query_get_value::<_, isize>(
@@ -1299,21 +1806,31 @@ pub fn mdn_from_ext(
                  */
                 // for rounding, SELF is already included!
                 let soll_cnt = (chat::get_chat_contact_cnt(context, chat_id) + 1) / 2;
-                if ist_cnt >= soll_cnt {
-                    update_msg_state(context, msg_id, MessageState::OutMdnRcvd);
-                    read_by_all = true;
-                } // else wait for more receipts
-            }
+                ist_cnt >= soll_cnt
+            };
+            if read_by_all {
+                update_msg_state(context, msg_id, MessageState::OutMdnRcvd);
+            } // else wait for more receipts
         }
-        return if read_by_all {
-            Some((chat_id, msg_id))
-        } else {
-            None
-        };
+        return Some((chat_id, msg_id));
     }
     None
 }
 
+/// Returns the contacts that sent a read receipt for the message
+/// together with the time they read the message, the earliest first.
+pub fn get_read_receipts(context: &Context, msg_id: MsgId) -> Vec<(u32, i64)> {
+    context
+        .sql
+        .query_map(
+            "SELECT contact_id, timestamp_sent FROM msgs_mdns WHERE msg_id=? ORDER BY timestamp_sent, contact_id;",
+            params![msg_id],
+            |row| Ok((row.get::<_, u32>(0)?, row.get::<_, i64>(1)?)),
+            |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+        )
+        .unwrap_or_default()
+}
+
 /// The number of messages assigned to real chat (!=deaddrop, !=trash)
 pub fn get_real_msg_cnt(context: &Context) -> i32 {
     match context.sql.query_row(
@@ -1443,6 +1960,235 @@ mod tests {
         assert_eq!(_msg2.get_filemime(), None);
     }
 
+    #[test]
+    pub fn test_quote() {
+        use crate::config::Config;
+
+        let d = test::dummy_context();
+        let ctx = &d.ctx;
+
+        let contact =
+            Contact::create(ctx, "", "dest@example.com").expect("failed to create contact");
+        ctx.set_config(Config::ConfiguredAddr, Some("self@example.com"))
+            .unwrap();
+        let chat = chat::create_by_contact_id(ctx, contact).unwrap();
+
+        let mut msg = Message::new(Viewtype::Text);
+        msg.set_text(Some("Quoted message".to_string()));
+        let msg_id = chat::prepare_msg(ctx, chat, &mut msg).unwrap();
+        let quoted = Message::load_from_db(ctx, msg_id).unwrap();
+
+        let mut msg = Message::new(Viewtype::Text);
+        msg.set_text(Some("Reply".to_string()));
+        msg.set_quote(ctx, &quoted).unwrap();
+        assert_eq!(msg.quoted_text().unwrap(), "Quoted message");
+        let msg_id = chat::prepare_msg(ctx, chat, &mut msg).unwrap();
+
+        let reply = Message::load_from_db(ctx, msg_id).unwrap();
+        assert_eq!(reply.quoted_text().unwrap(), "Quoted message");
+        assert_eq!(reply.in_reply_to.as_ref(), Some(&quoted.rfc724_mid));
+        let quoted_msg = reply.get_quoted_msg(ctx).unwrap().unwrap();
+        assert_eq!(quoted_msg.id, quoted.id);
+    }
+
+    #[test]
+    fn test_read_receipts() {
+        use crate::config::Config;
+
+        let d = test::dummy_context();
+        let ctx = &d.ctx;
+        ctx.set_config(Config::ConfiguredAddr, Some("self@example.com"))
+            .unwrap();
+        let bob = Contact::create(ctx, "Bob", "bob@example.net").unwrap();
+        let claire = Contact::create(ctx, "Claire", "claire@example.net").unwrap();
+        let dave = Contact::create(ctx, "Dave", "dave@example.net").unwrap();
+        let chat_id = chat::create_group_chat(ctx, VerifiedStatus::Unverified, "foo").unwrap();
+        for contact_id in &[bob, claire, dave] {
+            assert!(chat::add_contact_to_chat(ctx, chat_id, *contact_id));
+        }
+        let mut msg = Message::new(Viewtype::Text);
+        msg.set_text(Some("hi".to_string()));
+        let msg_id = chat::prepare_msg(ctx, chat_id, &mut msg).unwrap();
+        let rfc724_mid = Message::load_from_db(ctx, msg_id).unwrap().rfc724_mid;
+
+        assert_eq!(
+            mdn_from_ext(ctx, bob, &rfc724_mid, 1000),
+            Some((chat_id, msg_id))
+        );
+        assert_eq!(mdn_from_ext(ctx, bob, &rfc724_mid, 1001), None);
+        let msg = Message::load_from_db(ctx, msg_id).unwrap();
+        assert_ne!(msg.get_state(), MessageState::OutMdnRcvd);
+
+        assert_eq!(
+            mdn_from_ext(ctx, claire, &rfc724_mid, 900),
+            Some((chat_id, msg_id))
+        );
+        let msg = Message::load_from_db(ctx, msg_id).unwrap();
+        assert_eq!(msg.get_state(), MessageState::OutMdnRcvd);
+
+        // receipts are recorded also after the message is marked as read
+        assert_eq!(
+            mdn_from_ext(ctx, dave, &rfc724_mid, 1100),
+            Some((chat_id, msg_id))
+        );
+        assert_eq!(
+            get_read_receipts(ctx, msg_id),
+            vec![(claire, 900), (bob, 1000), (dave, 1100)]
+        );
+        assert!(get_msg_info(ctx, msg_id).contains("Read by 3 of 3 members"));
+    }
+
+    fn receive_chat_msg(ctx: &Context, server_uid: u32, msgid: &str) -> MsgId {
+        let mail = format!(
+            "From: Bob <bob@example.net>\n\
+             To: Alice <alice@example.com>\n\
+             Subject: Chat: hi\n\
+             Message-ID: <{}>\n\
+             Chat-Version: 1.0\n\
+             Date: Sun, 22 Mar 2020 22:37:57 +0000\n\
+             \n\
+             hi\n",
+            msgid
+        );
+        crate::dc_receive_imf::dc_receive_imf(ctx, mail.as_bytes(), "INBOX", server_uid, false)
+            .unwrap();
+        rfc724_mid_exists(ctx, msgid).unwrap().2
+    }
+
+    #[test]
+    fn test_sync_from_server() {
+        let d = test::dummy_context();
+        let ctx = &d.ctx;
+        test::configure_alice_keypair(ctx);
+
+        let seen_id = receive_chat_msg(ctx, 1, "1@example.net");
+        let deleted_id = receive_chat_msg(ctx, 2, "2@example.net");
+        assert_eq!(
+            Message::load_from_db(ctx, seen_id).unwrap().get_state(),
+            MessageState::InFresh
+        );
+
+        markseen_msgs_from_server(ctx, &[seen_id]);
+        assert_eq!(
+            Message::load_from_db(ctx, seen_id).unwrap().get_state(),
+            MessageState::InSeen
+        );
+
+        delete_msgs_from_server(ctx, &[deleted_id]);
+        let msg = Message::load_from_db(ctx, deleted_id).unwrap();
+        assert!(msg.get_chat_id().is_trash());
+        assert_eq!(msg.server_uid, 0);
+        assert!(!job_action_exists(ctx, Action::DeleteMsgOnImap));
+    }
+
+    #[test]
+    fn test_delete_old_msgs() {
+        let d = test::dummy_context();
+        let ctx = &d.ctx;
+        test::configure_alice_keypair(ctx);
+        ctx.set_config(Config::SaveMimeHeaders, Some("1")).unwrap();
+
+        let old_id = receive_chat_msg(ctx, 1, "1@example.net");
+        let starred_id = receive_chat_msg(ctx, 2, "2@example.net");
+        star_msgs(ctx, &[starred_id], true);
+        // an unstarred part of the starred message
+        ctx.sql
+            .execute(
+                "INSERT INTO msgs (rfc724_mid, chat_id, from_id, timestamp, server_folder, server_uid) \
+                 SELECT rfc724_mid, chat_id, from_id, timestamp, server_folder, server_uid \
+                 FROM msgs WHERE id=?;",
+                params![starred_id],
+            )
+            .unwrap();
+        assert!(!get_mime_headers(ctx, old_id).unwrap_or_default().is_empty());
+        let jobs_cnt = || {
+            ctx.sql
+                .query_get_value::<_, i32>(
+                    ctx,
+                    "SELECT COUNT(*) FROM jobs WHERE action=?;",
+                    params![Action::DeleteMsgOnImap],
+                )
+                .unwrap_or_default()
+        };
+
+        schedule_deletion_on_server(ctx).unwrap();
+        assert!(!prune_device_msgs(ctx).unwrap());
+        assert_eq!(jobs_cnt(), 0);
+
+        ctx.set_config(Config::DeleteServerAfter, Some("86400"))
+            .unwrap();
+        schedule_deletion_on_server(ctx).unwrap();
+        assert_eq!(jobs_cnt(), 1);
+        schedule_deletion_on_server(ctx).unwrap();
+        assert_eq!(jobs_cnt(), 1);
+
+        ctx.set_config(Config::DeleteDeviceAfter, Some("86400"))
+            .unwrap();
+        assert!(prune_device_msgs(ctx).unwrap());
+        let msg = Message::load_from_db(ctx, old_id).unwrap();
+        assert!(msg.get_chat_id().is_trash());
+        assert_eq!(msg.get_text(), Some("".to_string()));
+        assert!(get_mime_headers(ctx, old_id).unwrap_or_default().is_empty());
+        let msg = Message::load_from_db(ctx, starred_id).unwrap();
+        assert!(!msg.get_chat_id().is_trash());
+        assert!(!prune_device_msgs(ctx).unwrap());
+    }
+
+    #[test]
+    fn test_ephemeral_timer() {
+        let d = test::dummy_context();
+        let ctx = &d.ctx;
+        test::configure_alice_keypair(ctx);
+        ctx.set_config(Config::SaveMimeHeaders, Some("1")).unwrap();
+
+        let mail = "From: Bob <bob@example.net>\n\
+                    To: Alice <alice@example.com>\n\
+                    Subject: Chat: hi\n\
+                    Message-ID: <1@example.net>\n\
+                    Chat-Version: 1.0\n\
+                    Ephemeral-Timer: 60\n\
+                    Date: Sun, 22 Mar 2020 22:37:57 +0000\n\
+                    \n\
+                    secret\n";
+        crate::dc_receive_imf::dc_receive_imf(ctx, mail.as_bytes(), "INBOX", 1, false).unwrap();
+        let msg_id = rfc724_mid_exists(ctx, "1@example.net").unwrap().2;
+        let msg = Message::load_from_db(ctx, msg_id).unwrap();
+        let chat_id = msg.get_chat_id();
+        assert_eq!(
+            chat_id.get_ephemeral_timer(ctx).unwrap(),
+            EphemeralTimer::Enabled { duration: 60 }
+        );
+
+        // the timer of unseen incoming messages does not run yet
+        assert!(!delete_expired_messages(ctx).unwrap());
+        ctx.sql
+            .execute(
+                "UPDATE msgs SET ephemeral_timestamp=? WHERE id=?;",
+                params![time() - 1, msg_id],
+            )
+            .unwrap();
+        let fts_cnt = || {
+            ctx.sql
+                .query_get_value::<_, i32>(
+                    ctx,
+                    "SELECT COUNT(*) FROM msgs_fts WHERE msgs_fts MATCH 'secret';",
+                    params![],
+                )
+                .unwrap_or_default()
+        };
+        assert_eq!(fts_cnt(), 1);
+        assert!(!get_mime_headers(ctx, msg_id).unwrap_or_default().is_empty());
+
+        // the content of expired messages is removed, not only hidden
+        assert!(delete_expired_messages(ctx).unwrap());
+        let msg = Message::load_from_db(ctx, msg_id).unwrap();
+        assert!(msg.get_chat_id().is_trash());
+        assert_eq!(msg.get_text(), Some("".to_string()));
+        assert_eq!(get_mime_headers(ctx, msg_id).unwrap_or_default(), "");
+        assert_eq!(fts_cnt(), 0);
+        assert!(job_action_exists(ctx, Action::DeleteMsgOnImap));
+    }
+
     #[test]
     pub fn test_get_summarytext_by_raw() {
         let d = test::dummy_context();
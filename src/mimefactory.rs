@@ -2,7 +2,7 @@ use chrono::TimeZone;
 use lettre_email::{mime, Address, Header, MimeMultipartType, PartBuilder};
 
 use crate::blob::BlobObject;
-use crate::chat::{self, Chat};
+use crate::chat::{self, Chat, EphemeralTimer};
 use crate::config::Config;
 use crate::constants::*;
 use crate::contact::*;
@@ -81,6 +81,11 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
 
         if chat.is_self_talk() {
             recipients.push((from_displayname.to_string(), from_addr.to_string()));
+        } else if chat.is_mailing_list() {
+            // mailing lists are sent to the list address only and no read receipts are requested
+            if let Some(list_post) = chat.get_mailinglist_addr() {
+                recipients.push(("".to_string(), list_post.to_string()));
+            }
         } else {
             context.sql.query_map(
                 "SELECT c.authname, c.addr  \
@@ -123,24 +128,33 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
             }
             if command != SystemMessage::AutocryptSetupMessage
                 && command != SystemMessage::SecurejoinMessage
+                && !msg.param.exists(Param::Reaction)
+                && !msg.param.exists(Param::TextEditFor)
+                && !msg.param.exists(Param::DeleteRequestFor)
+                && !msg.param.exists(Param::Typing)
                 && context.get_config_bool(Config::MdnsEnabled)
             {
                 req_mdn = true;
             }
         }
-        let (in_reply_to, references) = context.sql.query_row(
-            "SELECT mime_in_reply_to, mime_references FROM msgs WHERE id=?",
-            params![msg.id],
-            |row| {
-                let in_reply_to: String = row.get(0)?;
-                let references: String = row.get(1)?;
-
-                Ok((
-                    render_rfc724_mid_list(&in_reply_to),
-                    render_rfc724_mid_list(&references),
-                ))
-            },
-        )?;
+        // messages not saved to the database, eg. typing notifications, refer to no other messages
+        let (in_reply_to, references) = if msg.id.is_unset() {
+            (String::default(), String::default())
+        } else {
+            context.sql.query_row(
+                "SELECT mime_in_reply_to, mime_references FROM msgs WHERE id=?",
+                params![msg.id],
+                |row| {
+                    let in_reply_to: String = row.get(0)?;
+                    let references: String = row.get(1)?;
+
+                    Ok((
+                        render_rfc724_mid_list(&in_reply_to),
+                        render_rfc724_mid_list(&references),
+                    ))
+                },
+            )?
+        };
 
         let factory = MimeFactory {
             from_addr,
@@ -712,6 +726,12 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
                     "location-streaming-enabled".into(),
                 ));
             }
+            SystemMessage::EphemeralTimerChanged => {
+                protected_headers.push(Header::new(
+                    "Chat-Content".into(),
+                    "ephemeral-timer-changed".into(),
+                ));
+            }
             SystemMessage::AutocryptSetupMessage => {
                 unprotected_headers
                     .push(Header::new("Autocrypt-Setup-Message".into(), "v1".into()));
@@ -759,6 +779,17 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
             _ => {}
         }
 
+        // the timer is also sent with ordinary messages,
+        // so that members who missed the change pick it up.
+        if self.msg.ephemeral_timer != EphemeralTimer::Disabled
+            || command == SystemMessage::EphemeralTimerChanged
+        {
+            protected_headers.push(Header::new(
+                "Ephemeral-Timer".into(),
+                self.msg.ephemeral_timer.to_u32().to_string(),
+            ));
+        }
+
         if let Some(grpimage) = grpimage {
             info!(self.context, "setting group image '{}'", grpimage);
             let mut meta = Message::default();
@@ -774,6 +805,29 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
             protected_headers.push(Header::new("Chat-Content".into(), "sticker".into()));
         }
 
+        if self.msg.param.exists(Param::Reaction) {
+            protected_headers.push(Header::new("Chat-Content".into(), "reaction".into()));
+        }
+
+        if let Some(typing) = self.msg.param.get_bool(Param::Typing) {
+            let value = if typing { "typing" } else { "typing-stopped" };
+            protected_headers.push(Header::new("Chat-Content".into(), value.into()));
+        }
+
+        if let Some(rfc724_mid) = self.msg.param.get(Param::TextEditFor) {
+            protected_headers.push(Header::new(
+                "Chat-Edit".into(),
+                render_rfc724_mid(rfc724_mid),
+            ));
+        }
+
+        if let Some(mid_list) = self.msg.param.get(Param::DeleteRequestFor) {
+            protected_headers.push(Header::new(
+                "Chat-Delete".into(),
+                render_rfc724_mid_list(mid_list),
+            ));
+        }
+
         if self.msg.viewtype == Viewtype::Voice
             || self.msg.viewtype == Viewtype::Audio
             || self.msg.viewtype == Viewtype::Video
@@ -814,10 +868,24 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
             }
         };
 
+        let quoted_text = self
+            .msg
+            .param
+            .get(Param::Quote)
+            .map(|quote| {
+                quote
+                    .split('\n')
+                    .map(|line| format!("> {}\r\n", line.trim_end()))
+                    .collect::<String>()
+                    + "\r\n"
+            })
+            .unwrap_or_default();
+
         let footer = &self.selfstatus;
         let message_text = format!(
-            "{}{}{}{}{}",
+            "{}{}{}{}{}{}",
             fwdhint.unwrap_or_default(),
+            quoted_text,
             &final_text,
             if !final_text.is_empty() && !footer.is_empty() {
                 "\r\n\r\n"
@@ -855,7 +923,10 @@ impl<'a, 'b> MimeFactory<'a, 'b> {
             parts.push(msg_kml_part);
         }
 
-        if location::is_sending_locations_to_chat(context, self.msg.chat_id) {
+        // typing notifications are kept small and do not carry locations
+        if !self.msg.param.exists(Param::Typing)
+            && location::is_sending_locations_to_chat(context, self.msg.chat_id)
+        {
             match self.get_location_kml_part() {
                 Ok(part) => parts.push(part),
                 Err(err) => {
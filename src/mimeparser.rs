@@ -7,8 +7,9 @@ use mailparse::{DispositionType, MailAddr, MailHeaderMap};
 use crate::aheader::Aheader;
 use crate::bail;
 use crate::blob::BlobObject;
+use crate::chat;
 use crate::config::Config;
-use crate::constants::Viewtype;
+use crate::constants::{Viewtype, DC_CONTACT_ID_SELF};
 use crate::contact::*;
 use crate::context::Context;
 use crate::dc_tools::*;
@@ -49,6 +50,9 @@ pub struct MimeMessage {
     pub(crate) user_avatar: Option<AvatarAction>,
     pub(crate) group_avatar: Option<AvatarAction>,
     pub(crate) reports: Vec<Report>,
+    pub(crate) delivery_reports: Vec<DeliveryReport>,
+    /// Standard footer of the text, for chat messages this is the status of the sender.
+    pub(crate) footer: Option<String>,
 }
 
 #[derive(Debug, PartialEq)]
@@ -69,6 +73,7 @@ pub enum SystemMessage {
     SecurejoinMessage = 7,
     LocationStreamingEnabled = 8,
     LocationOnly = 9,
+    EphemeralTimerChanged = 10,
 }
 
 impl Default for SystemMessage {
@@ -149,11 +154,13 @@ impl MimeMessage {
             gossipped_addr,
             is_forwarded: false,
             reports: Vec::new(),
+            delivery_reports: Vec::new(),
             is_system_message: SystemMessage::Unknown,
             location_kml: None,
             message_kml: None,
             user_avatar: None,
             group_avatar: None,
+            footer: None,
         };
         parser.parse_mime_recursive(context, &mail)?;
         parser.parse_headers(context)?;
@@ -182,11 +189,31 @@ impl MimeMessage {
         } else if let Some(value) = self.get(HeaderDef::ChatContent) {
             if value == "location-streaming-enabled" {
                 self.is_system_message = SystemMessage::LocationStreamingEnabled;
+            } else if value == "ephemeral-timer-changed" {
+                self.is_system_message = SystemMessage::EphemeralTimerChanged;
             }
         }
         Ok(())
     }
 
+    /// Returns `Some(true)` if the sender started typing, `Some(false)` if the sender stopped typing
+    /// and `None` if the message is no typing notification.
+    pub(crate) fn get_typing(&self) -> Option<bool> {
+        match self.get(HeaderDef::ChatContent).map(String::as_str) {
+            Some("typing") => Some(true),
+            Some("typing-stopped") => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the message is a reaction to another message.
+    pub(crate) fn is_reaction(&self) -> bool {
+        self.get(HeaderDef::ChatContent)
+            .map(|value| value == "reaction")
+            .unwrap_or_default()
+            && self.get(HeaderDef::InReplyTo).is_some()
+    }
+
     /// Parses avatar action headers.
     fn parse_avatar_headers(&mut self) {
         if let Some(header_value) = self.get(HeaderDef::ChatGroupAvatar).cloned() {
@@ -546,8 +573,18 @@ impl MimeMessage {
                                 self.reports.push(report);
                             }
                         } else {
-                            /* eg. `report-type=delivery-status`;
-                            maybe we should show them as a little error icon */
+                            if report_type == "delivery-status" {
+                                // a malformed report must not hide the explanation
+                                match self.process_delivery_status(context, mail) {
+                                    Ok(Some(report)) => self.delivery_reports.push(report),
+                                    Ok(None) => {}
+                                    Err(err) => warn!(
+                                        context,
+                                        "ignoring malformed delivery-status: {}", err
+                                    ),
+                                }
+                            }
+                            // the explanation of the mail server is shown as a normal message
                             if let Some(first) = mail.subparts.iter().next() {
                                 any_part_added = self.parse_mime_recursive(context, first)?;
                             }
@@ -609,17 +646,25 @@ impl MimeMessage {
                             }
                         };
 
-                        let (simplified_txt, is_forwarded) = if decoded_data.is_empty() {
-                            ("".into(), false)
-                        } else {
-                            let is_html = mime_type == mime::TEXT_HTML;
-                            let out = if is_html {
-                                dehtml(&decoded_data)
+                        let (simplified_txt, is_forwarded, quote, footer) =
+                            if decoded_data.is_empty() {
+                                ("".into(), false, None, None)
                             } else {
-                                decoded_data.clone()
+                                let is_html = mime_type == mime::TEXT_HTML;
+                                let out = if is_html {
+                                    dehtml(&decoded_data)
+                                } else {
+                                    decoded_data.clone()
+                                };
+                                // chat messages only quote the message they reply to
+                                let is_reply = self
+                                    .get(HeaderDef::InReplyTo)
+                                    .and_then(|value| parse_message_id(value).ok())
+                                    .map_or(false, |rfc724_mid| {
+                                        message::rfc724_mid_exists(context, &rfc724_mid).is_ok()
+                                    });
+                                simplify(out, self.has_chat_version(), is_reply)
                             };
-                            simplify(out, self.has_chat_version())
-                        };
 
                         if !simplified_txt.is_empty() {
                             let mut part = Part::default();
@@ -627,12 +672,18 @@ impl MimeMessage {
                             part.mimetype = Some(mime_type);
                             part.msg = simplified_txt;
                             part.msg_raw = Some(decoded_data);
+                            if let Some(quote) = quote {
+                                part.param.set(Param::Quote, quote);
+                            }
                             self.do_add_single_part(part);
                         }
 
                         if is_forwarded {
                             self.is_forwarded = true;
                         }
+                        if self.footer.is_none() {
+                            self.footer = footer;
+                        }
                     }
                     _ => {}
                 }
@@ -806,7 +857,118 @@ impl MimeMessage {
         Ok(None)
     }
 
-    /// Handle reports (only MDNs for now)
+    /// Parses a delivery status notification (DSN), see RFC 3464.
+    ///
+    /// Returns the failed deliveries of the original message,
+    /// `None` if the message was delivered or cannot be identified.
+    fn process_delivery_status(
+        &self,
+        context: &Context,
+        report: &mailparse::ParsedMail<'_>,
+    ) -> Result<Option<DeliveryReport>> {
+        // the per-message fields are followed by blocks of per-recipient fields,
+        // each separated by an empty line
+        let status_body = report.subparts[1].get_body_raw()?;
+        let (message_fields, mut offset) = mailparse::parse_headers(&status_body)?;
+
+        let mut failures = Vec::new();
+        let mut failed_recipients = Vec::new();
+        let mut permanent = false;
+        while offset < status_body.len() {
+            let (recipient_fields, len) = mailparse::parse_headers(&status_body[offset..])?;
+            if len == 0 {
+                break;
+            }
+            offset += len;
+
+            let action = recipient_fields
+                .get_header_value(HeaderDef::Action)
+                .ok()
+                .flatten()
+                .unwrap_or_default()
+                .trim()
+                .to_lowercase();
+            let status = recipient_fields
+                .get_header_value(HeaderDef::Status)
+                .ok()
+                .flatten()
+                .unwrap_or_default()
+                .trim()
+                .to_string();
+            let is_permanent = match action.as_str() {
+                // the server gave up, whatever the class of the status code is
+                "failed" => true,
+                "delayed" => false,
+                _ => continue,
+            };
+            permanent |= is_permanent;
+
+            let recipient = recipient_fields
+                .get_header_value(HeaderDef::FinalRecipient)
+                .ok()
+                .flatten()
+                .map(|v| strip_type_prefix(&v))
+                .unwrap_or_default();
+            failed_recipients.push(recipient.clone());
+            let diagnostic = recipient_fields
+                .get_header_value(HeaderDef::DiagnosticCode)
+                .ok()
+                .flatten()
+                .map(|v| strip_type_prefix(&v))
+                .filter(|v| !v.is_empty())
+                .unwrap_or(status);
+            failures.push(format!(
+                "{} to {}: {}",
+                if is_permanent {
+                    "Permanent delivery failure"
+                } else {
+                    "Temporary delivery problem"
+                },
+                recipient,
+                diagnostic
+            ));
+        }
+        if failures.is_empty() {
+            return Ok(None);
+        }
+
+        // the third part contains the original message or its header
+        let original_headers = match report.subparts.get(2) {
+            Some(original) => original.get_body_raw()?,
+            None => Vec::new(),
+        };
+        let (original_fields, _) = mailparse::parse_headers(&original_headers)?;
+        let rfc724_mid = original_fields
+            .get_header_value(HeaderDef::MessageId)
+            .ok()
+            .flatten()
+            .or_else(|| {
+                message_fields
+                    .get_header_value(HeaderDef::OriginalMessageId)
+                    .ok()
+                    .flatten()
+            })
+            .and_then(|v| parse_message_id(&v).ok());
+
+        match rfc724_mid {
+            Some(rfc724_mid) => Ok(Some(DeliveryReport {
+                rfc724_mid,
+                failed_recipients,
+                reason: failures.join("; "),
+                permanent,
+            })),
+            None => {
+                warn!(
+                    context,
+                    "ignoring delivery-status without original Message-Id: {}",
+                    failures.join("; ")
+                );
+                Ok(None)
+            }
+        }
+    }
+
+    /// Handle reports (MDNs and delivery status notifications)
     pub fn handle_reports(
         &self,
         context: &Context,
@@ -815,6 +977,21 @@ impl MimeMessage {
         server_folder: impl AsRef<str>,
         server_uid: u32,
     ) {
+        if !self.delivery_reports.is_empty() {
+            if self.has_chat_version() {
+                // delivery reports are sent by mail servers, not by chat clients
+                warn!(context, "ignoring delivery report sent by a chat client");
+            } else {
+                let from = match self.parse_first_addr(context, HeaderDef::From_) {
+                    Some(MailAddr::Single(info)) => info.addr,
+                    _ => String::new(),
+                };
+                for report in &self.delivery_reports {
+                    handle_delivery_report(context, report, &from);
+                }
+            }
+        }
+
         if self.reports.is_empty() {
             return;
         }
@@ -827,7 +1004,11 @@ impl MimeMessage {
                 if let Some((chat_id, msg_id)) =
                     message::mdn_from_ext(context, from_id, original_message_id, sent_timestamp)
                 {
-                    context.call_cb(Event::MsgRead { chat_id, msg_id });
+                    context.call_cb(Event::MsgRead {
+                        chat_id,
+                        msg_id,
+                        contact_id: from_id,
+                    });
                     mdn_recognized = true;
                 }
             }
@@ -845,6 +1026,98 @@ impl MimeMessage {
     }
 }
 
+/// Marks the own message a delivery report refers to as failed
+/// or, for temporary problems, stores the reason only.
+///
+/// Message-IDs are known to all members of a chat,
+/// so the failed recipients must be members of the chat of the message
+/// and the report must be sent by a mail server in charge of the delivery.
+fn handle_delivery_report(context: &Context, report: &DeliveryReport, from: &str) {
+    if !is_delivery_report_sender(context, from, &report.failed_recipients) {
+        warn!(
+            context,
+            "ignoring delivery report for message {} sent by {}", report.rfc724_mid, from
+        );
+        return;
+    }
+    let msg_id = match message::rfc724_mid_exists(context, &report.rfc724_mid) {
+        Ok((_, _, msg_id)) => msg_id,
+        Err(_) => {
+            info!(
+                context,
+                "delivery report for unknown message {}", report.rfc724_mid
+            );
+            return;
+        }
+    };
+    match message::Message::load_from_db(context, msg_id) {
+        Ok(msg) if msg.from_id == DC_CONTACT_ID_SELF => {
+            if msg.state == message::MessageState::OutMdnRcvd {
+                // the message was read, so it was delivered
+                info!(
+                    context,
+                    "ignoring delivery report for read message {}", report.rfc724_mid
+                );
+                return;
+            }
+            let is_member = |addr: &String| {
+                let contact_id = Contact::lookup_id_by_addr(context, addr);
+                contact_id != 0 && chat::is_contact_in_chat(context, msg.chat_id, contact_id)
+            };
+            if report.failed_recipients.is_empty()
+                || !report.failed_recipients.iter().all(is_member)
+            {
+                warn!(
+                    context,
+                    "delivery report for message {} names recipients not in the chat",
+                    report.rfc724_mid
+                );
+            } else if report.permanent {
+                message::set_msg_failed(context, msg_id, Some(&report.reason));
+            } else {
+                message::set_msg_error(context, msg_id, &report.reason);
+            }
+        }
+        _ => warn!(
+            context,
+            "delivery report for message {} not sent by us", report.rfc724_mid
+        ),
+    }
+}
+
+/// Checks that `from` is the MAILER-DAEMON or postmaster of the own provider
+/// or an address at the domain of a recipient.
+fn is_delivery_report_sender(context: &Context, from: &str, recipients: &[String]) -> bool {
+    let from: EmailAddress = match from.parse() {
+        Ok(addr) => addr,
+        Err(_) => return false,
+    };
+    let is_domain = |addr: &str| {
+        addr.parse::<EmailAddress>()
+            .map(|addr| from.domain.eq_ignore_ascii_case(&addr.domain))
+            .unwrap_or_default()
+    };
+
+    let local = from.local.to_lowercase();
+    if local == "mailer-daemon" || local == "postmaster" {
+        if let Some(self_addr) = context.get_config(Config::ConfiguredAddr) {
+            if is_domain(&self_addr) {
+                return true;
+            }
+        }
+    }
+    recipients.iter().any(|recipient| is_domain(recipient))
+}
+
+/// Strips the type of a DSN field, eg. `rfc822;` of `Final-Recipient`
+/// or `smtp;` of `Diagnostic-Code`.
+fn strip_type_prefix(value: &str) -> String {
+    match value.find(';') {
+        Some(i) => value[i + 1..].trim().to_string(),
+        None => value.trim().to_string(),
+    }
+}
+
 fn update_gossip_peerstates(
     context: &Context,
     message_time: i64,
@@ -911,6 +1184,18 @@ pub(crate) struct Report {
     additional_message_ids: Vec<String>,
 }
 
+#[derive(Debug)]
+pub(crate) struct DeliveryReport {
+    /// Message-ID of the message that could not be delivered
+    rfc724_mid: String,
+    /// Final-Recipients of the failed deliveries
+    failed_recipients: Vec<String>,
+    /// Human-readable description of the failed deliveries
+    reason: String,
+    /// Whether the server gave up delivering the message
+    permanent: bool,
+}
+
 pub(crate) fn parse_message_id(value: &str) -> crate::error::Result<String> {
     let ids = mailparse::msgidparse(value)
         .map_err(|err| format_err!("failed to parse message id {:?}", err))?;
@@ -71,6 +71,26 @@ pub enum Param {
     /// For Messages
     AttachGroupImage = b'A',
 
+    /// For Messages: the message is a reaction to the message with this Message-ID.
+    Reaction = b'y',
+
+    /// For Messages: excerpt of the quoted message,
+    /// the quoted message itself is referenced by `In-Reply-To`.
+    Quote = b'q',
+
+    /// For Messages: the message replaces the text of the message with this Message-ID.
+    TextEditFor = b'T',
+
+    /// For Messages: space-separated list of Message-IDs of messages to delete for everyone.
+    DeleteRequestFor = b'W',
+
+    /// For Messages: the text of the message was edited after sending.
+    IsEdited = b'j',
+
+    /// For Messages: the message is a typing notification,
+    /// `1` if the user started typing, `0` if the user stopped typing.
+    Typing = b'Y',
+
     /// For Messages: space-separated list of messaged IDs of forwarded copies.
     ///
     /// This is used when a [crate::message::Message] is in the
@@ -100,18 +120,36 @@ pub enum Param {
     /// For Jobs: space-separated list of message recipients
     Recipients = b'R',
 
+    /// For Jobs: delete the message from the server only, keep it on the device
+    KeepOnDevice = b'k',
+
     /// For Groups
     Unpromoted = b'U',
 
     /// For Groups and Contacts
     ProfileImage = b'i',
 
+    /// For Contacts: status of the contact, taken from the footer of their messages.
+    Status = b't',
+
+    /// For Contacts: timestamp of the message the status was taken from.
+    StatusTimestamp = b'X',
+
     /// For Chats
     Selftalk = b'K',
 
     /// For Chats
     Devicetalk = b'D',
 
+    /// For Chats: address messages to a mailing list are sent to, taken from `List-Post`.
+    ListPost = b'p',
+
+    /// For Chats: URI to unsubscribe from a mailing list, taken from `List-Unsubscribe`.
+    ListUnsubscribe = b'o',
+
+    /// For Chats: set to 1 if the user enabled sending messages to a mailing list.
+    ListSendingEnabled = b'b',
+
     /// For QR
     Auth = b's',
 
@@ -1,6 +1,7 @@
 //! # QR code module
 
-use lazy_static::lazy_static;
+use std::net::SocketAddr;
+
 use percent_encoding::percent_decode_str;
 
 use crate::chat;
@@ -14,11 +15,13 @@ use crate::key::dc_normalize_fingerprint;
 use crate::lot::{Lot, LotState};
 use crate::param::*;
 use crate::peerstate::*;
+use crate::vcard;
 use reqwest::Url;
 use serde::Deserialize;
 
 const OPENPGP4FPR_SCHEME: &str = "OPENPGP4FPR:"; // yes: uppercase
 const DCACCOUNT_SCHEME: &str = "DCACCOUNT:";
+pub(crate) const DCBACKUP_SCHEME: &str = "DCBACKUP:";
 const MAILTO_SCHEME: &str = "mailto:";
 const MATMSG_SCHEME: &str = "MATMSG:";
 const VCARD_SCHEME: &str = "BEGIN:VCARD";
@@ -49,6 +52,8 @@ pub fn check_qr(context: &Context, qr: impl AsRef<str>) -> Lot {
         decode_openpgp(context, qr)
     } else if qr.starts_with(DCACCOUNT_SCHEME) {
         decode_account(context, qr)
+    } else if qr.starts_with(DCBACKUP_SCHEME) {
+        decode_backup(qr)
     } else if qr.starts_with(MAILTO_SCHEME) {
         decode_mailto(context, qr)
     } else if qr.starts_with(SMTP_SCHEME) {
@@ -185,6 +190,28 @@ fn decode_openpgp(context: &Context, qr: &str) -> Lot {
     lot
 }
 
+/// scheme: `DCBACKUP:ADDR:PORT#SECRET`, see [crate::imex::BackupProvider]
+fn decode_backup(qr: &str) -> Lot {
+    let payload = &qr[DCBACKUP_SCHEME.len()..];
+
+    let (addr, secret) = match payload.find('#') {
+        Some(offset) => (&payload[..offset], &payload[offset + 1..]),
+        None => return format_err!("Missing secret in backup QR code").into(),
+    };
+    if let Err(err) = addr.parse::<SocketAddr>() {
+        return format_err!("Invalid address in backup QR code: {}", err).into();
+    }
+    if secret.is_empty() {
+        return format_err!("Missing secret in backup QR code").into();
+    }
+
+    let mut lot = Lot::new();
+    lot.state = LotState::QrBackup;
+    lot.text1 = Some(addr.to_string());
+    lot.auth = Some(secret.to_string());
+    lot
+}
+
 /// scheme: `DCACCOUNT:https://example.org/new_email?t=1w_7wDjgjelxeX884x96v3`
 fn decode_account(_context: &Context, qr: &str) -> Lot {
     let payload = &qr[DCACCOUNT_SCHEME.len()..];
@@ -322,37 +349,21 @@ fn decode_matmsg(context: &Context, qr: &str) -> Lot {
     Lot::from_address(context, name, addr)
 }
 
-lazy_static! {
-    static ref VCARD_NAME_RE: regex::Regex =
-        regex::Regex::new(r"(?m)^N:([^;]*);([^;\n]*)").unwrap();
-    static ref VCARD_EMAIL_RE: regex::Regex =
-        regex::Regex::new(r"(?m)^EMAIL([^:\n]*):([^;\n]*)").unwrap();
-}
-
-/// Extract address for the matmsg scheme.
+/// Extract address for the vcard scheme.
 ///
-/// Scheme: `VCARD:BEGIN\nN:last name;first name;...;\nEMAIL;<type>:addr...;
+/// Scheme: `BEGIN:VCARD\nN:last name;first name;...;\nEMAIL;<type>:addr...;
+/// Only the first contact of the vCard is used.
 fn decode_vcard(context: &Context, qr: &str) -> Lot {
-    let name = VCARD_NAME_RE
-        .captures(qr)
-        .map(|caps| {
-            let last_name = &caps[1];
-            let first_name = &caps[2];
-
-            format!("{} {}", first_name.trim(), last_name.trim())
-        })
-        .unwrap_or_default();
-
-    let addr = if let Some(caps) = VCARD_EMAIL_RE.captures(qr) {
-        match normalize_address(caps[2].trim()) {
-            Ok(addr) => addr,
-            Err(err) => return err.into(),
-        }
-    } else {
-        return format_err!("Bad e-mail address").into();
+    let contact = match vcard::parse_vcard(qr).into_iter().next() {
+        Some(contact) => contact,
+        None => return format_err!("Bad e-mail address").into(),
+    };
+    let addr = match normalize_address(&contact.addr) {
+        Ok(addr) => addr,
+        Err(err) => return err.into(),
     };
 
-    Lot::from_address(context, name, addr)
+    Lot::from_address(context, contact.display_name, addr)
 }
 
 impl Lot {
@@ -590,4 +601,22 @@ mod tests {
         assert_eq!(res.get_state(), LotState::QrError);
         assert!(res.get_text1().is_some());
     }
+
+    #[test]
+    fn test_decode_backup() {
+        let ctx = dummy_context();
+
+        let res = check_qr(&ctx.ctx, "DCBACKUP:192.168.1.2:4321#0123abcd");
+        assert_eq!(res.get_state(), LotState::QrBackup);
+        assert_eq!(res.get_text1().unwrap(), "192.168.1.2:4321");
+
+        let res = check_qr(&ctx.ctx, "DCBACKUP:[fe80::1]:4321#0123abcd");
+        assert_eq!(res.get_state(), LotState::QrBackup);
+
+        let res = check_qr(&ctx.ctx, "DCBACKUP:192.168.1.2:4321");
+        assert_eq!(res.get_state(), LotState::QrError);
+
+        let res = check_qr(&ctx.ctx, "DCBACKUP:example.org#0123abcd");
+        assert_eq!(res.get_state(), LotState::QrError);
+    }
 }
@@ -0,0 +1,404 @@
+//! # Storage quota
+//!
+//! The quota of the INBOX is requested using GETQUOTAROOT, see
+//! [RFC 2087](https://tools.ietf.org/html/rfc2087),
+//! after fetching messages, at most every [QUOTA_UPDATE_INTERVAL].
+//! If the usage of a resource exceeds 80% or 95%,
+//! a device message warns the user as sending and receiving messages
+//! fails once the mailbox is full.
+//! Only the highest exceeded threshold is warned about.
+
+use async_std::task;
+use serde::Serialize;
+
+use crate::chat;
+use crate::constants::Viewtype;
+use crate::context::Context;
+use crate::dc_tools::time;
+use crate::error::Error;
+use crate::message::Message;
+use crate::stock::StockMessage;
+
+/// Minimal interval between two quota requests, in seconds.
+const QUOTA_UPDATE_INTERVAL: i64 = 10 * 60;
+
+/// Usage percentages warned about, each one only once.
+const QUOTA_WARN_THRESHOLDS: [u64; 2] = [80, 95];
+
+/// Usage percentage below which the warnings are reset.
+const QUOTA_ALLCLEAR_PERCENTAGE: u64 = 75;
+
+/// Usage and limit of a resource, eg. `STORAGE` in units of 1024 octets.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct QuotaResource {
+    /// The quota root the resource belongs to, may be empty.
+    pub root: String,
+    pub name: String,
+    pub usage: u64,
+    pub limit: u64,
+}
+
+impl QuotaResource {
+    /// Returns the usage in percent of the limit.
+    pub fn get_usage_percentage(&self) -> u64 {
+        if self.limit == 0 {
+            0
+        } else {
+            self.usage.saturating_mul(100) / self.limit
+        }
+    }
+}
+
+/// The quota of the account as reported by the server.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Quota {
+    /// The resources with limits, empty if the server does not support quotas.
+    pub resources: Vec<QuotaResource>,
+
+    /// Time of the request.
+    pub timestamp: i64,
+}
+
+impl Quota {
+    /// Returns the highest usage percentage of all resources.
+    pub fn get_highest_usage_percentage(&self) -> u64 {
+        self.resources
+            .iter()
+            .map(|resource| resource.get_usage_percentage())
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+impl Context {
+    /// Returns the quota of the account, `None` if it was not requested yet.
+    pub fn get_quota(&self) -> Option<Quota> {
+        self.quota.read().unwrap().clone()
+    }
+
+    /// Returns a human readable summary of the connections to the servers
+    /// and of the storage quota, one item per line.
+    pub fn get_connectivity_info(&self) -> String {
+        let connected = |connected: bool| {
+            if connected {
+                "connected"
+            } else {
+                "not connected"
+            }
+        };
+        let imap_connected = task::block_on(self.inbox_thread.read().unwrap().imap.is_connected());
+        let smtp_connected = self.smtp.lock().unwrap().is_connected();
+
+        let mut lines = vec![
+            format!("Incoming messages: {}", connected(imap_connected)),
+            format!("Outgoing messages: {}", connected(smtp_connected)),
+        ];
+        match self.get_quota() {
+            None => lines.push("Storage: not requested yet".to_string()),
+            Some(ref quota) if quota.resources.is_empty() => {
+                lines.push("Storage: not reported by the server".to_string())
+            }
+            Some(quota) => {
+                for resource in &quota.resources {
+                    let name = if resource.root.is_empty() {
+                        resource.name.clone()
+                    } else {
+                        format!("{} of {}", resource.name, resource.root)
+                    };
+                    let unit = if resource.name == "STORAGE" {
+                        " KiB"
+                    } else {
+                        ""
+                    };
+                    lines.push(format!(
+                        "Storage: {} {}{} of {}{} used ({}%)",
+                        name,
+                        resource.usage,
+                        unit,
+                        resource.limit,
+                        unit,
+                        resource.get_usage_percentage()
+                    ));
+                }
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+/// Requests the quota if it was not requested within the [QUOTA_UPDATE_INTERVAL]
+/// and warns about an almost full mailbox.
+pub(crate) fn update_quota_if_needed(context: &Context) {
+    if let Some(quota) = &*context.quota.read().unwrap() {
+        if time() - quota.timestamp < QUOTA_UPDATE_INTERVAL {
+            return;
+        }
+    }
+
+    let res = task::block_on(context.inbox_thread.read().unwrap().imap.get_quota(context));
+    let quota = match res {
+        Ok(resources) => Quota {
+            resources,
+            timestamp: time(),
+        },
+        Err(err) => {
+            info!(context, "Cannot get quota: {}", err);
+            return;
+        }
+    };
+
+    if let Err(err) = warn_if_needed(context, &quota) {
+        warn!(context, "Cannot add quota warning: {}", err);
+    }
+    *context.quota.write().unwrap() = Some(quota);
+}
+
+/// Adds a device message if the highest exceeded threshold was not warned about yet.
+///
+/// Lower thresholds are marked as warned about,
+/// so that there is no warning when the usage decreases a bit.
+fn warn_if_needed(context: &Context, quota: &Quota) -> Result<(), Error> {
+    if quota.resources.is_empty() {
+        return Ok(());
+    }
+
+    let percentage = quota.get_highest_usage_percentage();
+    let label = |threshold: &u64| format!("quota-warning-{}", threshold);
+    if percentage < QUOTA_ALLCLEAR_PERCENTAGE {
+        // warn again if the mailbox fills up again
+        for threshold in QUOTA_WARN_THRESHOLDS.iter() {
+            chat::reset_device_msg_label(context, &label(threshold))?;
+        }
+        return Ok(());
+    }
+
+    let mut exceeded = QUOTA_WARN_THRESHOLDS
+        .iter()
+        .rev()
+        .filter(|threshold| percentage >= **threshold);
+    if let Some(highest) = exceeded.next() {
+        let mut msg = Message::new(Viewtype::Text);
+        msg.set_text(Some(context.stock_string_repl_str(
+            StockMessage::QuotaExceeding,
+            percentage.to_string(),
+        )));
+        chat::add_device_msg(context, Some(&label(highest)), Some(&mut msg))?;
+        for lower in exceeded {
+            chat::add_device_msg(context, Some(&label(lower)), None)?;
+        }
+    }
+    Ok(())
+}
+
+/// Parses the untagged QUOTA responses of a GETQUOTAROOT command,
+/// eg. `* QUOTA "" (STORAGE 10 512)`.
+///
+/// The response contains a QUOTA response for every quota root of the mailbox,
+/// the resources of all roots are returned.
+/// Some servers omit the quota root, eg. `* QUOTA (STORAGE 10 512)`.
+pub(crate) fn parse_quota_response(response: &str) -> Vec<QuotaResource> {
+    response
+        .lines()
+        .filter_map(parse_quota_line)
+        .flatten()
+        .collect()
+}
+
+/// Parses a single `* QUOTA <root> (<name> <usage> <limit> ...)` line,
+/// returns `None` if the line is no valid QUOTA response.
+fn parse_quota_line(line: &str) -> Option<Vec<QuotaResource>> {
+    let line = line.trim();
+    let prefix = "* QUOTA ";
+    match line.get(..prefix.len()) {
+        Some(start) if start.eq_ignore_ascii_case(prefix) => {}
+        _ => return None,
+    }
+    let rest = line[prefix.len()..].trim_start();
+    let (root, rest) = if rest.starts_with('(') {
+        (String::new(), rest)
+    } else {
+        parse_astring(rest)?
+    };
+    let rest = rest.trim_start();
+    if !rest.starts_with('(') || !rest.ends_with(')') {
+        return None;
+    }
+    let tokens: Vec<&str> = rest[1..rest.len() - 1].split_whitespace().collect();
+    if tokens.len() % 3 != 0 {
+        return None;
+    }
+    tokens
+        .chunks(3)
+        .map(|triple| {
+            Some(QuotaResource {
+                root: root.clone(),
+                name: triple[0].to_uppercase(),
+                usage: triple[1].parse().ok()?,
+                limit: triple[2].parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+/// Parses an IMAP atom or quoted string at the start of `input`,
+/// returns the string and the remaining input.
+fn parse_astring(input: &str) -> Option<(String, &str)> {
+    if input.starts_with('"') {
+        let mut value = String::new();
+        let mut escaped = false;
+        for (i, c) in input.char_indices().skip(1) {
+            match c {
+                _ if escaped => {
+                    value.push(c);
+                    escaped = false;
+                }
+                '\\' => escaped = true,
+                '"' => return Some((value, &input[i + 1..])),
+                _ => value.push(c),
+            }
+        }
+        None
+    } else {
+        let end = input
+            .find(|c: char| c.is_whitespace() || c == '(')
+            .unwrap_or(input.len());
+        if end == 0 {
+            None
+        } else {
+            Some((input[..end].to_string(), &input[end..]))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::constants::DC_CONTACT_ID_DEVICE;
+    use crate::test_utils::*;
+
+    fn resource(root: &str, name: &str, usage: u64, limit: u64) -> QuotaResource {
+        QuotaResource {
+            root: root.to_string(),
+            name: name.to_string(),
+            usage,
+            limit,
+        }
+    }
+
+    #[test]
+    fn test_parse_quota_response() {
+        let response = "* QUOTAROOT INBOX \"\"\r\n\
+                        * QUOTA \"\" (STORAGE 8192 10240 MESSAGE 12 1000)\r\n\
+                        A0003 OK Getquotaroot completed\r\n";
+        let resources = parse_quota_response(response);
+        assert_eq!(
+            resources,
+            vec![
+                resource("", "STORAGE", 8192, 10240),
+                resource("", "MESSAGE", 12, 1000),
+            ]
+        );
+        assert_eq!(resources[0].get_usage_percentage(), 80);
+
+        assert!(parse_quota_response("* QUOTAROOT INBOX\r\nA1 OK done\r\n").is_empty());
+    }
+
+    #[test]
+    fn test_parse_quota_response_several_roots() {
+        let response = "* QUOTAROOT INBOX user/alice \"shared (all)\"\r\n\
+                        * QUOTA user/alice (STORAGE 100 1000)\r\n\
+                        * quota \"shared (all)\" (storage 900 1000)\r\n\
+                        * QUOTA \"with \\\"quote\\\"\" (MESSAGE 1 10)\r\n\
+                        A0003 OK Getquotaroot completed\r\n";
+        let quota = Quota {
+            resources: parse_quota_response(response),
+            timestamp: 0,
+        };
+        assert_eq!(
+            quota.resources,
+            vec![
+                resource("user/alice", "STORAGE", 100, 1000),
+                resource("shared (all)", "STORAGE", 900, 1000),
+                resource("with \"quote\"", "MESSAGE", 1, 10),
+            ]
+        );
+        assert_eq!(quota.get_highest_usage_percentage(), 90);
+    }
+
+    #[test]
+    fn test_parse_quota_response_without_root() {
+        assert_eq!(
+            parse_quota_response("* QUOTA (STORAGE 10 512)\r\nA1 OK done\r\n"),
+            vec![resource("", "STORAGE", 10, 512)]
+        );
+        assert_eq!(
+            parse_quota_response("* QUOTA  INBOX(STORAGE 10 512)\r\n"),
+            vec![resource("INBOX", "STORAGE", 10, 512)]
+        );
+
+        // malformed responses are skipped
+        assert!(parse_quota_response("* QUOTA\r\n").is_empty());
+        assert!(parse_quota_response("* QUOTA \"\" ()\r\n").is_empty());
+        assert!(parse_quota_response("* QUOTA \"\" (STORAGE 10)\r\n").is_empty());
+        assert!(parse_quota_response("* QUOTA \"\" (STORAGE 10 x)\r\n").is_empty());
+        assert!(parse_quota_response("* QUOTA \"unterminated (STORAGE 1 2)\r\n").is_empty());
+        assert!(parse_quota_response("* QUOTAROOT INBOX (STORAGE 1 2)\r\n").is_empty());
+    }
+
+    #[test]
+    fn test_get_connectivity_info() {
+        let t = dummy_context();
+        let info = t.ctx.get_connectivity_info();
+        assert!(info.contains("Incoming messages: not connected"));
+        assert!(info.contains("Storage: not requested yet"));
+
+        *t.ctx.quota.write().unwrap() = Some(Quota {
+            resources: vec![
+                resource("", "STORAGE", 8192, 10240),
+                resource("shared", "MESSAGE", 12, 1000),
+            ],
+            timestamp: time(),
+        });
+        let info = t.ctx.get_connectivity_info();
+        assert!(info.contains("Storage: STORAGE 8192 KiB of 10240 KiB used (80%)"));
+        assert!(info.contains("Storage: MESSAGE of shared 12 of 1000 used (1%)"));
+    }
+
+    #[test]
+    fn test_quota_warning() {
+        let t = dummy_context();
+        let quota = |usage| Quota {
+            resources: vec![resource("", "STORAGE", usage, 100)],
+            timestamp: time(),
+        };
+        let warnings = |t: &TestContext| {
+            t.ctx
+                .sql
+                .query_get_value::<_, i32>(
+                    &t.ctx,
+                    "SELECT COUNT(*) FROM msgs WHERE from_id=?;",
+                    params![DC_CONTACT_ID_DEVICE],
+                )
+                .unwrap_or_default()
+        };
+
+        warn_if_needed(&t.ctx, &quota(50)).unwrap();
+        assert_eq!(warnings(&t), 0);
+
+        // only the highest threshold is warned about
+        warn_if_needed(&t.ctx, &quota(96)).unwrap();
+        assert_eq!(warnings(&t), 1);
+        warn_if_needed(&t.ctx, &quota(85)).unwrap();
+        assert_eq!(warnings(&t), 1);
+
+        // the warnings are reset once there is enough space again
+        warn_if_needed(&t.ctx, &quota(10)).unwrap();
+        warn_if_needed(&t.ctx, &quota(85)).unwrap();
+        assert_eq!(warnings(&t), 2);
+        warn_if_needed(&t.ctx, &quota(86)).unwrap();
+        assert_eq!(warnings(&t), 2);
+        warn_if_needed(&t.ctx, &quota(96)).unwrap();
+        assert_eq!(warnings(&t), 3);
+    }
+}
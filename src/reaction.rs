@@ -0,0 +1,146 @@
+//! # Reactions
+//!
+//! Reactions are short messages, typically a single emoji, that refer to another message
+//! by its Message-ID in the `In-Reply-To` header and carry `Chat-Content: reaction`.
+//! They are not shown as messages in the chat,
+//! instead they are collected per message and contact.
+//! A new reaction of a contact replaces the previous one, an empty reaction removes it.
+
+use std::collections::BTreeMap;
+
+use crate::chat::{self, Chat, ChatId};
+use crate::constants::*;
+use crate::context::Context;
+use crate::dc_tools::*;
+use crate::error::Result;
+use crate::events::Event;
+use crate::message::{self, Message, MsgId};
+use crate::param::*;
+
+/// Sends a reaction to the message `msg_id`, use an empty reaction to remove a reaction sent before.
+///
+/// The reaction is applied locally at once, other chat members get it by a hidden message.
+pub fn send_reaction(context: &Context, msg_id: MsgId, reaction: impl AsRef<str>) -> Result<MsgId> {
+    let reaction = reaction.as_ref().trim();
+    let target = Message::load_from_db(context, msg_id)?;
+    ensure!(
+        !target.rfc724_mid.is_empty(),
+        "Cannot react to message {} without Message-ID",
+        msg_id
+    );
+    let chat = Chat::load_from_db(context, target.chat_id)?;
+    ensure!(chat.can_send(), "Cannot send reaction to chat {}", chat.id);
+
+    let mut msg = Message::new(Viewtype::Text);
+    msg.text = Some(reaction.to_string());
+    msg.hidden = true;
+    msg.param.set(Param::Reaction, &target.rfc724_mid);
+    let reaction_msg_id = chat::send_msg(context, chat.id, &mut msg)?;
+
+    set_reaction(context, chat.id, msg_id, DC_CONTACT_ID_SELF, reaction)?;
+    Ok(reaction_msg_id)
+}
+
+/// Returns the reactions to a message as a map from contact ID to the reaction.
+pub fn get_reactions(context: &Context, msg_id: MsgId) -> Result<BTreeMap<u32, String>> {
+    let reactions = context.sql.query_map(
+        "SELECT contact_id, reaction FROM reactions WHERE msg_id=?;",
+        params![msg_id],
+        |row| Ok((row.get::<_, u32>(0)?, row.get::<_, String>(1)?)),
+        |rows| {
+            rows.collect::<std::result::Result<BTreeMap<_, _>, _>>()
+                .map_err(Into::into)
+        },
+    )?;
+    Ok(reactions)
+}
+
+/// Stores the reaction of a contact to a received reaction message.
+///
+/// `in_reply_to` is the `In-Reply-To` header of the reaction message;
+/// reactions to unknown messages are ignored.
+pub(crate) fn set_reaction_by_in_reply_to(
+    context: &Context,
+    in_reply_to: &str,
+    contact_id: u32,
+    reaction: &str,
+) -> Result<()> {
+    let rfc724_mid = crate::mimeparser::parse_message_id(in_reply_to)?;
+    match message::rfc724_mid_exists(context, &rfc724_mid) {
+        Ok((_, _, msg_id)) => {
+            let target = Message::load_from_db(context, msg_id)?;
+            if target.chat_id.is_special() {
+                info!(context, "Ignoring reaction to deleted message {}.", msg_id);
+                return Ok(());
+            }
+            if !chat::is_contact_in_chat(context, target.chat_id, contact_id)
+                && contact_id != DC_CONTACT_ID_SELF
+            {
+                warn!(
+                    context,
+                    "Ignoring reaction of contact {} not in chat {}.", contact_id, target.chat_id
+                );
+                return Ok(());
+            }
+            set_reaction(context, target.chat_id, msg_id, contact_id, reaction.trim())
+        }
+        Err(_) => {
+            info!(
+                context,
+                "Ignoring reaction to unknown message {}.", rfc724_mid
+            );
+            Ok(())
+        }
+    }
+}
+
+fn set_reaction(
+    context: &Context,
+    chat_id: ChatId,
+    msg_id: MsgId,
+    contact_id: u32,
+    reaction: &str,
+) -> Result<()> {
+    if reaction.is_empty() {
+        context.sql.execute(
+            "DELETE FROM reactions WHERE msg_id=? AND contact_id=?;",
+            params![msg_id, contact_id],
+        )?;
+    } else {
+        context.sql.execute(
+            "INSERT OR REPLACE INTO reactions (msg_id, contact_id, reaction, timestamp) \
+             VALUES (?,?,?,?);",
+            params![msg_id, contact_id, reaction, time()],
+        )?;
+    }
+    context.call_cb(Event::ReactionsChanged { chat_id, msg_id });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_set_and_get_reactions() {
+        let t = dummy_context();
+        let chat_id = ChatId::new(10);
+        let msg_id = MsgId::new(100);
+        assert!(get_reactions(&t.ctx, msg_id).unwrap().is_empty());
+
+        set_reaction(&t.ctx, chat_id, msg_id, 10, "👍").unwrap();
+        set_reaction(&t.ctx, chat_id, msg_id, 11, "❤️").unwrap();
+        let reactions = get_reactions(&t.ctx, msg_id).unwrap();
+        assert_eq!(reactions.len(), 2);
+        assert_eq!(reactions.get(&10).map(|s| s.as_str()), Some("👍"));
+
+        // a new reaction replaces the old one, an empty reaction removes it
+        set_reaction(&t.ctx, chat_id, msg_id, 10, "😂").unwrap();
+        set_reaction(&t.ctx, chat_id, msg_id, 11, "").unwrap();
+        let reactions = get_reactions(&t.ctx, msg_id).unwrap();
+        assert_eq!(reactions.len(), 1);
+        assert_eq!(reactions.get(&10).map(|s| s.as_str()), Some("😂"));
+    }
+}
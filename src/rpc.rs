@@ -0,0 +1,675 @@
+//! # JSON-RPC interface
+//!
+//! [RpcServer] exposes the core API as [JSON-RPC 2.0](https://www.jsonrpc.org/specification)
+//! methods, it is used by the `deltachat-rpc-server` binary which reads requests
+//! from stdin and writes responses to stdout, one JSON object per line.
+//!
+//! Parameters are passed by position, eg. `{"jsonrpc":"2.0","id":1,"method":"send_text_msg","params":[10,"hi"]}`.
+//! All methods except the account methods act on the selected account.
+//! Batches, ie. arrays of requests, are answered by arrays of responses.
+//!
+//! Events are pushed as notifications created by [event_notification],
+//! `{"jsonrpc":"2.0","method":"event","params":{"account_id":1,"event_id":2000,"event":{...}}}`.
+
+use std::sync::Arc;
+
+use num_traits::FromPrimitive;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::accounts::Accounts;
+use crate::chat::{self, Chat, ChatId, EphemeralTimer};
+use crate::chatlist::Chatlist;
+use crate::config::Config;
+use crate::constants::Viewtype;
+use crate::contact::{Contact, VerifiedStatus};
+use crate::context::Context;
+use crate::error::Error;
+use crate::events::Event;
+use crate::imex::{self, ImexMode};
+use crate::job;
+use crate::message::{self, Message, MsgId};
+use crate::qr;
+use crate::reaction;
+use crate::securejoin;
+
+/// Invalid JSON was received.
+const PARSE_ERROR: i64 = -32700;
+
+/// The JSON sent is not a valid request object.
+const INVALID_REQUEST: i64 = -32600;
+
+/// The method does not exist.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// Invalid method parameters.
+const INVALID_PARAMS: i64 = -32602;
+
+/// The core returned an error, the message contains the details.
+const CORE_ERROR: i64 = -32000;
+
+/// Error object of a JSON-RPC response.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        RpcError {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<Error> for RpcError {
+    fn from(err: Error) -> Self {
+        RpcError::new(CORE_ERROR, err.to_string())
+    }
+}
+
+impl From<crate::sql::Error> for RpcError {
+    fn from(err: crate::sql::Error) -> Self {
+        RpcError::new(CORE_ERROR, err.to_string())
+    }
+}
+
+type RpcResult = std::result::Result<Value, RpcError>;
+
+/// Creates the notification pushed to the client for an event of an account.
+pub fn event_notification(account_id: u32, event: &Event) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "event",
+        "params": {
+            "account_id": account_id,
+            "event_id": event.as_id(),
+            "event": event,
+        }
+    })
+}
+
+/// Dispatches JSON-RPC requests to the accounts.
+#[derive(Debug)]
+pub struct RpcServer {
+    accounts: Arc<Accounts>,
+}
+
+impl RpcServer {
+    pub fn new(accounts: Arc<Accounts>) -> Self {
+        RpcServer { accounts }
+    }
+
+    /// Handles a single request line and returns the response line.
+    ///
+    /// The line may contain a batch, ie. an array of requests,
+    /// which is answered by an array of responses.
+    /// Returns `None` if there is nothing to respond,
+    /// ie. for valid notifications without `id` and batches of them.
+    pub fn handle_request(&self, request: &str) -> Option<String> {
+        let request: Value = match serde_json::from_str(request) {
+            Ok(request) => request,
+            Err(err) => {
+                let err = RpcError::new(PARSE_ERROR, err.to_string());
+                return Some(response(Value::Null, Err(err)).to_string());
+            }
+        };
+        let response = match request {
+            Value::Array(requests) => {
+                if requests.is_empty() {
+                    let err = RpcError::new(INVALID_REQUEST, "Empty batch");
+                    return Some(response(Value::Null, Err(err)).to_string());
+                }
+                let responses: Vec<Value> = requests
+                    .into_iter()
+                    .filter_map(|request| self.handle_single_request(request))
+                    .collect();
+                if responses.is_empty() {
+                    None
+                } else {
+                    Some(Value::Array(responses))
+                }
+            }
+            request => self.handle_single_request(request),
+        };
+        response.map(|response| response.to_string())
+    }
+
+    /// Handles a request object, invalid requests are answered with `"id":null`
+    /// if their `id` cannot be determined.
+    fn handle_single_request(&self, request: Value) -> Option<Value> {
+        let request = match request {
+            Value::Object(request) => request,
+            _ => {
+                let err = RpcError::new(INVALID_REQUEST, "Request is not an object");
+                return Some(response(Value::Null, Err(err)));
+            }
+        };
+        let id = match request.get("id") {
+            None => None,
+            Some(id @ Value::Null) | Some(id @ Value::Number(_)) | Some(id @ Value::String(_)) => {
+                Some(id.clone())
+            }
+            Some(_) => {
+                let err = RpcError::new(INVALID_REQUEST, "Invalid id");
+                return Some(response(Value::Null, Err(err)));
+            }
+        };
+        let params = match request.get("params") {
+            None => Some(json!([])),
+            Some(params @ Value::Array(_)) | Some(params @ Value::Object(_)) => {
+                Some(params.clone())
+            }
+            Some(_) => None,
+        };
+        match (
+            request.get("jsonrpc").and_then(Value::as_str),
+            request.get("method").and_then(Value::as_str),
+            params,
+        ) {
+            (Some("2.0"), Some(method), Some(params)) => {
+                let result = self.call(method, params);
+                id.map(|id| response(id, result))
+            }
+            _ => {
+                let err = RpcError::new(INVALID_REQUEST, "Invalid request");
+                Some(response(id.unwrap_or(Value::Null), Err(err)))
+            }
+        }
+    }
+
+    fn call(&self, method: &str, params: Value) -> RpcResult {
+        // account methods, they do not need a selected account
+        match method {
+            "get_all_account_ids" => {
+                no_params(&params)?;
+                return to_value(self.accounts.get_all());
+            }
+            "add_account" => {
+                no_params(&params)?;
+                return to_value(self.accounts.add_account()?);
+            }
+            "remove_account" => {
+                let (id,): (u32,) = parse_params(params)?;
+                self.accounts.remove_account(id)?;
+                return Ok(Value::Null);
+            }
+            "select_account" => {
+                let (id,): (u32,) = parse_params(params)?;
+                self.accounts.select_account(id)?;
+                return Ok(Value::Null);
+            }
+            "get_selected_account_id" => {
+                no_params(&params)?;
+                return to_value(self.accounts.get_selected_account_id());
+            }
+            _ => {}
+        }
+
+        let context = self
+            .accounts
+            .get_selected_account()
+            .ok_or_else(|| RpcError::new(CORE_ERROR, "No account selected"))?;
+        call_context_method(&context, method, params)
+    }
+}
+
+fn call_context_method(context: &Context, method: &str, params: Value) -> RpcResult {
+    match method {
+        // config
+        "get_config" => {
+            let (key,): (String,) = parse_params(params)?;
+            to_value(context.get_config(parse_config_key(&key)?))
+        }
+        "set_config" => {
+            let (key, value): (String, Option<String>) = parse_params(params)?;
+            context.set_config(parse_config_key(&key)?, value.as_deref())?;
+            Ok(Value::Null)
+        }
+        "configure" => {
+            no_params(&params)?;
+            context.configure();
+            Ok(Value::Null)
+        }
+        "is_configured" => {
+            no_params(&params)?;
+            to_value(context.is_configured())
+        }
+        "maybe_network" => {
+            no_params(&params)?;
+            job::maybe_network(context);
+            Ok(Value::Null)
+        }
+
+        // chats
+        "get_chatlist_entries" => {
+            let (flags, query): (usize, Option<String>) = parse_params(params)?;
+            let chatlist = Chatlist::try_load(context, flags, query.as_deref(), None)?;
+            let mut entries = Vec::with_capacity(chatlist.len());
+            for i in 0..chatlist.len() {
+                entries.push((chatlist.get_chat_id(i), chatlist.get_msg_id(i)?));
+            }
+            to_value(entries)
+        }
+        "get_chat_info" => {
+            let (chat_id,): (ChatId,) = parse_params(params)?;
+            to_value(Chat::load_from_db(context, chat_id)?.get_info(context)?)
+        }
+        "create_chat_by_contact_id" => {
+            let (contact_id,): (u32,) = parse_params(params)?;
+            to_value(chat::create_by_contact_id(context, contact_id)?)
+        }
+        "create_group_chat" => {
+            let (verified, name): (bool, String) = parse_params(params)?;
+            let verified = if verified {
+                VerifiedStatus::Verified
+            } else {
+                VerifiedStatus::Unverified
+            };
+            to_value(chat::create_group_chat(context, verified, name)?)
+        }
+        "get_chat_msgs" => {
+            let (chat_id, flags): (ChatId, u32) = parse_params(params)?;
+            to_value(chat::get_chat_msgs(context, chat_id, flags, None))
+        }
+        "get_chat_contacts" => {
+            let (chat_id,): (ChatId,) = parse_params(params)?;
+            to_value(chat::get_chat_contacts(context, chat_id))
+        }
+        "add_contact_to_chat" => {
+            let (chat_id, contact_id): (ChatId, u32) = parse_params(params)?;
+            to_value(chat::add_contact_to_chat(context, chat_id, contact_id))
+        }
+        "remove_contact_from_chat" => {
+            let (chat_id, contact_id): (ChatId, u32) = parse_params(params)?;
+            chat::remove_contact_from_chat(context, chat_id, contact_id)?;
+            Ok(Value::Null)
+        }
+        "set_chat_name" => {
+            let (chat_id, name): (ChatId, String) = parse_params(params)?;
+            chat::set_chat_name(context, chat_id, name)?;
+            Ok(Value::Null)
+        }
+        "set_chat_ephemeral_timer" => {
+            let (chat_id, timer): (ChatId, u32) = parse_params(params)?;
+            chat::set_ephemeral_timer(context, chat_id, EphemeralTimer::from_u32(timer))?;
+            Ok(Value::Null)
+        }
+        "marknoticed_chat" => {
+            let (chat_id,): (ChatId,) = parse_params(params)?;
+            chat::marknoticed_chat(context, chat_id)?;
+            Ok(Value::Null)
+        }
+
+        // messages
+        "send_text_msg" => {
+            let (chat_id, text): (ChatId, String) = parse_params(params)?;
+            to_value(chat::send_text_msg(context, chat_id, text)?)
+        }
+        "send_text_msg_at" => {
+            let (chat_id, text, timestamp): (ChatId, String, i64) = parse_params(params)?;
+            let mut msg = Message::new(Viewtype::Text);
+            msg.set_text(Some(text));
+            to_value(chat::send_msg_at(context, chat_id, &mut msg, timestamp)?)
+        }
+        "get_scheduled_msgs" => {
+            let (chat_id,): (ChatId,) = parse_params(params)?;
+            to_value(chat::get_scheduled_msgs(context, chat_id)?)
+        }
+        "reschedule_msg" => {
+            let (msg_id, timestamp): (MsgId, i64) = parse_params(params)?;
+            chat::reschedule_msg(context, msg_id, timestamp)?;
+            Ok(Value::Null)
+        }
+        "cancel_scheduled_msgs" => {
+            let (msg_ids,): (Vec<MsgId>,) = parse_params(params)?;
+            chat::cancel_scheduled_msgs(context, &msg_ids)?;
+            Ok(Value::Null)
+        }
+        "send_typing" => {
+            let (chat_id, typing): (ChatId, bool) = parse_params(params)?;
+            chat::send_typing(context, chat_id, typing)?;
+            Ok(Value::Null)
+        }
+        "get_message" => {
+            let (msg_id,): (MsgId,) = parse_params(params)?;
+            to_value(Message::load_from_db(context, msg_id)?)
+        }
+        "get_msg_info" => {
+            let (msg_id,): (MsgId,) = parse_params(params)?;
+            to_value(message::get_msg_info(context, msg_id))
+        }
+        "get_read_receipts" => {
+            let (msg_id,): (MsgId,) = parse_params(params)?;
+            to_value(message::get_read_receipts(context, msg_id))
+        }
+        "delete_msgs" => {
+            let (msg_ids,): (Vec<MsgId>,) = parse_params(params)?;
+            message::delete_msgs(context, &msg_ids);
+            Ok(Value::Null)
+        }
+        "delete_msgs_for_everyone" => {
+            let (msg_ids,): (Vec<MsgId>,) = parse_params(params)?;
+            chat::delete_msgs_for_everyone(context, &msg_ids)?;
+            Ok(Value::Null)
+        }
+        "resend_msgs" => {
+            let (msg_ids,): (Vec<MsgId>,) = parse_params(params)?;
+            chat::resend_msgs(context, &msg_ids)?;
+            Ok(Value::Null)
+        }
+        "get_outbox_msgs" => {
+            no_params(&params)?;
+            to_value(context.get_outbox_msgs())
+        }
+        "markseen_msgs" => {
+            let (msg_ids,): (Vec<MsgId>,) = parse_params(params)?;
+            message::markseen_msgs(context, &msg_ids);
+            Ok(Value::Null)
+        }
+        "forward_msgs" => {
+            let (msg_ids, chat_id): (Vec<MsgId>, ChatId) = parse_params(params)?;
+            chat::forward_msgs(context, &msg_ids, chat_id)?;
+            Ok(Value::Null)
+        }
+        "edit_msg" => {
+            let (msg_id, text): (MsgId, String) = parse_params(params)?;
+            chat::edit_msg(context, msg_id, text)?;
+            Ok(Value::Null)
+        }
+        "send_reaction" => {
+            let (msg_id, reaction): (MsgId, String) = parse_params(params)?;
+            to_value(reaction::send_reaction(context, msg_id, reaction)?)
+        }
+        "get_reactions" => {
+            let (msg_id,): (MsgId,) = parse_params(params)?;
+            to_value(reaction::get_reactions(context, msg_id)?)
+        }
+
+        // contacts
+        "create_contact" => {
+            let (name, addr): (String, String) = parse_params(params)?;
+            to_value(Contact::create(context, name, addr)?)
+        }
+        "get_contact_ids" => {
+            let (flags, query): (u32, Option<String>) = parse_params(params)?;
+            to_value(Contact::get_all(context, flags, query)?)
+        }
+        "get_contact" => {
+            let (contact_id,): (u32,) = parse_params(params)?;
+            let contact = Contact::load_from_db(context, contact_id)?;
+            Ok(json!({
+                "id": contact.get_id(),
+                "name": contact.get_name(),
+                "display_name": contact.get_display_name(),
+                "addr": contact.get_addr(),
+                "color": contact.get_color(),
+                "profile_image": contact.get_profile_image(context),
+                "is_blocked": contact.is_blocked(),
+                "is_verified": contact.is_verified(context) == VerifiedStatus::BidirectVerified,
+            }))
+        }
+        "block_contact" => {
+            let (contact_id,): (u32,) = parse_params(params)?;
+            Contact::block(context, contact_id);
+            Ok(Value::Null)
+        }
+        "unblock_contact" => {
+            let (contact_id,): (u32,) = parse_params(params)?;
+            Contact::unblock(context, contact_id);
+            Ok(Value::Null)
+        }
+
+        // import/export
+        "imex" => {
+            let (what, param1, param2): (i32, Option<String>, Option<String>) =
+                parse_params(params)?;
+            let what = ImexMode::from_i32(what)
+                .ok_or_else(|| RpcError::new(INVALID_PARAMS, "Invalid imex mode"))?;
+            imex::imex(context, what, param1, param2);
+            Ok(Value::Null)
+        }
+        "imex_has_backup" => {
+            let (dir,): (String,) = parse_params(params)?;
+            to_value(imex::has_backup(context, dir)?)
+        }
+        "initiate_key_transfer" => {
+            no_params(&params)?;
+            to_value(imex::initiate_key_transfer(context)?)
+        }
+        "receive_backup" => {
+            let (qr,): (String,) = parse_params(params)?;
+            imex::receive_backup(context, &qr)?;
+            Ok(Value::Null)
+        }
+
+        // QR codes and secure-join
+        "check_qr" => {
+            let (qr,): (String,) = parse_params(params)?;
+            let lot = qr::check_qr(context, qr);
+            Ok(json!({
+                "state": lot.get_state() as u32,
+                "id": lot.get_id(),
+                "text1": lot.get_text1(),
+                "text2": lot.get_text2(),
+            }))
+        }
+        "get_securejoin_qr" => {
+            let (chat_id,): (ChatId,) = parse_params(params)?;
+            to_value(securejoin::dc_get_securejoin_qr(context, chat_id))
+        }
+        "join_securejoin" => {
+            let (qr,): (String,) = parse_params(params)?;
+            to_value(securejoin::dc_join_securejoin(context, &qr))
+        }
+
+        _ => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("Method not found: {}", method),
+        )),
+    }
+}
+
+/// Parses positional parameters into a tuple.
+///
+/// A missing `params` member is treated as an empty list.
+fn parse_params<T: DeserializeOwned>(params: Value) -> std::result::Result<T, RpcError> {
+    serde_json::from_value(params).map_err(|err| RpcError::new(INVALID_PARAMS, err.to_string()))
+}
+
+/// Checks that no parameters are passed to a method without parameters.
+fn no_params(params: &Value) -> std::result::Result<(), RpcError> {
+    match params {
+        Value::Null => Ok(()),
+        Value::Array(params) if params.is_empty() => Ok(()),
+        _ => Err(RpcError::new(INVALID_PARAMS, "Method takes no parameters")),
+    }
+}
+
+fn parse_config_key(key: &str) -> std::result::Result<Config, RpcError> {
+    key.parse()
+        .map_err(|_| RpcError::new(INVALID_PARAMS, format!("Unknown config key: {}", key)))
+}
+
+fn to_value(value: impl Serialize) -> RpcResult {
+    serde_json::to_value(value).map_err(|err| RpcError::new(CORE_ERROR, err.to_string()))
+}
+
+fn response(id: Value, result: RpcResult) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::tempdir;
+
+    fn call(server: &RpcServer, request: Value) -> Value {
+        call_raw(server, &request.to_string())
+    }
+
+    fn call_raw(server: &RpcServer, request: &str) -> Value {
+        let response = server.handle_request(request).unwrap();
+        serde_json::from_str(&response).unwrap()
+    }
+
+    #[test]
+    fn test_rpc_server() {
+        let dir = tempdir().unwrap();
+        let accounts = Accounts::new(
+            Box::new(|_, _, _| {}),
+            "FakeOs".into(),
+            dir.path().join("accounts"),
+        )
+        .unwrap();
+        let server = RpcServer::new(Arc::new(accounts));
+
+        let res = call(
+            &server,
+            json!({"jsonrpc": "2.0", "id": 1, "method": "get_config", "params": ["addr"]}),
+        );
+        assert_eq!(res["error"]["code"], CORE_ERROR);
+
+        let res = call(
+            &server,
+            json!({"jsonrpc": "2.0", "id": 2, "method": "add_account"}),
+        );
+        assert_eq!(res["id"], 2);
+        assert_eq!(res["result"], 1);
+
+        let res = call(
+            &server,
+            json!({"jsonrpc": "2.0", "id": 3, "method": "create_contact", "params": ["Bob", "bob@example.org"]}),
+        );
+        let contact_id = res["result"].as_u64().unwrap();
+        let res = call(
+            &server,
+            json!({"jsonrpc": "2.0", "id": 4, "method": "get_contact", "params": [contact_id]}),
+        );
+        assert_eq!(res["result"]["addr"], "bob@example.org");
+
+        let res = call(
+            &server,
+            json!({"jsonrpc": "2.0", "id": 5, "method": "get_contact", "params": ["wrong"]}),
+        );
+        assert_eq!(res["error"]["code"], INVALID_PARAMS);
+
+        let res = call(
+            &server,
+            json!({"jsonrpc": "2.0", "id": 6, "method": "no_such_method"}),
+        );
+        assert_eq!(res["error"]["code"], METHOD_NOT_FOUND);
+
+        let res = call(
+            &server,
+            json!({"jsonrpc": "2.0", "id": 7, "method": "get_outbox_msgs"}),
+        );
+        assert_eq!(res["result"], json!([]));
+        let res = call(
+            &server,
+            json!({"jsonrpc": "2.0", "id": 8, "method": "get_outbox_msgs", "params": [1]}),
+        );
+        assert_eq!(res["error"]["code"], INVALID_PARAMS);
+
+        // notifications get no response
+        assert!(server
+            .handle_request(r#"{"jsonrpc":"2.0","method":"is_configured"}"#)
+            .is_none());
+
+        let res: Value = serde_json::from_str(&server.handle_request("{").unwrap()).unwrap();
+        assert_eq!(res["error"]["code"], PARSE_ERROR);
+    }
+
+    #[test]
+    fn test_rpc_invalid_requests() {
+        let dir = tempdir().unwrap();
+        let accounts = Accounts::new(
+            Box::new(|_, _, _| {}),
+            "FakeOs".into(),
+            dir.path().join("accounts"),
+        )
+        .unwrap();
+        let server = RpcServer::new(Arc::new(accounts));
+
+        let res = call_raw(&server, r#"{"jsonrpc":"2.0","method":"is_configured""#);
+        assert_eq!(res["error"]["code"], PARSE_ERROR);
+        assert_eq!(res["id"], Value::Null);
+
+        // invalid requests are answered even without id
+        for request in &[
+            r#"1"#,
+            r#""is_configured""#,
+            r#"{}"#,
+            r#"{"jsonrpc":"1.0","method":"is_configured"}"#,
+            r#"{"jsonrpc":"2.0","method":1}"#,
+            r#"{"jsonrpc":"2.0","method":"is_configured","params":1}"#,
+            r#"{"jsonrpc":"2.0","id":[1],"method":"is_configured"}"#,
+            r#"[]"#,
+        ] {
+            let res = call_raw(&server, request);
+            assert_eq!(res["error"]["code"], INVALID_REQUEST, "{}", request);
+            assert_eq!(res["id"], Value::Null, "{}", request);
+        }
+
+        let res = call_raw(&server, r#"{"jsonrpc":"2.0","id":"a","method":1}"#);
+        assert_eq!(res["error"]["code"], INVALID_REQUEST);
+        assert_eq!(res["id"], "a");
+    }
+
+    #[test]
+    fn test_rpc_batch() {
+        let dir = tempdir().unwrap();
+        let accounts = Accounts::new(
+            Box::new(|_, _, _| {}),
+            "FakeOs".into(),
+            dir.path().join("accounts"),
+        )
+        .unwrap();
+        let server = RpcServer::new(Arc::new(accounts));
+
+        let res = call_raw(
+            &server,
+            r#"[
+                {"jsonrpc":"2.0","id":1,"method":"get_all_account_ids"},
+                {"jsonrpc":"2.0","method":"get_all_account_ids"},
+                1,
+                {"jsonrpc":"2.0","id":2,"method":"no_such_method"}
+            ]"#,
+        );
+        let responses = res.as_array().unwrap();
+        assert_eq!(responses.len(), 3);
+        assert_eq!(responses[0]["id"], 1);
+        assert_eq!(responses[0]["result"], json!([]));
+        assert_eq!(responses[1]["id"], Value::Null);
+        assert_eq!(responses[1]["error"]["code"], INVALID_REQUEST);
+        assert_eq!(responses[2]["id"], 2);
+        assert_eq!(responses[2]["error"]["code"], METHOD_NOT_FOUND);
+
+        // a batch of notifications gets no response
+        assert!(server
+            .handle_request(r#"[{"jsonrpc":"2.0","method":"get_all_account_ids"}]"#)
+            .is_none());
+    }
+
+    #[test]
+    fn test_event_notification() {
+        let notification = event_notification(
+            2,
+            &Event::MsgsChanged {
+                chat_id: ChatId::new(10),
+                msg_id: MsgId::new(11),
+            },
+        );
+        assert_eq!(notification["params"]["account_id"], 2);
+        assert_eq!(notification["params"]["event_id"], 2000);
+        assert_eq!(notification["params"]["event"]["type"], "MsgsChanged");
+        assert_eq!(notification["params"]["event"]["data"]["chat_id"], 10);
+    }
+}
@@ -0,0 +1,119 @@
+//! # Scheduler for the IMAP and SMTP loops
+//!
+//! The scheduler owns the tasks running the jobs, fetching and idling of the
+//! INBOX, MVBOX, SENTBOX and WATCH [JobThread]s and the SMTP loop.
+//! It is started by [Context::start_io] and stopped by [Context::stop_io].
+//!
+//! [JobThread]: crate::job_thread::JobThread
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use async_std::task::{self, JoinHandle};
+
+use crate::context::Context;
+use crate::job::*;
+
+#[derive(Debug)]
+pub(crate) enum Scheduler {
+    Stopped,
+    Running {
+        stop: Arc<AtomicBool>,
+        handles: Vec<JoinHandle<()>>,
+    },
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Scheduler::Stopped
+    }
+}
+
+impl Scheduler {
+    /// Starts the loops, each loop runs until the scheduler is stopped.
+    pub(crate) fn start(context: &Context) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let handles = vec![
+            spawn_loop(context, &stop, |ctx, stop| {
+                perform_inbox_jobs(ctx);
+                if !stop.load(Ordering::Relaxed) {
+                    perform_inbox_fetch(ctx);
+                    if !stop.load(Ordering::Relaxed) {
+                        perform_inbox_idle(ctx);
+                    }
+                }
+            }),
+            spawn_loop(context, &stop, |ctx, stop| {
+                perform_mvbox_fetch(ctx);
+                if !stop.load(Ordering::Relaxed) {
+                    perform_mvbox_idle(ctx);
+                }
+            }),
+            spawn_loop(context, &stop, |ctx, stop| {
+                perform_sentbox_fetch(ctx);
+                if !stop.load(Ordering::Relaxed) {
+                    perform_sentbox_idle(ctx);
+                }
+            }),
+            spawn_loop(context, &stop, |ctx, stop| {
+                perform_watch_fetch(ctx);
+                if !stop.load(Ordering::Relaxed) {
+                    perform_watch_idle(ctx);
+                }
+            }),
+            spawn_loop(context, &stop, |ctx, stop| {
+                perform_smtp_jobs(ctx);
+                if !stop.load(Ordering::Relaxed) {
+                    perform_smtp_idle(ctx);
+                }
+            }),
+        ];
+        Scheduler::Running { stop, handles }
+    }
+
+    pub(crate) fn is_running(&self) -> bool {
+        match self {
+            Scheduler::Stopped => false,
+            Scheduler::Running { .. } => true,
+        }
+    }
+
+    /// Stops the loops and waits until they are finished.
+    ///
+    /// The stop flag is set before interrupting, so a loop that is interrupted
+    /// while not idling does not start idling again.
+    pub(crate) fn stop(self, context: &Context) {
+        if let Scheduler::Running { stop, handles } = self {
+            stop.store(true, Ordering::Relaxed);
+            interrupt_inbox_idle(context);
+            interrupt_mvbox_idle(context);
+            interrupt_sentbox_idle(context);
+            interrupt_watch_idle(context);
+            interrupt_smtp_idle(context);
+            task::block_on(async move {
+                for handle in handles {
+                    handle.await;
+                }
+            });
+        }
+    }
+}
+
+/// Spawns a task calling `step` until `stop` is set.
+///
+/// The task holds an internal handle of the context,
+/// so the loop does not keep the account open.
+///
+/// The steps block while idling, so the loop runs on the blocking thread pool.
+fn spawn_loop<F>(context: &Context, stop: &Arc<AtomicBool>, step: F) -> JoinHandle<()>
+where
+    F: Fn(&Context, &AtomicBool) + Send + 'static,
+{
+    let context = context.clone_internal();
+    let stop = Arc::clone(stop);
+    task::spawn_blocking(move || {
+        while !stop.load(Ordering::Relaxed) {
+            step(&context, &stop);
+        }
+    })
+}
@@ -8,12 +8,14 @@ use crate::config::*;
 use crate::constants::*;
 use crate::contact::*;
 use crate::context::Context;
+use crate::dc_receive_imf::create_group_record;
+use crate::dc_tools::time;
 use crate::e2ee::*;
 use crate::error::Error;
 use crate::events::Event;
 use crate::headerdef::HeaderDef;
 use crate::key::{dc_normalize_fingerprint, Key};
-use crate::lot::LotState;
+use crate::lot::{Lot, LotState};
 use crate::message::Message;
 use crate::mimeparser::*;
 use crate::param::*;
@@ -24,6 +26,10 @@ use crate::token;
 
 pub const NON_ALPHANUMERIC_WITHOUT_DOT: &AsciiSet = &NON_ALPHANUMERIC.remove(b'.');
 
+/// Handshakes not finished after this number of seconds are aborted by the housekeeping,
+/// inviters may be offline for some days.
+const BOBSTATE_TIMEOUT: i64 = 7 * 24 * 60 * 60;
+
 macro_rules! joiner_progress {
     ($context:tt, $contact_id:expr, $progress:expr) => {
         assert!(
@@ -50,21 +56,6 @@ macro_rules! inviter_progress {
     };
 }
 
-macro_rules! get_qr_attr {
-    ($context:tt, $attr:ident) => {
-        $context
-            .bob
-            .read()
-            .unwrap()
-            .qr_scan
-            .as_ref()
-            .unwrap()
-            .$attr
-            .as_ref()
-            .unwrap()
-    };
-}
-
 pub fn dc_get_securejoin_qr(context: &Context, group_chat_id: ChatId) -> Option<String> {
     /*=======================================================
     ====             Alice - the inviter side            ====
@@ -143,89 +134,240 @@ fn get_self_fingerprint(context: &Context) -> Option<String> {
     None
 }
 
-/// Take a scanned QR-code and do the setup-contact/join-group handshake.
-/// See the ffi-documentation for more details.
-pub fn dc_join_securejoin(context: &Context, qr: &str) -> ChatId {
-    let cleanup =
-        |context: &Context, contact_chat_id: ChatId, ongoing_allocated: bool, join_vg: bool| {
-            let mut bob = context.bob.write().unwrap();
-            bob.expects = 0;
-            let ret_chat_id: ChatId = if bob.status == DC_BOB_SUCCESS {
-                if join_vg {
-                    chat::get_chat_id_by_grpid(
-                        context,
-                        bob.qr_scan.as_ref().unwrap().text2.as_ref().unwrap(),
-                    )
-                    .unwrap_or((ChatId::new(0), false, Blocked::Not))
-                    .0
-                } else {
-                    contact_chat_id
-                }
-            } else {
-                ChatId::new(0)
-            };
-            bob.qr_scan = None;
+/// State of a secure-join started by scanning a QR code, from the view of the joiner.
+///
+/// The state is stored in the `bobstate` table with the invite number as key,
+/// so that several handshakes can run at the same time.
+#[derive(Debug, Clone, PartialEq)]
+struct BobState {
+    invitenumber: String,
+    /// ID of the inviting contact.
+    contact_id: u32,
+    /// Fingerprint of the inviter as scanned from the QR code.
+    fingerprint: String,
+    auth: String,
+    /// ID of the group to join, empty for the setup-contact protocol.
+    grpid: String,
+    /// The step expected next, [DC_VC_AUTH_REQUIRED] or [DC_VC_CONTACT_CONFIRM].
+    expects: i32,
+    /// The group created for the handshake, removed if the handshake fails.
+    /// Unset if the group existed before.
+    created_chat_id: ChatId,
+}
+
+impl BobState {
+    fn is_group(&self) -> bool {
+        !self.grpid.is_empty()
+    }
 
-            if ongoing_allocated {
-                context.free_ongoing();
+    fn save(&self, context: &Context) -> Result<(), Error> {
+        context.sql.execute(
+            "INSERT OR REPLACE INTO bobstate \
+             (invitenumber, contact_id, fingerprint, auth, grpid, expects, chat_id, timestamp) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?);",
+            params![
+                self.invitenumber,
+                self.contact_id,
+                self.fingerprint,
+                self.auth,
+                self.grpid,
+                self.expects,
+                self.created_chat_id,
+                time()
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn set_expects(&mut self, context: &Context, expects: i32) -> Result<(), Error> {
+        self.expects = expects;
+        context.sql.execute(
+            "UPDATE bobstate SET expects=? WHERE invitenumber=?;",
+            params![expects, self.invitenumber],
+        )?;
+        Ok(())
+    }
+
+    fn delete(&self, context: &Context) -> Result<(), Error> {
+        context.sql.execute(
+            "DELETE FROM bobstate WHERE invitenumber=?;",
+            params![self.invitenumber],
+        )?;
+        Ok(())
+    }
+
+    /// Ends the handshake unsuccessfully.
+    ///
+    /// A group created for the handshake is deleted
+    /// unless another handshake for the same group is still pending.
+    fn abort(&self, context: &Context) {
+        if let Err(err) = self.delete(context) {
+            warn!(context, "Failed to delete secure-join state: {}", err);
+        }
+        if !self.created_chat_id.is_unset()
+            && !context
+                .sql
+                .exists(
+                    "SELECT invitenumber FROM bobstate WHERE grpid=?;",
+                    params![self.grpid],
+                )
+                .unwrap_or_default()
+        {
+            if let Err(err) = self.created_chat_id.delete(context) {
+                warn!(
+                    context,
+                    "Failed to delete group {} of secure-join: {}", self.created_chat_id, err
+                );
             }
-            ret_chat_id
-        };
+        }
+        joiner_progress!(context, self.contact_id, 0);
+    }
+
+    fn from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<BobState> {
+        Ok(BobState {
+            invitenumber: row.get(0)?,
+            contact_id: row.get(1)?,
+            fingerprint: row.get(2)?,
+            auth: row.get(3)?,
+            grpid: row.get(4)?,
+            expects: row.get(5)?,
+            created_chat_id: row.get(6)?,
+        })
+    }
+
+    /// Loads the handshake with `contact_id` expecting `expects` and matching `filter`.
+    ///
+    /// The handshake is identified by the `invitenumber` echoed by the inviter
+    /// in the `Secure-Join-Invitenumber` header.
+    /// Inviters of older versions do not echo the invite number,
+    /// their messages are only assigned if a single handshake matches,
+    /// so that concurrent handshakes are never mixed up.
+    fn load_pending(
+        context: &Context,
+        contact_id: u32,
+        expects: i32,
+        invitenumber: Option<&str>,
+        filter: impl Fn(&BobState) -> bool,
+    ) -> Result<Option<BobState>, Error> {
+        let states = context.sql.query_map(
+            "SELECT invitenumber, contact_id, fingerprint, auth, grpid, expects, chat_id \
+             FROM bobstate WHERE contact_id=? AND expects=?;",
+            params![contact_id, expects],
+            BobState::from_row,
+            |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+        )?;
+        let mut states = states.into_iter().filter(|state| {
+            filter(state) && invitenumber.map_or(true, |n| state.invitenumber == n)
+        });
+        let state = states.next();
+        if invitenumber.is_none() && states.next().is_some() {
+            warn!(
+                context,
+                "Several secure-joins with contact {} pending, cannot assign message.", contact_id
+            );
+            return Ok(None);
+        }
+        Ok(state)
+    }
+}
 
+/// Aborts the handshakes started as joiner that did not finish in time.
+///
+/// Called by the housekeeping, so that states and groups of handshakes
+/// that never get an answer do not stay forever.
+pub(crate) fn expire_bobstates(context: &Context) -> Result<(), Error> {
+    let states = context.sql.query_map(
+        "SELECT invitenumber, contact_id, fingerprint, auth, grpid, expects, chat_id \
+         FROM bobstate WHERE timestamp<?;",
+        params![time() - BOBSTATE_TIMEOUT],
+        BobState::from_row,
+        |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+    )?;
+    for state in states {
+        info!(
+            context,
+            "Secure-join with contact {} timed out.", state.contact_id
+        );
+        state.abort(context);
+    }
+    Ok(())
+}
+
+/// Take a scanned QR-code and start the setup-contact/join-group handshake.
+/// See the ffi-documentation for more details.
+///
+/// The function returns immediately, the handshake is done in the background
+/// and reported by [Event::SecurejoinJoinerProgress].
+/// Several handshakes can run at the same time.
+///
+/// Returns the one-to-one chat with the inviter for the setup-contact protocol
+/// and the group chat for the join-group protocol.
+/// If the group is not yet known, it is created without other members than the inviter,
+/// the remaining members are added when the inviter adds us to the group.
+/// On errors, `ChatId::new(0)` is returned.
+pub fn dc_join_securejoin(context: &Context, qr: &str) -> ChatId {
     /*========================================================
     ====             Bob - the joiner's side             =====
     ====   Step 2 in "Setup verified contact" protocol   =====
     ========================================================*/
 
-    let mut contact_chat_id = ChatId::new(0);
-    let mut join_vg: bool = false;
-
     info!(context, "Requesting secure-join ...",);
     ensure_secret_key_exists(context).ok();
-    if !context.alloc_ongoing() {
-        return cleanup(&context, contact_chat_id, false, join_vg);
-    }
     let qr_scan = check_qr(context, &qr);
     if qr_scan.state != LotState::QrAskVerifyContact && qr_scan.state != LotState::QrAskVerifyGroup
     {
         error!(context, "Unknown QR code.",);
-        return cleanup(&context, contact_chat_id, true, join_vg);
+        return ChatId::new(0);
     }
-    contact_chat_id = match chat::create_by_contact_id(context, qr_scan.id) {
+    let contact_chat_id = match chat::create_by_contact_id(context, qr_scan.id) {
         Ok(chat_id) => chat_id,
         Err(_) => {
             error!(context, "Unknown contact.");
-            return cleanup(&context, contact_chat_id, true, join_vg);
+            return ChatId::new(0);
         }
     };
-    if context.shall_stop_ongoing() {
-        return cleanup(&context, contact_chat_id, true, join_vg);
-    }
-    join_vg = qr_scan.get_state() == LotState::QrAskVerifyGroup;
-    {
-        let mut bob = context.bob.write().unwrap();
-        bob.status = 0;
-        bob.qr_scan = Some(qr_scan);
-    }
-    if fingerprint_equals_sender(
-        context,
-        context
-            .bob
-            .read()
-            .unwrap()
-            .qr_scan
-            .as_ref()
-            .unwrap()
-            .fingerprint
-            .as_ref()
-            .unwrap(),
-        contact_chat_id,
-    ) {
+    let join_vg = qr_scan.get_state() == LotState::QrAskVerifyGroup;
+    let (ret_chat_id, created_chat_id) = if join_vg {
+        match get_or_create_group(context, &qr_scan, qr_scan.id) {
+            Ok((chat_id, true)) => (chat_id, chat_id),
+            Ok((chat_id, false)) => (chat_id, ChatId::new(0)),
+            Err(err) => {
+                error!(context, "Cannot create group: {}", err);
+                return ChatId::new(0);
+            }
+        }
+    } else {
+        (contact_chat_id, ChatId::new(0))
+    };
+
+    let mut bobstate = BobState {
+        invitenumber: qr_scan.invitenumber.unwrap_or_default(),
+        contact_id: qr_scan.id,
+        fingerprint: qr_scan.fingerprint.unwrap_or_default(),
+        auth: qr_scan.auth.unwrap_or_default(),
+        grpid: if join_vg {
+            qr_scan.text2.unwrap_or_default()
+        } else {
+            String::new()
+        },
+        expects: DC_VC_AUTH_REQUIRED,
+        created_chat_id,
+    };
+    let shortcut = fingerprint_equals_sender(context, &bobstate.fingerprint, contact_chat_id);
+    if shortcut {
         // the scanned fingerprint matches Alice's key,
         // we can proceed to step 4b) directly and save two mails
         info!(context, "Taking protocol shortcut.");
-        context.bob.write().unwrap().expects = DC_VC_CONTACT_CONFIRM;
-        joiner_progress!(context, chat_id_2_contact_id(context, contact_chat_id), 400);
+        bobstate.expects = DC_VC_CONTACT_CONFIRM;
+    }
+    if let Err(err) = bobstate.save(context) {
+        error!(context, "Cannot save secure-join state: {}", err);
+        bobstate.abort(context);
+        return ChatId::new(0);
+    }
+
+    if shortcut {
+        joiner_progress!(context, bobstate.contact_id, 400);
         let own_fingerprint = get_self_fingerprint(context).unwrap_or_default();
 
         // Bob -> Alice
@@ -237,40 +379,55 @@ pub fn dc_join_securejoin(context: &Context, qr: &str) -> ChatId {
             } else {
                 "vc-request-with-auth"
             },
-            get_qr_attr!(context, auth).to_string(),
+            &bobstate.auth,
             Some(own_fingerprint),
-            if join_vg {
-                get_qr_attr!(context, text2).to_string()
-            } else {
-                "".to_string()
-            },
+            &bobstate.grpid,
         );
     } else {
-        context.bob.write().unwrap().expects = DC_VC_AUTH_REQUIRED;
-
         // Bob -> Alice
         send_handshake_msg(
             context,
             contact_chat_id,
             if join_vg { "vg-request" } else { "vc-request" },
-            get_qr_attr!(context, invitenumber),
+            &bobstate.invitenumber,
             None,
             "",
         );
     }
 
-    if join_vg {
-        // for a group-join, wait until the secure-join is done and the group is created
-        while !context.shall_stop_ongoing() {
-            std::thread::sleep(std::time::Duration::from_millis(200));
+    ret_chat_id
+}
+
+/// Returns the group to join and whether it was created,
+/// the group is created if it does not exist yet.
+///
+/// The group is created as a normal group with the inviter as the only other member,
+/// it is turned into a verified group if the inviter adds us to a verified group.
+fn get_or_create_group(
+    context: &Context,
+    qr_scan: &Lot,
+    inviter_id: u32,
+) -> Result<(ChatId, bool), Error> {
+    let grpid = qr_scan.text2.as_deref().unwrap_or_default();
+    ensure!(!grpid.is_empty(), "QR code without group ID");
+    if let Ok((chat_id, _, blocked)) = chat::get_chat_id_by_grpid(context, grpid) {
+        if blocked != Blocked::Not {
+            chat_id.unblock(context);
         }
-        cleanup(&context, contact_chat_id, true, join_vg)
-    } else {
-        // for a one-to-one-chat, the chat is already known, return the chat-id,
-        // the verification runs in background
-        context.free_ongoing();
-        contact_chat_id
+        return Ok((chat_id, false));
     }
+    let chat_id = create_group_record(
+        context,
+        grpid,
+        qr_scan.text1.as_deref().unwrap_or_default(),
+        Blocked::Not,
+        VerifiedStatus::Unverified,
+    );
+    ensure!(!chat_id.is_unset(), "Failed to create group {}", grpid);
+    chat::add_to_chat_contacts_table(context, chat_id, DC_CONTACT_ID_SELF);
+    chat::add_to_chat_contacts_table(context, chat_id, inviter_id);
+    emit_event!(context, Event::ChatModified(chat_id));
+    Ok((chat_id, true))
 }
 
 fn send_handshake_msg(
@@ -376,9 +533,8 @@ pub(crate) enum HandshakeMessage {
 
 /// Handle incoming secure-join handshake.
 ///
-/// This function will update the securejoin state in [Context::bob]
-/// and also terminate the ongoing process using
-/// [Context::stop_ongoing] as required by the protocol.
+/// This function will update the securejoin state of the joiner
+/// stored in the `bobstate` table as required by the protocol.
 ///
 /// A message which results in [Err] will be hidden from the user but
 /// not deleted, it may be a valid message for something else we are
@@ -451,12 +607,13 @@ pub(crate) fn handle_securejoin_handshake(
 
             inviter_progress!(context, contact_id, 300);
 
-            // Alice -> Bob
+            // Alice -> Bob, the invitenumber is echoed
+            // so that Bob can tell concurrent handshakes apart
             send_handshake_msg(
                 context,
                 contact_chat_id,
                 &format!("{}-auth-required", &step[..2]),
-                "",
+                &invitenumber,
                 None,
                 "",
             );
@@ -469,23 +626,27 @@ pub(crate) fn handle_securejoin_handshake(
             ========================================================*/
 
             // verify that Alice's Autocrypt key and fingerprint matches the QR-code
-            let cond = {
-                let bob = context.bob.read().unwrap();
-                let scan = bob.qr_scan.as_ref();
-                scan.is_none()
-                    || bob.expects != DC_VC_AUTH_REQUIRED
-                    || join_vg && scan.unwrap().state != LotState::QrAskVerifyGroup
+            let invitenumber = mime_message.get(HeaderDef::SecureJoinInvitenumber);
+            let mut bobstate = match BobState::load_pending(
+                context,
+                contact_id,
+                DC_VC_AUTH_REQUIRED,
+                invitenumber.map(|n| n.as_str()),
+                |state| state.is_group() == join_vg,
+            ) {
+                Ok(Some(bobstate)) => bobstate,
+                Ok(None) => {
+                    warn!(context, "auth-required message out of sync.");
+                    // no error, just aborted somehow or a mail from another handshake
+                    return Ok(HandshakeMessage::Ignore);
+                }
+                Err(err) => {
+                    warn!(context, "Failed to load secure-join state: {}", err);
+                    return Ok(HandshakeMessage::Ignore);
+                }
             };
 
-            if cond {
-                warn!(context, "auth-required message out of sync.");
-                // no error, just aborted somehow or a mail from another handshake
-                return Ok(HandshakeMessage::Ignore);
-            }
-            let scanned_fingerprint_of_alice = get_qr_attr!(context, fingerprint).to_string();
-            let auth = get_qr_attr!(context, auth).to_string();
-
-            if !encrypted_and_signed(context, mime_message, &scanned_fingerprint_of_alice) {
+            if !encrypted_and_signed(context, mime_message, &bobstate.fingerprint) {
                 could_not_establish_secure_connection(
                     context,
                     contact_chat_id,
@@ -495,37 +656,34 @@ pub(crate) fn handle_securejoin_handshake(
                         "Not encrypted."
                     },
                 );
-                context.bob.write().unwrap().status = 0; // secure-join failed
-                context.stop_ongoing();
+                bobstate.abort(context);
                 return Ok(HandshakeMessage::Ignore);
             }
-            if !fingerprint_equals_sender(context, &scanned_fingerprint_of_alice, contact_chat_id) {
+            if !fingerprint_equals_sender(context, &bobstate.fingerprint, contact_chat_id) {
                 could_not_establish_secure_connection(
                     context,
                     contact_chat_id,
                     "Fingerprint mismatch on joiner-side.",
                 );
-                context.bob.write().unwrap().status = 0; // secure-join failed
-                context.stop_ongoing();
+                bobstate.abort(context);
                 return Ok(HandshakeMessage::Ignore);
             }
             info!(context, "Fingerprint verified.",);
             own_fingerprint = get_self_fingerprint(context).unwrap();
+            if let Err(err) = bobstate.set_expects(context, DC_VC_CONTACT_CONFIRM) {
+                warn!(context, "Failed to save secure-join state: {}", err);
+                return Ok(HandshakeMessage::Ignore);
+            }
             joiner_progress!(context, contact_id, 400);
-            context.bob.write().unwrap().expects = DC_VC_CONTACT_CONFIRM;
 
             // Bob -> Alice
             send_handshake_msg(
                 context,
                 contact_chat_id,
                 &format!("{}-request-with-auth", &step[..2]),
-                auth,
+                &bobstate.auth,
                 Some(own_fingerprint),
-                if join_vg {
-                    get_qr_attr!(context, text2).to_string()
-                } else {
-                    "".to_string()
-                },
+                &bobstate.grpid,
             );
             Ok(HandshakeMessage::Done)
         }
@@ -621,8 +779,19 @@ pub(crate) fn handle_securejoin_handshake(
                     }
                 }
             } else {
-                // Alice -> Bob
-                send_handshake_msg(context, contact_chat_id, "vc-contact-confirm", "", None, "");
+                // Alice -> Bob, the invitenumber of the setup-contact QR code is echoed
+                // so that Bob can tell concurrent handshakes apart
+                let invitenumber =
+                    token::lookup(context, token::Namespace::InviteNumber, ChatId::new(0))
+                        .unwrap_or_default();
+                send_handshake_msg(
+                    context,
+                    contact_chat_id,
+                    "vc-contact-confirm",
+                    invitenumber,
+                    None,
+                    "",
+                );
                 inviter_progress!(context, contact_id, 1000);
             }
             Ok(HandshakeMessage::Done)
@@ -638,60 +807,67 @@ pub(crate) fn handle_securejoin_handshake(
                 HandshakeMessage::Ignore
             };
 
-            if context.bob.read().unwrap().expects != DC_VC_CONTACT_CONFIRM {
-                info!(context, "Message belongs to a different handshake.",);
-                return Ok(abort_retval);
-            }
-            let cond = {
-                let bob = context.bob.read().unwrap();
-                let scan = bob.qr_scan.as_ref();
-                scan.is_none() || (join_vg && scan.unwrap().state != LotState::QrAskVerifyGroup)
+            let grpid = if join_vg {
+                match mime_message.get(HeaderDef::ChatGroupId) {
+                    Some(grpid) => grpid.to_string(),
+                    None => {
+                        warn!(context, "vg-member-added without group ID.");
+                        return Ok(abort_retval);
+                    }
+                }
+            } else {
+                String::new()
             };
-            if cond {
-                warn!(
-                    context,
-                    "Message out of sync or belongs to a different handshake.",
-                );
-                return Ok(abort_retval);
-            }
-            let scanned_fingerprint_of_alice = get_qr_attr!(context, fingerprint).to_string();
-
-            let vg_expect_encrypted = if join_vg {
-                let group_id = get_qr_attr!(context, text2).to_string();
-                // This is buggy, is_verified_group will always be
-                // false since the group is created by receive_imf by
-                // the very handshake message we're handling now.  But
-                // only after we have returned.  It does not impact
-                // the security invariants of secure-join however.
-                let (_, is_verified_group, _) = chat::get_chat_id_by_grpid(context, &group_id)
-                    .unwrap_or((ChatId::new(0), false, Blocked::Not));
-                // when joining a non-verified group
-                // the vg-member-added message may be unencrypted
-                // when not all group members have keys or prefer encryption.
-                // So only expect encryption if this is a verified group
-                is_verified_group
+            // vg-member-added is an ordinary group message identified by the group ID
+            let invitenumber = if join_vg {
+                None
             } else {
-                // setup contact is always encrypted
-                true
+                mime_message.get(HeaderDef::SecureJoinInvitenumber)
+            };
+            let bobstate = match BobState::load_pending(
+                context,
+                contact_id,
+                DC_VC_CONTACT_CONFIRM,
+                invitenumber.map(|n| n.as_str()),
+                |state| state.grpid == grpid,
+            ) {
+                Ok(Some(bobstate)) => bobstate,
+                Ok(None) => {
+                    info!(context, "Message belongs to a different handshake.",);
+                    return Ok(abort_retval);
+                }
+                Err(err) => {
+                    warn!(context, "Failed to load secure-join state: {}", err);
+                    return Ok(abort_retval);
+                }
             };
-            if vg_expect_encrypted
-                && !encrypted_and_signed(context, mime_message, &scanned_fingerprint_of_alice)
+
+            // when joining a non-verified group
+            // the vg-member-added message may be unencrypted
+            // when not all group members have keys or prefer encryption.
+            // So only expect encryption if this is a verified group,
+            // setup contact is always encrypted.
+            let join_verified_group =
+                join_vg && mime_message.get(HeaderDef::ChatVerified).is_some();
+            if (!join_vg || join_verified_group)
+                && !encrypted_and_signed(context, mime_message, &bobstate.fingerprint)
             {
                 could_not_establish_secure_connection(
                     context,
                     contact_chat_id,
                     "Contact confirm message not encrypted.",
                 );
-                context.bob.write().unwrap().status = 0;
+                bobstate.abort(context);
                 return Ok(abort_retval);
             }
 
-            if mark_peer_as_verified(context, &scanned_fingerprint_of_alice).is_err() {
+            if mark_peer_as_verified(context, &bobstate.fingerprint).is_err() {
                 could_not_establish_secure_connection(
                     context,
                     contact_chat_id,
                     "Fingerprint mismatch on joiner-side.",
                 );
+                bobstate.abort(context);
                 return Ok(abort_retval);
             }
             Contact::scaleup_origin_by_id(context, contact_id, Origin::SecurejoinJoined);
@@ -709,7 +885,14 @@ pub(crate) fn handle_securejoin_handshake(
                 return Ok(abort_retval);
             }
             secure_connection_established(context, contact_chat_id);
-            context.bob.write().unwrap().expects = 0;
+            if let Err(err) = bobstate.delete(context) {
+                warn!(context, "Failed to delete secure-join state: {}", err);
+            }
+            if join_verified_group {
+                // the group was created as a normal group by dc_join_securejoin(),
+                // the member-added message is added to the verified group then.
+                set_group_verified(context, &bobstate.grpid);
+            }
             if join_vg {
                 // Bob -> Alice
                 send_handshake_msg(
@@ -721,8 +904,7 @@ pub(crate) fn handle_securejoin_handshake(
                     "",
                 );
             }
-            context.bob.write().unwrap().status = 1;
-            context.stop_ongoing();
+            joiner_progress!(context, contact_id, 1000);
             Ok(if join_vg {
                 HandshakeMessage::Propagate
             } else {
@@ -770,6 +952,16 @@ pub(crate) fn handle_securejoin_handshake(
     }
 }
 
+/// Turns the group joined by secure-join into a verified group.
+fn set_group_verified(context: &Context, grpid: &str) {
+    if let Err(err) = context.sql.execute(
+        "UPDATE chats SET type=? WHERE grpid=? AND type=?;",
+        params![Chattype::VerifiedGroup, grpid, Chattype::Group],
+    ) {
+        warn!(context, "Failed to set group {} verified: {}", grpid, err);
+    }
+}
+
 fn secure_connection_established(context: &Context, contact_chat_id: ChatId) {
     let contact_id: u32 = chat_id_2_contact_id(context, contact_chat_id);
     let contact = Contact::get_by_id(context, contact_id);
@@ -891,3 +1083,112 @@ pub fn handle_degrade_event(context: &Context, peerstate: &Peerstate) -> Result<
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_expire_bobstates() {
+        let t = dummy_context();
+        let contact_id = Contact::create(&t.ctx, "Alice", "alice@example.net").unwrap();
+        let chat_id = create_group_record(
+            &t.ctx,
+            "abcdefghijk",
+            "foo",
+            Blocked::Not,
+            VerifiedStatus::Unverified,
+        );
+        let bobstate = |invitenumber: &str, grpid: &str, created_chat_id: ChatId| BobState {
+            invitenumber: invitenumber.to_string(),
+            contact_id,
+            fingerprint: "fingerprint".to_string(),
+            auth: "auth".to_string(),
+            grpid: grpid.to_string(),
+            expects: DC_VC_AUTH_REQUIRED,
+            created_chat_id,
+        };
+        bobstate("old", "abcdefghijk", chat_id)
+            .save(&t.ctx)
+            .unwrap();
+        bobstate("new", "", ChatId::new(0)).save(&t.ctx).unwrap();
+        t.ctx
+            .sql
+            .execute(
+                "UPDATE bobstate SET timestamp=? WHERE invitenumber='old';",
+                params![time() - BOBSTATE_TIMEOUT - 1],
+            )
+            .unwrap();
+
+        // the expired handshake and the group created for it are removed
+        expire_bobstates(&t.ctx).unwrap();
+        let pending = |expects| {
+            BobState::load_pending(&t.ctx, contact_id, expects, None, |_| true)
+                .unwrap()
+                .map(|state| state.invitenumber)
+        };
+        assert_eq!(pending(DC_VC_AUTH_REQUIRED), Some("new".to_string()));
+        assert!(Chat::load_from_db(&t.ctx, chat_id).is_err());
+        expire_bobstates(&t.ctx).unwrap();
+        assert_eq!(pending(DC_VC_AUTH_REQUIRED), Some("new".to_string()));
+    }
+
+    #[test]
+    fn test_concurrent_bobstates() {
+        let t = dummy_context();
+        let contact_id = Contact::create(&t.ctx, "Alice", "alice@example.net").unwrap();
+        let bobstate = |invitenumber: &str, grpid: &str| BobState {
+            invitenumber: invitenumber.to_string(),
+            contact_id,
+            fingerprint: "fingerprint".to_string(),
+            auth: format!("auth-{}", invitenumber),
+            grpid: grpid.to_string(),
+            expects: DC_VC_AUTH_REQUIRED,
+            created_chat_id: ChatId::new(0),
+        };
+        bobstate("first", "group1").save(&t.ctx).unwrap();
+        let mut second = bobstate("second", "group2");
+        second.save(&t.ctx).unwrap();
+        bobstate("third", "").save(&t.ctx).unwrap();
+
+        // the echoed invitenumber selects the handshake regardless of the order
+        let load = |expects, invitenumber: Option<&str>, join_vg: bool| {
+            BobState::load_pending(&t.ctx, contact_id, expects, invitenumber, |state| {
+                state.is_group() == join_vg
+            })
+            .unwrap()
+        };
+        let state = load(DC_VC_AUTH_REQUIRED, Some("second"), true).unwrap();
+        assert_eq!(state.grpid, "group2");
+        assert_eq!(state.auth, "auth-second");
+        let state = load(DC_VC_AUTH_REQUIRED, Some("first"), true).unwrap();
+        assert_eq!(state.grpid, "group1");
+        assert!(load(DC_VC_AUTH_REQUIRED, Some("third"), true).is_none());
+        assert!(load(DC_VC_AUTH_REQUIRED, Some("unknown"), true).is_none());
+
+        // without invitenumber, ambiguous messages are not assigned
+        assert!(load(DC_VC_AUTH_REQUIRED, None, true).is_none());
+        let state = load(DC_VC_AUTH_REQUIRED, None, false).unwrap();
+        assert_eq!(state.invitenumber, "third");
+
+        // the member-added message is assigned by the group ID
+        second.set_expects(&t.ctx, DC_VC_CONTACT_CONFIRM).unwrap();
+        assert!(load(DC_VC_AUTH_REQUIRED, Some("second"), true).is_none());
+        let state =
+            BobState::load_pending(&t.ctx, contact_id, DC_VC_CONTACT_CONFIRM, None, |state| {
+                state.grpid == "group2"
+            })
+            .unwrap()
+            .unwrap();
+        assert_eq!(state.invitenumber, "second");
+        assert!(
+            BobState::load_pending(&t.ctx, contact_id, DC_VC_CONTACT_CONFIRM, None, |state| {
+                state.grpid == "group1"
+            })
+            .unwrap()
+            .is_none()
+        );
+    }
+}
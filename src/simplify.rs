@@ -1,14 +1,15 @@
 /// Remove standard (RFC 3676, §4.3) footer if it is found.
-fn remove_message_footer<'a>(lines: &'a [&str]) -> &'a [&'a str] {
+/// Returns the lines before the footer and the lines of the footer, if any.
+fn remove_message_footer<'a>(lines: &'a [&str]) -> (&'a [&'a str], Option<&'a [&'a str]>) {
     for (ix, &line) in lines.iter().enumerate() {
         // quoted-printable may encode `-- ` to `-- =20` which is converted
         // back to `--  `
         match line {
-            "-- " | "--  " => return &lines[..ix],
+            "-- " | "--  " => return (&lines[..ix], Some(&lines[ix + 1..])),
             _ => (),
         }
     }
-    lines
+    (lines, None)
 }
 
 /// Remove nonstandard footer and a boolean indicating whether such
@@ -36,31 +37,45 @@ fn split_lines(buf: &str) -> Vec<&str> {
 
 /// Simplify message text for chat display.
 /// Remove quotes, signatures, trailing empty lines etc.
-pub fn simplify(mut input: String, is_chat_message: bool) -> (String, bool) {
+///
+/// Returns the simplified text, a boolean indicating whether the message is forwarded,
+/// the text of the removed quote, if any, and the text of the standard footer, if any.
+/// For chat messages, the footer is the status of the sender
+/// and a top quote is only removed if the message `is_reply` to a known message,
+/// otherwise lines starting with `>` are part of the text.
+pub fn simplify(
+    mut input: String,
+    is_chat_message: bool,
+    is_reply: bool,
+) -> (String, bool, Option<String>, Option<String>) {
     input.retain(|c| c != '\r');
     let lines = split_lines(&input);
     let (lines, is_forwarded) = skip_forward_header(&lines);
 
-    let lines = remove_message_footer(lines);
+    let (lines, footer_lines) = remove_message_footer(lines);
+    let footer = footer_lines
+        .map(|lines| lines.join("\n").trim().to_string())
+        .filter(|footer| !footer.is_empty());
     let (lines, has_nonstandard_footer) = remove_nonstandard_footer(lines);
-    let (lines, has_bottom_quote) = if !is_chat_message {
+    let (lines, bottom_quote) = if !is_chat_message {
         remove_bottom_quote(lines)
     } else {
-        (lines, false)
+        (lines, None)
     };
-    let (lines, has_top_quote) = if !is_chat_message {
+    // chat messages quote only at the top of replies, see `MimeFactory::render_message()`
+    let (lines, top_quote) = if !is_chat_message || is_reply {
         remove_top_quote(lines)
     } else {
-        (lines, false)
+        (lines, None)
     };
 
     // re-create buffer from the remaining lines
     let text = render_message(
         lines,
-        has_top_quote,
-        has_nonstandard_footer || has_bottom_quote,
+        top_quote.is_some() && !is_chat_message,
+        has_nonstandard_footer || bottom_quote.is_some(),
     );
-    (text, is_forwarded)
+    (text, is_forwarded, top_quote.or(bottom_quote), footer)
 }
 
 /// Skips "forwarded message" header.
@@ -78,7 +93,7 @@ fn skip_forward_header<'a>(lines: &'a [&str]) -> (&'a [&'a str], bool) {
     }
 }
 
-fn remove_bottom_quote<'a>(lines: &'a [&str]) -> (&'a [&'a str], bool) {
+fn remove_bottom_quote<'a>(lines: &'a [&str]) -> (&'a [&'a str], Option<String>) {
     let mut last_quoted_line = None;
     for (l, line) in lines.iter().enumerate().rev() {
         if is_plain_quote(line) {
@@ -97,13 +112,14 @@ fn remove_bottom_quote<'a>(lines: &'a [&str]) -> (&'a [&'a str], bool) {
                 l_last -= 1
             }
         }
-        (&lines[..l_last], true)
+        let quote = render_quote(&lines[l_last..]);
+        (&lines[..l_last], Some(quote))
     } else {
-        (lines, false)
+        (lines, None)
     }
 }
 
-fn remove_top_quote<'a>(lines: &'a [&str]) -> (&'a [&'a str], bool) {
+fn remove_top_quote<'a>(lines: &'a [&str]) -> (&'a [&'a str], Option<String>) {
     let mut last_quoted_line = None;
     let mut has_quoted_headline = false;
     for (l, line) in lines.iter().enumerate() {
@@ -119,12 +135,32 @@ fn remove_top_quote<'a>(lines: &'a [&str]) -> (&'a [&'a str], bool) {
         }
     }
     if let Some(last_quoted_line) = last_quoted_line {
-        (&lines[last_quoted_line + 1..], true)
+        let quote = render_quote(&lines[..=last_quoted_line]);
+        (&lines[last_quoted_line + 1..], Some(quote))
     } else {
-        (lines, false)
+        (lines, None)
     }
 }
 
+/// Returns the text of the quoted lines without the leading `>` and without headlines.
+fn render_quote(lines: &[&str]) -> String {
+    lines
+        .iter()
+        .filter(|line| is_plain_quote(line))
+        .map(|line| {
+            let line = &line[1..];
+            if line.starts_with(' ') {
+                &line[1..]
+            } else {
+                line
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
 fn render_message(lines: &[&str], is_cut_at_begin: bool, is_cut_at_end: bool) -> String {
     let mut ret = String::new();
     if is_cut_at_begin {
@@ -199,7 +235,7 @@ mod tests {
         #[test]
         // proptest does not support [[:graphical:][:space:]] regex.
         fn test_simplify_plain_text_fuzzy(input in "[!-~\t \n]+") {
-            let (output, _is_forwarded) = simplify(input, true);
+            let (output, _is_forwarded, _quote, _footer) = simplify(input, true, false);
             assert!(output.split('\n').all(|s| s != "-- "));
         }
     }
@@ -207,7 +243,7 @@ mod tests {
     #[test]
     fn test_simplify_trim() {
         let input = "line1\n\r\r\rline2".to_string();
-        let (plain, is_forwarded) = simplify(input, false);
+        let (plain, is_forwarded, _, _) = simplify(input, false, false);
 
         assert_eq!(plain, "line1\nline2");
         assert!(!is_forwarded);
@@ -216,10 +252,28 @@ mod tests {
     #[test]
     fn test_simplify_forwarded_message() {
         let input = "---------- Forwarded message ----------\r\nFrom: test@example.com\r\n\r\nForwarded message\r\n-- \r\nSignature goes here".to_string();
-        let (plain, is_forwarded) = simplify(input, false);
+        let (plain, is_forwarded, _, footer) = simplify(input, false, false);
 
         assert_eq!(plain, "Forwarded message");
         assert!(is_forwarded);
+        assert_eq!(footer.unwrap(), "Signature goes here");
+    }
+
+    #[test]
+    fn test_simplify_footer() {
+        let input = "Hi\r\n-- \r\nSent with my\r\nDelta Chat Messenger\r\n".to_string();
+        let (plain, _, _, footer) = simplify(input, true, false);
+        assert_eq!(plain, "Hi");
+        assert_eq!(footer.unwrap(), "Sent with my\nDelta Chat Messenger");
+
+        let input = "Hi\r\n-- \r\n\r\n".to_string();
+        let (plain, _, _, footer) = simplify(input, true, false);
+        assert_eq!(plain, "Hi");
+        assert!(footer.is_none());
+
+        let input = "Hi\r\n---\r\nnonstandard footer".to_string();
+        let (_, _, _, footer) = simplify(input, false, false);
+        assert!(footer.is_none());
     }
 
     #[test]
@@ -236,16 +290,41 @@ mod tests {
 
     #[test]
     fn test_remove_top_quote() {
-        let (lines, has_top_quote) = remove_top_quote(&["> first", "> second"]);
+        let (lines, top_quote) = remove_top_quote(&["> first", "> second"]);
         assert!(lines.is_empty());
-        assert!(has_top_quote);
+        assert_eq!(top_quote.unwrap(), "first\nsecond");
 
-        let (lines, has_top_quote) = remove_top_quote(&["> first", "> second", "not a quote"]);
+        let (lines, top_quote) = remove_top_quote(&["> first", "> second", "not a quote"]);
         assert_eq!(lines, &["not a quote"]);
-        assert!(has_top_quote);
+        assert_eq!(top_quote.unwrap(), "first\nsecond");
 
-        let (lines, has_top_quote) = remove_top_quote(&["not a quote", "> first", "> second"]);
+        let (lines, top_quote) = remove_top_quote(&["not a quote", "> first", "> second"]);
         assert_eq!(lines, &["not a quote", "> first", "> second"]);
-        assert!(!has_top_quote);
+        assert!(top_quote.is_none());
+    }
+
+    #[test]
+    fn test_simplify_keeps_quote() {
+        let input = "> quoted\n>\n> text\n\nreply".to_string();
+        let (plain, _, quote, _) = simplify(input.clone(), true, true);
+        assert_eq!(plain, "reply");
+        assert_eq!(quote.unwrap(), "quoted\n\ntext");
+
+        let (plain, _, quote, _) = simplify(input, false, false);
+        assert_eq!(plain, "[...]reply");
+        assert_eq!(quote.unwrap(), "quoted\n\ntext");
+
+        let input = "reply\n\nOn 01.02.2020, bob@example.org wrote:\n> quoted".to_string();
+        let (plain, _, quote, _) = simplify(input, false, false);
+        assert_eq!(plain, "reply [...]");
+        assert_eq!(quote.unwrap(), "quoted");
+    }
+
+    #[test]
+    fn test_simplify_chat_message_without_reply() {
+        // chat messages that are no replies are not quoting
+        let (plain, _, quote, _) = simplify("> foo".to_string(), true, false);
+        assert_eq!(plain, "> foo");
+        assert!(quote.is_none());
     }
 }
@@ -20,6 +20,26 @@ pub enum Error {
     NoTransport,
 }
 
+/// Returns a human-readable description of an error reply of the SMTP server.
+///
+/// Permanent 5xx replies are not retried,
+/// the sending of messages failing with temporary 4xx replies is tried again later.
+pub(crate) fn describe_send_error(err: &async_smtp::smtp::error::Error) -> String {
+    match err {
+        async_smtp::smtp::error::Error::Permanent(response) => format!(
+            "Permanent SMTP error {}: {}",
+            response.code,
+            response.message.join(" ")
+        ),
+        async_smtp::smtp::error::Error::Transient(response) => format!(
+            "Temporary SMTP error {}: {}",
+            response.code,
+            response.message.join(" ")
+        ),
+        _ => err.to_string(),
+    }
+}
+
 impl Smtp {
     /// Send a prepared mail to recipients.
     /// On successful send out Ok() is returned.
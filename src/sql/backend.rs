@@ -0,0 +1,126 @@
+//! # Storage backends
+//!
+//! The database is always SQLite accessed through a pool of rusqlite connections,
+//! a [Backend] decides how the database file is prepared, how new connections
+//! are initialized and whether an opened database is usable.
+//!
+//! [Sqlite] opens plain database files, [SqlCipher] encrypts them with a passphrase.
+
+use std::fmt;
+use std::path::Path;
+
+use rusqlite::{Connection, NO_PARAMS};
+
+use super::{has_sqlcipher, is_unencrypted_database, rewrite_database_file, Error, Result};
+use crate::context::Context;
+
+/// Storage backend of a [Sql](super::Sql).
+pub trait Backend: fmt::Debug + Send + Sync {
+    /// Returns the passphrase the database is encrypted with,
+    /// `None` if the database is not encrypted.
+    fn passphrase(&self) -> Option<&str>;
+
+    /// Prepares the database file before the connection pool is created.
+    fn prepare_file(
+        &self,
+        context: &Context,
+        dbfile: &Path,
+        readonly: bool,
+    ) -> crate::error::Result<()>;
+
+    /// Initializes every new connection of the pool.
+    fn init_connection(&self, conn: &mut Connection) -> rusqlite::Result<()>;
+
+    /// Checks that the opened database can be used.
+    fn check(&self, conn: &Connection) -> Result<()>;
+}
+
+/// Returns the backend for a database encrypted with `passphrase`,
+/// without a passphrase the database is not encrypted.
+pub fn from_passphrase(passphrase: Option<&str>) -> Box<dyn Backend> {
+    match passphrase {
+        Some(passphrase) => Box::new(SqlCipher::new(passphrase)),
+        None => Box::new(Sqlite),
+    }
+}
+
+/// Plain SQLite database file.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Sqlite;
+
+impl Backend for Sqlite {
+    fn passphrase(&self) -> Option<&str> {
+        None
+    }
+
+    fn prepare_file(
+        &self,
+        _context: &Context,
+        _dbfile: &Path,
+        _readonly: bool,
+    ) -> crate::error::Result<()> {
+        Ok(())
+    }
+
+    fn init_connection(&self, conn: &mut Connection) -> rusqlite::Result<()> {
+        conn.execute_batch("PRAGMA secure_delete=on;")
+    }
+
+    fn check(&self, _conn: &Connection) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// SQLite database file encrypted by SQLCipher.
+///
+/// An existing unencrypted database is encrypted when it is opened the first time.
+#[derive(DebugStub, Clone)]
+pub struct SqlCipher {
+    #[debug_stub = "String"]
+    passphrase: String,
+}
+
+impl SqlCipher {
+    pub fn new(passphrase: impl Into<String>) -> Self {
+        Self {
+            passphrase: passphrase.into(),
+        }
+    }
+}
+
+impl Backend for SqlCipher {
+    fn passphrase(&self) -> Option<&str> {
+        Some(&self.passphrase)
+    }
+
+    fn prepare_file(
+        &self,
+        context: &Context,
+        dbfile: &Path,
+        readonly: bool,
+    ) -> crate::error::Result<()> {
+        // an empty key leaves the database unencrypted
+        ensure!(!self.passphrase.is_empty(), "Empty database passphrase");
+
+        if is_unencrypted_database(dbfile)? {
+            ensure!(!readonly, "Cannot encrypt database opened read-only");
+            info!(context, "Encrypting database {:?}.", dbfile);
+            rewrite_database_file(dbfile, None, Some(&self.passphrase))?;
+        }
+        Ok(())
+    }
+
+    fn init_connection(&self, conn: &mut Connection) -> rusqlite::Result<()> {
+        conn.pragma_update(None, "key", &self.passphrase)?;
+        conn.execute_batch("PRAGMA secure_delete=on;")
+    }
+
+    fn check(&self, conn: &Connection) -> Result<()> {
+        if !has_sqlcipher(conn)? {
+            return Err(Error::SqlCipherUnavailable);
+        }
+        // the key is only checked when the database is read
+        conn.query_row("SELECT COUNT(*) FROM sqlite_master;", NO_PARAMS, |_| Ok(()))
+            .map_err(|_| Error::SqlWrongPassphrase)
+    }
+}
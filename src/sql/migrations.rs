@@ -0,0 +1,1055 @@
+//! # Database migrations
+//!
+//! The database schema is upgraded by a list of migrations,
+//! each migration brings the database to the next `dbversion`.
+//!
+//! Every migration runs in its own transaction together with the update of `dbversion`,
+//! so a failed migration is rolled back and leaves the database at the previous version.
+//! There are no down-migrations, a database is never downgraded.
+//!
+//! To change the schema, append a migration to [MIGRATIONS]
+//! with a version higher than the last one.
+
+use std::path::Path;
+
+use rusqlite::{Connection, NO_PARAMS};
+
+use super::{Error, Result, Sql};
+use crate::chat::{update_device_icon, update_saved_messages_icon};
+use crate::constants::ShowEmails;
+use crate::context::Context;
+use crate::peerstate::*;
+
+/// A step of the database upgrade.
+pub(crate) struct Migration {
+    /// The `dbversion` of the database after the migration.
+    pub version: i32,
+    /// Short description of the migration, used for logging.
+    pub name: &'static str,
+    /// Changes the database, runs inside a transaction.
+    pub up: fn(&Connection, &mut Upgrade) -> Result<()>,
+}
+
+/// State shared by the migrations of one upgrade.
+#[derive(Debug, Default)]
+pub(crate) struct Upgrade {
+    /// True if the database existed before, false for new databases.
+    pub exists_before_update: bool,
+    /// Set by migrations that need the peerstate fingerprints to be recalculated.
+    pub recalc_fingerprints: bool,
+    /// Set by migrations that need the icons of special chats to be updated.
+    pub update_icons: bool,
+}
+
+/// All migrations, ordered by version.
+pub(crate) const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "leftgrps",
+        up: v1_leftgrps,
+    },
+    Migration {
+        version: 2,
+        name: "contact authname",
+        up: v2_contact_authname,
+    },
+    Migration {
+        version: 7,
+        name: "keypairs",
+        up: v7_keypairs,
+    },
+    Migration {
+        version: 10,
+        name: "acpeerstates",
+        up: v10_acpeerstates,
+    },
+    Migration {
+        version: 12,
+        name: "msgs_mdns",
+        up: v12_msgs_mdns,
+    },
+    Migration {
+        version: 17,
+        name: "archived chats and starred messages",
+        up: v17_archived_starred,
+    },
+    Migration {
+        version: 18,
+        name: "gossip",
+        up: v18_gossip,
+    },
+    Migration {
+        version: 27,
+        name: "sent and received timestamps",
+        up: v27_timestamps,
+    },
+    Migration {
+        version: 34,
+        name: "fingerprints",
+        up: v34_fingerprints,
+    },
+    Migration {
+        version: 39,
+        name: "tokens and verified keys",
+        up: v39_tokens_verified_keys,
+    },
+    Migration {
+        version: 40,
+        name: "job threads",
+        up: v40_job_thread,
+    },
+    Migration {
+        version: 44,
+        name: "mime headers",
+        up: v44_mime_headers,
+    },
+    Migration {
+        version: 46,
+        name: "in-reply-to and references",
+        up: v46_in_reply_to_references,
+    },
+    Migration {
+        version: 47,
+        name: "job tries",
+        up: v47_job_tries,
+    },
+    Migration {
+        version: 48,
+        name: "move state",
+        up: v48_move_state,
+    },
+    Migration {
+        version: 49,
+        name: "gossiped timestamp",
+        up: v49_gossiped_timestamp,
+    },
+    Migration {
+        version: 50,
+        name: "show emails default",
+        up: v50_show_emails,
+    },
+    Migration {
+        version: 53,
+        name: "locations",
+        up: v53_locations,
+    },
+    Migration {
+        version: 54,
+        name: "message locations",
+        up: v54_msg_location,
+    },
+    Migration {
+        version: 55,
+        name: "independent locations",
+        up: v55_independent_locations,
+    },
+    Migration {
+        version: 59,
+        name: "device message labels",
+        up: v59_devmsglabels,
+    },
+    Migration {
+        version: 60,
+        name: "chat creation timestamp",
+        up: v60_chat_created_timestamp,
+    },
+    Migration {
+        version: 61,
+        name: "self-avatar sent",
+        up: v61_selfavatar_sent,
+    },
+    Migration {
+        version: 62,
+        name: "muted chats",
+        up: v62_muted_until,
+    },
+    Migration {
+        version: 63,
+        name: "no grpid for single chats",
+        up: v63_reset_single_grpid,
+    },
+    Migration {
+        version: 64,
+        name: "ephemeral messages",
+        up: v64_ephemeral,
+    },
+    Migration {
+        version: 65,
+        name: "reactions",
+        up: v65_reactions,
+    },
+    Migration {
+        version: 66,
+        name: "full-text search",
+        up: v66_fts,
+    },
+    Migration {
+        version: 67,
+        name: "bobstate",
+        up: v67_bobstate,
+    },
+    Migration {
+        version: 68,
+        name: "bobstate chat",
+        up: v68_bobstate_chat,
+    },
+];
+
+/// Returns the `dbversion` the migrations upgrade to.
+pub(crate) fn latest_version() -> i32 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or_default()
+}
+
+/// Creates the tables of a new database and upgrades the database to the latest version.
+pub(super) fn run(context: &Context, sql: &Sql, dbfile: &Path) -> crate::error::Result<()> {
+    let exists_before_update = sql.table_exists("config");
+    if !exists_before_update {
+        info!(context, "First time init: creating tables in {:?}.", dbfile);
+        sql.with_conn(|conn| {
+            let tx = conn.transaction()?;
+            create_tables(&tx)?;
+            set_config_int(&tx, "dbversion", 0)?;
+            tx.commit()?;
+            Ok(())
+        })
+        .map_err(|err| {
+            // cannot create the tables - maybe we cannot write?
+            error!(
+                context,
+                "Cannot create tables in new database {:?}: {}", dbfile, err
+            );
+            Error::SqlFailedToOpen
+        })?;
+    }
+
+    // (1) update low-level database structure.
+    // this should be done before updates that use high-level objects that
+    // rely themselves on the low-level structure.
+    // --------------------------------------------------------------------
+
+    let dbversion = sql
+        .get_raw_config_int(context, "dbversion")
+        .unwrap_or_default();
+    if dbversion > latest_version() {
+        warn!(
+            context,
+            "Database version {} is newer than the supported version {}.",
+            dbversion,
+            latest_version()
+        );
+    }
+    let mut upgrade = Upgrade {
+        exists_before_update,
+        ..Default::default()
+    };
+    apply(context, sql, MIGRATIONS, &mut upgrade)?;
+
+    // (2) updates that require high-level objects
+    // (the structure is complete now and all objects are usable)
+    // --------------------------------------------------------------------
+
+    if upgrade.recalc_fingerprints {
+        info!(context, "[migration] recalc fingerprints");
+        sql.query_map(
+            "SELECT addr FROM acpeerstates;",
+            params![],
+            |row| row.get::<_, String>(0),
+            |addrs| {
+                for addr in addrs {
+                    if let Some(ref mut peerstate) = Peerstate::from_addr(context, sql, &addr?) {
+                        peerstate.recalc_fingerprint();
+                        peerstate.save_to_db(sql, false)?;
+                    }
+                }
+                Ok(())
+            },
+        )?;
+    }
+    if upgrade.update_icons {
+        update_saved_messages_icon(context)?;
+        update_device_icon(context)?;
+    }
+    Ok(())
+}
+
+/// Runs all `migrations` with a version above the current `dbversion`.
+///
+/// Stops at the first failing migration, which is rolled back.
+fn apply(
+    context: &Context,
+    sql: &Sql,
+    migrations: &[Migration],
+    upgrade: &mut Upgrade,
+) -> Result<()> {
+    let dbversion = sql
+        .get_raw_config_int(context, "dbversion")
+        .unwrap_or_default();
+    for migration in migrations.iter().filter(|m| m.version > dbversion) {
+        info!(
+            context,
+            "[migration] v{}: {}", migration.version, migration.name
+        );
+        sql.with_conn(|conn| {
+            let tx = conn.transaction()?;
+            (migration.up)(&tx, upgrade)?;
+            set_config_int(&tx, "dbversion", migration.version)?;
+            tx.commit()?;
+            Ok(())
+        })
+        .map_err(|err| {
+            error!(
+                context,
+                "[migration] v{} failed and was rolled back: {}", migration.version, err
+            );
+            err
+        })?;
+    }
+    Ok(())
+}
+
+fn config_exists(conn: &Connection, key: &str) -> Result<bool> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM config WHERE keyname=?;",
+        params![key],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+/// Sets a config value inside the transaction of a migration,
+/// [Sql::set_raw_config_int] would use another connection.
+fn set_config_int(conn: &Connection, key: &str, value: i32) -> Result<()> {
+    if config_exists(conn, key)? {
+        conn.execute(
+            "UPDATE config SET value=? WHERE keyname=?;",
+            params![value.to_string(), key],
+        )?;
+    } else {
+        conn.execute(
+            "INSERT INTO config (keyname, value) VALUES (?, ?);",
+            params![key, value.to_string()],
+        )?;
+    }
+    Ok(())
+}
+
+/// Creates the tables of a new database at dbversion 0.
+fn create_tables(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE config (id INTEGER PRIMARY KEY, keyname TEXT, value TEXT);",
+        NO_PARAMS,
+    )?;
+    conn.execute("CREATE INDEX config_index1 ON config (keyname);", NO_PARAMS)?;
+    conn.execute(
+        "CREATE TABLE contacts (\
+         id INTEGER PRIMARY KEY AUTOINCREMENT, \
+         name TEXT DEFAULT '', \
+         addr TEXT DEFAULT '' COLLATE NOCASE, \
+         origin INTEGER DEFAULT 0, \
+         blocked INTEGER DEFAULT 0, \
+         last_seen INTEGER DEFAULT 0, \
+         param TEXT DEFAULT '');",
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        "CREATE INDEX contacts_index1 ON contacts (name COLLATE NOCASE);",
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        "CREATE INDEX contacts_index2 ON contacts (addr COLLATE NOCASE);",
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        "INSERT INTO contacts (id,name,origin) VALUES \
+         (1,'self',262144), (2,'info',262144), (3,'rsvd',262144), \
+         (4,'rsvd',262144), (5,'device',262144), (6,'rsvd',262144), \
+         (7,'rsvd',262144), (8,'rsvd',262144), (9,'rsvd',262144);",
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        "CREATE TABLE chats (\
+         id INTEGER PRIMARY KEY AUTOINCREMENT,  \
+         type INTEGER DEFAULT 0, \
+         name TEXT DEFAULT '', \
+         draft_timestamp INTEGER DEFAULT 0, \
+         draft_txt TEXT DEFAULT '', \
+         blocked INTEGER DEFAULT 0, \
+         grpid TEXT DEFAULT '', \
+         param TEXT DEFAULT '');",
+        NO_PARAMS,
+    )?;
+    conn.execute("CREATE INDEX chats_index1 ON chats (grpid);", NO_PARAMS)?;
+    conn.execute(
+        "CREATE TABLE chats_contacts (chat_id INTEGER, contact_id INTEGER);",
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        "CREATE INDEX chats_contacts_index1 ON chats_contacts (chat_id);",
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        "INSERT INTO chats (id,type,name) VALUES \
+         (1,120,'deaddrop'), (2,120,'rsvd'), (3,120,'trash'), \
+         (4,120,'msgs_in_creation'), (5,120,'starred'), (6,120,'archivedlink'), \
+         (7,100,'rsvd'), (8,100,'rsvd'), (9,100,'rsvd');",
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        "CREATE TABLE msgs (\
+         id INTEGER PRIMARY KEY AUTOINCREMENT, \
+         rfc724_mid TEXT DEFAULT '', \
+         server_folder TEXT DEFAULT '', \
+         server_uid INTEGER DEFAULT 0, \
+         chat_id INTEGER DEFAULT 0, \
+         from_id INTEGER DEFAULT 0, \
+         to_id INTEGER DEFAULT 0, \
+         timestamp INTEGER DEFAULT 0, \
+         type INTEGER DEFAULT 0, \
+         state INTEGER DEFAULT 0, \
+         msgrmsg INTEGER DEFAULT 1, \
+         bytes INTEGER DEFAULT 0, \
+         txt TEXT DEFAULT '', \
+         txt_raw TEXT DEFAULT '', \
+         param TEXT DEFAULT '');",
+        NO_PARAMS,
+    )?;
+    conn.execute("CREATE INDEX msgs_index1 ON msgs (rfc724_mid);", NO_PARAMS)?;
+    conn.execute("CREATE INDEX msgs_index2 ON msgs (chat_id);", NO_PARAMS)?;
+    conn.execute("CREATE INDEX msgs_index3 ON msgs (timestamp);", NO_PARAMS)?;
+    conn.execute("CREATE INDEX msgs_index4 ON msgs (state);", NO_PARAMS)?;
+    conn.execute(
+        "INSERT INTO msgs (id,msgrmsg,txt) VALUES \
+         (1,0,'marker1'), (2,0,'rsvd'), (3,0,'rsvd'), \
+         (4,0,'rsvd'), (5,0,'rsvd'), (6,0,'rsvd'), (7,0,'rsvd'), \
+         (8,0,'rsvd'), (9,0,'daymarker');",
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        "CREATE TABLE jobs (\
+         id INTEGER PRIMARY KEY AUTOINCREMENT, \
+         added_timestamp INTEGER, \
+         desired_timestamp INTEGER DEFAULT 0, \
+         action INTEGER, \
+         foreign_id INTEGER, \
+         param TEXT DEFAULT '');",
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        "CREATE INDEX jobs_index1 ON jobs (desired_timestamp);",
+        NO_PARAMS,
+    )?;
+    Ok(())
+}
+
+fn v1_leftgrps(conn: &Connection, _upgrade: &mut Upgrade) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE leftgrps ( id INTEGER PRIMARY KEY, grpid TEXT DEFAULT '');",
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        "CREATE INDEX leftgrps_index1 ON leftgrps (grpid);",
+        NO_PARAMS,
+    )?;
+    Ok(())
+}
+
+fn v2_contact_authname(conn: &Connection, _upgrade: &mut Upgrade) -> Result<()> {
+    conn.execute(
+        "ALTER TABLE contacts ADD COLUMN authname TEXT DEFAULT '';",
+        NO_PARAMS,
+    )?;
+    Ok(())
+}
+
+fn v7_keypairs(conn: &Connection, _upgrade: &mut Upgrade) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE keypairs (\
+         id INTEGER PRIMARY KEY, \
+         addr TEXT DEFAULT '' COLLATE NOCASE, \
+         is_default INTEGER DEFAULT 0, \
+         private_key, \
+         public_key, \
+         created INTEGER DEFAULT 0);",
+        NO_PARAMS,
+    )?;
+    Ok(())
+}
+
+fn v10_acpeerstates(conn: &Connection, _upgrade: &mut Upgrade) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE acpeerstates (\
+         id INTEGER PRIMARY KEY, \
+         addr TEXT DEFAULT '' COLLATE NOCASE, \
+         last_seen INTEGER DEFAULT 0, \
+         last_seen_autocrypt INTEGER DEFAULT 0, \
+         public_key, \
+         prefer_encrypted INTEGER DEFAULT 0);",
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        "CREATE INDEX acpeerstates_index1 ON acpeerstates (addr);",
+        NO_PARAMS,
+    )?;
+    Ok(())
+}
+
+fn v12_msgs_mdns(conn: &Connection, _upgrade: &mut Upgrade) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE msgs_mdns ( msg_id INTEGER,  contact_id INTEGER);",
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        "CREATE INDEX msgs_mdns_index1 ON msgs_mdns (msg_id);",
+        NO_PARAMS,
+    )?;
+    Ok(())
+}
+
+fn v17_archived_starred(conn: &Connection, _upgrade: &mut Upgrade) -> Result<()> {
+    conn.execute(
+        "ALTER TABLE chats ADD COLUMN archived INTEGER DEFAULT 0;",
+        NO_PARAMS,
+    )?;
+    conn.execute("CREATE INDEX chats_index2 ON chats (archived);", NO_PARAMS)?;
+    conn.execute(
+        "ALTER TABLE msgs ADD COLUMN starred INTEGER DEFAULT 0;",
+        NO_PARAMS,
+    )?;
+    conn.execute("CREATE INDEX msgs_index5 ON msgs (starred);", NO_PARAMS)?;
+    Ok(())
+}
+
+fn v18_gossip(conn: &Connection, _upgrade: &mut Upgrade) -> Result<()> {
+    conn.execute(
+        "ALTER TABLE acpeerstates ADD COLUMN gossip_timestamp INTEGER DEFAULT 0;",
+        NO_PARAMS,
+    )?;
+    conn.execute("ALTER TABLE acpeerstates ADD COLUMN gossip_key;", NO_PARAMS)?;
+    Ok(())
+}
+
+fn v27_timestamps(conn: &Connection, _upgrade: &mut Upgrade) -> Result<()> {
+    // chat.id=1 and chat.id=2 are the old deaddrops,
+    // the current ones are defined by chats.blocked=2
+    conn.execute("DELETE FROM msgs WHERE chat_id=1 OR chat_id=2;", NO_PARAMS)?;
+    conn.execute(
+        "CREATE INDEX chats_contacts_index2 ON chats_contacts (contact_id);",
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        "ALTER TABLE msgs ADD COLUMN timestamp_sent INTEGER DEFAULT 0;",
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        "ALTER TABLE msgs ADD COLUMN timestamp_rcvd INTEGER DEFAULT 0;",
+        NO_PARAMS,
+    )?;
+    Ok(())
+}
+
+fn v34_fingerprints(conn: &Connection, upgrade: &mut Upgrade) -> Result<()> {
+    conn.execute(
+        "ALTER TABLE msgs ADD COLUMN hidden INTEGER DEFAULT 0;",
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        "ALTER TABLE msgs_mdns ADD COLUMN timestamp_sent INTEGER DEFAULT 0;",
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        "ALTER TABLE acpeerstates ADD COLUMN public_key_fingerprint TEXT DEFAULT '';",
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        "ALTER TABLE acpeerstates ADD COLUMN gossip_key_fingerprint TEXT DEFAULT '';",
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        "CREATE INDEX acpeerstates_index3 ON acpeerstates (public_key_fingerprint);",
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        "CREATE INDEX acpeerstates_index4 ON acpeerstates (gossip_key_fingerprint);",
+        NO_PARAMS,
+    )?;
+    upgrade.recalc_fingerprints = true;
+    Ok(())
+}
+
+fn v39_tokens_verified_keys(conn: &Connection, _upgrade: &mut Upgrade) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE tokens ( id INTEGER PRIMARY KEY, namespc INTEGER DEFAULT 0, foreign_id INTEGER DEFAULT 0, token TEXT DEFAULT '', timestamp INTEGER DEFAULT 0);",
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        "ALTER TABLE acpeerstates ADD COLUMN verified_key;",
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        "ALTER TABLE acpeerstates ADD COLUMN verified_key_fingerprint TEXT DEFAULT '';",
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        "CREATE INDEX acpeerstates_index5 ON acpeerstates (verified_key_fingerprint);",
+        NO_PARAMS,
+    )?;
+    Ok(())
+}
+
+fn v40_job_thread(conn: &Connection, _upgrade: &mut Upgrade) -> Result<()> {
+    conn.execute(
+        "ALTER TABLE jobs ADD COLUMN thread INTEGER DEFAULT 0;",
+        NO_PARAMS,
+    )?;
+    Ok(())
+}
+
+fn v44_mime_headers(conn: &Connection, _upgrade: &mut Upgrade) -> Result<()> {
+    conn.execute("ALTER TABLE msgs ADD COLUMN mime_headers TEXT;", NO_PARAMS)?;
+    Ok(())
+}
+
+fn v46_in_reply_to_references(conn: &Connection, _upgrade: &mut Upgrade) -> Result<()> {
+    conn.execute(
+        "ALTER TABLE msgs ADD COLUMN mime_in_reply_to TEXT;",
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        "ALTER TABLE msgs ADD COLUMN mime_references TEXT;",
+        NO_PARAMS,
+    )?;
+    Ok(())
+}
+
+fn v47_job_tries(conn: &Connection, _upgrade: &mut Upgrade) -> Result<()> {
+    conn.execute(
+        "ALTER TABLE jobs ADD COLUMN tries INTEGER DEFAULT 0;",
+        NO_PARAMS,
+    )?;
+    Ok(())
+}
+
+fn v48_move_state(conn: &Connection, _upgrade: &mut Upgrade) -> Result<()> {
+    // NOTE: move_state is not used anymore
+    conn.execute(
+        "ALTER TABLE msgs ADD COLUMN move_state INTEGER DEFAULT 1;",
+        NO_PARAMS,
+    )?;
+    Ok(())
+}
+
+fn v49_gossiped_timestamp(conn: &Connection, _upgrade: &mut Upgrade) -> Result<()> {
+    conn.execute(
+        "ALTER TABLE chats ADD COLUMN gossiped_timestamp INTEGER DEFAULT 0;",
+        NO_PARAMS,
+    )?;
+    Ok(())
+}
+
+fn v50_show_emails(conn: &Connection, upgrade: &mut Upgrade) -> Result<()> {
+    // installations <= 0.100.1 used DC_SHOW_EMAILS_ALL implicitly;
+    // keep this default and use DC_SHOW_EMAILS_NO
+    // only for new installations
+    if upgrade.exists_before_update {
+        set_config_int(conn, "show_emails", ShowEmails::All as i32)?;
+    }
+    Ok(())
+}
+
+fn v53_locations(conn: &Connection, _upgrade: &mut Upgrade) -> Result<()> {
+    // the messages containing _only_ locations
+    // are also added to the database as _hidden_.
+    conn.execute(
+        "CREATE TABLE locations ( id INTEGER PRIMARY KEY AUTOINCREMENT, latitude REAL DEFAULT 0.0, longitude REAL DEFAULT 0.0, accuracy REAL DEFAULT 0.0, timestamp INTEGER DEFAULT 0, chat_id INTEGER DEFAULT 0, from_id INTEGER DEFAULT 0);",
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        "CREATE INDEX locations_index1 ON locations (from_id);",
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        "CREATE INDEX locations_index2 ON locations (timestamp);",
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        "ALTER TABLE chats ADD COLUMN locations_send_begin INTEGER DEFAULT 0;",
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        "ALTER TABLE chats ADD COLUMN locations_send_until INTEGER DEFAULT 0;",
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        "ALTER TABLE chats ADD COLUMN locations_last_sent INTEGER DEFAULT 0;",
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        "CREATE INDEX chats_index3 ON chats (locations_send_until);",
+        NO_PARAMS,
+    )?;
+    Ok(())
+}
+
+fn v54_msg_location(conn: &Connection, _upgrade: &mut Upgrade) -> Result<()> {
+    conn.execute(
+        "ALTER TABLE msgs ADD COLUMN location_id INTEGER DEFAULT 0;",
+        NO_PARAMS,
+    )?;
+    conn.execute("CREATE INDEX msgs_index6 ON msgs (location_id);", NO_PARAMS)?;
+    Ok(())
+}
+
+fn v55_independent_locations(conn: &Connection, _upgrade: &mut Upgrade) -> Result<()> {
+    conn.execute(
+        "ALTER TABLE locations ADD COLUMN independent INTEGER DEFAULT 0;",
+        NO_PARAMS,
+    )?;
+    Ok(())
+}
+
+fn v59_devmsglabels(conn: &Connection, upgrade: &mut Upgrade) -> Result<()> {
+    // records in the devmsglabels are kept when the message is deleted.
+    // so, msg_id may or may not exist.
+    conn.execute(
+        "CREATE TABLE devmsglabels (id INTEGER PRIMARY KEY AUTOINCREMENT, label TEXT, msg_id INTEGER DEFAULT 0);",
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        "CREATE INDEX devmsglabels_index1 ON devmsglabels (label);",
+        NO_PARAMS,
+    )?;
+    if upgrade.exists_before_update && !config_exists(conn, "bcc_self")? {
+        set_config_int(conn, "bcc_self", 1)?;
+    }
+    Ok(())
+}
+
+fn v60_chat_created_timestamp(conn: &Connection, _upgrade: &mut Upgrade) -> Result<()> {
+    conn.execute(
+        "ALTER TABLE chats ADD COLUMN created_timestamp INTEGER DEFAULT 0;",
+        NO_PARAMS,
+    )?;
+    Ok(())
+}
+
+fn v61_selfavatar_sent(conn: &Connection, upgrade: &mut Upgrade) -> Result<()> {
+    conn.execute(
+        "ALTER TABLE contacts ADD COLUMN selfavatar_sent INTEGER DEFAULT 0;",
+        NO_PARAMS,
+    )?;
+    upgrade.update_icons = true;
+    Ok(())
+}
+
+fn v62_muted_until(conn: &Connection, _upgrade: &mut Upgrade) -> Result<()> {
+    conn.execute(
+        "ALTER TABLE chats ADD COLUMN muted_until INTEGER DEFAULT 0;",
+        NO_PARAMS,
+    )?;
+    Ok(())
+}
+
+fn v63_reset_single_grpid(conn: &Connection, _upgrade: &mut Upgrade) -> Result<()> {
+    conn.execute("UPDATE chats SET grpid='' WHERE type=100", NO_PARAMS)?;
+    Ok(())
+}
+
+fn v64_ephemeral(conn: &Connection, _upgrade: &mut Upgrade) -> Result<()> {
+    conn.execute(
+        "ALTER TABLE chats ADD COLUMN ephemeral_timer INTEGER DEFAULT 0;",
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        "ALTER TABLE msgs ADD COLUMN ephemeral_timer INTEGER DEFAULT 0;",
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        "ALTER TABLE msgs ADD COLUMN ephemeral_timestamp INTEGER DEFAULT 0;",
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        "CREATE INDEX msgs_index7 ON msgs (ephemeral_timestamp);",
+        NO_PARAMS,
+    )?;
+    Ok(())
+}
+
+fn v65_reactions(conn: &Connection, _upgrade: &mut Upgrade) -> Result<()> {
+    // one reaction per message and contact,
+    // a new reaction of a contact replaces the old one.
+    conn.execute(
+        "CREATE TABLE reactions (\
+         id INTEGER PRIMARY KEY AUTOINCREMENT, \
+         msg_id INTEGER NOT NULL, \
+         contact_id INTEGER NOT NULL, \
+         reaction TEXT DEFAULT '' NOT NULL, \
+         timestamp INTEGER DEFAULT 0, \
+         UNIQUE(msg_id, contact_id));",
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        "CREATE INDEX reactions_index1 ON reactions (msg_id);",
+        NO_PARAMS,
+    )?;
+    Ok(())
+}
+
+fn v66_fts(conn: &Connection, _upgrade: &mut Upgrade) -> Result<()> {
+    // full-text index of message texts and attachment filenames.
+    // the index does not store the content itself, it is read from the view msgs_search,
+    // the filename is extracted from the `f=` line of the param column.
+    let filename = |param: &str| {
+        format!(
+            "CASE WHEN instr(char(10)||{p}, char(10)||'f=')>0 \
+             THEN replace(substr({p}, instr(char(10)||{p}, char(10)||'f=')+2, \
+             instr(substr({p}||char(10), instr(char(10)||{p}, char(10)||'f=')+2), char(10))-1), \
+             '$BLOBDIR/', '') ELSE '' END",
+            p = param
+        )
+    };
+    conn.execute(
+        &format!(
+            "CREATE VIEW msgs_search AS SELECT id, txt, {} AS filename FROM msgs;",
+            filename("param")
+        ),
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        "CREATE VIRTUAL TABLE msgs_fts USING fts5(\
+         txt, filename, content='msgs_search', content_rowid='id');",
+        NO_PARAMS,
+    )?;
+    let index_new = format!(
+        "INSERT INTO msgs_fts (rowid, txt, filename) VALUES (new.id, new.txt, {});",
+        filename("new.param")
+    );
+    let index_delete_old = format!(
+        "INSERT INTO msgs_fts (msgs_fts, rowid, txt, filename) \
+         VALUES ('delete', old.id, old.txt, {});",
+        filename("old.param")
+    );
+    conn.execute(
+        &format!(
+            "CREATE TRIGGER msgs_fts_insert AFTER INSERT ON msgs BEGIN {} END;",
+            index_new
+        ),
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        &format!(
+            "CREATE TRIGGER msgs_fts_delete AFTER DELETE ON msgs BEGIN {} END;",
+            index_delete_old
+        ),
+        NO_PARAMS,
+    )?;
+    conn.execute(
+        &format!(
+            "CREATE TRIGGER msgs_fts_update AFTER UPDATE OF txt, param ON msgs BEGIN {} {} END;",
+            index_delete_old, index_new
+        ),
+        NO_PARAMS,
+    )?;
+    // backfill existing messages
+    conn.execute(
+        "INSERT INTO msgs_fts (msgs_fts) VALUES ('rebuild');",
+        NO_PARAMS,
+    )?;
+    Ok(())
+}
+
+fn v67_bobstate(conn: &Connection, _upgrade: &mut Upgrade) -> Result<()> {
+    // state of the secure-joins started by scanning a QR code,
+    // one row per scanned invitation so that several joins can run at the same time.
+    conn.execute(
+        "CREATE TABLE bobstate (\
+         invitenumber TEXT PRIMARY KEY, \
+         contact_id INTEGER NOT NULL, \
+         fingerprint TEXT NOT NULL, \
+         auth TEXT NOT NULL, \
+         grpid TEXT DEFAULT '' NOT NULL, \
+         expects INTEGER NOT NULL, \
+         timestamp INTEGER DEFAULT 0);",
+        NO_PARAMS,
+    )?;
+    Ok(())
+}
+
+fn v68_bobstate_chat(conn: &Connection, _upgrade: &mut Upgrade) -> Result<()> {
+    // the group created for a secure-join, deleted if the secure-join fails.
+    conn.execute(
+        "ALTER TABLE bobstate ADD COLUMN chat_id INTEGER DEFAULT 0;",
+        NO_PARAMS,
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::chat::ChatId;
+    use crate::config::Config;
+    use crate::contact::Contact;
+    use crate::test_utils::*;
+
+    /// Returns the names of all tables, indices, views and triggers
+    /// together with the columns of the tables.
+    fn schema(sql: &Sql) -> Vec<String> {
+        sql.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT type, name FROM sqlite_master WHERE name NOT LIKE 'sqlite_%' ORDER BY name;",
+            )?;
+            let objects = stmt
+                .query_map(NO_PARAMS, |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            let mut schema = Vec::new();
+            for (kind, name) in objects {
+                schema.push(format!("{} {}", kind, name));
+                if kind == "table" {
+                    conn.pragma(None, "table_info", &name, |row| {
+                        schema.push(format!(
+                            "{}.{} {} {:?}",
+                            name,
+                            row.get::<_, String>(1)?,
+                            row.get::<_, String>(2)?,
+                            row.get::<_, Option<String>>(4)?
+                        ));
+                        Ok(())
+                    })?;
+                }
+            }
+            Ok(schema)
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_migrations_ordered() {
+        for pair in MIGRATIONS.windows(2) {
+            assert!(pair[0].version < pair[1].version);
+        }
+        assert!(MIGRATIONS[0].version > 0);
+    }
+
+    #[test]
+    fn test_upgrade_old_databases() {
+        let t = dummy_context();
+        let expected = schema(&t.ctx.sql);
+        assert_eq!(
+            t.ctx.sql.get_raw_config_int(&t.ctx, "dbversion"),
+            Some(latest_version())
+        );
+
+        // the fixtures are databases as created by older versions
+        // with a contact, a chat and a message added
+        let fixtures: &[(i32, &[u8])] = &[
+            (
+                0,
+                &include_bytes!("../../test-data/db/dbversion-0.sqlite")[..],
+            ),
+            (
+                27,
+                &include_bytes!("../../test-data/db/dbversion-27.sqlite")[..],
+            ),
+            (
+                50,
+                &include_bytes!("../../test-data/db/dbversion-50.sqlite")[..],
+            ),
+            (
+                62,
+                &include_bytes!("../../test-data/db/dbversion-62.sqlite")[..],
+            ),
+        ];
+        for (version, fixture) in fixtures {
+            let dir = tempfile::tempdir().unwrap();
+            let dbfile = dir.path().join("db.sqlite");
+            std::fs::write(&dbfile, fixture).unwrap();
+            let ctx = Context::new(None, "FakeOS".into(), dbfile).unwrap();
+
+            assert_eq!(
+                ctx.sql.get_raw_config_int(&ctx, "dbversion"),
+                Some(latest_version()),
+                "dbversion after upgrading from v{}",
+                version
+            );
+            assert_eq!(
+                schema(&ctx.sql),
+                expected,
+                "schema after upgrading from v{}",
+                version
+            );
+
+            let contact = Contact::load_from_db(&ctx, 10).unwrap();
+            assert_eq!(contact.get_addr(), "bob@example.net");
+            let msg_ids = ctx.search_msgs(ChatId::new(0), "hello");
+            assert_eq!(
+                msg_ids.len(),
+                1,
+                "message not found after upgrading from v{}",
+                version
+            );
+
+            // defaults of existing installations are kept
+            if *version < 50 {
+                assert_eq!(
+                    ctx.get_config_int(Config::ShowEmails),
+                    ShowEmails::All as i32
+                );
+            }
+            if *version < 59 {
+                assert!(ctx.get_config_bool(Config::BccSelf));
+            }
+        }
+    }
+
+    fn create_test_table(conn: &Connection, _upgrade: &mut Upgrade) -> Result<()> {
+        conn.execute("CREATE TABLE test_migration (id INTEGER);", NO_PARAMS)?;
+        Ok(())
+    }
+
+    fn fail_halfway(conn: &Connection, _upgrade: &mut Upgrade) -> Result<()> {
+        conn.execute(
+            "ALTER TABLE test_migration ADD COLUMN halfway INTEGER;",
+            NO_PARAMS,
+        )?;
+        conn.execute("ALTER TABLE no_such_table ADD COLUMN x;", NO_PARAMS)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_failed_migration_is_rolled_back() {
+        let t = dummy_context();
+        let version = latest_version();
+        let migrations = [
+            Migration {
+                version: version + 1,
+                name: "create",
+                up: create_test_table,
+            },
+            Migration {
+                version: version + 2,
+                name: "fail",
+                up: fail_halfway,
+            },
+        ];
+        let res = apply(&t.ctx, &t.ctx.sql, &migrations, &mut Upgrade::default());
+        assert!(res.is_err());
+
+        assert_eq!(
+            t.ctx.sql.get_raw_config_int(&t.ctx, "dbversion"),
+            Some(version + 1)
+        );
+        assert!(t.ctx.sql.table_exists("test_migration"));
+        assert!(!schema(&t.ctx.sql).contains(&"test_migration.halfway INTEGER None".to_string()));
+
+        // the next start retries the failed migration
+        let res = apply(&t.ctx, &t.ctx.sql, &migrations, &mut Upgrade::default());
+        assert!(res.is_err());
+        assert_eq!(
+            t.ctx.sql.get_raw_config_int(&t.ctx, "dbversion"),
+            Some(version + 1)
+        );
+    }
+}
@@ -1,18 +1,26 @@
 //! # SQLite wrapper
 
 use std::collections::HashSet;
+use std::ffi::OsString;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
 use std::time::Duration;
 
 use rusqlite::{Connection, OpenFlags, Statement, NO_PARAMS};
 use thread_local_object::ThreadLocal;
 
-use crate::chat::{update_device_icon, update_saved_messages_icon};
-use crate::constants::ShowEmails;
 use crate::context::Context;
 use crate::dc_tools::*;
 use crate::param::*;
-use crate::peerstate::*;
+use crate::securejoin;
+
+mod backend;
+mod migrations;
+
+pub use backend::{Backend, SqlCipher, Sqlite};
+pub(crate) use migrations::latest_version;
 
 #[derive(Debug, Fail)]
 pub enum Error {
@@ -26,6 +34,12 @@ pub enum Error {
     SqlAlreadyOpen,
     #[fail(display = "Sqlite: Failed to open")]
     SqlFailedToOpen,
+    #[fail(display = "Sqlite: Encrypted databases need SQLCipher")]
+    SqlCipherUnavailable,
+    #[fail(display = "Sqlite: Wrong passphrase or no database")]
+    SqlWrongPassphrase,
+    #[fail(display = "Sqlite: The full-text search extension FTS5 is missing")]
+    SqlFts5Unavailable,
     #[fail(display = "{:?}", _0)]
     Io(#[cause] std::io::Error),
     #[fail(display = "{:?}", _0)]
@@ -64,6 +78,8 @@ pub struct Sql {
     pool: RwLock<Option<r2d2::Pool<r2d2_sqlite::SqliteConnectionManager>>>,
     #[debug_stub = "ThreadLocal<String>"]
     in_use: Arc<ThreadLocal<String>>,
+    /// Backend the database was opened with.
+    backend: RwLock<Option<Arc<dyn Backend>>>,
 }
 
 impl Default for Sql {
@@ -71,6 +87,7 @@ impl Default for Sql {
         Self {
             pool: RwLock::new(None),
             in_use: Arc::new(ThreadLocal::new()),
+            backend: RwLock::new(None),
         }
     }
 }
@@ -92,18 +109,114 @@ impl Sql {
         info!(context, "Database closed.");
     }
 
-    // return true on success, false on failure
-    pub fn open(&self, context: &Context, dbfile: &std::path::Path, readonly: bool) -> bool {
-        match open(context, self, dbfile, readonly) {
-            Ok(_) => true,
+    /// Opens the database, returns true on success, false on failure.
+    ///
+    /// If a `passphrase` is given, the database is encrypted using SQLCipher,
+    /// an existing unencrypted database is encrypted when it is opened the first time.
+    pub fn open(
+        &self,
+        context: &Context,
+        dbfile: &std::path::Path,
+        readonly: bool,
+        passphrase: Option<&str>,
+    ) -> bool {
+        self.open_with_backend(
+            context,
+            dbfile,
+            readonly,
+            backend::from_passphrase(passphrase),
+        )
+    }
+
+    /// Opens the database using the storage `backend`,
+    /// returns true on success, false on failure.
+    pub fn open_with_backend(
+        &self,
+        context: &Context,
+        dbfile: &std::path::Path,
+        readonly: bool,
+        backend: Box<dyn Backend>,
+    ) -> bool {
+        self.open_backend(context, dbfile, readonly, backend.into())
+    }
+
+    fn open_backend(
+        &self,
+        context: &Context,
+        dbfile: &std::path::Path,
+        readonly: bool,
+        backend: Arc<dyn Backend>,
+    ) -> bool {
+        match open(context, self, dbfile, readonly, Arc::clone(&backend)) {
+            Ok(_) => {
+                *self.backend.write().unwrap() = Some(backend);
+                true
+            }
             Err(crate::error::Error::SqlError(Error::SqlAlreadyOpen)) => false,
-            Err(_) => {
+            Err(err) => {
+                error!(context, "Cannot open database {:?}: {}", dbfile, err);
                 self.close(context);
                 false
             }
         }
     }
 
+    /// Re-opens the database with the backend it was opened with before,
+    /// eg. after the database file was replaced.
+    pub(crate) fn reopen(&self, context: &Context, dbfile: &std::path::Path) -> bool {
+        let backend = self.backend.read().unwrap().clone();
+        let backend = backend.unwrap_or_else(|| Arc::new(Sqlite));
+        self.open_backend(context, dbfile, false, backend)
+    }
+
+    /// Returns the passphrase of the database, `None` if it is not encrypted.
+    fn passphrase(&self) -> Option<String> {
+        self.backend
+            .read()
+            .unwrap()
+            .as_ref()
+            .and_then(|backend| backend.passphrase().map(|p| p.to_string()))
+    }
+
+    /// Returns true if the database is encrypted with a passphrase.
+    pub fn is_encrypted(&self) -> bool {
+        self.passphrase().is_some()
+    }
+
+    /// Changes the passphrase of the database to `passphrase`,
+    /// `None` removes the encryption.
+    ///
+    /// The database is closed and rewritten while changing the passphrase.
+    pub(crate) fn change_passphrase(
+        &self,
+        context: &Context,
+        dbfile: &Path,
+        passphrase: Option<&str>,
+    ) -> crate::error::Result<()> {
+        ensure!(passphrase != Some(""), "Empty database passphrase");
+        let old_passphrase = self.passphrase();
+        if old_passphrase.as_deref() == passphrase {
+            return Ok(());
+        }
+
+        self.close(context);
+        let res = rewrite_database_file(dbfile, old_passphrase.as_deref(), passphrase);
+        let reopened = if res.is_ok() {
+            self.open(context, dbfile, false, passphrase)
+        } else {
+            self.open(context, dbfile, false, old_passphrase.as_deref())
+        };
+        res?;
+        ensure!(reopened, "Cannot re-open database with the new passphrase");
+        info!(context, "Database passphrase changed.");
+        Ok(())
+    }
+
+    /// Writes an unencrypted copy of the encrypted database to `dest`.
+    pub(crate) fn export_unencrypted(&self, dest: &Path) -> Result<()> {
+        self.with_conn(|conn| export_database(conn, dest, None))
+    }
+
     pub fn execute<P>(&self, sql: &str, params: P) -> Result<usize>
     where
         P: IntoIterator,
@@ -382,12 +495,91 @@ fn table_exists(conn: &Connection, name: impl AsRef<str>) -> Result<bool> {
     Ok(exists)
 }
 
-#[allow(clippy::cognitive_complexity)]
+/// Returns true if SQLite is built with SQLCipher, which is needed for encrypted databases.
+fn has_sqlcipher(conn: &Connection) -> Result<bool> {
+    let mut stmt = conn.prepare("PRAGMA cipher_version;")?;
+    let mut rows = stmt.query(NO_PARAMS)?;
+    Ok(rows.next()?.is_some())
+}
+
+/// Returns true if SQLite is built with the full-text search extension FTS5,
+/// which is needed for the search index.
+///
+/// The bundled SQLite contains FTS5, builds of SQLCipher may lack it.
+fn has_fts5(conn: &Connection) -> Result<bool> {
+    let used: i32 = conn.query_row(
+        "SELECT sqlite_compileoption_used('ENABLE_FTS5');",
+        NO_PARAMS,
+        |row| row.get(0),
+    )?;
+    Ok(used != 0)
+}
+
+/// Returns true if `dbfile` is an existing unencrypted SQLite database.
+fn is_unencrypted_database(dbfile: &Path) -> Result<bool> {
+    if !dbfile.exists() {
+        return Ok(false);
+    }
+    let mut header = [0u8; 16];
+    let mut file = fs::File::open(dbfile)?;
+    match file.read_exact(&mut header) {
+        Ok(()) => Ok(&header == b"SQLite format 3\0"),
+        Err(ref err) if err.kind() == std::io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Copies the database opened by `conn` to the new database `dest`
+/// encrypted with `passphrase`, without a passphrase the copy is unencrypted.
+fn export_database(conn: &Connection, dest: &Path, passphrase: Option<&str>) -> Result<()> {
+    if !has_sqlcipher(conn)? {
+        return Err(Error::SqlCipherUnavailable);
+    }
+    conn.execute(
+        "ATTACH DATABASE ? AS export KEY ?;",
+        params![dest.to_string_lossy(), passphrase.unwrap_or_default()],
+    )?;
+    let res = conn.query_row("SELECT sqlcipher_export('export');", NO_PARAMS, |_| Ok(()));
+    conn.execute("DETACH DATABASE export;", NO_PARAMS)?;
+    res?;
+    Ok(())
+}
+
+/// Rewrites the database file encrypted with `new_passphrase` instead of `old_passphrase`,
+/// a missing passphrase stands for an unencrypted database.
+fn rewrite_database_file(
+    dbfile: &Path,
+    old_passphrase: Option<&str>,
+    new_passphrase: Option<&str>,
+) -> Result<()> {
+    let mut tmp_dbfile = OsString::from(dbfile);
+    tmp_dbfile.push("-rekey");
+    let tmp_dbfile = PathBuf::from(tmp_dbfile);
+    if tmp_dbfile.exists() {
+        fs::remove_file(&tmp_dbfile)?;
+    }
+
+    let res = Connection::open(dbfile)
+        .map_err(Error::from)
+        .and_then(|conn| {
+            if let Some(passphrase) = old_passphrase {
+                conn.pragma_update(None, "key", &passphrase)?;
+            }
+            export_database(&conn, &tmp_dbfile, new_passphrase)
+        })
+        .and_then(|()| fs::rename(&tmp_dbfile, dbfile).map_err(Error::from));
+    if res.is_err() && tmp_dbfile.exists() {
+        fs::remove_file(&tmp_dbfile).ok();
+    }
+    res
+}
+
 fn open(
     context: &Context,
     sql: &Sql,
     dbfile: impl AsRef<std::path::Path>,
     readonly: bool,
+    backend: Arc<dyn Backend>,
 ) -> crate::error::Result<()> {
     if sql.is_open() {
         error!(
@@ -397,7 +589,6 @@ fn open(
         );
         return Err(Error::SqlAlreadyOpen.into());
     }
-
     let mut open_flags = OpenFlags::SQLITE_OPEN_NO_MUTEX;
     if readonly {
         open_flags.insert(OpenFlags::SQLITE_OPEN_READ_ONLY);
@@ -405,9 +596,13 @@ fn open(
         open_flags.insert(OpenFlags::SQLITE_OPEN_READ_WRITE);
         open_flags.insert(OpenFlags::SQLITE_OPEN_CREATE);
     }
+
+    backend.prepare_file(context, dbfile.as_ref(), readonly)?;
+
+    let init_backend = Arc::clone(&backend);
     let mgr = r2d2_sqlite::SqliteConnectionManager::file(dbfile.as_ref())
         .with_flags(open_flags)
-        .with_init(|c| c.execute_batch("PRAGMA secure_delete=on;"));
+        .with_init(move |c| init_backend.init_connection(c));
     let pool = r2d2::Pool::builder()
         .min_idle(Some(2))
         .max_size(10)
@@ -419,512 +614,18 @@ fn open(
         *sql.pool.write().unwrap() = Some(pool);
     }
 
-    if !readonly {
-        let mut exists_before_update = false;
-        let mut dbversion_before_update = 0;
-        /* Init tables to dbversion=0 */
-        if !sql.table_exists("config") {
-            info!(
-                context,
-                "First time init: creating tables in {:?}.",
-                dbfile.as_ref(),
-            );
-            sql.execute(
-                "CREATE TABLE config (id INTEGER PRIMARY KEY, keyname TEXT, value TEXT);",
-                NO_PARAMS,
-            )?;
-            sql.execute("CREATE INDEX config_index1 ON config (keyname);", NO_PARAMS)?;
-            sql.execute(
-                "CREATE TABLE contacts (\
-                 id INTEGER PRIMARY KEY AUTOINCREMENT, \
-                 name TEXT DEFAULT '', \
-                 addr TEXT DEFAULT '' COLLATE NOCASE, \
-                 origin INTEGER DEFAULT 0, \
-                 blocked INTEGER DEFAULT 0, \
-                 last_seen INTEGER DEFAULT 0, \
-                 param TEXT DEFAULT '');",
-                params![],
-            )?;
-            sql.execute(
-                "CREATE INDEX contacts_index1 ON contacts (name COLLATE NOCASE);",
-                params![],
-            )?;
-            sql.execute(
-                "CREATE INDEX contacts_index2 ON contacts (addr COLLATE NOCASE);",
-                params![],
-            )?;
-            sql.execute(
-                "INSERT INTO contacts (id,name,origin) VALUES \
-                 (1,'self',262144), (2,'info',262144), (3,'rsvd',262144), \
-                 (4,'rsvd',262144), (5,'device',262144), (6,'rsvd',262144), \
-                 (7,'rsvd',262144), (8,'rsvd',262144), (9,'rsvd',262144);",
-                params![],
-            )?;
-            sql.execute(
-                "CREATE TABLE chats (\
-                 id INTEGER PRIMARY KEY AUTOINCREMENT,  \
-                 type INTEGER DEFAULT 0, \
-                 name TEXT DEFAULT '', \
-                 draft_timestamp INTEGER DEFAULT 0, \
-                 draft_txt TEXT DEFAULT '', \
-                 blocked INTEGER DEFAULT 0, \
-                 grpid TEXT DEFAULT '', \
-                 param TEXT DEFAULT '');",
-                params![],
-            )?;
-            sql.execute("CREATE INDEX chats_index1 ON chats (grpid);", params![])?;
-            sql.execute(
-                "CREATE TABLE chats_contacts (chat_id INTEGER, contact_id INTEGER);",
-                params![],
-            )?;
-            sql.execute(
-                "CREATE INDEX chats_contacts_index1 ON chats_contacts (chat_id);",
-                params![],
-            )?;
-            sql.execute(
-                "INSERT INTO chats (id,type,name) VALUES \
-                 (1,120,'deaddrop'), (2,120,'rsvd'), (3,120,'trash'), \
-                 (4,120,'msgs_in_creation'), (5,120,'starred'), (6,120,'archivedlink'), \
-                 (7,100,'rsvd'), (8,100,'rsvd'), (9,100,'rsvd');",
-                params![],
-            )?;
-            sql.execute(
-                "CREATE TABLE msgs (\
-                 id INTEGER PRIMARY KEY AUTOINCREMENT, \
-                 rfc724_mid TEXT DEFAULT '', \
-                 server_folder TEXT DEFAULT '', \
-                 server_uid INTEGER DEFAULT 0, \
-                 chat_id INTEGER DEFAULT 0, \
-                 from_id INTEGER DEFAULT 0, \
-                 to_id INTEGER DEFAULT 0, \
-                 timestamp INTEGER DEFAULT 0, \
-                 type INTEGER DEFAULT 0, \
-                 state INTEGER DEFAULT 0, \
-                 msgrmsg INTEGER DEFAULT 1, \
-                 bytes INTEGER DEFAULT 0, \
-                 txt TEXT DEFAULT '', \
-                 txt_raw TEXT DEFAULT '', \
-                 param TEXT DEFAULT '');",
-                params![],
-            )?;
-            sql.execute("CREATE INDEX msgs_index1 ON msgs (rfc724_mid);", params![])?;
-            sql.execute("CREATE INDEX msgs_index2 ON msgs (chat_id);", params![])?;
-            sql.execute("CREATE INDEX msgs_index3 ON msgs (timestamp);", params![])?;
-            sql.execute("CREATE INDEX msgs_index4 ON msgs (state);", params![])?;
-            sql.execute(
-                "INSERT INTO msgs (id,msgrmsg,txt) VALUES \
-                 (1,0,'marker1'), (2,0,'rsvd'), (3,0,'rsvd'), \
-                 (4,0,'rsvd'), (5,0,'rsvd'), (6,0,'rsvd'), (7,0,'rsvd'), \
-                 (8,0,'rsvd'), (9,0,'daymarker');",
-                params![],
-            )?;
-            sql.execute(
-                "CREATE TABLE jobs (\
-                 id INTEGER PRIMARY KEY AUTOINCREMENT, \
-                 added_timestamp INTEGER, \
-                 desired_timestamp INTEGER DEFAULT 0, \
-                 action INTEGER, \
-                 foreign_id INTEGER, \
-                 param TEXT DEFAULT '');",
-                params![],
-            )?;
-            sql.execute(
-                "CREATE INDEX jobs_index1 ON jobs (desired_timestamp);",
-                params![],
-            )?;
-            if !sql.table_exists("config")
-                || !sql.table_exists("contacts")
-                || !sql.table_exists("chats")
-                || !sql.table_exists("chats_contacts")
-                || !sql.table_exists("msgs")
-                || !sql.table_exists("jobs")
-            {
-                error!(
-                    context,
-                    "Cannot create tables in new database \"{:?}\".",
-                    dbfile.as_ref(),
-                );
-                // cannot create the tables - maybe we cannot write?
-                return Err(Error::SqlFailedToOpen.into());
-            } else {
-                sql.set_raw_config_int(context, "dbversion", 0)?;
-            }
-        } else {
-            exists_before_update = true;
-            dbversion_before_update = sql
-                .get_raw_config_int(context, "dbversion")
-                .unwrap_or_default();
-        }
+    sql.with_conn(|conn| backend.check(conn))?;
 
-        // (1) update low-level database structure.
-        // this should be done before updates that use high-level objects that
-        // rely themselves on the low-level structure.
-        // --------------------------------------------------------------------
-
-        let mut dbversion = dbversion_before_update;
-        let mut recalc_fingerprints = false;
-        let mut update_icons = false;
-
-        if dbversion < 1 {
-            info!(context, "[migration] v1");
-            sql.execute(
-                "CREATE TABLE leftgrps ( id INTEGER PRIMARY KEY, grpid TEXT DEFAULT '');",
-                params![],
-            )?;
-            sql.execute(
-                "CREATE INDEX leftgrps_index1 ON leftgrps (grpid);",
-                params![],
-            )?;
-            dbversion = 1;
-            sql.set_raw_config_int(context, "dbversion", 1)?;
-        }
-        if dbversion < 2 {
-            info!(context, "[migration] v2");
-            sql.execute(
-                "ALTER TABLE contacts ADD COLUMN authname TEXT DEFAULT '';",
-                params![],
-            )?;
-            dbversion = 2;
-            sql.set_raw_config_int(context, "dbversion", 2)?;
-        }
-        if dbversion < 7 {
-            info!(context, "[migration] v7");
-            sql.execute(
-                "CREATE TABLE keypairs (\
-                 id INTEGER PRIMARY KEY, \
-                 addr TEXT DEFAULT '' COLLATE NOCASE, \
-                 is_default INTEGER DEFAULT 0, \
-                 private_key, \
-                 public_key, \
-                 created INTEGER DEFAULT 0);",
-                params![],
-            )?;
-            dbversion = 7;
-            sql.set_raw_config_int(context, "dbversion", 7)?;
-        }
-        if dbversion < 10 {
-            info!(context, "[migration] v10");
-            sql.execute(
-                "CREATE TABLE acpeerstates (\
-                 id INTEGER PRIMARY KEY, \
-                 addr TEXT DEFAULT '' COLLATE NOCASE, \
-                 last_seen INTEGER DEFAULT 0, \
-                 last_seen_autocrypt INTEGER DEFAULT 0, \
-                 public_key, \
-                 prefer_encrypted INTEGER DEFAULT 0);",
-                params![],
-            )?;
-            sql.execute(
-                "CREATE INDEX acpeerstates_index1 ON acpeerstates (addr);",
-                params![],
-            )?;
-            dbversion = 10;
-            sql.set_raw_config_int(context, "dbversion", 10)?;
-        }
-        if dbversion < 12 {
-            info!(context, "[migration] v12");
-            sql.execute(
-                "CREATE TABLE msgs_mdns ( msg_id INTEGER,  contact_id INTEGER);",
-                params![],
-            )?;
-            sql.execute(
-                "CREATE INDEX msgs_mdns_index1 ON msgs_mdns (msg_id);",
-                params![],
-            )?;
-            dbversion = 12;
-            sql.set_raw_config_int(context, "dbversion", 12)?;
-        }
-        if dbversion < 17 {
-            info!(context, "[migration] v17");
-            sql.execute(
-                "ALTER TABLE chats ADD COLUMN archived INTEGER DEFAULT 0;",
-                params![],
-            )?;
-            sql.execute("CREATE INDEX chats_index2 ON chats (archived);", params![])?;
-            sql.execute(
-                "ALTER TABLE msgs ADD COLUMN starred INTEGER DEFAULT 0;",
-                params![],
-            )?;
-            sql.execute("CREATE INDEX msgs_index5 ON msgs (starred);", params![])?;
-            dbversion = 17;
-            sql.set_raw_config_int(context, "dbversion", 17)?;
-        }
-        if dbversion < 18 {
-            info!(context, "[migration] v18");
-            sql.execute(
-                "ALTER TABLE acpeerstates ADD COLUMN gossip_timestamp INTEGER DEFAULT 0;",
-                params![],
-            )?;
-            sql.execute("ALTER TABLE acpeerstates ADD COLUMN gossip_key;", params![])?;
-            dbversion = 18;
-            sql.set_raw_config_int(context, "dbversion", 18)?;
-        }
-        if dbversion < 27 {
-            info!(context, "[migration] v27");
-            // chat.id=1 and chat.id=2 are the old deaddrops,
-            // the current ones are defined by chats.blocked=2
-            sql.execute("DELETE FROM msgs WHERE chat_id=1 OR chat_id=2;", params![])?;
-            sql.execute(
-                "CREATE INDEX chats_contacts_index2 ON chats_contacts (contact_id);",
-                params![],
-            )?;
-            sql.execute(
-                "ALTER TABLE msgs ADD COLUMN timestamp_sent INTEGER DEFAULT 0;",
-                params![],
-            )?;
-            sql.execute(
-                "ALTER TABLE msgs ADD COLUMN timestamp_rcvd INTEGER DEFAULT 0;",
-                params![],
-            )?;
-            dbversion = 27;
-            sql.set_raw_config_int(context, "dbversion", 27)?;
-        }
-        if dbversion < 34 {
-            info!(context, "[migration] v34");
-            sql.execute(
-                "ALTER TABLE msgs ADD COLUMN hidden INTEGER DEFAULT 0;",
-                params![],
-            )?;
-            sql.execute(
-                "ALTER TABLE msgs_mdns ADD COLUMN timestamp_sent INTEGER DEFAULT 0;",
-                params![],
-            )?;
-            sql.execute(
-                "ALTER TABLE acpeerstates ADD COLUMN public_key_fingerprint TEXT DEFAULT '';",
-                params![],
-            )?;
-            sql.execute(
-                "ALTER TABLE acpeerstates ADD COLUMN gossip_key_fingerprint TEXT DEFAULT '';",
-                params![],
-            )?;
-            sql.execute(
-                "CREATE INDEX acpeerstates_index3 ON acpeerstates (public_key_fingerprint);",
-                params![],
-            )?;
-            sql.execute(
-                "CREATE INDEX acpeerstates_index4 ON acpeerstates (gossip_key_fingerprint);",
-                params![],
-            )?;
-            recalc_fingerprints = true;
-            dbversion = 34;
-            sql.set_raw_config_int(context, "dbversion", 34)?;
-        }
-        if dbversion < 39 {
-            info!(context, "[migration] v39");
-            sql.execute(
-                "CREATE TABLE tokens ( id INTEGER PRIMARY KEY, namespc INTEGER DEFAULT 0, foreign_id INTEGER DEFAULT 0, token TEXT DEFAULT '', timestamp INTEGER DEFAULT 0);",
-                params![]
-            )?;
-            sql.execute(
-                "ALTER TABLE acpeerstates ADD COLUMN verified_key;",
-                params![],
-            )?;
-            sql.execute(
-                "ALTER TABLE acpeerstates ADD COLUMN verified_key_fingerprint TEXT DEFAULT '';",
-                params![],
-            )?;
-            sql.execute(
-                "CREATE INDEX acpeerstates_index5 ON acpeerstates (verified_key_fingerprint);",
-                params![],
-            )?;
-            dbversion = 39;
-            sql.set_raw_config_int(context, "dbversion", 39)?;
-        }
-        if dbversion < 40 {
-            info!(context, "[migration] v40");
-            sql.execute(
-                "ALTER TABLE jobs ADD COLUMN thread INTEGER DEFAULT 0;",
-                params![],
-            )?;
-            dbversion = 40;
-            sql.set_raw_config_int(context, "dbversion", 40)?;
-        }
-        if dbversion < 44 {
-            info!(context, "[migration] v44");
-            sql.execute("ALTER TABLE msgs ADD COLUMN mime_headers TEXT;", params![])?;
-            dbversion = 44;
-            sql.set_raw_config_int(context, "dbversion", 44)?;
-        }
-        if dbversion < 46 {
-            info!(context, "[migration] v46");
-            sql.execute(
-                "ALTER TABLE msgs ADD COLUMN mime_in_reply_to TEXT;",
-                params![],
-            )?;
-            sql.execute(
-                "ALTER TABLE msgs ADD COLUMN mime_references TEXT;",
-                params![],
-            )?;
-            dbversion = 46;
-            sql.set_raw_config_int(context, "dbversion", 46)?;
-        }
-        if dbversion < 47 {
-            info!(context, "[migration] v47");
-            sql.execute(
-                "ALTER TABLE jobs ADD COLUMN tries INTEGER DEFAULT 0;",
-                params![],
-            )?;
-            dbversion = 47;
-            sql.set_raw_config_int(context, "dbversion", 47)?;
-        }
-        if dbversion < 48 {
-            info!(context, "[migration] v48");
-            // NOTE: move_state is not used anymore
-            sql.execute(
-                "ALTER TABLE msgs ADD COLUMN move_state INTEGER DEFAULT 1;",
-                params![],
-            )?;
-
-            dbversion = 48;
-            sql.set_raw_config_int(context, "dbversion", 48)?;
-        }
-        if dbversion < 49 {
-            info!(context, "[migration] v49");
-            sql.execute(
-                "ALTER TABLE chats ADD COLUMN gossiped_timestamp INTEGER DEFAULT 0;",
-                params![],
-            )?;
-            dbversion = 49;
-            sql.set_raw_config_int(context, "dbversion", 49)?;
-        }
-        if dbversion < 50 {
-            info!(context, "[migration] v50");
-            // installations <= 0.100.1 used DC_SHOW_EMAILS_ALL implicitly;
-            // keep this default and use DC_SHOW_EMAILS_NO
-            // only for new installations
-            if exists_before_update {
-                sql.set_raw_config_int(context, "show_emails", ShowEmails::All as i32)?;
-            }
-            dbversion = 50;
-            sql.set_raw_config_int(context, "dbversion", 50)?;
-        }
-        if dbversion < 53 {
-            info!(context, "[migration] v53");
-            // the messages containing _only_ locations
-            // are also added to the database as _hidden_.
-            sql.execute(
-                "CREATE TABLE locations ( id INTEGER PRIMARY KEY AUTOINCREMENT, latitude REAL DEFAULT 0.0, longitude REAL DEFAULT 0.0, accuracy REAL DEFAULT 0.0, timestamp INTEGER DEFAULT 0, chat_id INTEGER DEFAULT 0, from_id INTEGER DEFAULT 0);",
-                params![]
-            )?;
-            sql.execute(
-                "CREATE INDEX locations_index1 ON locations (from_id);",
-                params![],
-            )?;
-            sql.execute(
-                "CREATE INDEX locations_index2 ON locations (timestamp);",
-                params![],
-            )?;
-            sql.execute(
-                "ALTER TABLE chats ADD COLUMN locations_send_begin INTEGER DEFAULT 0;",
-                params![],
-            )?;
-            sql.execute(
-                "ALTER TABLE chats ADD COLUMN locations_send_until INTEGER DEFAULT 0;",
-                params![],
-            )?;
-            sql.execute(
-                "ALTER TABLE chats ADD COLUMN locations_last_sent INTEGER DEFAULT 0;",
-                params![],
-            )?;
-            sql.execute(
-                "CREATE INDEX chats_index3 ON chats (locations_send_until);",
-                params![],
-            )?;
-            dbversion = 53;
-            sql.set_raw_config_int(context, "dbversion", 53)?;
-        }
-        if dbversion < 54 {
-            info!(context, "[migration] v54");
-            sql.execute(
-                "ALTER TABLE msgs ADD COLUMN location_id INTEGER DEFAULT 0;",
-                params![],
-            )?;
-            sql.execute("CREATE INDEX msgs_index6 ON msgs (location_id);", params![])?;
-            dbversion = 54;
-            sql.set_raw_config_int(context, "dbversion", 54)?;
-        }
-        if dbversion < 55 {
-            info!(context, "[migration] v55");
-            sql.execute(
-                "ALTER TABLE locations ADD COLUMN independent INTEGER DEFAULT 0;",
-                params![],
-            )?;
-            sql.set_raw_config_int(context, "dbversion", 55)?;
-        }
-        if dbversion < 59 {
-            info!(context, "[migration] v59");
-            // records in the devmsglabels are kept when the message is deleted.
-            // so, msg_id may or may not exist.
-            sql.execute(
-                "CREATE TABLE devmsglabels (id INTEGER PRIMARY KEY AUTOINCREMENT, label TEXT, msg_id INTEGER DEFAULT 0);",
-                NO_PARAMS,
-            )?;
-            sql.execute(
-                "CREATE INDEX devmsglabels_index1 ON devmsglabels (label);",
-                NO_PARAMS,
-            )?;
-            if exists_before_update && sql.get_raw_config_int(context, "bcc_self").is_none() {
-                sql.set_raw_config_int(context, "bcc_self", 1)?;
-            }
-            sql.set_raw_config_int(context, "dbversion", 59)?;
-        }
-        if dbversion < 60 {
-            info!(context, "[migration] v60");
-            sql.execute(
-                "ALTER TABLE chats ADD COLUMN created_timestamp INTEGER DEFAULT 0;",
-                NO_PARAMS,
-            )?;
-            sql.set_raw_config_int(context, "dbversion", 60)?;
-        }
-        if dbversion < 61 {
-            info!(context, "[migration] v61");
-            sql.execute(
-                "ALTER TABLE contacts ADD COLUMN selfavatar_sent INTEGER DEFAULT 0;",
-                NO_PARAMS,
-            )?;
-            update_icons = true;
-            sql.set_raw_config_int(context, "dbversion", 61)?;
-        }
-        if dbversion < 62 {
-            info!(context, "[migration] v62");
-            sql.execute(
-                "ALTER TABLE chats ADD COLUMN muted_until INTEGER DEFAULT 0;",
-                NO_PARAMS,
-            )?;
-            sql.set_raw_config_int(context, "dbversion", 62)?;
-        }
-        if dbversion < 63 {
-            info!(context, "[migration] v63");
-            sql.execute("UPDATE chats SET grpid='' WHERE type=100", NO_PARAMS)?;
-            sql.set_raw_config_int(context, "dbversion", 63)?;
+    sql.with_conn(|conn| {
+        if has_fts5(conn)? {
+            Ok(())
+        } else {
+            Err(Error::SqlFts5Unavailable)
         }
+    })?;
 
-        // (2) updates that require high-level objects
-        // (the structure is complete now and all objects are usable)
-        // --------------------------------------------------------------------
-
-        if recalc_fingerprints {
-            info!(context, "[migration] recalc fingerprints");
-            sql.query_map(
-                "SELECT addr FROM acpeerstates;",
-                params![],
-                |row| row.get::<_, String>(0),
-                |addrs| {
-                    for addr in addrs {
-                        if let Some(ref mut peerstate) = Peerstate::from_addr(context, sql, &addr?)
-                        {
-                            peerstate.recalc_fingerprint();
-                            peerstate.save_to_db(sql, false)?;
-                        }
-                    }
-                    Ok(())
-                },
-            )?;
-        }
-        if update_icons {
-            update_saved_messages_icon(context)?;
-            update_device_icon(context)?;
-        }
+    if !readonly {
+        migrations::run(context, sql, dbfile.as_ref())?;
     }
 
     info!(context, "Opened {:?}.", dbfile.as_ref(),);
@@ -1058,6 +759,13 @@ pub fn housekeeping(context: &Context) {
     let mut unreferenced_count = 0;
 
     info!(context, "Start housekeeping...");
+    if let Err(err) = securejoin::expire_bobstates(context) {
+        warn!(
+            context,
+            "Housekeeping: Cannot expire secure-join states: {}", err
+        );
+    }
+
     maybe_add_from_param(
         context,
         &mut files_in_use,
@@ -1244,4 +952,45 @@ mod test {
         assert!(!is_file_in_use(&files, Some(".txt"), "hello"));
         assert!(is_file_in_use(&files, Some("-suffix"), "world.txt-suffix"));
     }
+
+    #[derive(Debug)]
+    struct ReadonlyBackend;
+
+    impl Backend for ReadonlyBackend {
+        fn passphrase(&self) -> Option<&str> {
+            None
+        }
+
+        fn prepare_file(
+            &self,
+            _context: &Context,
+            _dbfile: &Path,
+            _readonly: bool,
+        ) -> crate::error::Result<()> {
+            Ok(())
+        }
+
+        fn init_connection(&self, conn: &mut Connection) -> rusqlite::Result<()> {
+            conn.execute_batch("PRAGMA query_only=on;")
+        }
+
+        fn check(&self, _conn: &Connection) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_open_with_backend() {
+        let t = crate::test_utils::dummy_context();
+        let dbfile = t.dir.path().join("backend.sqlite");
+        let sql = Sql::new();
+        assert!(sql.open_with_backend(&t.ctx, &dbfile, false, Box::new(Sqlite)));
+        assert!(!sql.is_encrypted());
+        sql.close(&t.ctx);
+
+        assert!(sql.open_with_backend(&t.ctx, &dbfile, true, Box::new(ReadonlyBackend)));
+        assert!(sql.table_exists("config"));
+        assert!(sql.execute("DELETE FROM config;", NO_PARAMS).is_err());
+        sql.close(&t.ctx);
+    }
 }
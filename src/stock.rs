@@ -188,6 +188,23 @@ pub enum StockMessage {
 
     #[strum(props(fallback = "Unknown Sender for this chat. See 'info' for more details."))]
     UnknownSenderForChat = 72,
+
+    #[strum(props(fallback = "Message deletion timer is disabled."))]
+    MsgEphemeralTimerDisabled = 73,
+
+    #[strum(props(fallback = "Message deletion timer is set to %1$s s."))]
+    MsgEphemeralTimerEnabled = 74,
+
+    #[strum(props(fallback = "Message deleted."))]
+    MsgDeletedForEveryone = 75,
+
+    #[strum(props(
+        fallback = "⚠️ The storage space on your server is almost exhausted, %1$s% are used.\n\n\
+                    You may not be able to receive messages when the storage is 100% used.\n\n\
+                    👉 Please check if you can delete old data in the provider's webinterface \
+                    or let messages be deleted from the server automatically."
+    ))]
+    QuotaExceeding = 76,
 }
 
 /*
@@ -28,11 +28,7 @@ pub(crate) struct TestContext {
 pub(crate) fn test_context(callback: Option<Box<ContextCallback>>) -> TestContext {
     let dir = tempdir().unwrap();
     let dbfile = dir.path().join("db.sqlite");
-    let cb: Box<ContextCallback> = match callback {
-        Some(cb) => cb,
-        None => Box::new(|_, _| ()),
-    };
-    let ctx = Context::new(cb, "FakeOs".into(), dbfile).unwrap();
+    let ctx = Context::new(callback, "FakeOs".into(), dbfile).unwrap();
     TestContext { ctx, dir }
 }
 
@@ -40,7 +36,7 @@ pub(crate) fn test_context(callback: Option<Box<ContextCallback>>) -> TestContex
 ///
 /// The context will be opened and use the SQLite database as
 /// specified in [test_context] but there is no callback hooked up,
-/// i.e. [Context::call_cb] will always return `0`.
+/// events are only received by emitters, see [Context::get_event_emitter].
 pub(crate) fn dummy_context() -> TestContext {
     test_context(None)
 }
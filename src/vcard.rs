@@ -0,0 +1,224 @@
+//! # vCard support
+//!
+//! Contacts are exchanged as vCards (RFC 6350) containing
+//! the name, the e-mail address, the profile image and the Autocrypt key.
+//! Parsing is lenient and also accepts vCards 3.0 as exported by most address books.
+
+use crate::dc_tools::EmailAddress;
+
+/// A contact as contained in a vCard.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct VcardContact {
+    /// E-mail address, the first `EMAIL` of the vCard.
+    pub addr: String,
+
+    /// Name from `FN` or, if unset, from `N`.
+    pub display_name: String,
+
+    /// Base64 of the binary public key from `KEY`.
+    pub key: Option<String>,
+
+    /// Base64 of the image from `PHOTO`.
+    pub profile_image: Option<String>,
+}
+
+/// Returns a vCard 4.0 containing all `contacts`.
+pub fn make_vcard(contacts: &[VcardContact]) -> String {
+    let mut res = String::new();
+    for contact in contacts {
+        res += "BEGIN:VCARD\r\n";
+        res += "VERSION:4.0\r\n";
+        res += &format!("EMAIL:{}\r\n", escape(&contact.addr));
+        res += &format!("FN:{}\r\n", escape(&contact.display_name));
+        if let Some(ref key) = contact.key {
+            res += &format!("KEY:data:application/pgp-keys;base64,{}\r\n", key);
+        }
+        if let Some(ref profile_image) = contact.profile_image {
+            res += &format!("PHOTO:data:image/jpeg;base64,{}\r\n", profile_image);
+        }
+        res += "END:VCARD\r\n";
+    }
+    res
+}
+
+/// Parses all vCards contained in `vcard`.
+///
+/// vCards without a valid e-mail address are skipped.
+pub fn parse_vcard(vcard: &str) -> Vec<VcardContact> {
+    let mut contacts = Vec::new();
+    let mut contact: Option<VcardContact> = None;
+    let mut name_from_n = String::new();
+
+    for line in unfold_lines(vcard) {
+        let (name, params, value) = match split_line(&line) {
+            Some(parts) => parts,
+            None => continue,
+        };
+        if name == "BEGIN" && value.eq_ignore_ascii_case("VCARD") {
+            contact = Some(VcardContact::default());
+            name_from_n.clear();
+            continue;
+        }
+        let current = match contact.as_mut() {
+            Some(current) => current,
+            None => continue,
+        };
+        match name.as_str() {
+            "END" => {
+                if let Some(mut finished) = contact.take() {
+                    if finished.display_name.is_empty() {
+                        finished.display_name = name_from_n.clone();
+                    }
+                    if !finished.addr.is_empty() {
+                        contacts.push(finished);
+                    }
+                }
+            }
+            "EMAIL" => {
+                if current.addr.is_empty() {
+                    let addr = unescape(value).trim().to_string();
+                    if addr.parse::<EmailAddress>().is_ok() {
+                        current.addr = addr;
+                    }
+                }
+            }
+            "FN" => current.display_name = unescape(value).trim().to_string(),
+            "N" => {
+                let components: Vec<_> = value.split(';').map(unescape).collect();
+                let last_name = components.get(0).map(|s| s.trim()).unwrap_or_default();
+                let first_name = components.get(1).map(|s| s.trim()).unwrap_or_default();
+                name_from_n = format!("{} {}", first_name, last_name).trim().to_string();
+            }
+            "KEY" => current.key = get_base64_value(params, value),
+            "PHOTO" => current.profile_image = get_base64_value(params, value),
+            _ => {}
+        }
+    }
+    contacts
+}
+
+/// Joins folded lines, continuation lines start with a space or a tab.
+fn unfold_lines(vcard: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in vcard.split('\n') {
+        let line = line.trim_end_matches('\r');
+        if line.starts_with(' ') || line.starts_with('\t') {
+            if let Some(last) = lines.last_mut() {
+                last.push_str(&line[1..]);
+                continue;
+            }
+        }
+        lines.push(line.to_string());
+    }
+    lines
+}
+
+/// Splits a content line into the uppercased property name without group,
+/// the parameters and the value.
+fn split_line(line: &str) -> Option<(String, &str, &str)> {
+    let colon = line.find(':')?;
+    let (name_and_params, value) = (&line[..colon], &line[colon + 1..]);
+    let mut parts = name_and_params.splitn(2, ';');
+    let name = parts.next()?;
+    let params = parts.next().unwrap_or_default();
+    let name = name.rsplit('.').next().unwrap_or_default();
+    Some((name.to_uppercase(), params, value))
+}
+
+/// Returns the base64 data of a `data:` URI (vCard 4.0)
+/// or of a value with `ENCODING=b` (vCard 3.0).
+fn get_base64_value(params: &str, value: &str) -> Option<String> {
+    let data = if value.len() > 5 && value[..5].eq_ignore_ascii_case("data:") {
+        let comma = value.find(',')?;
+        if !value[..comma].to_lowercase().ends_with(";base64") {
+            return None;
+        }
+        &value[comma + 1..]
+    } else if params
+        .split(';')
+        .any(|param| param.eq_ignore_ascii_case("ENCODING=b"))
+    {
+        value
+    } else {
+        return None;
+    };
+    let data: String = data.chars().filter(|c| !c.is_whitespace()).collect();
+    if data.is_empty() {
+        None
+    } else {
+        Some(data)
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(',', "\\,")
+        .replace(';', "\\;")
+        .replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut res = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') | Some('N') => res.push('\n'),
+                Some(c) => res.push(c),
+                None => {}
+            }
+        } else {
+            res.push(c);
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_make_and_parse_vcard() {
+        let contacts = vec![
+            VcardContact {
+                addr: "alice@example.org".to_string(),
+                display_name: "Alice Wonderland".to_string(),
+                key: Some("bWFkZSB1cCBrZXk=".to_string()),
+                profile_image: None,
+            },
+            VcardContact {
+                addr: "bob@example.net".to_string(),
+                display_name: "Bob; the builder, jr.".to_string(),
+                key: None,
+                profile_image: Some("aW1hZ2U=".to_string()),
+            },
+        ];
+        let vcard = make_vcard(&contacts);
+        assert!(vcard.starts_with("BEGIN:VCARD\r\nVERSION:4.0\r\n"));
+        assert!(vcard.contains("FN:Bob\\; the builder\\, jr.\r\n"));
+        assert_eq!(parse_vcard(&vcard), contacts);
+    }
+
+    #[test]
+    fn test_parse_vcard3() {
+        let vcard = "BEGIN:VCARD\r\n\
+                     VERSION:3.0\r\n\
+                     N:Doe;John;;;\r\n\
+                     item1.EMAIL;TYPE=INTERNET:john@example.org\r\n\
+                     EMAIL;TYPE=INTERNET:other@example.org\r\n\
+                     PHOTO;ENCODING=b;TYPE=JPEG:aW1h\r\n \
+                     Z2U=\r\n\
+                     END:VCARD\r\n\
+                     BEGIN:VCARD\r\n\
+                     VERSION:3.0\r\n\
+                     FN:No Address\r\n\
+                     END:VCARD\r\n";
+        let contacts = parse_vcard(vcard);
+        assert_eq!(contacts.len(), 1);
+        assert_eq!(contacts[0].addr, "john@example.org");
+        assert_eq!(contacts[0].display_name, "John Doe");
+        assert_eq!(contacts[0].profile_image, Some("aW1hZ2U=".to_string()));
+        assert_eq!(contacts[0].key, None);
+    }
+}
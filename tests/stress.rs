@@ -101,7 +101,7 @@ struct TestContext {
 fn create_test_context() -> TestContext {
     let dir = tempdir().unwrap();
     let dbfile = dir.path().join("db.sqlite");
-    let ctx = Context::new(Box::new(cb), "FakeOs".into(), dbfile).unwrap();
+    let ctx = Context::new(Some(Box::new(cb)), "FakeOs".into(), dbfile).unwrap();
     TestContext { ctx, dir }
 }
 